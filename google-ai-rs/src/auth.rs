@@ -295,6 +295,30 @@ impl AuthParsed {
     //     }
     // }
 
+    /// Returns the authentication header for plain HTTP requests
+    #[cfg(feature = "file-upload")]
+    pub(crate) async fn header(&self) -> (&'static str, String) {
+        let value = match self {
+            Self::ApiKey(metadata_value) => metadata_value.clone(),
+            #[cfg(feature = "jwt")]
+            Self::JwtKind(jwt_service) => {
+                return (
+                    JWT_HEADER,
+                    jwt_service
+                        .get_token()
+                        .await
+                        .to_str()
+                        .unwrap_or_default()
+                        .to_owned(),
+                )
+            }
+        };
+        (
+            API_KEY_HEADER,
+            value.to_str().unwrap_or_default().to_owned(),
+        )
+    }
+
     /// Adds authentication headers to gRPC requests
     #[cfg(feature = "jwt")]
     pub(crate) async fn to_request(&self, request: &mut tonic::RawRequestHeader) {
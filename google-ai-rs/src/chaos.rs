@@ -0,0 +1,209 @@
+//! Fault injection for testing failure handling
+//!
+//! Enabled by the `chaos` feature. Set a [`Chaos`] on a client's
+//! [`ClientConfig`](crate::ClientConfig) and its calls get slow, fail in
+//! bursts of `429`/`503`, return malformed JSON or have their streams cut
+//! short, so retry, circuit breaker, [stream resume] and
+//! [repair](crate::TypedModel::repair_attempts) settings can be checked
+//! before production finds them.
+//!
+//! Faults follow fixed patterns over the client's call count rather than
+//! chance, so tests stay reproducible.
+//!
+//! # Example
+//! ```rust,no_run
+//! use google_ai_rs::chaos::{Chaos, Code};
+//! use google_ai_rs::Client;
+//! use std::time::Duration;
+//!
+//! # async fn f(client: Client) -> Result<(), google_ai_rs::Error> {
+//! client.update_config(|config| {
+//!     config.chaos = Some(
+//!         Chaos::new()
+//!             .latency(Duration::from_millis(200))
+//!             .error_burst(Code::ResourceExhausted, 2, 10)
+//!             .malformed_json(3),
+//!     );
+//! });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [stream resume]: crate::GenerativeModel::with_stream_resume
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tonic::Status;
+
+pub use tonic::Code;
+
+use crate::{
+    error::{status_into_error, Error},
+    proto::{part::Data, GenerateContentResponse},
+};
+
+/// Faults injected into a client's calls
+///
+/// Clones share their call counts.
+#[derive(Clone, Debug, Default)]
+pub struct Chaos {
+    latency: Duration,
+    burst: Option<Burst>,
+    malformed_every: Option<u64>,
+    truncate_after: Option<usize>,
+    calls: Arc<AtomicU64>,
+    responses: Arc<AtomicU64>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Burst {
+    code: Code,
+    len: u64,
+    every: u64,
+}
+
+impl Chaos {
+    /// Creates a configuration injecting nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delays every call by `latency`
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Fails the first `len` of every `every` calls with `code`, e.g.
+    /// [`Code::ResourceExhausted`] for a 429 or [`Code::Unavailable`] for a
+    /// 503
+    pub fn error_burst(mut self, code: Code, len: u32, every: u32) -> Self {
+        self.burst = Some(Burst {
+            code,
+            len: len.into(),
+            every: every.max(1).into(),
+        });
+        self
+    }
+
+    /// Cuts the text of every `every`th generated response in half, so it
+    /// no longer parses as JSON
+    pub fn malformed_json(mut self, every: u32) -> Self {
+        self.malformed_every = Some(every.max(1).into());
+        self
+    }
+
+    /// Fails streams with [`Code::Unavailable`] after `chunks` chunks
+    ///
+    /// Resumed streams are cut again after as many chunks.
+    pub fn truncate_streams(mut self, chunks: usize) -> Self {
+        self.truncate_after = Some(chunks);
+        self
+    }
+
+    /// Returns how many calls the client made so far
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Applies latency and error bursts to a call about to be sent
+    pub(crate) async fn before_call(&self) -> Result<(), Error> {
+        let call = self.calls.fetch_add(1, Ordering::Relaxed);
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+
+        match self.burst {
+            Some(burst) if call % burst.every < burst.len => Err(status_into_error(Status::new(
+                burst.code,
+                "injected by chaos",
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Corrupts every `malformed_every`th response
+    pub(crate) fn corrupt(&self, response: &mut GenerateContentResponse) {
+        let Some(every) = self.malformed_every else {
+            return;
+        };
+        if self.responses.fetch_add(1, Ordering::Relaxed) % every != every - 1 {
+            return;
+        }
+
+        let parts = response
+            .candidates
+            .iter_mut()
+            .filter_map(|c| c.content.as_mut())
+            .flat_map(|c| c.parts.iter_mut());
+        for part in parts {
+            if let Some(Data::Text(text)) = &mut part.data {
+                let mut half = text.len() / 2;
+                while !text.is_char_boundary(half) {
+                    half -= 1;
+                }
+                text.truncate(half);
+            }
+        }
+    }
+
+    /// Chunks a stream delivers before it's cut
+    pub(crate) fn truncate_after(&self) -> Option<usize> {
+        self.truncate_after
+    }
+
+    /// The error a cut stream fails with
+    pub(crate) fn truncated() -> Error {
+        status_into_error(Status::unavailable("stream truncated by chaos"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Candidate, Content};
+
+    #[tokio::test]
+    async fn bursts_errors() {
+        let chaos = Chaos::new().error_burst(Code::Unavailable, 2, 5);
+        let mut failed = Vec::new();
+        for _ in 0..10 {
+            failed.push(chaos.before_call().await.is_err());
+        }
+        assert_eq!(
+            failed,
+            [true, true, false, false, false, true, true, false, false, false]
+        );
+        assert_eq!(chaos.calls(), 10);
+    }
+
+    #[test]
+    fn corrupts_every_nth_response() {
+        let response = GenerateContentResponse {
+            candidates: vec![Candidate {
+                content: Some(Content::model("{\"ok\": true}")),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let chaos = Chaos::new().malformed_json(2);
+        let texts: Vec<_> = (0..4)
+            .map(|_| {
+                let mut response = response.clone();
+                chaos.corrupt(&mut response);
+                response.to_text()
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            ["{\"ok\": true}", "{\"ok\":", "{\"ok\": true}", "{\"ok\":"]
+        );
+    }
+}
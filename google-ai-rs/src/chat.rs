@@ -1,12 +1,26 @@
-use std::{collections::HashMap, io::Write};
+use std::{collections::HashMap, fmt, io::Write, sync::Arc};
 
 use tokio::io::AsyncWrite;
 
+mod compaction;
+mod manager;
+mod memory;
+mod replay;
+mod sanitize;
+pub use compaction::Compaction;
+pub use manager::{SessionManager, SessionStore};
+pub use memory::{KeepRecent, Memory, MemoryPolicy};
+pub use replay::{Replay, ReplayReport, TurnReport};
+pub use sanitize::{Finding, FindingKind, SanitizeMode, Sanitizer};
+
 use crate::{
     content::TryIntoContents,
     error::{ActionError, Error, ServiceError},
     genai::{GenerativeModel, ResponseStream as GenResponseStream},
-    proto::{part::Data, Candidate, CitationMetadata, Content, GenerateContentResponse, Part},
+    proto::{
+        generate_content_response::UsageMetadata, part::Data, Candidate, CitationMetadata, Content,
+        FunctionCall, GenerateContentResponse, Part,
+    },
 };
 
 /// Interactive chat session maintaining conversation history
@@ -23,10 +37,50 @@ use crate::{
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug)]
 pub struct Session<'m> {
     model: &'m GenerativeModel<'m>,
     pub history: Vec<Content>,
+    on_event: Option<EventHook>,
+    memory: Option<Memory>,
+    compaction: Option<Compaction>,
+    sanitizer: Option<Sanitizer>,
+}
+
+type EventHook = Arc<dyn Fn(SessionEvent<'_>) + Send + Sync>;
+
+impl fmt::Debug for Session<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("model", &self.model)
+            .field("history", &self.history)
+            .field("memory", &self.memory)
+            .field("compaction", &self.compaction)
+            .field("sanitizer", &self.sanitizer)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Something that happened in a [`Session`]
+///
+/// See [`Session::on_event`].
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum SessionEvent<'a> {
+    /// These contents were added to the history and the history sent
+    MessageSent(&'a [Content]),
+    /// The model's reply was added to the history
+    ResponseReceived(&'a Content),
+    /// The model's reply asked for this function to be called
+    ToolInvoked(&'a FunctionCall),
+    /// This many of the oldest contents were dropped from the history
+    HistoryTrimmed(usize),
+    /// This many of the oldest contents were moved into a cache
+    HistoryCached(usize),
+    /// The [`Sanitizer`] found something suspicious in a message about to
+    /// be added to the history
+    ContentFlagged(&'a Finding),
+    /// Sending or receiving failed
+    Error(&'a Error),
 }
 
 impl GenerativeModel<'_> {
@@ -35,11 +89,36 @@ impl GenerativeModel<'_> {
         Session {
             model: self,
             history: Vec::new(),
+            on_event: None,
+            memory: None,
+            compaction: None,
+            sanitizer: None,
         }
     }
 }
 
 impl<'m> Session<'m> {
+    /// Calls `f` on every [`SessionEvent`] of this session
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::{chat::SessionEvent, GenerativeModel};
+    /// # fn f(model: &GenerativeModel) {
+    /// let chat = model.start_chat().on_event(|event| match event {
+    ///     SessionEvent::ToolInvoked(call) => println!("calling {}", call.name),
+    ///     SessionEvent::Error(err) => eprintln!("chat failed: {err}"),
+    ///     _ => {}
+    /// });
+    /// # }
+    /// ```
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(SessionEvent<'_>) + Send + Sync + 'static,
+    {
+        self.on_event = Some(Arc::new(f));
+        self
+    }
+
     /// Sends a message and appends response to history
     ///
     /// # Errors
@@ -48,18 +127,175 @@ impl<'m> Session<'m> {
     where
         T: TryIntoContents,
     {
-        self.history.extend(contents.try_into_contents()?);
-
-        let response = self.model.generate_content(self.history.clone()).await?;
+        self.push_message(contents)?;
+
+        let mut prompt = self.prompt().await;
+        let cached = self.compact(&mut prompt).await;
+        let model = cached.as_ref().unwrap_or(self.model);
+        let response = match model.generate_content(prompt).await {
+            Ok(response) => response,
+            Err(err) => return Err(self.failed(err)),
+        };
+        if let (Some(compaction), Some(usage)) = (&mut self.compaction, &response.usage_metadata) {
+            compaction.record(usage);
+        }
 
         self.add_best_candidate_to_history(&response.candidates)
-            .ok_or(Error::Service(ServiceError::InvalidResponse(
-                "No valid candidates".into(),
-            )))?;
+            .ok_or_else(|| {
+                self.failed(Error::Service(ServiceError::InvalidResponse(
+                    "No valid candidates".into(),
+                )))
+            })?;
 
         Ok(response)
     }
 
+    /// Summarizes old turns with `memory` instead of resending them all
+    ///
+    /// Turns folded into the summary are dropped from the
+    /// [`history`](Self::history), reported as
+    /// [`SessionEvent::HistoryTrimmed`].
+    pub fn with_memory(mut self, memory: Memory) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Returns the memory set with [`with_memory`](Self::with_memory)
+    pub fn memory(&self) -> Option<&Memory> {
+        self.memory.as_ref()
+    }
+
+    /// Moves the history into cached content as it grows, sending only
+    /// what's new
+    ///
+    /// Caching happens before a message is sent, reported as
+    /// [`SessionEvent::HistoryCached`]. See [`Compaction`].
+    pub fn with_compaction(mut self, compaction: Compaction) -> Self {
+        self.compaction = Some(compaction);
+        self
+    }
+
+    /// Returns the compaction set with
+    /// [`with_compaction`](Self::with_compaction)
+    pub fn compaction(&self) -> Option<&Compaction> {
+        self.compaction.as_ref()
+    }
+
+    /// Scans messages for prompt injection before they join the history
+    ///
+    /// Findings are reported as [`SessionEvent::ContentFlagged`]; whether
+    /// the message is also changed or refused depends on the sanitizer's
+    /// [mode](Sanitizer::mode). See [`Sanitizer`].
+    pub fn with_sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = Some(sanitizer);
+        self
+    }
+
+    /// Returns the sanitizer set with
+    /// [`with_sanitizer`](Self::with_sanitizer)
+    pub fn sanitizer(&self) -> Option<&Sanitizer> {
+        self.sanitizer.as_ref()
+    }
+
+    /// Returns the history for export, with the model's system instruction
+    /// and tools
+    ///
+    /// See [`Transcript`](crate::function::transcript::Transcript).
+    #[cfg(feature = "serde")]
+    pub fn transcript(&self) -> crate::function::transcript::Transcript<'_> {
+        crate::function::transcript::Transcript::with_model(self.model, &self.history)
+    }
+
+    /// Drops the oldest contents so that at most `max_contents` remain
+    ///
+    /// The history is cut before a user turn so it still alternates
+    /// starting from the user, which may leave fewer than `max_contents`.
+    /// Function responses don't start turns, since they can't be sent
+    /// without the calls they answer.
+    ///
+    /// Returns the number of contents dropped.
+    pub fn trim_history(&mut self, max_contents: usize) -> usize {
+        let excess = self.history.len().saturating_sub(max_contents);
+        let removed = self.history[excess..]
+            .iter()
+            .position(|content| {
+                content.role != "model"
+                    && !content
+                        .parts
+                        .iter()
+                        .any(|part| matches!(part.data, Some(Data::FunctionResponse(_))))
+            })
+            .map_or(self.history.len(), |i| excess + i);
+
+        if removed > 0 {
+            self.history.drain(..removed);
+            self.emit(SessionEvent::HistoryTrimmed(removed));
+        }
+        removed
+    }
+
+    fn push_message<T: TryIntoContents>(&mut self, contents: T) -> Result<(), Error> {
+        let mut contents = match contents.try_into_contents() {
+            Ok(contents) => contents,
+            Err(err) => return Err(self.failed(err)),
+        };
+        if let Some(sanitizer) = &self.sanitizer {
+            let findings = match sanitizer.sanitize(&mut contents) {
+                Ok(findings) => findings,
+                Err(err) => return Err(self.failed(err)),
+            };
+            for finding in &findings {
+                self.emit(SessionEvent::ContentFlagged(finding));
+            }
+        }
+        let start = self.history.len();
+        self.history.extend(contents);
+        self.emit(SessionEvent::MessageSent(&self.history[start..]));
+        Ok(())
+    }
+
+    /// Returns the contents to send: the history, after the memory's
+    /// summary of older turns if there's one
+    async fn prompt(&mut self) -> Vec<Content> {
+        let Some(memory) = &mut self.memory else {
+            return self.history.clone();
+        };
+
+        let folded = memory.take_summary(&self.history).await;
+        let mut prompt = memory.prefix();
+        if folded > 0 {
+            self.history.drain(..folded);
+            self.emit(SessionEvent::HistoryTrimmed(folded));
+        }
+        prompt.extend_from_slice(&self.history);
+        prompt
+    }
+
+    /// Takes what's cached off `prompt`, caching more first if it's due
+    ///
+    /// Returns the model to send the rest with, if a cache applies.
+    async fn compact(&mut self, prompt: &mut Vec<Content>) -> Option<GenerativeModel<'m>> {
+        let compaction = self.compaction.as_mut()?;
+        let cached = compaction.refresh(self.model, prompt).await;
+        let model = compaction.use_cache(self.model, prompt);
+        if let Some(cached) = cached {
+            self.emit(SessionEvent::HistoryCached(cached));
+        }
+        model
+    }
+
+    /// Reports an error to the observer and hands it back
+    fn failed(&self, err: Error) -> Error {
+        self.emit(SessionEvent::Error(&err));
+        err
+    }
+
+    fn emit(&self, event: SessionEvent<'_>) {
+        if let Some(on_event) = &self.on_event {
+            on_event(event);
+        }
+    }
+
     /// Starts a streaming response while maintaining session state
     ///
     /// `NOTE`: response is only added to history if whole message is consumed
@@ -70,16 +306,20 @@ impl<'m> Session<'m> {
     where
         T: TryIntoContents,
     {
-        self.history.extend(contents.try_into_contents()?);
-
-        let stream = self
-            .model
-            .stream_generate_content(self.history.clone())
-            .await?;
+        self.push_message(contents)?;
+
+        let mut prompt = self.prompt().await;
+        let cached = self.compact(&mut prompt).await;
+        let model = cached.as_ref().unwrap_or(self.model);
+        let stream = match model.stream_generate_content(prompt).await {
+            Ok(stream) => stream,
+            Err(err) => return Err(self.failed(err)),
+        };
 
         Ok(ResponseStream {
             inner: stream,
             merged_candidates: Vec::new(),
+            usage: None,
             session: self,
             is_complete: false,
         })
@@ -92,6 +332,18 @@ impl<'m> Session<'m> {
                 let mut model_content = content.clone();
                 model_content.role = "model".to_owned();
                 self.history.push(model_content);
+
+                let reply = &self.history[self.history.len() - 1];
+                self.emit(SessionEvent::ResponseReceived(reply));
+                for part in &reply.parts {
+                    if let Some(Data::FunctionCall(call)) = &part.data {
+                        self.emit(SessionEvent::ToolInvoked(call));
+                    }
+                }
+
+                if let Some(memory) = &mut self.memory {
+                    memory.update(&self.history);
+                }
             })
         })
     }
@@ -102,6 +354,7 @@ pub struct ResponseStream<'s, 'm> {
     session: &'s mut Session<'m>,
     inner: GenResponseStream,
     merged_candidates: Vec<Candidate>,
+    usage: Option<UsageMetadata>,
     is_complete: bool,
 }
 
@@ -162,12 +415,22 @@ impl ResponseStream<'_, '_> {
             return Ok(None);
         }
 
-        match self.inner.next().await? {
+        let next = match self.inner.next().await {
+            Ok(next) => next,
+            Err(err) => return Err(self.session.failed(err)),
+        };
+        match next {
             Some(response) => {
                 merge_candidates(&mut self.merged_candidates, &response.candidates);
+                // Chunks report the usage so far; the last one counts
+                self.usage = response.usage_metadata.or(self.usage);
                 Ok(Some(response))
             }
             None => {
+                if let (Some(compaction), Some(usage)) = (&mut self.session.compaction, &self.usage)
+                {
+                    compaction.record(usage);
+                }
                 self.session
                     .add_best_candidate_to_history(&self.merged_candidates);
                 self.is_complete = true;
@@ -262,6 +525,7 @@ pub fn merge_parts(mut existing: Vec<Part>, update: Vec<Part>) -> Vec<Part> {
     if !buffer.is_empty() {
         merged.push(Part {
             data: Some(Data::Text(buffer)),
+            metadata: None,
         });
     }
 
@@ -278,12 +542,122 @@ fn merge_citations(mut existing: CitationMetadata, update: &CitationMetadata) ->
 
 #[cfg(test)]
 mod tests {
-    use super::{merge_candidates, merge_parts};
+    use std::sync::{Arc, Mutex};
+
+    use prost::Message;
+
+    use super::{merge_candidates, merge_parts, SessionEvent};
     use crate::{
         content::IntoParts,
-        proto::{Candidate, Content, Part},
+        fake::FakeServer,
+        proto::{
+            part::Data, Candidate, Content, FunctionCall, FunctionResponse,
+            GenerateContentResponse, Part,
+        },
     };
 
+    fn call(name: &str) -> Content {
+        Content::model(Part {
+            data: Some(Data::FunctionCall(FunctionCall {
+                id: String::new(),
+                name: name.into(),
+                args: None,
+            })),
+            metadata: None,
+        })
+    }
+
+    fn response(name: &str) -> Content {
+        Content::user(Part {
+            data: Some(Data::FunctionResponse(FunctionResponse {
+                id: String::new(),
+                name: name.into(),
+                response: None,
+            })),
+            metadata: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn reports_events() {
+        // Asks for a lookup, then fails
+        let server = FakeServer::start(|_, body| {
+            let request = crate::proto::GenerateContentRequest::decode(body).unwrap();
+            if request.contents.len() > 1 {
+                return Err(tonic::Status::invalid_argument("bad turn"));
+            }
+            let response = GenerateContentResponse {
+                candidates: vec![Candidate {
+                    content: Some(call("lookup")),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            Ok(response.encode_to_vec())
+        })
+        .await;
+        let client = server.client().await;
+        let model = client.generative_model("gemini-test");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut chat = model.start_chat().on_event({
+            let events = events.clone();
+            move |event| {
+                let event = match event {
+                    SessionEvent::MessageSent(contents) => format!("sent {}", contents.len()),
+                    SessionEvent::ResponseReceived(content) => format!("received {}", content.role),
+                    SessionEvent::ToolInvoked(call) => format!("invoked {}", call.name),
+                    SessionEvent::HistoryTrimmed(n) => format!("trimmed {n}"),
+                    SessionEvent::Error(_) => "error".to_owned(),
+                    _ => "other".to_owned(),
+                };
+                events.lock().unwrap().push(event);
+            }
+        });
+
+        chat.send_message("Look it up").await.unwrap();
+        chat.send_message("Again").await.unwrap_err();
+        chat.trim_history(1);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "sent 1",
+                "received model",
+                "invoked lookup",
+                "sent 1",
+                "error",
+                "trimmed 2",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn trims_history_at_turns() {
+        let server = FakeServer::start(|_, _| unreachable!()).await;
+        let client = server.client().await;
+        let model = client.generative_model("gemini-test");
+        let mut chat = model.start_chat();
+        chat.history = vec![
+            Content::user("What's the weather?"),
+            call("weather"),
+            response("weather"),
+            Content::model("Sunny."),
+            Content::user("And tomorrow?"),
+            Content::model("Rain."),
+        ];
+
+        assert_eq!(chat.trim_history(10), 0);
+        // The function response can't lead without its call
+        assert_eq!(chat.trim_history(4), 4);
+        assert_eq!(
+            chat.history,
+            [Content::user("And tomorrow?"), Content::model("Rain.")]
+        );
+        assert_eq!(chat.trim_history(1), 2);
+        assert!(chat.history.is_empty());
+    }
+
     #[test]
     fn _merge_candidates() {
         let mut c1 = vec![
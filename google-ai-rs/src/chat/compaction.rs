@@ -0,0 +1,239 @@
+//! Moving long histories into cached content
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::error::{Error, ServiceError};
+use crate::genai::GenerativeModel;
+use crate::proto::{generate_content_response::UsageMetadata, CachedContent, Content};
+
+/// Moves a conversation's history into a [`CachedContent`] once it grows
+/// large, so later turns reuse it instead of resending it
+///
+/// Attached to a [`Session`](super::Session) with
+/// [`with_compaction`](super::Session::with_compaction). After a reply
+/// leaves at least [`min_tokens`](Self::min_tokens) of the prompt
+/// uncached, the next message first caches everything before it, along
+/// with the model's system instruction and tools. That message and the ones
+/// after it only send what followed. Once the uncached part grows past the
+/// threshold again, a new cache replaces the old one, which is deleted.
+///
+/// The [`history`](super::Session::history) itself is kept whole. A cache
+/// that expired or no longer matches the start of the history, e.g. after
+/// trimming, stops being used.
+///
+/// # Example
+/// ```
+/// # use google_ai_rs::{chat::Compaction, GenerativeModel};
+/// # use std::time::Duration;
+/// # async fn f(model: &GenerativeModel<'_>) -> Result<(), google_ai_rs::Error> {
+/// let mut chat = model
+///     .start_chat()
+///     .with_compaction(Compaction::new().ttl(Duration::from_secs(30 * 60)));
+/// chat.send_message("Hello!").await?;
+///
+/// if let Some(compaction) = chat.compaction() {
+///     println!("{} prompt tokens served from cache", compaction.cached_tokens());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Compaction {
+    min_tokens: i32,
+    ttl: Duration,
+    cache: Option<Cache>,
+    /// Tokens of the last exchange not served from the cache
+    uncached: i32,
+    /// Uncached tokens at which the next cache is created
+    next_at: i32,
+    cached_tokens: u64,
+}
+
+/// A cache holding the first contents of the prompt
+#[derive(Debug)]
+struct Cache {
+    name: String,
+    contents: Vec<Content>,
+    expires_at: Instant,
+}
+
+impl Default for Compaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compaction {
+    /// Caches once 4096 tokens are uncached, for an hour at a time
+    ///
+    /// 4096 tokens is the smallest cache some models accept.
+    pub fn new() -> Self {
+        Self {
+            min_tokens: 4096,
+            ttl: Duration::from_secs(60 * 60),
+            cache: None,
+            uncached: 0,
+            next_at: 4096,
+            cached_tokens: 0,
+        }
+    }
+
+    /// Sets how many uncached tokens make the history move into a cache
+    ///
+    /// Models reject caches below a minimum size, so this shouldn't be
+    /// lower than the model's.
+    pub fn min_tokens(mut self, min_tokens: i32) -> Self {
+        self.min_tokens = min_tokens;
+        self.next_at = min_tokens;
+        self
+    }
+
+    /// Sets how long each cache lives
+    ///
+    /// A cache is stored, and billed, until it expires or is replaced.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the name of the cache in use, e.g. to delete it once the
+    /// conversation is over
+    pub fn cache_name(&self) -> Option<&str> {
+        self.cache.as_ref().map(|cache| cache.name.as_str())
+    }
+
+    /// Returns how many prompt tokens were served from caches so far
+    pub fn cached_tokens(&self) -> u64 {
+        self.cached_tokens
+    }
+
+    /// Counts the tokens of an exchange
+    pub(super) fn record(&mut self, usage: &UsageMetadata) {
+        self.uncached = usage.prompt_token_count - usage.cached_content_token_count
+            + usage.candidates_token_count;
+        self.cached_tokens += u64::from(usage.cached_content_token_count.unsigned_abs());
+    }
+
+    /// Caches what precedes the new message of `prompt` if it's due,
+    /// returning how many contents were cached
+    pub(super) async fn refresh(
+        &mut self,
+        model: &GenerativeModel<'_>,
+        prompt: &[Content],
+    ) -> Option<usize> {
+        if self.uncached < self.next_at {
+            return None;
+        }
+        // Everything before the new message is settled
+        let settled = prompt
+            .iter()
+            .rposition(|content| content.role == "model")
+            .map_or(0, |i| i + 1);
+        if settled == 0 {
+            return None;
+        }
+
+        match self.create(model, &prompt[..settled]).await {
+            Ok(cache) => {
+                self.uncached = 0;
+                self.next_at = self.min_tokens;
+                if let Some(old) = self.cache.replace(cache) {
+                    // Best effort: it expires anyway
+                    let _ = model.client.delete_cached_content(&old.name).await;
+                }
+                Some(settled)
+            }
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("caching chat history failed: {_err}");
+                // Not again before the history grows some more
+                self.next_at = self.uncached.saturating_add(self.min_tokens);
+                None
+            }
+        }
+    }
+
+    /// Takes the cached contents off the start of `prompt`, returning the
+    /// model to send the rest with
+    pub(super) fn use_cache<'m>(
+        &mut self,
+        model: &GenerativeModel<'m>,
+        prompt: &mut Vec<Content>,
+    ) -> Option<GenerativeModel<'m>> {
+        let usable = self.cache.as_ref().is_some_and(|cache| {
+            Instant::now() < cache.expires_at && prompt.starts_with(&cache.contents)
+        });
+        if !usable {
+            self.cache = None;
+            return None;
+        }
+        let cache = self.cache.as_ref()?;
+        prompt.drain(..cache.contents.len());
+
+        // The cache carries the instruction and tools; requests using it
+        // can't repeat them
+        let mut cached = model.clone();
+        cached.cached_content = Some(cache.name.as_str().into());
+        cached.system_instruction = None;
+        cached.tools = None;
+        cached.tool_config = None;
+        Some(cached)
+    }
+
+    async fn create(
+        &self,
+        model: &GenerativeModel<'_>,
+        contents: &[Content],
+    ) -> Result<Cache, Error> {
+        let request = model.to_request(contents.to_vec())?;
+        let started = Instant::now();
+        let cache = CachedContent {
+            model: Some(request.model),
+            system_instruction: request.system_instruction,
+            contents: request.contents,
+            tools: request.tools,
+            tool_config: request.tool_config,
+            ..Default::default()
+        }
+        .with_ttl(self.ttl);
+        let cache = model.client.create_cached_content(cache).await?;
+        let name = cache.name.ok_or_else(|| {
+            Error::Service(ServiceError::InvalidResponse(
+                "Cached content has no name".into(),
+            ))
+        })?;
+        Ok(Cache {
+            name,
+            contents: contents.to_vec(),
+            // Measured from before the request, so it's never late
+            expires_at: started + self.ttl,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_uncached_and_cached_tokens() {
+        let mut compaction = Compaction::new().min_tokens(100);
+        let usage = |prompt, cached, candidates| UsageMetadata {
+            prompt_token_count: prompt,
+            cached_content_token_count: cached,
+            candidates_token_count: candidates,
+            ..Default::default()
+        };
+
+        compaction.record(&usage(80, 0, 30));
+        assert_eq!(compaction.uncached, 110);
+        assert!(compaction.uncached >= compaction.next_at);
+
+        compaction.record(&usage(150, 120, 10));
+        compaction.record(&usage(170, 120, 10));
+        assert_eq!(compaction.uncached, 60);
+        assert_eq!(compaction.cached_tokens(), 240);
+    }
+}
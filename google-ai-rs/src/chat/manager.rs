@@ -0,0 +1,376 @@
+//! Chat sessions shared by many users of one server
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::time::Instant;
+
+use super::Session;
+use crate::content::TryIntoContents;
+use crate::error::Error;
+use crate::genai::GenerativeModel;
+use crate::proto::{Content, GenerateContentResponse};
+
+/// Where a [`SessionManager`] persists conversation histories
+///
+/// `()` stores nothing; histories then live only as long as they stay
+/// cached.
+pub trait SessionStore: Send + Sync {
+    /// Returns the history of a conversation, or `None` for a new one
+    fn load(&self, id: &str) -> impl Future<Output = Result<Option<Vec<Content>>, Error>> + Send;
+
+    /// Saves the history of a conversation after each exchange
+    fn save(&self, id: &str, history: &[Content])
+        -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Forgets a conversation
+    fn remove(&self, id: &str) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+impl SessionStore for () {
+    async fn load(&self, _id: &str) -> Result<Option<Vec<Content>>, Error> {
+        Ok(None)
+    }
+
+    async fn save(&self, _id: &str, _history: &[Content]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn remove(&self, _id: &str) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Chat sessions keyed by conversation ID
+///
+/// Requests for the same conversation run one at a time, in the order they
+/// arrive; requests for different conversations run concurrently. Histories
+/// are loaded from the [`SessionStore`] on first use, cached, and saved
+/// after each exchange. The least recently used conversations are evicted
+/// once there are more than [`capacity`](Self::capacity), as are those idle
+/// for longer than the [`ttl`](Self::ttl).
+///
+/// # Example
+/// ```
+/// # use google_ai_rs::{chat::SessionManager, SharedClient};
+/// # use std::time::Duration;
+/// # async fn f(client: SharedClient) -> Result<(), google_ai_rs::Error> {
+/// let sessions = SessionManager::new(client.generative_model("gemini-2.5-flash"))
+///     .capacity(10_000)
+///     .ttl(Duration::from_secs(30 * 60));
+///
+/// let reply = sessions.send_message("user-42", "Hello!").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SessionManager<S = ()> {
+    model: GenerativeModel<'static>,
+    store: S,
+    capacity: usize,
+    ttl: Option<Duration>,
+    sessions: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// `None` until loaded from the store
+    history: Arc<AsyncMutex<Option<Vec<Content>>>>,
+    last_used: Instant,
+}
+
+impl SessionManager {
+    /// Creates a manager without persistence for conversations with `model`
+    pub fn new(model: GenerativeModel<'static>) -> Self {
+        Self {
+            model,
+            store: (),
+            capacity: 1024,
+            ttl: None,
+            sessions: Mutex::default(),
+        }
+    }
+}
+
+impl<S: SessionStore> SessionManager<S> {
+    /// Persists histories in `store`
+    pub fn with_store<T: SessionStore>(self, store: T) -> SessionManager<T> {
+        SessionManager {
+            model: self.model,
+            store,
+            capacity: self.capacity,
+            ttl: self.ttl,
+            sessions: self.sessions,
+        }
+    }
+
+    /// Sets how many conversations stay cached. Defaults to 1024.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Evicts conversations idle for longer than `ttl`
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sends a message in conversation `id` and appends the reply to its
+    /// history
+    ///
+    /// A failed exchange leaves the history as it was.
+    pub async fn send_message<T>(
+        &self,
+        id: &str,
+        contents: T,
+    ) -> Result<GenerateContentResponse, Error>
+    where
+        T: TryIntoContents,
+    {
+        let contents = contents.try_into_contents()?;
+        let entry = self.entry(id);
+        let mut history = entry.lock().await;
+
+        if history.is_none() {
+            *history = Some(self.store.load(id).await?.unwrap_or_default());
+        }
+        let mut session = Session {
+            model: &self.model,
+            history: history.take().unwrap_or_default(),
+            on_event: None,
+            memory: None,
+            compaction: None,
+            sanitizer: None,
+        };
+
+        let len = session.history.len();
+        let result = session.send_message(contents).await;
+        if result.is_err() {
+            session.history.truncate(len);
+        }
+        *history = Some(session.history);
+
+        let response = result?;
+        self.store
+            .save(id, history.as_deref().unwrap_or_default())
+            .await?;
+        Ok(response)
+    }
+
+    /// Forgets conversation `id`, in the cache and the store
+    pub async fn remove(&self, id: &str) -> Result<(), Error> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id);
+        self.store.remove(id).await
+    }
+
+    /// Returns the lock over a conversation's history, evicting stale
+    /// conversations on the way
+    fn entry(&self, id: &str) -> Arc<AsyncMutex<Option<Vec<Content>>>> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Conversations with a request in progress are never evicted, so
+        // the next request for them can't load a stale history
+        let idle = |entry: &Entry| Arc::strong_count(&entry.history) == 1;
+        if let Some(ttl) = self.ttl {
+            sessions.retain(|_, entry| !idle(entry) || now - entry.last_used <= ttl);
+        }
+
+        let history = match sessions.get_mut(id) {
+            Some(entry) => {
+                entry.last_used = now;
+                entry.history.clone()
+            }
+            None => {
+                let history = Arc::new(AsyncMutex::new(None));
+                sessions.insert(
+                    id.to_owned(),
+                    Entry {
+                        history: history.clone(),
+                        last_used: now,
+                    },
+                );
+                history
+            }
+        };
+
+        while sessions.len() > self.capacity {
+            let oldest = sessions
+                .iter()
+                .filter(|(_, entry)| idle(entry))
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(id, _)| id.clone());
+            match oldest {
+                Some(oldest) => sessions.remove(&oldest),
+                None => break,
+            };
+        }
+        history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use prost::Message;
+    use tonic::Status;
+
+    use super::*;
+    use crate::{
+        fake::FakeServer,
+        proto::{Candidate, GenerateContentRequest},
+    };
+
+    /// Keeps histories in memory, counting loads and saves
+    #[derive(Default)]
+    struct CountingStore {
+        histories: Mutex<HashMap<String, Vec<Content>>>,
+        loads: AtomicUsize,
+        saves: AtomicUsize,
+    }
+
+    impl CountingStore {
+        fn history(&self, id: &str) -> Option<Vec<Content>> {
+            self.histories.lock().unwrap().get(id).cloned()
+        }
+    }
+
+    impl SessionStore for &CountingStore {
+        async fn load(&self, id: &str) -> Result<Option<Vec<Content>>, Error> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            Ok(self.history(id))
+        }
+
+        async fn save(&self, id: &str, history: &[Content]) -> Result<(), Error> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            let mut histories = self.histories.lock().unwrap();
+            histories.insert(id.to_owned(), history.to_vec());
+            Ok(())
+        }
+
+        async fn remove(&self, id: &str) -> Result<(), Error> {
+            self.histories.lock().unwrap().remove(id);
+            Ok(())
+        }
+    }
+
+    /// Replies with the number of contents in the request, and fails
+    /// messages saying "fail"
+    async fn server() -> FakeServer {
+        FakeServer::start(|_, body| {
+            let request = GenerateContentRequest::decode(body).unwrap();
+            let last = request.contents.last().unwrap();
+            if last.parts[0].to_text() == "fail" {
+                return Err(Status::invalid_argument("failing as asked"));
+            }
+            let response = GenerateContentResponse {
+                candidates: vec![Candidate {
+                    content: Some(Content::model(request.contents.len().to_string())),
+                    finish_reason: 1,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            Ok(response.encode_to_vec())
+        })
+        .await
+    }
+
+    async fn manager(server: &FakeServer) -> SessionManager {
+        let client = server.client().await.into_shared();
+        SessionManager::new(client.generative_model("gemini-test"))
+    }
+
+    fn cached(manager: &SessionManager<impl SessionStore>) -> Vec<String> {
+        let mut ids: Vec<_> = manager.sessions.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn loads_and_saves_histories() {
+        let server = server().await;
+        let store = CountingStore::default();
+        store.histories.lock().unwrap().insert(
+            "a".into(),
+            vec![Content::user("earlier"), Content::model("before")],
+        );
+        let sessions = manager(&server).await.with_store(&store);
+
+        let reply = sessions.send_message("a", "hi").await.unwrap();
+        assert_eq!(reply.to_text(), "3");
+        assert_eq!(store.history("a").unwrap().len(), 4);
+
+        // Cached histories aren't loaded again
+        let reply = sessions.send_message("a", "again").await.unwrap();
+        assert_eq!(reply.to_text(), "5");
+        assert_eq!(store.loads.load(Ordering::SeqCst), 1);
+        assert_eq!(store.saves.load(Ordering::SeqCst), 2);
+        assert_eq!(store.history("a").unwrap().len(), 6);
+
+        sessions.remove("a").await.unwrap();
+        assert_eq!(store.history("a"), None);
+        assert!(cached(&sessions).is_empty());
+        let reply = sessions.send_message("a", "hello").await.unwrap();
+        assert_eq!(reply.to_text(), "1");
+        assert_eq!(store.loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn rolls_back_failed_exchanges() {
+        let server = server().await;
+        let store = CountingStore::default();
+        let sessions = manager(&server).await.with_store(&store);
+
+        sessions.send_message("a", "hi").await.unwrap();
+        assert!(sessions.send_message("a", "fail").await.is_err());
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+        assert_eq!(store.history("a").unwrap().len(), 2);
+
+        // Neither the failed message nor a reply made it into the history
+        let reply = sessions.send_message("a", "again").await.unwrap();
+        assert_eq!(reply.to_text(), "3");
+        assert_eq!(store.loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let server = server().await;
+        let sessions = manager(&server).await.capacity(2);
+
+        sessions.entry("a");
+        sessions.entry("b");
+        sessions.entry("a");
+        sessions.entry("c");
+        assert_eq!(cached(&sessions), ["a", "c"]);
+
+        // Conversations in progress stay, even past the capacity
+        let _a = sessions.entry("a");
+        let _c = sessions.entry("c");
+        sessions.entry("d");
+        assert_eq!(cached(&sessions), ["a", "c", "d"]);
+        sessions.entry("e");
+        assert_eq!(cached(&sessions), ["a", "c", "e"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn evicts_idle_sessions() {
+        let server = server().await;
+        let sessions = manager(&server).await.ttl(Duration::from_secs(30 * 60));
+
+        sessions.entry("a");
+        let _b = sessions.entry("b");
+        tokio::time::sleep(Duration::from_secs(31 * 60)).await;
+        sessions.entry("c");
+        // b has a request in progress
+        assert_eq!(cached(&sessions), ["b", "c"]);
+    }
+}
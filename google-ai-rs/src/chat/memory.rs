@@ -0,0 +1,241 @@
+//! Summarizing old turns of long conversations
+
+use std::fmt::{Debug, Write as _};
+
+use tokio::task::JoinHandle;
+
+use crate::error::Error;
+use crate::genai::GenerativeModel;
+use crate::proto::Content;
+
+/// Decides when a [`Memory`] folds turns into its summary, and how it asks
+///
+/// The default is [`KeepRecent`].
+pub trait MemoryPolicy: Debug + Send + Sync {
+    /// Returns how many of the oldest contents of `history` to fold into
+    /// the summary, or 0 to keep them verbatim for now
+    ///
+    /// The count should end before a user turn, so the history left still
+    /// starts from the user.
+    fn fold(&self, history: &[Content]) -> usize;
+
+    /// Returns the request asking the summarizer to fold `contents` into
+    /// `summary`
+    fn prompt(&self, summary: Option<&str>, contents: &[Content]) -> Vec<Content> {
+        let mut prompt = String::from(
+            "Summarize the conversation below for the assistant taking part in it. \
+             Keep facts, decisions, names and open questions; drop pleasantries. \
+             Reply with the summary only.\n\n",
+        );
+        if let Some(summary) = summary {
+            let _ = write!(
+                prompt,
+                "Summary of the earlier conversation:\n{summary}\n\n"
+            );
+        }
+        prompt.push_str("Conversation:\n");
+        for content in contents {
+            for part in &content.parts {
+                let text = part.to_text();
+                if !text.is_empty() {
+                    let _ = writeln!(prompt, "{}: {text}", content.role);
+                }
+            }
+        }
+        vec![Content::user(prompt)]
+    }
+}
+
+/// Keeps the most recent contents verbatim and folds older ones in batches
+///
+/// Once the history reaches twice [`recent`](Self::new) contents, all but
+/// the last `recent` are folded into the summary, cut before a user turn
+/// so that the verbatim part may be a turn longer.
+/// Folding in batches keeps the summary, and with it the prompt prefix,
+/// stable for several turns at a time.
+#[derive(Clone, Copy, Debug)]
+pub struct KeepRecent {
+    recent: usize,
+}
+
+impl KeepRecent {
+    /// Keeps the last `recent` contents verbatim
+    pub fn new(recent: usize) -> Self {
+        Self { recent }
+    }
+}
+
+impl Default for KeepRecent {
+    fn default() -> Self {
+        Self::new(20)
+    }
+}
+
+impl MemoryPolicy for KeepRecent {
+    fn fold(&self, history: &[Content]) -> usize {
+        if history.len() < self.recent.saturating_mul(2).max(1) {
+            return 0;
+        }
+        // Keeps at least `recent`, from the user turn starting them
+        let excess = history.len() - self.recent;
+        history[..=excess]
+            .iter()
+            .rposition(|content| content.role != "model")
+            .unwrap_or(0)
+    }
+}
+
+/// A rolling summary of a conversation's old turns
+///
+/// Attached to a [`Session`](super::Session) with
+/// [`with_memory`](super::Session::with_memory). After each reply, the
+/// [policy](MemoryPolicy) may pick old turns to fold into the summary; a
+/// cheap summarizer model does so in the background while the conversation
+/// goes on. The next message after it finishes drops the folded turns from
+/// the history and sends the summary in their place.
+///
+/// # Example
+/// ```
+/// # use google_ai_rs::{chat::{KeepRecent, Memory}, SharedClient};
+/// # async fn f(client: SharedClient) -> Result<(), google_ai_rs::Error> {
+/// let model = client.generative_model("gemini-2.5-pro");
+/// let memory = Memory::new(client.generative_model("gemini-2.5-flash-lite"))
+///     .policy(KeepRecent::new(10));
+///
+/// let mut chat = model.start_chat().with_memory(memory);
+/// chat.send_message("Hello!").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Memory {
+    summarizer: GenerativeModel<'static>,
+    policy: Box<dyn MemoryPolicy>,
+    summary: Option<String>,
+    pending: Option<Pending>,
+}
+
+/// A summary being written of the oldest `folded` contents
+#[derive(Debug)]
+struct Pending {
+    folded: Vec<Content>,
+    task: JoinHandle<Result<String, Error>>,
+}
+
+impl Memory {
+    /// Creates a memory summarizing with `summarizer`, under [`KeepRecent`]'s
+    /// defaults
+    pub fn new(summarizer: GenerativeModel<'static>) -> Self {
+        Self {
+            summarizer,
+            policy: Box::new(KeepRecent::default()),
+            summary: None,
+            pending: None,
+        }
+    }
+
+    /// Sets when and how turns are folded
+    pub fn policy<P: MemoryPolicy + 'static>(mut self, policy: P) -> Self {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    /// Returns the summary of the turns folded so far
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Returns the contents standing in for the folded turns
+    pub(super) fn prefix(&self) -> Vec<Content> {
+        match &self.summary {
+            Some(summary) => vec![
+                Content::user(format!("Summary of our conversation so far:\n{summary}")),
+                Content::model("Understood."),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    /// Takes a finished summary, returning how many of the oldest contents
+    /// of `history` it replaces
+    ///
+    /// A summary of contents no longer at the front of the history, e.g.
+    /// after trimming, is dropped.
+    pub(super) async fn take_summary(&mut self, history: &[Content]) -> usize {
+        if !self.pending.as_ref().is_some_and(|p| p.task.is_finished()) {
+            return 0;
+        }
+        let Pending { folded, task } = self.pending.take().unwrap();
+
+        // Finished, so this doesn't wait
+        let summary = match task.await {
+            Ok(Ok(summary)) => summary,
+            Ok(Err(_err)) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("summarizing chat history failed: {_err}");
+                return 0;
+            }
+            Err(_) => return 0,
+        };
+
+        if !history.starts_with(&folded) {
+            return 0;
+        }
+        self.summary = Some(summary);
+        folded.len()
+    }
+
+    /// Starts summarizing old turns if the policy says so and no summary is
+    /// being written
+    pub(super) fn update(&mut self, history: &[Content]) {
+        if self.pending.is_some() {
+            return;
+        }
+        let fold = self.policy.fold(history).min(history.len());
+        if fold == 0 {
+            return;
+        }
+
+        let folded = history[..fold].to_vec();
+        let prompt = self.policy.prompt(self.summary.as_deref(), &folded);
+        let summarizer = self.summarizer.clone();
+        let task = tokio::spawn(async move {
+            summarizer
+                .generate_content(prompt)
+                .await
+                .map(|response| response.to_text())
+        });
+        self.pending = Some(Pending { folded, task });
+    }
+}
+
+impl Drop for Memory {
+    fn drop(&mut self) {
+        if let Some(pending) = &self.pending {
+            pending.task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_recent_folds_before_user_turn() {
+        let turns = |n: usize| -> Vec<Content> {
+            (0..n)
+                .map(|i| match i % 2 {
+                    0 => Content::user(format!("q{i}")),
+                    _ => Content::model(format!("a{i}")),
+                })
+                .collect()
+        };
+        let policy = KeepRecent::new(3);
+
+        assert_eq!(policy.fold(&turns(5)), 0);
+        // The last 3 start with a model turn, so one more is kept
+        assert_eq!(policy.fold(&turns(6)), 2);
+        assert_eq!(policy.fold(&turns(7)), 4);
+    }
+}
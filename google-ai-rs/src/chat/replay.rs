@@ -0,0 +1,289 @@
+//! Replaying recorded conversations against a model
+
+use std::{collections::HashMap, fmt, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{
+    error::{Error, ServiceError},
+    genai::GenerativeModel,
+    proto::{part::Data, Content, FunctionCall},
+};
+
+type Score = Pin<Box<dyn Future<Output = Result<f64, Error>> + Send>>;
+
+type Judge = Arc<dyn Fn(&[Content], &Content, &Content) -> Score + Send + Sync>;
+
+/// Replays golden transcripts turn by turn, reporting how a model's replies
+/// diverge from the recorded ones
+///
+/// For validating a prompt or model change against historical
+/// conversations: each model turn of a transcript is generated again from
+/// the recorded turns before it, so one diverging reply doesn't throw off
+/// the rest. The model's configuration, e.g. its system instruction and
+/// tools, applies as usual, so give it the one under test.
+///
+/// Each regenerated turn is compared in a [`TurnReport`]: word overlap
+/// with the recorded text, whether the same functions were called with the
+/// same arguments, latency, and optionally a [judge](Self::judge)'s score.
+/// Turns are sent one at a time.
+///
+/// # Example
+/// ```
+/// # use google_ai_rs::{chat::Replay, Content, GenerativeModel};
+/// # async fn f(model: GenerativeModel<'_>, transcripts: Vec<(String, Vec<Content>)>) {
+/// let reports = Replay::new(&model).run_all(transcripts).await;
+/// for (id, report) in &reports {
+///     if let Some(turn) = report.first_divergence(0.5) {
+///         println!("{id} diverges at content {}: {:?}", turn.index, turn.reply);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Replay<'m> {
+    model: &'m GenerativeModel<'m>,
+    judge: Option<Judge>,
+}
+
+impl fmt::Debug for Replay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("model", &self.model)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'m> Replay<'m> {
+    /// Replays against `model`
+    pub fn new(model: &'m GenerativeModel<'m>) -> Self {
+        Self { model, judge: None }
+    }
+
+    /// Scores each reply against the recorded one, e.g. by asking another
+    /// model whether they mean the same
+    ///
+    /// `judge` is given the recorded turns before the reply, the recorded
+    /// reply and the model's. Higher scores should mean closer replies.
+    pub fn judge<F, Fut>(mut self, judge: F) -> Self
+    where
+        F: Fn(&[Content], &Content, &Content) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<f64, Error>> + Send + 'static,
+    {
+        let judge: Judge = Arc::new(
+            move |history: &[Content], expected: &Content, reply: &Content| -> Score {
+                Box::pin(judge(history, expected, reply))
+            },
+        );
+        self.judge = Some(judge);
+        self
+    }
+
+    /// Regenerates every model turn of `transcript`
+    ///
+    /// Model turns are those with the role `model`; a transcript opening
+    /// with one has nothing to regenerate it from, so it's skipped.
+    pub async fn run(&self, transcript: &[Content]) -> ReplayReport {
+        let mut turns = Vec::new();
+        for (index, expected) in transcript.iter().enumerate() {
+            if expected.role != "model" || index == 0 {
+                continue;
+            }
+            let history = &transcript[..index];
+
+            let started = Instant::now();
+            let reply = self.reply(history).await;
+            let latency = started.elapsed();
+
+            let (similarity, calls_match) = match &reply {
+                Ok(reply) => (
+                    similarity(&text(expected), &text(reply)),
+                    calls(expected) == calls(reply),
+                ),
+                Err(_) => (0.0, false),
+            };
+            let score = match (&self.judge, &reply) {
+                (Some(judge), Ok(reply)) => Some(judge(history, expected, reply).await),
+                _ => None,
+            };
+            turns.push(TurnReport {
+                index,
+                expected: expected.clone(),
+                reply,
+                similarity,
+                calls_match,
+                latency,
+                score,
+            });
+        }
+        ReplayReport { turns }
+    }
+
+    /// Replays each transcript in turn, keeping the ID it was given with
+    pub async fn run_all<I, K>(&self, transcripts: I) -> Vec<(K, ReplayReport)>
+    where
+        I: IntoIterator<Item = (K, Vec<Content>)>,
+    {
+        let mut reports = Vec::new();
+        for (id, transcript) in transcripts {
+            let report = self.run(&transcript).await;
+            reports.push((id, report));
+        }
+        reports
+    }
+
+    async fn reply(&self, history: &[Content]) -> Result<Content, Error> {
+        let response = self.model.generate_content(history.to_vec()).await?;
+        let mut reply = response
+            .candidates
+            .into_iter()
+            .next()
+            .and_then(|candidate| candidate.content)
+            .ok_or_else(|| {
+                Error::Service(ServiceError::InvalidResponse("No valid candidates".into()))
+            })?;
+        reply.role = "model".to_owned();
+        Ok(reply)
+    }
+}
+
+/// How one transcript's replayed turns compared to the recorded ones
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ReplayReport {
+    /// One per regenerated model turn, in transcript order
+    pub turns: Vec<TurnReport>,
+}
+
+impl ReplayReport {
+    /// Returns the first turn that failed, called other functions, or
+    /// whose text overlaps the recorded one less than `min_similarity`
+    pub fn first_divergence(&self, min_similarity: f64) -> Option<&TurnReport> {
+        self.turns
+            .iter()
+            .find(|turn| !turn.calls_match || turn.similarity < min_similarity)
+    }
+
+    /// Returns the mean [similarity](TurnReport::similarity) of the turns,
+    /// or `None` without any
+    pub fn mean_similarity(&self) -> Option<f64> {
+        let len = self.turns.len();
+        (len > 0).then(|| self.turns.iter().map(|turn| turn.similarity).sum::<f64>() / len as f64)
+    }
+
+    /// Returns how many turns failed to generate
+    pub fn errors(&self) -> usize {
+        self.turns.iter().filter(|turn| turn.reply.is_err()).count()
+    }
+}
+
+/// How a regenerated turn compared to the recorded one
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct TurnReport {
+    /// Position of the turn in the transcript
+    pub index: usize,
+    /// The recorded turn
+    pub expected: Content,
+    /// The model's turn, or why it failed
+    pub reply: Result<Content, Error>,
+    /// How much of the recorded text the reply shares, from 0 to 1
+    ///
+    /// The F1 score of their words, compared case-insensitively regardless
+    /// of order: 1 for the same words, 0 for none in common or a failure.
+    /// Two turns without text, e.g. both only calling functions, score 1.
+    pub similarity: f64,
+    /// Whether the reply called the same functions, in the same order, with
+    /// the same arguments. True when neither called any.
+    pub calls_match: bool,
+    /// How long the model took to reply or fail
+    pub latency: Duration,
+    /// The [judge](Replay::judge)'s score, if one is set and the model
+    /// replied
+    pub score: Option<Result<f64, Error>>,
+}
+
+fn text(content: &Content) -> String {
+    content
+        .parts
+        .iter()
+        .filter_map(|part| match &part.data {
+            Some(Data::Text(text)) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn calls(content: &Content) -> Vec<&FunctionCall> {
+    content
+        .parts
+        .iter()
+        .filter_map(|part| match &part.data {
+            Some(Data::FunctionCall(call)) => Some(call),
+            _ => None,
+        })
+        .collect()
+}
+
+/// F1 score of the words of `a` and `b`, counted with repeats
+fn similarity(a: &str, b: &str) -> f64 {
+    let words = |text: &str| -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+        }
+        counts
+    };
+    let (a, b) = (words(a), words(b));
+    let (len_a, len_b): (usize, usize) = (a.values().sum(), b.values().sum());
+    if len_a == 0 || len_b == 0 {
+        return if len_a == len_b { 1.0 } else { 0.0 };
+    }
+
+    let shared: usize = a
+        .iter()
+        .map(|(word, count)| (*count).min(b.get(word).copied().unwrap_or(0)))
+        .sum();
+    2.0 * shared as f64 / (len_a + len_b) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_word_overlap() {
+        assert_eq!(similarity("The cat sat.", "the CAT sat"), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("hello", ""), 0.0);
+        assert_eq!(similarity("a b c d", "e f"), 0.0);
+        // 2 shared of 4 + 4 words
+        assert_eq!(similarity("a b c d", "a b e f"), 0.5);
+        // Repeats count once per occurrence
+        assert_eq!(similarity("yes yes", "yes"), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn finds_divergence() {
+        let turn = |index, similarity, calls_match| TurnReport {
+            index,
+            expected: Content::model("x"),
+            reply: Ok(Content::model("x")),
+            similarity,
+            calls_match,
+            latency: Duration::ZERO,
+            score: None,
+        };
+        let report = ReplayReport {
+            turns: vec![turn(1, 1.0, true), turn(3, 0.5, false), turn(5, 0.0, true)],
+        };
+        assert_eq!(report.first_divergence(0.5).map(|turn| turn.index), Some(3));
+        assert_eq!(report.first_divergence(0.0).map(|turn| turn.index), Some(3));
+        assert_eq!(report.mean_similarity(), Some(0.5));
+        assert_eq!(report.errors(), 0);
+        assert_eq!(ReplayReport { turns: Vec::new() }.mean_similarity(), None);
+    }
+}
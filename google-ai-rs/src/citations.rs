@@ -0,0 +1,444 @@
+//! Which grounding sources back which fields of a typed response
+//!
+//! With grounding on, e.g. through Google Search, the API reports which
+//! bytes of the response each source supports. For typed output those bytes
+//! are JSON, so [`FieldCitations`] maps them onto the values they fall in,
+//! named by JSON pointer, e.g. `/address/city`. A UI can then show which
+//! source backed which field.
+//!
+//! # Example
+#![cfg_attr(feature = "serde", doc = "```rust,no_run")]
+#![cfg_attr(not(feature = "serde"), doc = "```rust,ignore")]
+//! use google_ai_rs::{AsSchema, Client};
+//!
+//! #[derive(AsSchema, serde::Deserialize)]
+//! struct City {
+//!     name: String,
+//!     population: u64,
+//! }
+//!
+//! # async fn f(client: Client) -> Result<(), google_ai_rs::Error> {
+//! let model = client.typed_model::<City>("gemini-2.5-flash");
+//! let response = model.generate_typed_content("Tell me about Lyon").await?;
+//!
+//! for source in response.field_citations().get("/population") {
+//!     println!("population from {}", source.uri);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, marker::PhantomData, ops::Range};
+
+use crate::proto::{grounding_chunk::ChunkType, part::Data, Candidate};
+
+/// Deeper values aren't mapped
+const MAX_DEPTH: usize = 128;
+
+/// The grounding sources of each value of a typed response
+///
+/// Built from the first candidate with
+/// [`TypedResponse::field_citations`](crate::TypedResponse::field_citations).
+/// Values are named by JSON pointer, as serialized: a field renamed for
+/// serde goes by its JSON name. See the [module docs](self).
+pub struct FieldCitations<T> {
+    fields: Vec<FieldCitation>,
+    _t: PhantomData<fn() -> T>,
+}
+
+// Not derived, which would require the same of `T`
+impl<T> Clone for FieldCitations<T> {
+    fn clone(&self) -> Self {
+        Self::from(self.fields.clone())
+    }
+}
+
+impl<T> fmt::Debug for FieldCitations<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.fields).finish()
+    }
+}
+
+/// The sources backing one value
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct FieldCitation {
+    /// JSON pointer of the value, e.g. `/items/0/name`
+    pub pointer: String,
+    /// Sources in the order the API reported them
+    pub sources: Vec<Source>,
+}
+
+/// A source grounding part of a response
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Source {
+    /// Index of the source among the candidate's grounding chunks
+    pub chunk: usize,
+    /// Where the source can be found
+    pub uri: String,
+    /// Title of the source
+    pub title: String,
+    /// How confident the API is that the source supports the value, from 0
+    /// to 1
+    pub confidence: Option<f32>,
+}
+
+impl<T> FieldCitations<T> {
+    /// Maps the grounding supports of `candidate` onto its JSON values
+    ///
+    /// Text that isn't JSON, or stops being JSON partway, is mapped as far
+    /// as it goes.
+    pub fn new(candidate: &Candidate) -> Self {
+        let mut fields = Vec::<FieldCitation>::new();
+        let (Some(content), Some(grounding)) = (&candidate.content, &candidate.grounding_metadata)
+        else {
+            return Self::from(fields);
+        };
+
+        // Segments are measured within their part; values across all text
+        let mut text = String::new();
+        let mut starts = Vec::with_capacity(content.parts.len());
+        for part in &content.parts {
+            starts.push(text.len());
+            if let Some(Data::Text(part)) = &part.data {
+                text.push_str(part);
+            }
+        }
+        let values = scan(&text);
+
+        for support in &grounding.grounding_supports {
+            let Some(segment) = &support.segment else {
+                continue;
+            };
+            let Some(start) = usize::try_from(segment.part_index)
+                .ok()
+                .and_then(|index| starts.get(index))
+            else {
+                continue;
+            };
+            let span = start + segment.start_index.max(0) as usize
+                ..start + segment.end_index.max(0) as usize;
+
+            let sources = support
+                .grounding_chunk_indices
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &chunk)| {
+                    let chunk = usize::try_from(chunk).ok()?;
+                    let Some(ChunkType::Web(web)) =
+                        &grounding.grounding_chunks.get(chunk)?.chunk_type
+                    else {
+                        return None;
+                    };
+                    Some(Source {
+                        chunk,
+                        uri: web.uri.clone().unwrap_or_default(),
+                        title: web.title.clone().unwrap_or_default(),
+                        confidence: support.confidence_scores.get(i).copied(),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for (pointer, value) in &values {
+                if value.start >= span.end || span.start >= value.end {
+                    continue;
+                }
+                let field = match fields.iter_mut().find(|f| f.pointer == *pointer) {
+                    Some(field) => field,
+                    None => {
+                        fields.push(FieldCitation {
+                            pointer: pointer.clone(),
+                            sources: Vec::new(),
+                        });
+                        fields.last_mut().unwrap()
+                    }
+                };
+                for source in &sources {
+                    if !field.sources.iter().any(|s| s.chunk == source.chunk) {
+                        field.sources.push(source.clone());
+                    }
+                }
+            }
+        }
+        Self::from(fields)
+    }
+
+    /// Returns the sources of the value at `pointer` and of the values
+    /// inside it, without repeats
+    ///
+    /// `""` is the whole response.
+    pub fn get(&self, pointer: &str) -> Vec<&Source> {
+        let mut sources: Vec<&Source> = Vec::new();
+        for field in self
+            .fields
+            .iter()
+            .filter(|field| within(&field.pointer, pointer))
+        {
+            for source in &field.sources {
+                if !sources.iter().any(|s| s.chunk == source.chunk) {
+                    sources.push(source);
+                }
+            }
+        }
+        sources
+    }
+
+    /// Returns the cited values in the order they appear in the response
+    pub fn iter(&self) -> std::slice::Iter<'_, FieldCitation> {
+        self.fields.iter()
+    }
+
+    /// Returns `true` if no value is cited
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl<T> From<Vec<FieldCitation>> for FieldCitations<T> {
+    fn from(fields: Vec<FieldCitation>) -> Self {
+        Self {
+            fields,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a FieldCitations<T> {
+    type Item = &'a FieldCitation;
+    type IntoIter = std::slice::Iter<'a, FieldCitation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Whether `pointer` is `parent` or points inside it
+fn within(pointer: &str, parent: &str) -> bool {
+    pointer
+        .strip_prefix(parent)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Finds the scalar values of the JSON in `text` and where they are
+///
+/// Anything before the first `{` or `[`, like a code fence, is skipped.
+fn scan(text: &str) -> Vec<(String, Range<usize>)> {
+    let mut scanner = Scanner {
+        text: text.as_bytes(),
+        pos: text.find(['{', '[']).unwrap_or(text.len()),
+        values: Vec::new(),
+    };
+    scanner.value(&mut String::new(), 0);
+    scanner.values
+}
+
+struct Scanner<'a> {
+    text: &'a [u8],
+    pos: usize,
+    values: Vec<(String, Range<usize>)>,
+}
+
+impl Scanner<'_> {
+    fn peek(&mut self) -> Option<u8> {
+        while self.text.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+        self.text.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let ate = self.peek() == Some(byte);
+        if ate {
+            self.pos += 1;
+        }
+        ate
+    }
+
+    /// Scans the value at `pointer`, returning `None` where the JSON breaks
+    fn value(&mut self, pointer: &mut String, depth: usize) -> Option<()> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let byte = self.peek()?;
+        let start = self.pos;
+        match byte {
+            b'{' => {
+                self.pos += 1;
+                if self.eat(b'}') {
+                    return Some(());
+                }
+                loop {
+                    let key = self.string()?;
+                    if !self.eat(b':') {
+                        return None;
+                    }
+                    let len = pointer.len();
+                    pointer.push('/');
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    self.value(pointer, depth + 1)?;
+                    pointer.truncate(len);
+                    if !self.eat(b',') {
+                        break;
+                    }
+                }
+                self.eat(b'}').then_some(())
+            }
+            b'[' => {
+                self.pos += 1;
+                if self.eat(b']') {
+                    return Some(());
+                }
+                for index in 0.. {
+                    let len = pointer.len();
+                    pointer.push('/');
+                    pointer.push_str(&index.to_string());
+                    self.value(pointer, depth + 1)?;
+                    pointer.truncate(len);
+                    if !self.eat(b',') {
+                        break;
+                    }
+                }
+                self.eat(b']').then_some(())
+            }
+            b'"' => {
+                self.string()?;
+                self.values.push((pointer.clone(), start..self.pos));
+                Some(())
+            }
+            _ => {
+                while self
+                    .text
+                    .get(self.pos)
+                    .is_some_and(|b| b.is_ascii_alphanumeric() || b"+-.".contains(b))
+                {
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    return None;
+                }
+                self.values.push((pointer.clone(), start..self.pos));
+                Some(())
+            }
+        }
+    }
+
+    /// Scans a string, returning it unescaped
+    fn string(&mut self) -> Option<String> {
+        if !self.eat(b'"') {
+            return None;
+        }
+        let mut out = Vec::new();
+        loop {
+            let byte = *self.text.get(self.pos)?;
+            self.pos += 1;
+            match byte {
+                b'"' => return String::from_utf8(out).ok(),
+                b'\\' => {
+                    let escaped = *self.text.get(self.pos)?;
+                    self.pos += 1;
+                    let c = match escaped {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let hex = self.text.get(self.pos..self.pos + 4)?;
+                            self.pos += 4;
+                            let code =
+                                u32::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        other => other as char,
+                    };
+                    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte => out.push(byte),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{
+        grounding_chunk::Web, Content, GroundingChunk, GroundingMetadata, GroundingSupport, Part,
+        Segment,
+    };
+
+    #[test]
+    fn scans_values_with_their_spans() {
+        let text = "```json\n{\"name\": \"Lyon\", \"a/b\": [1, true], \"stats\": {\"pop\": 5.2e5}}";
+        let values: Vec<_> = scan(text)
+            .into_iter()
+            .map(|(pointer, span)| (pointer, &text[span]))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                ("/name".into(), "\"Lyon\""),
+                ("/a~1b/0".into(), "1"),
+                ("/a~1b/1".into(), "true"),
+                ("/stats/pop".into(), "5.2e5"),
+            ]
+        );
+
+        // Broken JSON is scanned up to the break
+        assert_eq!(scan("{\"a\": 1, \"b\": ").len(), 1);
+    }
+
+    #[test]
+    fn maps_supports_onto_fields() {
+        let parts = ["{\"name\": \"Lyon\", ", "\"stats\": {\"pop\": 520000}}"];
+        let web = |uri: &str| GroundingChunk {
+            chunk_type: Some(ChunkType::Web(Web {
+                uri: Some(uri.into()),
+                title: Some(uri.to_uppercase()),
+            })),
+        };
+        let support = |part_index, text: &str, chunks: Vec<i32>| GroundingSupport {
+            segment: Some(Segment {
+                part_index,
+                start_index: parts[part_index as usize].find(text).unwrap() as i32,
+                end_index: (parts[part_index as usize].find(text).unwrap() + text.len()) as i32,
+                text: text.into(),
+            }),
+            confidence_scores: chunks.iter().map(|_| 0.9).collect(),
+            grounding_chunk_indices: chunks,
+        };
+        let candidate = Candidate {
+            content: Some(Content {
+                role: "model".into(),
+                parts: parts.into_iter().map(Part::text).collect(),
+            }),
+            grounding_metadata: Some(GroundingMetadata {
+                grounding_chunks: vec![web("a"), web("b")],
+                grounding_supports: vec![
+                    support(0, "Lyon", vec![0]),
+                    support(1, "520000", vec![1, 0]),
+                    support(1, "pop", vec![1]),
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let citations = FieldCitations::<()>::new(&candidate);
+        let uris = |pointer| {
+            citations
+                .get(pointer)
+                .into_iter()
+                .map(|source| source.uri.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(uris("/name"), ["a"]);
+        assert_eq!(uris("/stats/pop"), ["b", "a"]);
+        assert_eq!(uris("/stats"), ["b", "a"]);
+        assert_eq!(uris(""), ["a", "b"]);
+        assert!(uris("/stat").is_empty());
+        assert_eq!(citations.iter().count(), 2);
+        assert_eq!(citations.get("/name")[0].confidence, Some(0.9));
+    }
+}
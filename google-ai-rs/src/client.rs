@@ -1,25 +1,32 @@
-#[allow(unused_imports)]
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
+use tokio::time::Instant;
 use tonic::body::Body;
-use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
-use tonic::{IntoRequest, RawRequest};
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tonic::{IntoRequest, RawRequest, Status};
 
 use crate::auth::{Auth, AuthParsed};
+use crate::config::{ClientConfig, ConfigCell};
 use crate::content::UpdateFieldMask as _;
+use crate::context::TokenCounts;
+use crate::deadline;
 use crate::error::{status_into_error, Error, NetError, SetupError, TonicTransportError};
+use crate::failover::{Failover, Region, Regions};
 use crate::full_model_name;
-use crate::proto::model_service_client::ModelServiceClient;
+use crate::genai::ParseStats;
+use crate::operation::Operation;
+use crate::proto::longrunning::GetOperationRequest;
 use crate::proto::{
-    cache_service_client::CacheServiceClient, generative_service_client::GenerativeServiceClient,
     CachedContent, CreateCachedContentRequest, DeleteCachedContentRequest, GetCachedContentRequest,
     ListCachedContentsRequest, UpdateCachedContentRequest,
 };
 use crate::proto::{
-    DeleteTunedModelRequest, GetModelRequest, GetTunedModelRequest, ListModelsRequest,
+    CreateTunedModelMetadata, CreateTunedModelRequest, DeleteTunedModelRequest, File,
+    GetFileRequest, GetModelRequest, GetTunedModelRequest, ListModelsRequest,
     ListTunedModelsRequest, Model, TunedModel, UpdateTunedModelRequest,
 };
 
@@ -51,11 +58,20 @@ const USER_AGENT: &str = "google-ai-rs/0.1 (Rust)";
 /// ```
 #[derive(Clone, Debug)]
 pub struct Client {
-    /// Generative service gRPC client
-    pub(super) gc: GenerativeServiceClient<Channel>,
-    /// Cache service gRPC client
-    pub(super) cc: CacheServiceClient<Channel>,
-    pub(super) mc: ModelServiceClient<Channel>,
+    /// gRPC clients of each endpoint and which one calls go to
+    pub(super) regions: Arc<Regions>,
+    /// Runtime-swappable defaults shared by clones
+    pub(super) config: Arc<ConfigCell>,
+    /// In-flight call tracking for graceful shutdown
+    pub(super) lifecycle: Arc<Lifecycle>,
+    /// Model info by full name, fetched once per client
+    pub(super) models: Arc<Mutex<HashMap<String, Model>>>,
+    /// Typed parsing outcomes by response type
+    pub(super) parse_stats: Arc<Mutex<HashMap<&'static str, ParseStats>>>,
+    /// Token counts of recent requests
+    pub(super) token_counts: Arc<Mutex<TokenCounts>>,
+    /// Whether calls that change server-side state are refused
+    read_only: bool,
     /// Authentication credentials with concurrent access support
     #[cfg(feature = "auth_update")]
     // Enable this if we have auth_update
@@ -169,6 +185,229 @@ impl Client {
         Ok(())
     }
 
+    /// Returns a snapshot of the current configuration
+    ///
+    /// The snapshot doesn't change when the configuration is swapped.
+    pub fn config(&self) -> Arc<ClientConfig> {
+        self.config.load()
+    }
+
+    /// Returns how typed parsing has gone so far, by response type
+    ///
+    /// Counts every response a [`TypedModel`](crate::TypedModel) built on
+    /// this client or its clones tried to parse, so the schemas the model
+    /// struggles with stand out. Types are named by
+    /// [`std::any::type_name`]. Each outcome is also logged as a `tracing`
+    /// event when that feature is on.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::Client;
+    /// # fn f(client: &Client) {
+    /// for (ty, stats) in client.parse_stats() {
+    ///     println!(
+    ///         "{ty}: {} ok, {} failed, {:.0}% repaired",
+    ///         stats.parsed,
+    ///         stats.failed,
+    ///         stats.repair_rate() * 100.0
+    ///     );
+    /// }
+    /// # }
+    /// ```
+    pub fn parse_stats(&self) -> HashMap<&'static str, ParseStats> {
+        self.parse_stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replaces the configuration atomically, returning the previous one
+    ///
+    /// Requests sent afterwards, including from models built earlier, use
+    /// the new configuration. Clones of this client share it.
+    pub fn set_config(&self, config: ClientConfig) -> Arc<ClientConfig> {
+        self.config.store(config)
+    }
+
+    /// Edits a copy of the current configuration and swaps it in atomically
+    ///
+    /// Concurrent updates are applied one after the other, so none is lost.
+    pub fn update_config(&self, f: impl FnOnce(&mut ClientConfig)) {
+        self.config.update(f)
+    }
+
+    /// Returns the full name of the model `name` refers to, following
+    /// [`ClientConfig::aliases`]
+    pub fn resolve_model(&self, name: &str) -> String {
+        let config = self.config.load();
+        full_model_name(config.resolve_model(name)).into_owned()
+    }
+
+    /// Resolves the name of a model being created, warning if it's retired
+    pub(crate) fn model_name(&self, name: &str) -> String {
+        let name = self.resolve_model(name);
+        crate::retired::warn(&self.config.load(), &name);
+        name
+    }
+
+    /// Fills in the `"fast"`, `"smart"` and `"embed"` aliases from the
+    /// models the API lists
+    ///
+    /// Each goes to the newest stable model of its kind (flash, pro and
+    /// embedding). Aliases already in the configuration are kept. Returns
+    /// the aliases that were picked.
+    ///
+    /// # Example
+    /// ```
+    /// # async fn f() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = google_ai_rs::Client::new("your-api-key").await?;
+    /// client.discover_model_aliases().await?;
+    ///
+    /// let model = client.generative_model("fast");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn discover_model_aliases(&self) -> Result<HashMap<String, String>, Error> {
+        let mut models = Vec::new();
+        let mut pages = self.list_models().await;
+        while let Some(model) = pages.next().await? {
+            models.push(model);
+        }
+
+        let picked = crate::config::pick_aliases(&models);
+        self.config.update(|config| {
+            for (alias, model) in &picked {
+                config
+                    .aliases
+                    .entry(alias.clone())
+                    .or_insert_with(|| model.clone());
+            }
+        });
+        Ok(picked)
+    }
+
+    /// Shuts the client down gracefully
+    ///
+    /// New calls through this client or any of its clones fail with
+    /// [`NetError::Shutdown`] from now on. In-flight calls, including open
+    /// response streams, get until `grace` elapses to finish; any still
+    /// running then are cancelled and fail with the same error.
+    ///
+    /// Connections are closed then, for all clones of the client.
+    ///
+    /// Returns the number of calls that had to be cancelled.
+    pub async fn shutdown(&self, grace: Duration) -> usize {
+        let cancelled = self.lifecycle.shutdown(grace).await;
+        self.regions.close();
+        cancelled
+    }
+
+    /// Returns `true` once [`Self::shutdown`] has been called
+    pub fn is_shutdown(&self) -> bool {
+        self.lifecycle.calls.borrow().closing
+    }
+
+    /// Returns a clone that refuses calls changing server-side state
+    ///
+    /// Creating, updating and deleting cached content and tuned models,
+    /// cancelling operations and uploading files fail locally with
+    /// `PERMISSION_DENIED`, the way the API reports missing permissions.
+    /// Generating content, counting tokens and reading resources still work,
+    /// which makes the clone fit for handing to less trusted code such as
+    /// plugins.
+    ///
+    /// Clones of the returned client are read-only too; this client is
+    /// unaffected. Configuration and credentials stay shared with it, so
+    /// don't hand out a client whose [`update_config`](Self::update_config)
+    /// must not be called.
+    pub fn read_only(&self) -> Client {
+        Client {
+            read_only: true,
+            ..self.clone()
+        }
+    }
+
+    /// Returns `true` if the client was made with [`Self::read_only`]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Fails if the client is read-only, naming the refused operation
+    pub(crate) fn check_writable(&self, operation: &str) -> Result<(), Error> {
+        if self.read_only {
+            return Err(status_into_error(tonic::Status::permission_denied(
+                format!("{operation} refused: the client is read-only"),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Runs a call as in-flight, failing if the client is shut down or
+    /// cancelling it at the shutdown deadline
+    #[cfg(feature = "file-upload")]
+    pub(crate) async fn track<F: Future>(&self, call: F) -> Result<F::Output, Error> {
+        self.lifecycle.enter()?.run(call).await
+    }
+
+    /// Tracks a gRPC call sent to the endpoint picked for it, counting its
+    /// outcome toward failover
+    pub(crate) async fn route<T>(
+        &self,
+        call: impl AsyncFnOnce(&Region) -> Result<T, Status>,
+    ) -> Result<Result<T, Status>, Error> {
+        self.route_in(&mut self.lifecycle.enter()?, None, call)
+            .await
+    }
+
+    /// [`Self::route`] for a call about the region-scoped resource
+    /// `resource` if any, sent to the region that created it if the client
+    /// did
+    pub(crate) async fn route_to<T>(
+        &self,
+        resource: Option<&str>,
+        call: impl AsyncFnOnce(&Region) -> Result<T, Status>,
+    ) -> Result<Result<T, Status>, Error> {
+        self.route_in(&mut self.lifecycle.enter()?, resource, call)
+            .await
+    }
+
+    /// [`Self::route`] for a call already registered as `in_flight`, about
+    /// the region-scoped resource `resource` if any
+    pub(crate) async fn route_in<T>(
+        &self,
+        in_flight: &mut InFlight,
+        resource: Option<&str>,
+        call: impl AsyncFnOnce(&Region) -> Result<T, Status>,
+    ) -> Result<Result<T, Status>, Error> {
+        if let Some(origin) = resource.and_then(|name| self.regions.origin(name)) {
+            // No other region has the resource, so the outcome doesn't
+            // count toward failover
+            let region = self.regions.region(origin)?;
+            return in_flight.run(call(&region)).await;
+        }
+        let (index, region) = self.regions.pick()?;
+        let result = in_flight.run(call(&region)).await?;
+        self.regions.record(index, result.as_ref().err());
+        Ok(result)
+    }
+
+    /// [`Self::route`] for a call creating a region-scoped resource,
+    /// remembering the region that created it under the name `name` returns
+    async fn route_creating<T>(
+        &self,
+        call: impl AsyncFnOnce(&Region) -> Result<T, Status>,
+        name: impl FnOnce(&T) -> &str,
+    ) -> Result<Result<T, Status>, Error> {
+        let mut in_flight = self.lifecycle.enter()?;
+        let (index, region) = self.regions.pick()?;
+        let result = in_flight.run(call(&region)).await?;
+        self.regions.record(index, result.as_ref().err());
+        if let Ok(created) = &result {
+            self.regions.pin(name(created), index);
+        }
+        Ok(result)
+    }
+
     /// Creates a new cached content entry
     ///
     /// # Arguments
@@ -180,6 +419,7 @@ impl Client {
         &self,
         content: CachedContent,
     ) -> Result<CachedContent, Error> {
+        self.check_writable("create_cached_content")?;
         if content.name.is_some() {
             return Err(Error::InvalidArgument(
                 "CachedContent name must be empty for creation".into(),
@@ -191,12 +431,13 @@ impl Client {
         }
         .into_request();
 
-        self.cc
-            .clone()
-            .create_cached_content(request)
-            .await
-            .map_err(status_into_error)
-            .map(|r| r.into_inner())
+        self.route_creating(
+            async |region| region.cc.clone().create_cached_content(request).await,
+            |created| created.get_ref().name.as_deref().unwrap_or_default(),
+        )
+        .await?
+        .map_err(status_into_error)
+        .map(|r| r.into_inner())
     }
 
     /// Retrieves the `CachedContent` with the given name.
@@ -206,27 +447,29 @@ impl Client {
         }
         .into_request();
 
-        self.cc
-            .clone()
-            .get_cached_content(request)
-            .await
-            .map_err(status_into_error)
-            .map(|r| r.into_inner())
+        self.route_to(Some(name), async |region| {
+            region.cc.clone().get_cached_content(request).await
+        })
+        .await?
+        .map_err(status_into_error)
+        .map(|r| r.into_inner())
     }
 
     /// Deletes the `CachedContent` with the given name.
     pub async fn delete_cached_content(&self, name: &str) -> Result<(), Error> {
+        self.check_writable("delete_cached_content")?;
         let request = DeleteCachedContentRequest {
             name: name.to_owned(),
         }
         .into_request();
 
-        self.cc
-            .clone()
-            .delete_cached_content(request)
-            .await
-            .map_err(status_into_error)
-            .map(|r| r.into_inner())
+        self.route_to(Some(name), async |region| {
+            region.cc.clone().delete_cached_content(request).await
+        })
+        .await?
+        .map_err(status_into_error)?;
+        self.regions.forget(name);
+        Ok(())
     }
 
     /// Modifies the `CachedContent`.
@@ -235,18 +478,33 @@ impl Client {
     ///
     /// The argument CachedContent must have its name field and fields to update populated.
     pub async fn update_cached_content(&self, cc: &CachedContent) -> Result<CachedContent, Error> {
+        self.check_writable("update_cached_content")?;
+        let name = cc.name.as_deref().unwrap_or_default();
         let request = UpdateCachedContentRequest {
             cached_content: Some(cc.to_owned()),
             update_mask: Some(cc.field_mask()),
         }
         .into_request();
 
-        self.cc
-            .clone()
-            .update_cached_content(request)
-            .await
-            .map_err(status_into_error)
-            .map(|r| r.into_inner())
+        self.route_to(Some(name), async |region| {
+            region.cc.clone().update_cached_content(request).await
+        })
+        .await?
+        .map_err(status_into_error)
+        .map(|r| r.into_inner())
+    }
+
+    /// Extends the life of the `CachedContent` with the given name to `ttl`
+    /// from now.
+    ///
+    /// It returns the modified CachedContent.
+    pub async fn update_ttl(&self, name: &str, ttl: Duration) -> Result<CachedContent, Error> {
+        let cc = CachedContent {
+            name: Some(name.to_owned()),
+            ..Default::default()
+        }
+        .with_ttl(ttl);
+        self.update_cached_content(&cc).await
     }
 
     /// Returns an async iterator over cached content entries
@@ -260,18 +518,59 @@ impl Client {
     /// limits, etc
     pub async fn get_model(&self, name: &str) -> Result<Model, Error> {
         let request = GetModelRequest {
-            name: full_model_name(name).to_string(),
+            name: self.resolve_model(name),
         }
         .into_request();
 
-        self.mc
-            .clone()
-            .get_model(request)
-            .await
+        self.route(async |region| region.mc.clone().get_model(request).await)
+            .await?
             .map_err(status_into_error)
             .map(|r| r.into_inner())
     }
 
+    /// Like [`get_model`](Self::get_model), but fetches each model only once
+    /// per client
+    pub(crate) async fn cached_model(&self, name: &str) -> Result<Model, Error> {
+        let name = self.resolve_model(name);
+        let cached = self
+            .models
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&name)
+            .cloned();
+        if let Some(model) = cached {
+            return Ok(model);
+        }
+
+        let model = self.get_model(&name).await?;
+        self.models
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name, model.clone());
+        Ok(model)
+    }
+
+    /// Gets metadata of a file uploaded with the File API
+    pub async fn get_file(&self, name: &str) -> Result<File, Error> {
+        let request = GetFileRequest {
+            name: name.to_owned(),
+        }
+        .into_request();
+
+        self.route_to(Some(name), async |region| {
+            region.fc.clone().get_file(request).await
+        })
+        .await?
+        .map_err(status_into_error)
+        .map(|r| r.into_inner())
+    }
+
+    /// Returns the header authenticating plain HTTP requests
+    #[cfg(feature = "file-upload")]
+    pub(crate) async fn auth_header(&self) -> (&'static str, String) {
+        self.auth_update.read().await.header().await
+    }
+
     /// Gets information about a specific `TunedModel`.
     pub async fn get_tuned_model(&self, resource_name: &str) -> Result<TunedModel, Error> {
         let request = GetTunedModelRequest {
@@ -279,10 +578,8 @@ impl Client {
         }
         .into_request();
 
-        self.mc
-            .clone()
-            .get_tuned_model(request)
-            .await
+        self.route(async |region| region.mc.clone().get_tuned_model(request).await)
+            .await?
             .map_err(status_into_error)
             .map(|r| r.into_inner())
     }
@@ -303,31 +600,76 @@ impl Client {
 
     /// Updates a tuned model.
     pub async fn update_tuned_model(&self, m: &TunedModel) -> Result<TunedModel, Error> {
+        self.check_writable("update_tuned_model")?;
         let request = UpdateTunedModelRequest {
             tuned_model: Some(m.to_owned()),
             update_mask: Some(m.field_mask()),
         }
         .into_request();
 
-        self.mc
-            .clone()
-            .update_tuned_model(request)
-            .await
+        self.route(async |region| region.mc.clone().update_tuned_model(request).await)
+            .await?
             .map_err(status_into_error)
             .map(|r| r.into_inner())
     }
 
+    /// Starts tuning a model.
+    ///
+    /// Tuning runs in the background; the returned [`Operation`] reports
+    /// progress and resolves to the tuned model once training completes.
+    pub async fn create_tuned_model(
+        &self,
+        tuned_model: TunedModel,
+        tuned_model_id: Option<&str>,
+    ) -> Result<Operation<'_, TunedModel, CreateTunedModelMetadata>, Error> {
+        self.check_writable("create_tuned_model")?;
+        let request = CreateTunedModelRequest {
+            tuned_model_id: tuned_model_id.map(ToOwned::to_owned),
+            tuned_model: Some(tuned_model),
+        }
+        .into_request();
+
+        self.route_creating(
+            async |region| region.mc.clone().create_tuned_model(request).await,
+            |created| &created.get_ref().name,
+        )
+        .await?
+        .map_err(status_into_error)
+        .map(|r| Operation::new(self, r.into_inner()))
+    }
+
+    /// Resumes tracking a long-running operation by name.
+    ///
+    /// `T` and `M` must match the result and metadata types of the call that
+    /// started the operation.
+    pub async fn get_operation<T, M>(&self, name: &str) -> Result<Operation<'_, T, M>, Error>
+    where
+        T: prost::Message + Default,
+        M: prost::Message + Default,
+    {
+        let request = GetOperationRequest {
+            name: name.to_owned(),
+        }
+        .into_request();
+
+        self.route_to(Some(name), async |region| {
+            region.oc.clone().get_operation(request).await
+        })
+        .await?
+        .map_err(status_into_error)
+        .map(|r| Operation::new(self, r.into_inner()))
+    }
+
     /// Deletes the `TunedModel` with the given name.
     pub async fn delete_tuned_model(&self, name: &str) -> Result<(), Error> {
+        self.check_writable("delete_tuned_model")?;
         let request = DeleteTunedModelRequest {
             name: name.to_owned(),
         }
         .into_request();
 
-        self.mc
-            .clone()
-            .delete_tuned_model(request)
-            .await
+        self.route(async |region| region.mc.clone().delete_tuned_model(request).await)
+            .await?
             .map_err(status_into_error)
             .map(|r| r.into_inner())
     }
@@ -335,7 +677,40 @@ impl Client {
 
 #[derive(Debug, Clone)]
 pub struct ClientBuilder {
-    endpoint: Endpoint,
+    /// Settings applied to every endpoint
+    endpoint: EndpointSettings,
+    config: ClientConfig,
+    failover: Option<Failover>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct EndpointSettings {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    concurrency_limit: Option<usize>,
+}
+
+impl EndpointSettings {
+    fn apply(&self, mut endpoint: Endpoint) -> Result<Endpoint, Error> {
+        if let Some(timeout) = self.timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            endpoint = endpoint.connect_timeout(connect_timeout);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            endpoint = endpoint
+                .user_agent(user_agent.clone())
+                .map_err(|e| SetupError::new("User-Agent configuration", e))?;
+        }
+        if let Some(limit) = self.concurrency_limit {
+            endpoint = endpoint.concurrency_limit(limit);
+        }
+        endpoint
+            .tls_config(ClientTlsConfig::new().with_enabled_roots())
+            .map_err(|e| SetupError::new("TLS configuration", e))
+    }
 }
 
 impl Default for ClientBuilder {
@@ -348,34 +723,74 @@ impl ClientBuilder {
     /// Creates new builder with required authentication
     pub fn new() -> Self {
         Self {
-            endpoint: Endpoint::from_static(BASE_API_URL),
+            endpoint: EndpointSettings::default(),
+            config: ClientConfig::default(),
+            failover: None,
         }
     }
 
     /// Sets overall request timeout (default: 120s)
     pub fn timeout(mut self, duration: Duration) -> Self {
-        self.endpoint = self.endpoint.timeout(duration);
+        self.endpoint.timeout = Some(duration);
         self
     }
 
     /// Set connection establishment timeout
     pub fn connect_timeout(mut self, duration: Duration) -> Self {
-        self.endpoint = self.endpoint.connect_timeout(duration);
+        self.endpoint.connect_timeout = Some(duration);
         self
     }
 
     /// Set custom user agent string
     pub fn user_agent(mut self, ua: impl Into<String>) -> Result<Self, Error> {
-        self.endpoint = self
-            .endpoint
-            .user_agent(ua.into())
+        let ua = ua.into();
+        // Rejected now rather than at build
+        Endpoint::from_static(BASE_API_URL)
+            .user_agent(ua.clone())
             .map_err(|e| SetupError::new("User-Agent configuration", e))?;
+        self.endpoint.user_agent = Some(ua);
         Ok(self)
     }
 
     /// Set maximum concurrent requests per connection
     pub fn concurrency_limit(mut self, limit: usize) -> Self {
-        self.endpoint = self.endpoint.concurrency_limit(limit);
+        self.endpoint.concurrency_limit = Some(limit);
+        self
+    }
+
+    /// Sends calls to the first of `failover`'s endpoints instead of the
+    /// public API, moving to the next while one is unavailable
+    ///
+    /// Every endpoint is connected to when the client is built, with the
+    /// other settings of this builder. See [`crate::failover`].
+    pub fn failover(mut self, failover: Failover) -> Self {
+        self.failover = Some(failover);
+        self
+    }
+
+    /// Sets the initial client configuration
+    ///
+    /// See [`Client::set_config`] for changing it later.
+    pub fn config(mut self, config: ClientConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Checks generation requests locally before sending them
+    ///
+    /// Shorthand for [`ClientConfig::strict_validation`]; set it after
+    /// [`config`](Self::config), which replaces the whole configuration.
+    pub fn strict_validation(mut self, enabled: bool) -> Self {
+        self.config.strict_validation = enabled;
+        self
+    }
+
+    /// Checks prompts against the model's input token limit before sending
+    ///
+    /// Shorthand for [`ClientConfig::check_context_window`]; set it after
+    /// [`config`](Self::config), which replaces the whole configuration.
+    pub fn check_context_window(mut self, enabled: bool) -> Self {
+        self.config.check_context_window = enabled;
         self
     }
 
@@ -393,11 +808,6 @@ impl ClientBuilder {
     /// - Returns [`Error::Setup`] for invalid configurations
     /// - Returns [`Error::Net`] for connection failures  
     pub async fn build(self, auth: impl Into<Auth> + Send) -> Result<Client, Error> {
-        let endpoint = self
-            .endpoint
-            .tls_config(ClientTlsConfig::new().with_enabled_roots())
-            .map_err(|e| SetupError::new("TLS configuration", e))?;
-
         // We make sure to parse to avoid 'after init' error
         let auth = auth.into().parsed()?;
 
@@ -406,32 +816,49 @@ impl ClientBuilder {
         let auth = Arc::new(RwLock::new(auth));
         let auth_update = auth.clone();
 
-        // This is done to reduce client size and eliminate calls to add_auth
-        // in library methods.
-        let auth_adder = async move |mut raw_request: RawRequest<Body>| {
-            #[cfg(not(feature = "auth_update"))]
-            let _jwt_fut = auth._into_request(raw_request.headers_mut());
-
-            #[cfg(feature = "auth_update")]
-            let binding = auth.read().await;
-            let _jwt_fut = binding.to_request(raw_request.headers_mut());
-
-            #[cfg(feature = "jwt")]
-            _jwt_fut.await;
-
-            raw_request
+        let uris = match &self.failover {
+            Some(failover) if !failover.endpoints.is_empty() => failover.endpoints.clone(),
+            _ => vec![BASE_API_URL.to_owned()],
         };
+        let mut regions = Vec::with_capacity(uris.len());
+        for uri in uris {
+            let endpoint = Endpoint::from_shared(uri.clone())
+                .map_err(|e| SetupError::new(format!("Endpoint {uri}"), e))?;
+            let endpoint = self.endpoint.apply(endpoint)?;
+
+            // This is done to reduce client size and eliminate calls to add_auth
+            // in library methods.
+            let auth = auth.clone();
+            let auth_adder = async move |mut raw_request: RawRequest<Body>| {
+                #[cfg(not(feature = "auth_update"))]
+                let _jwt_fut = auth._into_request(raw_request.headers_mut());
+
+                #[cfg(feature = "auth_update")]
+                let binding = auth.read().await;
+                let _jwt_fut = binding.to_request(raw_request.headers_mut());
+
+                #[cfg(feature = "jwt")]
+                _jwt_fut.await;
+
+                raw_request
+            };
 
-        let channel = unsafe { endpoint.connect_with_modifier_fn(auth_adder) };
+            let channel = unsafe { endpoint.connect_with_modifier_fn(auth_adder) };
 
-        let channel = channel.await.map_err(|e| {
-            Error::Net(NetError::TransportFailure(TonicTransportError(Box::new(e))))
-        })?;
+            let channel = channel.await.map_err(|e| {
+                Error::Net(NetError::TransportFailure(TonicTransportError(Box::new(e))))
+            })?;
+            regions.push(Region::new(uri, channel));
+        }
 
         let client = Client {
-            gc: GenerativeServiceClient::new(channel.clone()),
-            cc: CacheServiceClient::new(channel.clone()),
-            mc: ModelServiceClient::new(channel),
+            regions: Arc::new(Regions::new(regions, self.failover)),
+            config: Arc::new(ConfigCell::new(self.config)),
+            lifecycle: Arc::new(Lifecycle::new()),
+            models: Arc::default(),
+            parse_stats: Arc::default(),
+            token_counts: Arc::default(),
+            read_only: false,
             #[cfg(feature = "auth_update")]
             auth_update,
         };
@@ -440,6 +867,104 @@ impl ClientBuilder {
     }
 }
 
+#[derive(Debug, Default)]
+struct Calls {
+    closing: bool,
+    in_flight: usize,
+}
+
+/// Tracks the calls of a client and its clones
+#[derive(Debug)]
+pub(crate) struct Lifecycle {
+    calls: watch::Sender<Calls>,
+    cancel: watch::Sender<bool>,
+}
+
+impl Lifecycle {
+    fn new() -> Self {
+        Self {
+            calls: watch::Sender::new(Calls::default()),
+            cancel: watch::Sender::new(false),
+        }
+    }
+
+    /// Registers a new in-flight call
+    pub(crate) fn enter(self: &Arc<Self>) -> Result<InFlight, Error> {
+        // Checked and counted under one lock so shutdown can't miss it
+        let entered = self.calls.send_if_modified(|calls| {
+            if calls.closing {
+                return false;
+            }
+            calls.in_flight += 1;
+            true
+        });
+
+        if !entered {
+            return Err(Error::Net(NetError::Shutdown));
+        }
+
+        Ok(InFlight {
+            lifecycle: self.clone(),
+            cancelled: self.cancel.subscribe(),
+            deadline: deadline::current(),
+        })
+    }
+
+    async fn shutdown(&self, grace: Duration) -> usize {
+        self.calls.send_modify(|calls| calls.closing = true);
+
+        let mut calls = self.calls.subscribe();
+        let drained = tokio::time::timeout(grace, calls.wait_for(|c| c.in_flight == 0)).await;
+        if drained.is_ok() {
+            return 0;
+        }
+
+        let remaining = self.calls.borrow().in_flight;
+        self.cancel.send_replace(true);
+        remaining
+    }
+}
+
+/// Guard for an in-flight call
+#[derive(Debug)]
+pub(crate) struct InFlight {
+    lifecycle: Arc<Lifecycle>,
+    cancelled: watch::Receiver<bool>,
+    /// Deadline in scope when the call started
+    deadline: Option<Instant>,
+}
+
+impl InFlight {
+    /// Runs `call` unless the shutdown or call deadline passes first
+    pub(crate) async fn run<F: Future>(&mut self, call: F) -> Result<F::Output, Error> {
+        let deadline = self.deadline;
+        let deadline = async move {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            // Don't start calls that are already too late
+            biased;
+            _ = deadline => Err(Error::Net(NetError::DeadlineExceeded)),
+            _ = self.cancelled.wait_for(|cancelled| *cancelled) => {
+                Err(Error::Net(NetError::Shutdown))
+            }
+            output = call => Ok(output),
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.lifecycle
+            .calls
+            .send_modify(|calls| calls.in_flight -= 1);
+    }
+}
+
 // I don't know what to name it but think CowClient
 #[derive(Clone, Debug)]
 pub(crate) enum CClient<'a> {
@@ -454,6 +979,16 @@ impl CClient<'_> {
             CClient::Borrowed(client) => CClient::Borrowed(client),
         }
     }
+
+    /// Returns a handle that outlives the borrow
+    ///
+    /// Borrowed clients are cloned, which shares their config and lifecycle.
+    pub(crate) fn to_shared(&self) -> SharedClient {
+        match self {
+            CClient::Shared(shared_client) => shared_client.clone(),
+            CClient::Borrowed(client) => Client::clone(client).into(),
+        }
+    }
 }
 
 #[allow(clippy::from_over_into)]
@@ -571,10 +1106,8 @@ impl Page for CachedContentPager {
         .into_request();
 
         let response = client
-            .cc
-            .clone()
-            .list_cached_contents(request)
-            .await
+            .route(async |region| region.cc.clone().list_cached_contents(request).await)
+            .await?
             .map_err(status_into_error)?
             .into_inner();
         Ok((response.cached_contents, response.next_page_token))
@@ -598,10 +1131,8 @@ impl Page for ModelsListPager {
         .into_request();
 
         let response = client
-            .mc
-            .clone()
-            .list_models(request)
-            .await
+            .route(async |region| region.mc.clone().list_models(request).await)
+            .await?
             .map_err(status_into_error)?
             .into_inner();
         Ok((response.models, response.next_page_token))
@@ -626,12 +1157,215 @@ impl Page for TunedModelsListPager {
         .into_request();
 
         let response = client
-            .mc
-            .clone()
-            .list_tuned_models(request)
-            .await
+            .route(async |region| region.mc.clone().list_tuned_models(request).await)
+            .await?
             .map_err(status_into_error)?
             .into_inner();
         Ok((response.tuned_models, response.next_page_token))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use prost::Message;
+
+    use super::*;
+    use crate::{
+        error::{ServiceError, TonicStatus},
+        fake::FakeServer,
+        proto::{longrunning::Operation as RawOperation, GenerateContentResponse},
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_drains_then_cancels() {
+        let lifecycle = Arc::new(Lifecycle::new());
+
+        // Finishes within the grace period
+        let mut quick = lifecycle.enter().unwrap();
+        let quick = tokio::spawn(async move {
+            quick
+                .run(tokio::time::sleep(Duration::from_millis(50)))
+                .await
+        });
+
+        // Outlives it
+        let mut slow = lifecycle.enter().unwrap();
+        let slow =
+            tokio::spawn(
+                async move { slow.run(tokio::time::sleep(Duration::from_secs(60))).await },
+            );
+
+        let start = Instant::now();
+        let cancelled = lifecycle.shutdown(Duration::from_millis(200)).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+        assert_eq!(cancelled, 1);
+        assert!(matches!(
+            lifecycle.enter(),
+            Err(Error::Net(NetError::Shutdown))
+        ));
+
+        assert!(quick.await.unwrap().is_ok());
+        assert!(matches!(
+            slow.await.unwrap(),
+            Err(Error::Net(NetError::Shutdown))
+        ));
+        assert_eq!(lifecycle.calls.borrow().in_flight, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deadline_abandons_call() {
+        let lifecycle = Arc::new(Lifecycle::new());
+
+        let start = Instant::now();
+        let result = deadline::scope_timeout(Duration::from_millis(50), async {
+            lifecycle
+                .enter()
+                .unwrap()
+                .run(tokio::time::sleep(Duration::from_secs(60)))
+                .await
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::Net(NetError::DeadlineExceeded))
+        ));
+        assert_eq!(start.elapsed(), Duration::from_millis(50));
+
+        // Guards keep the deadline they started with
+        let mut in_flight = deadline::scope_timeout(Duration::from_millis(50), async {
+            lifecycle.enter().unwrap()
+        })
+        .await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            in_flight.run(async {}).await,
+            Err(Error::Net(NetError::DeadlineExceeded))
+        ));
+    }
+
+    #[tokio::test]
+    async fn shutdown_when_idle() {
+        let lifecycle = Arc::new(Lifecycle::new());
+        drop(lifecycle.enter().unwrap());
+        assert_eq!(lifecycle.shutdown(Duration::from_secs(60)).await, 0);
+    }
+
+    fn assert_denied<T: fmt::Debug>(result: Result<T, Error>) {
+        match result {
+            Err(Error::Service(ServiceError::ApiError(TonicStatus(status)))) => {
+                assert_eq!(status.code(), tonic::Code::PermissionDenied);
+            }
+            other => panic!("expected PERMISSION_DENIED, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_closes_connections() {
+        let server =
+            FakeServer::start(|_, _| Ok(GenerateContentResponse::default().encode_to_vec())).await;
+        let client = server.client().await;
+        let clone = client.clone();
+        let model = clone.generative_model("gemini-2.0-flash");
+        model.generate_content("hi").await.unwrap();
+        assert_eq!(server.connections(), 1);
+
+        assert_eq!(client.shutdown(Duration::from_secs(1)).await, 0);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while server.connections() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("connection still open");
+        assert!(matches!(
+            model.generate_content("hi").await,
+            Err(Error::Net(NetError::Shutdown))
+        ));
+    }
+
+    #[tokio::test]
+    async fn pins_cached_contents_to_their_region() {
+        let primary = FakeServer::start(|path, _| match path {
+            "/google.ai.generativelanguage.v1beta.CacheService/CreateCachedContent"
+            | "/google.ai.generativelanguage.v1beta.CacheService/GetCachedContent" => {
+                Ok(CachedContent {
+                    name: Some("cachedContents/c".into()),
+                    ..Default::default()
+                }
+                .encode_to_vec())
+            }
+            _ => Err(Status::unavailable("down")),
+        })
+        .await;
+        let secondary =
+            FakeServer::start(|_, _| Ok(GenerateContentResponse::default().encode_to_vec())).await;
+        let client = Client::builder()
+            .failover(Failover::new([primary.uri(), secondary.uri()]).unavailable_after(1))
+            .build(Auth::new("fake"))
+            .await
+            .unwrap();
+
+        let cache = client
+            .create_cached_content(CachedContent::default())
+            .await
+            .unwrap();
+        let model = client.generative_model("gemini-2.0-flash");
+        assert!(model.generate_content("hi").await.is_err());
+        assert_eq!(primary.calls(), 2);
+
+        // Calls moved on, but the cache only exists where it was created
+        model.generate_content("hi").await.unwrap();
+        client.get_cached_content("cachedContents/c").await.unwrap();
+        let cached = model.with_cached_content(&cache).unwrap();
+        assert!(cached.generate_content("hi").await.is_err());
+        assert_eq!((primary.calls(), secondary.calls()), (4, 1));
+    }
+
+    #[tokio::test]
+    async fn read_only_refuses_writes_locally() {
+        let server = FakeServer::start(|path, _| {
+            assert_eq!(path, "/google.longrunning.Operations/GetOperation");
+            Ok(RawOperation {
+                name: "operations/tune".into(),
+                ..Default::default()
+            }
+            .encode_to_vec())
+        })
+        .await;
+        let client = server.client().await;
+        let read_only = client.read_only();
+        assert!(read_only.is_read_only());
+        assert!(!client.is_read_only());
+
+        let cache = CachedContent {
+            name: Some("cachedContents/c".into()),
+            ..Default::default()
+        };
+        assert_denied(
+            read_only
+                .create_cached_content(CachedContent::default())
+                .await,
+        );
+        assert_denied(read_only.update_cached_content(&cache).await);
+        assert_denied(read_only.delete_cached_content("cachedContents/c").await);
+
+        let model = TunedModel::default();
+        assert_denied(read_only.create_tuned_model(model.clone(), None).await);
+        assert_denied(read_only.update_tuned_model(&model).await);
+        assert_denied(read_only.delete_tuned_model("tunedModels/t").await);
+
+        #[cfg(feature = "file-upload")]
+        assert_denied(read_only.upload_file("text/plain", "hi").send().await);
+
+        // Reads still reach the server
+        let op = read_only
+            .get_operation::<TunedModel, ()>("operations/tune")
+            .await
+            .unwrap();
+        assert_eq!(server.calls(), 1);
+        assert_denied(op.cancel().await);
+        assert_eq!(server.calls(), 1);
+    }
+}
@@ -0,0 +1,835 @@
+//! Client-wide configuration that can be swapped at runtime
+//!
+//! A [`ClientConfig`] holds defaults every model built from a client falls
+//! back to. The client keeps it behind a snapshot that requests read when
+//! they are sent, so [`Client::set_config`] and [`Client::update_config`]
+//! take effect for existing models without rebuilding them.
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::{Client, ClientConfig, Profile, RateLimit};
+//! use std::time::Duration;
+//!
+//! # async fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = Client::new("your-api-key").await?;
+//! let model = client.generative_model("gemini-pro").with_profile("support");
+//!
+//! // e.g. from a config watcher
+//! client.update_config(|config| {
+//!     config.rate_limit = Some(RateLimit::new(60, Duration::from_secs(60)));
+//!     config.profiles.insert(
+//!         "support".into(),
+//!         Profile::new().system_instruction("You are a patient support agent"),
+//!     );
+//! });
+//!
+//! // Picks up the new profile and rate limit
+//! model.generate_content("My order is late").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Client::set_config`]: crate::Client::set_config
+//! [`Client::update_config`]: crate::Client::update_config
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tonic::Code;
+
+use crate::content::IntoContent;
+use crate::deadline;
+use crate::error::{Error, NetError, ServiceError};
+use crate::proto::{Content, GenerationConfig, Model, SafetySetting};
+
+/// Defaults shared by every model of a client
+///
+/// Settings on a model take precedence over its profile, which takes
+/// precedence over the defaults here.
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfig {
+    /// Safety settings for models that don't set their own
+    pub safety_settings: Option<Vec<SafetySetting>>,
+    /// Generation parameters for models that don't set their own, each
+    /// field apart
+    pub generation_config: Option<GenerationConfig>,
+    /// Named presets models opt into with
+    /// [`GenerativeModel::with_profile`](crate::GenerativeModel::with_profile)
+    pub profiles: HashMap<String, Profile>,
+    /// Limit on generation, token counting and embedding requests sent
+    /// through the client
+    pub rate_limit: Option<RateLimit>,
+    /// Fails those requests fast while the upstream keeps failing
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Model names by alias, e.g. `"fast"` to `"gemini-2.0-flash"`
+    ///
+    /// Aliases are resolved when a model is created, so swapping them
+    /// doesn't affect existing models. See
+    /// [`Client::discover_model_aliases`](crate::Client::discover_model_aliases)
+    /// for filling them in from the available models.
+    pub aliases: HashMap<String, String>,
+    /// What happens when a model Google has retired is used
+    pub retired_models: RetiredModels,
+    /// Checks generation requests against the server's known rules before
+    /// sending them
+    ///
+    /// Catches out-of-range sampling parameters, a response schema without
+    /// a JSON mime type and turns that don't alternate between user and
+    /// model, failing with [`Error::InvalidArgument`].
+    pub strict_validation: bool,
+    /// Counts a prompt's tokens before generating and compares them with
+    /// the model's input token limit
+    ///
+    /// An oversized prompt fails with [`Error::ContextOverflow`] naming its
+    /// largest parts, instead of a generic rejection from the server. Costs
+    /// a token counting call per request, plus one model lookup per model.
+    pub check_context_window: bool,
+    /// Faults injected into calls, for testing failure handling
+    #[cfg(feature = "chaos")]
+    pub chaos: Option<crate::chaos::Chaos>,
+}
+
+impl ClientConfig {
+    /// Creates an empty configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the default safety settings
+    pub fn safety_settings<I>(mut self, safety_settings: I) -> Self
+    where
+        I: IntoIterator<Item = SafetySetting>,
+    {
+        self.safety_settings = Some(safety_settings.into_iter().collect());
+        self
+    }
+
+    /// Sets the default generation parameters
+    pub fn generation_config(mut self, generation_config: impl Into<GenerationConfig>) -> Self {
+        self.generation_config = Some(generation_config.into());
+        self
+    }
+
+    /// Adds or replaces a named profile
+    pub fn profile(mut self, name: impl Into<String>, profile: Profile) -> Self {
+        self.profiles.insert(name.into(), profile);
+        self
+    }
+
+    /// Sets the request rate limit
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Enables the circuit breaker
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Adds or replaces a model alias
+    pub fn alias(mut self, alias: impl Into<String>, model: impl Into<String>) -> Self {
+        self.aliases.insert(alias.into(), model.into());
+        self
+    }
+
+    /// Sets what happens when a retired model is used
+    pub fn retired_models(mut self, retired_models: RetiredModels) -> Self {
+        self.retired_models = retired_models;
+        self
+    }
+
+    /// Enables or disables strict request validation
+    pub fn strict_validation(mut self, strict_validation: bool) -> Self {
+        self.strict_validation = strict_validation;
+        self
+    }
+
+    /// Enables or disables the context window check
+    pub fn check_context_window(mut self, check_context_window: bool) -> Self {
+        self.check_context_window = check_context_window;
+        self
+    }
+
+    /// Injects faults into calls, for testing failure handling
+    #[cfg(feature = "chaos")]
+    pub fn chaos(mut self, chaos: crate::chaos::Chaos) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Returns the model an alias stands for, or `name` if it isn't one
+    pub fn resolve_model<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map_or(name, String::as_str)
+    }
+}
+
+/// Picks a model for each well-known alias from a model listing
+///
+/// `"fast"` and `"smart"` go to the newest stable flash and pro models,
+/// `"embed"` to the newest stable embedding model. Aliases without a match
+/// are left out.
+pub(crate) fn pick_aliases(models: &[Model]) -> HashMap<String, String> {
+    const UNSTABLE: [&str; 9] = [
+        "exp", "preview", "latest", "lite", "thinking", "tts", "live", "image", "audio",
+    ];
+
+    fn supports(model: &Model, method: &str) -> bool {
+        model
+            .supported_generation_methods
+            .iter()
+            .any(|m| m == method)
+    }
+
+    // Compares version numbers in the name, e.g. gemini-1.5-pro-002 < gemini-2.0-pro
+    fn version(name: &str) -> Vec<u32> {
+        name.split(|c: char| !c.is_ascii_digit())
+            .filter_map(|n| n.parse().ok())
+            .collect()
+    }
+
+    let stable = models.iter().filter(|model| {
+        let id = model.name.strip_prefix("models/").unwrap_or(&model.name);
+        !UNSTABLE.iter().any(|word| id.contains(word))
+    });
+
+    let mut aliases = HashMap::new();
+    let candidates: [(&str, &str, &str); 3] = [
+        ("fast", "generateContent", "flash"),
+        ("smart", "generateContent", "pro"),
+        ("embed", "embedContent", "embedding"),
+    ];
+    for (alias, method, family) in candidates {
+        let newest = stable
+            .clone()
+            .filter(|model| supports(model, method) && model.name.contains(family))
+            .max_by(|a, b| {
+                version(&a.name)
+                    .cmp(&version(&b.name))
+                    .then_with(|| b.name.cmp(&a.name))
+            });
+        if let Some(model) = newest {
+            aliases.insert(alias.to_owned(), model.name.clone());
+        }
+    }
+    aliases
+}
+
+/// Handling of models Google has retired
+///
+/// Requests to a retired model fail with `NOT_FOUND` either way; this
+/// decides whether we say why first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RetiredModels {
+    /// Logs a `tracing` warning naming the replacement when a model is
+    /// created (with the `tracing` feature)
+    #[default]
+    Warn,
+    /// Fails requests with [`Error::InvalidArgument`] naming the replacement
+    Error,
+    /// Says nothing
+    Allow,
+}
+
+/// A named preset of model settings
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    /// System prompt for models that don't set their own
+    pub system_instruction: Option<Content>,
+    /// Safety settings for models that don't set their own
+    pub safety_settings: Option<Vec<SafetySetting>>,
+    /// Generation parameters for models that don't set their own, each
+    /// field apart
+    pub generation_config: Option<GenerationConfig>,
+    /// Models requests are split between, for models that don't set their
+    /// own
+    pub split: Option<Split>,
+}
+
+impl Profile {
+    /// Creates an empty profile
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the system instruction
+    pub fn system_instruction<I: IntoContent>(mut self, instruction: I) -> Self {
+        self.system_instruction = Some(instruction.into_content());
+        self
+    }
+
+    /// Sets the safety settings
+    pub fn safety_settings<I>(mut self, safety_settings: I) -> Self
+    where
+        I: IntoIterator<Item = SafetySetting>,
+    {
+        self.safety_settings = Some(safety_settings.into_iter().collect());
+        self
+    }
+
+    /// Sets the generation parameters
+    pub fn generation_config(mut self, generation_config: impl Into<GenerationConfig>) -> Self {
+        self.generation_config = Some(generation_config.into());
+        self
+    }
+
+    /// Splits requests between models by weight
+    ///
+    /// See [`Split`].
+    pub fn split<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = (S, u32)>,
+        S: Into<String>,
+    {
+        self.split = Some(Split::new(models));
+        self
+    }
+}
+
+/// Requests routed between models by weight, e.g. for a gradual rollout
+///
+/// Each request goes to one of the models, with a chance proportional to
+/// its weight. Requests with the same
+/// [conversation ID](crate::GenerativeModel::with_conversation_id) go to
+/// the same model, and keep doing so as weight shifts toward later models
+/// if the total stays the same, e.g. 100. Requests without one are spread at a fixed
+/// pattern, and clones share it.
+///
+/// Set a split on a model, or on a [`Profile`] to change weights through
+/// [`Client::update_config`](crate::Client::update_config) without
+/// rebuilding models. Names follow [`ClientConfig::aliases`].
+///
+/// # Example
+/// ```
+/// # use google_ai_rs::Client;
+/// # fn f(client: &Client) {
+/// let model = client
+///     .generative_model("gemini-2.0-flash")
+///     .split([("gemini-2.0-flash", 90), ("gemini-2.5-flash", 10)])
+///     .with_conversation_id("user-42");
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Split {
+    /// Models and their weights
+    pub models: Vec<(String, u32)>,
+    requests: Arc<AtomicU64>,
+}
+
+impl Split {
+    /// Splits between `models`, weighted by the number next to each
+    pub fn new<I, S>(models: I) -> Self
+    where
+        I: IntoIterator<Item = (S, u32)>,
+        S: Into<String>,
+    {
+        Self {
+            models: models
+                .into_iter()
+                .map(|(name, weight)| (name.into(), weight))
+                .collect(),
+            requests: Arc::default(),
+        }
+    }
+
+    /// Picks the model for a request, sticking to one per conversation
+    ///
+    /// Returns `None` if every weight is zero.
+    pub fn pick(&self, conversation_id: Option<&str>) -> Option<&str> {
+        let total: u64 = self
+            .models
+            .iter()
+            .map(|(_, weight)| u64::from(*weight))
+            .sum();
+        if total == 0 {
+            return None;
+        }
+        let hash = match conversation_id {
+            Some(id) => fnv1a(id.as_bytes()),
+            None => fnv1a(&self.requests.fetch_add(1, Ordering::Relaxed).to_le_bytes()),
+        };
+
+        // Weights are laid out in order, so a growing later model only
+        // takes points from the ones before it
+        let mut point = hash % total;
+        self.models.iter().find_map(|(name, weight)| {
+            let weight = u64::from(*weight);
+            if point < weight {
+                return Some(name.as_str());
+            }
+            point -= weight;
+            None
+        })
+    }
+}
+
+/// The same on every platform and release, unlike std's hasher, so
+/// conversations stick across processes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// At most `requests` requests in any window of `per`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Creates a limit of `requests` per `per`
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self { requests, per }
+    }
+}
+
+/// Stops sending requests to an upstream that keeps failing
+///
+/// The circuit opens once at least `min_calls` requests completed within
+/// `window` and `failure_rate` of them failed. While open, requests fail
+/// right away with [`NetError::CircuitOpen`]. After `open_for`, a single
+/// trial request is let through (half-open): the circuit closes if it
+/// succeeds and stays open for another `open_for` if it fails.
+///
+/// Only transport failures and `UNAVAILABLE`, `INTERNAL`, `UNKNOWN`,
+/// `DEADLINE_EXCEEDED` and `RESOURCE_EXHAUSTED` statuses count as failures.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CircuitBreaker {
+    /// Share of failed requests, in `(0.0, 1.0]`, that opens the circuit
+    pub failure_rate: f64,
+    /// Requests needed within `window` before the circuit may open
+    pub min_calls: u32,
+    /// How far back outcomes are counted
+    pub window: Duration,
+    /// How long the circuit stays open before a trial request
+    pub open_for: Duration,
+    /// Which requests share a circuit
+    pub scope: BreakerScope,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.5,
+            min_calls: 10,
+            window: Duration::from_secs(60),
+            open_for: Duration::from_secs(30),
+            scope: BreakerScope::PerModel,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Creates a breaker opening at 50% failures over at least 10 requests
+    /// in a minute, for 30 seconds, per model
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the share of failed requests that opens the circuit
+    pub fn failure_rate(mut self, failure_rate: f64) -> Self {
+        self.failure_rate = failure_rate;
+        self
+    }
+
+    /// Sets the requests needed within the window before the circuit may open
+    pub fn min_calls(mut self, min_calls: u32) -> Self {
+        self.min_calls = min_calls;
+        self
+    }
+
+    /// Sets how far back outcomes are counted
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets how long the circuit stays open before a trial request
+    pub fn open_for(mut self, open_for: Duration) -> Self {
+        self.open_for = open_for;
+        self
+    }
+
+    /// Sets which requests share a circuit
+    pub fn scope(mut self, scope: BreakerScope) -> Self {
+        self.scope = scope;
+        self
+    }
+}
+
+/// Which requests share a [`CircuitBreaker`] circuit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BreakerScope {
+    /// One circuit per model
+    #[default]
+    PerModel,
+    /// One circuit for the whole client
+    Client,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    /// Completion times of recent requests and whether they failed
+    outcomes: VecDeque<(Instant, bool)>,
+    /// Open or half-open; the next request after `open_until` is the trial
+    tripped: bool,
+    open_until: Option<Instant>,
+}
+
+impl Circuit {
+    fn admit(&mut self, breaker: &CircuitBreaker, now: Instant) -> bool {
+        if !self.tripped {
+            return true;
+        }
+
+        match self.open_until {
+            Some(until) if now < until => false,
+            // Hold everyone else off while the trial runs
+            _ => {
+                self.open_until = Some(now + breaker.open_for);
+                true
+            }
+        }
+    }
+
+    fn record(&mut self, breaker: &CircuitBreaker, now: Instant, failed: bool) {
+        if self.tripped {
+            if failed {
+                self.open_until = Some(now + breaker.open_for);
+            } else {
+                *self = Circuit::default();
+            }
+            return;
+        }
+
+        self.outcomes.push_back((now, failed));
+        while self
+            .outcomes
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) >= breaker.window)
+        {
+            self.outcomes.pop_front();
+        }
+
+        let calls = self.outcomes.len();
+        let failures = self.outcomes.iter().filter(|(_, failed)| *failed).count();
+        if calls >= breaker.min_calls as usize
+            && failures as f64 >= breaker.failure_rate * calls as f64
+        {
+            self.outcomes.clear();
+            self.tripped = true;
+            self.open_until = Some(now + breaker.open_for);
+        }
+    }
+}
+
+/// Whether an error points at the upstream rather than the request
+///
+/// Running out of time doesn't, whether our deadline passed or the server
+/// gave up on the request: both mean the call needed more time than it had.
+pub(crate) fn is_upstream_failure(err: &Error) -> bool {
+    match err {
+        Error::Net(NetError::Shutdown | NetError::CircuitOpen(_) | NetError::DeadlineExceeded) => {
+            false
+        }
+        Error::Net(_) => true,
+        Error::Service(ServiceError::ApiError(status)) => matches!(
+            status.0.code(),
+            Code::Unavailable | Code::Internal | Code::Unknown | Code::ResourceExhausted
+        ),
+        _ => false,
+    }
+}
+
+/// The swappable configuration shared by clones of a client
+#[derive(Debug)]
+pub(crate) struct ConfigCell {
+    config: RwLock<Arc<ClientConfig>>,
+    /// Send times of requests in the current rate limit window
+    sent: Mutex<VecDeque<Instant>>,
+    /// Circuit breaker state by scope
+    circuits: std::sync::Mutex<HashMap<String, Circuit>>,
+}
+
+impl ConfigCell {
+    pub(crate) fn new(config: ClientConfig) -> Self {
+        Self {
+            config: RwLock::new(Arc::new(config)),
+            sent: Mutex::default(),
+            circuits: std::sync::Mutex::default(),
+        }
+    }
+
+    pub(crate) fn load(&self) -> Arc<ClientConfig> {
+        // A panic elsewhere can't leave a half-written Arc behind
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub(crate) fn store(&self, config: ClientConfig) -> Arc<ClientConfig> {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(&mut *current, Arc::new(config))
+    }
+
+    pub(crate) fn update(&self, f: impl FnOnce(&mut ClientConfig)) {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        let mut config = ClientConfig::clone(&current);
+        f(&mut config);
+        *current = Arc::new(config);
+    }
+
+    /// Sends a request to `model` through the circuit breaker and rate limit
+    pub(crate) async fn call<T, F>(&self, model: &str, call: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        let config = self.load();
+        crate::retired::check(&config, model)?;
+        let breaker = config.circuit_breaker;
+        let key = match breaker.map(|b| b.scope) {
+            Some(BreakerScope::PerModel) => model,
+            _ => "",
+        };
+
+        if let Some(breaker) = &breaker {
+            let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+            if !circuits
+                .entry(key.to_owned())
+                .or_default()
+                .admit(breaker, Instant::now())
+            {
+                return Err(Error::Net(NetError::CircuitOpen(key.to_owned())));
+            }
+        } else {
+            // Start over if the breaker comes back
+            self.circuits
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+        }
+
+        match deadline::current() {
+            Some(deadline) => tokio::time::timeout_at(deadline, self.throttle())
+                .await
+                .map_err(|_| Error::Net(NetError::DeadlineExceeded))?,
+            None => self.throttle().await,
+        }
+        // Injected failures count against the breaker like real ones. Not
+        // wrapped around `call`, which would double its future's size
+        #[cfg(feature = "chaos")]
+        let injected = match &config.chaos {
+            Some(chaos) => chaos.before_call().await.err(),
+            None => None,
+        };
+        #[cfg(not(feature = "chaos"))]
+        let injected = None;
+        let result = match injected {
+            Some(err) => Err(err),
+            None => call.await,
+        };
+
+        if let Some(breaker) = &breaker {
+            // Other errors still mean the upstream answered
+            let failed = result.as_ref().err().is_some_and(is_upstream_failure);
+            self.record(breaker, key, failed);
+        }
+        result
+    }
+
+    fn record(&self, breaker: &CircuitBreaker, key: &str, failed: bool) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        circuits
+            .entry(key.to_owned())
+            .or_default()
+            .record(breaker, Instant::now(), failed);
+    }
+
+    /// Waits until the current rate limit allows another request
+    async fn throttle(&self) {
+        let mut sent = self.sent.lock().await;
+        loop {
+            // Read on every pass so a new limit applies to waiting requests
+            let Some(RateLimit { requests, per }) = self.load().rate_limit else {
+                sent.clear();
+                return;
+            };
+
+            let now = Instant::now();
+            while sent.front().is_some_and(|t| now.duration_since(*t) >= per) {
+                sent.pop_front();
+            }
+
+            if sent.len() < requests as usize {
+                sent.push_back(now);
+                return;
+            }
+
+            match sent.front() {
+                Some(oldest) => tokio::time::sleep_until(*oldest + per).await,
+                // A zero limit never frees up; recheck for a new one
+                None => tokio::time::sleep(per.max(Duration::from_millis(100))).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_sticks_to_conversations() {
+        let split = Split::new([("a", 90), ("b", 10)]);
+        let picked = (0..1000).filter(|_| split.pick(None) == Some("b")).count();
+        assert!((50..150).contains(&picked), "{picked}");
+
+        // Conversations stay put, and only move to a growing model
+        let ids: Vec<_> = (0..200).map(|i| format!("user-{i}")).collect();
+        let wider = Split::new([("a", 50), ("b", 50)]);
+        for id in &ids {
+            let model = split.pick(Some(id));
+            assert_eq!(split.pick(Some(id)), model);
+            if model == Some("b") {
+                assert_eq!(wider.pick(Some(id)), Some("b"));
+            }
+        }
+
+        assert_eq!(Split::new([("a", 0)]).pick(None), None);
+        assert_eq!(Split::default().pick(Some("user-1")), None);
+    }
+
+    #[test]
+    fn update_swaps_snapshot() {
+        let cell = ConfigCell::new(ClientConfig::new().profile("a", Profile::new()));
+        let before = cell.load();
+
+        cell.update(|config| {
+            config.profiles.remove("a");
+            config.rate_limit = Some(RateLimit::new(1, Duration::from_secs(1)));
+        });
+
+        // Old snapshots are unaffected
+        assert!(before.profiles.contains_key("a"));
+        assert!(before.rate_limit.is_none());
+
+        let after = cell.load();
+        assert!(after.profiles.is_empty());
+        assert_eq!(
+            after.rate_limit,
+            Some(RateLimit::new(1, Duration::from_secs(1)))
+        );
+
+        let previous = cell.store(ClientConfig::new());
+        assert!(Arc::ptr_eq(&previous, &after));
+    }
+
+    #[test]
+    fn aliases_pick_newest_stable() {
+        let model = |name: &str, method: &str| Model {
+            name: format!("models/{name}"),
+            supported_generation_methods: vec![method.to_owned()],
+            ..Default::default()
+        };
+        let models = [
+            model("gemini-1.5-flash-002", "generateContent"),
+            model("gemini-2.0-flash", "generateContent"),
+            model("gemini-2.0-flash-lite", "generateContent"),
+            model("gemini-2.5-flash-preview-05-20", "generateContent"),
+            model("gemini-1.5-pro", "generateContent"),
+            model("gemini-1.5-pro-002", "generateContent"),
+            model("embedding-001", "embedContent"),
+            model("text-embedding-004", "embedContent"),
+        ];
+
+        let aliases = pick_aliases(&models);
+        assert_eq!(aliases["fast"], "models/gemini-2.0-flash");
+        assert_eq!(aliases["smart"], "models/gemini-1.5-pro-002");
+        assert_eq!(aliases["embed"], "models/text-embedding-004");
+
+        let config = ClientConfig::new().alias("fast", "gemini-2.0-flash");
+        assert_eq!(config.resolve_model("fast"), "gemini-2.0-flash");
+        assert_eq!(config.resolve_model("gemini-pro"), "gemini-pro");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn throttle_waits_for_window() {
+        let window = Duration::from_secs(60);
+        let cell = ConfigCell::new(ClientConfig::new().rate_limit(RateLimit::new(2, window)));
+
+        let start = Instant::now();
+        cell.throttle().await;
+        cell.throttle().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        cell.throttle().await;
+        assert_eq!(start.elapsed(), window);
+
+        // Lifting the limit lets requests through immediately
+        cell.update(|config| config.rate_limit = None);
+        let lifted = Instant::now();
+        cell.throttle().await;
+        assert_eq!(lifted.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn circuit_opens_and_recovers() {
+        let breaker = CircuitBreaker::new()
+            .failure_rate(0.5)
+            .min_calls(4)
+            .window(Duration::from_secs(60))
+            .open_for(Duration::from_secs(10));
+        let mut circuit = Circuit::default();
+        let start = Instant::now();
+
+        // Not enough calls yet
+        for failed in [true, true, false] {
+            assert!(circuit.admit(&breaker, start));
+            circuit.record(&breaker, start, failed);
+        }
+        assert!(!circuit.tripped);
+
+        assert!(circuit.admit(&breaker, start));
+        circuit.record(&breaker, start, false);
+        assert!(circuit.tripped);
+        assert!(!circuit.admit(&breaker, start + Duration::from_secs(5)));
+
+        // Half-open: one trial, everyone else waits
+        let trial = start + Duration::from_secs(10);
+        assert!(circuit.admit(&breaker, trial));
+        assert!(!circuit.admit(&breaker, trial));
+
+        // A failed trial keeps it open
+        circuit.record(&breaker, trial, true);
+        assert!(!circuit.admit(&breaker, trial + Duration::from_secs(5)));
+
+        let trial = trial + Duration::from_secs(10);
+        assert!(circuit.admit(&breaker, trial));
+        circuit.record(&breaker, trial, false);
+        assert!(!circuit.tripped);
+        assert!(circuit.admit(&breaker, trial));
+    }
+
+    #[test]
+    fn old_outcomes_leave_the_window() {
+        let breaker = CircuitBreaker::new()
+            .min_calls(2)
+            .window(Duration::from_secs(60));
+        let mut circuit = Circuit::default();
+        let start = Instant::now();
+
+        circuit.record(&breaker, start, true);
+        circuit.record(&breaker, start + Duration::from_secs(61), true);
+        assert!(!circuit.tripped);
+        circuit.record(&breaker, start + Duration::from_secs(62), false);
+        assert!(circuit.tripped);
+    }
+}
@@ -44,8 +44,23 @@ pub trait TryIntoContents {
     where
         Self: Sized,
     {
-        let contents = self.try_into_contents()?;
-        Ok(contents.into_cached_content_for(model_name))
+        let (system_instruction, contents) = self.try_into_system_and_contents()?;
+        Ok(CachedContent {
+            system_instruction,
+            ..contents.into_cached_content_for(model_name)
+        })
+    }
+
+    /// Convert to a system instruction and content items
+    ///
+    /// Used when building requests so that inputs can carry their own
+    /// system instruction. Default has none.
+    #[inline]
+    fn try_into_system_and_contents(self) -> Result<(Option<Content>, Vec<Content>), Error>
+    where
+        Self: Sized,
+    {
+        Ok((None, self.try_into_contents()?))
     }
 }
 
@@ -54,6 +69,11 @@ impl<T: IntoContents> TryIntoContents for T {
     fn try_into_contents(self) -> Result<Vec<Content>, Error> {
         Ok(self.into_contents())
     }
+
+    #[inline]
+    fn try_into_system_and_contents(self) -> Result<(Option<Content>, Vec<Content>), Error> {
+        Ok(self.into_system_and_contents())
+    }
 }
 
 /// Fallible conversion to a single [`Content`] item
@@ -110,12 +130,26 @@ pub trait IntoContents: sealed::Sealed {
     where
         Self: Sized,
     {
+        let (system_instruction, contents) = self.into_system_and_contents();
         CachedContent {
             model: Some(full_model_name(model_name).into()),
-            contents: self.into_contents(),
+            system_instruction,
+            contents,
             ..Default::default()
         }
     }
+
+    /// Convert to a system instruction and content items
+    ///
+    /// Default has no system instruction. `#[derive(IntoContents)]`
+    /// overrides this for fields marked `#[content(system)]`.
+    #[inline]
+    fn into_system_and_contents(self) -> (Option<Content>, Vec<Content>)
+    where
+        Self: Sized,
+    {
+        (None, self.into_contents())
+    }
 }
 
 impl IntoContents for Vec<Content> {
@@ -173,6 +207,7 @@ impl<T: IntoParts> IntoContent for T {
 /// - `Part` → Direct passthrough
 /// - `Vec<T: IntoParts>` → Flattened parts
 /// - Arrays/slices of `T: IntoParts`
+/// - `Option<T: IntoParts>` → No parts when `None`
 /// - Tuples of `any implementations of IntoParts` up-to 16 elements
 ///
 /// # Examples
@@ -240,14 +275,17 @@ into_parts_single!(&str, |s| s.into());
 into_parts_single!(String, |s| s.into());
 into_parts_single!(Part, |p| p);
 into_parts_single!(Blob, |b| Part {
-    data: Some(Data::InlineData(b))
+    data: Some(Data::InlineData(b)),
+    metadata: None,
 });
 // TODO: Remove
 into_parts_single!(FunctionCall, |f| Part {
-    data: Some(Data::FunctionCall(f))
+    data: Some(Data::FunctionCall(f)),
+    metadata: None,
 });
 into_parts_single!(FileData, |f| Part {
-    data: Some(Data::FileData(f))
+    data: Some(Data::FileData(f)),
+    metadata: None,
 });
 
 macro_rules! into_parts_iter {
@@ -307,6 +345,28 @@ impl<T: IntoParts + Clone> IntoParts for std::borrow::Cow<'_, T> {
     }
 }
 
+impl<T: IntoParts> IntoParts for Option<T> {
+    #[inline]
+    fn into_parts(self) -> Vec<Part> {
+        self.map(IntoParts::into_parts).unwrap_or_default()
+    }
+
+    #[inline]
+    fn into_parts_in_place(self, parts: &mut Vec<Part>) {
+        if let Some(inner) = self {
+            inner.into_parts_in_place(parts);
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Some(inner) => inner.size_hint(),
+            None => (0, Some(0)),
+        }
+    }
+}
+
 // Tuple implementations (up to 16 elements)
 macro_rules! into_parts_for_tuple {
     (
@@ -456,7 +516,7 @@ pub trait TryFromCandidates: Sized {
 ///         let text = contents.into_iter()
 ///              .flat_map(|c| c.parts.iter())
 ///              .find_map(|p| match p {
-///                    Part { data: Some(Data::Text(text)) } => {
+///                    Part { data: Some(Data::Text(text)), .. } => {
 ///                        Some(text)
 ///                    }
 ///                    _ => None
@@ -501,9 +561,10 @@ mod serde_support {
 
     /// JSON deserialization support
     ///
-    /// Enabled with `serde` feature. Deserializes concatenated JSON content.
-    ///
-    /// The implementation for DeserializeOwned assumes the content is json
+    /// Enabled with `serde` feature. Deserializes the concatenated text
+    /// parts as JSON. Other parts, like images generated alongside, are
+    /// skipped; [`TypedResponse::extra_parts`](crate::TypedResponse::extra_parts)
+    /// returns them.
     ///
     /// returns ['ServiceError::InvalidResponse`] on invalid json
     impl<T: DeserializeOwned> TryFromContents for T {
@@ -516,8 +577,10 @@ mod serde_support {
             // for better memory, but the current from_reader is not that
             // performant.
             let mut buf = Vec::new();
-            for content in contents {
-                content._try_to_bytes_with(&mut buf, try_to_bytes)?;
+            for part in contents.flat_map(|content| &content.parts) {
+                if let Some(Data::Text(text)) = &part.data {
+                    buf.extend(text.as_bytes());
+                }
             }
 
             serde_json::from_slice(&buf).map_err(|err| {
@@ -533,6 +596,7 @@ impl Part {
     pub fn text(text: impl Into<String>) -> Self {
         Self {
             data: Some(Data::Text(text.into())),
+            metadata: None,
         }
     }
 
@@ -553,6 +617,15 @@ impl Part {
         }
     }
 
+    /// Returns `true` if the part carries no data or only empty text
+    pub fn is_empty(&self) -> bool {
+        match &self.data {
+            Some(Data::Text(text)) => text.is_empty(),
+            Some(_) => false,
+            None => true,
+        }
+    }
+
     /// Create a binary blob part
     ///
     /// # Example
@@ -566,6 +639,7 @@ impl Part {
                 mime_type: mime_type.to_owned(),
                 data,
             })),
+            metadata: None,
         }
     }
 
@@ -576,7 +650,100 @@ impl Part {
                 mime_type: mime_type.to_owned(),
                 file_uri: uri.to_owned(),
             })),
+            metadata: None,
+        }
+    }
+
+    /// Create a part referencing a public YouTube video
+    ///
+    /// Accepts `watch`, `youtu.be`, `shorts`, `embed` and `live` links and
+    /// normalizes them to the `watch?v=` form the API expects.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidArgument`] if `url` isn't a YouTube video link.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::Part;
+    /// # use std::time::Duration;
+    /// let video = Part::youtube("https://youtu.be/9hE5-98ZeCg")?
+    ///     .clip(Duration::from_secs(40), Duration::from_secs(80));
+    /// # Ok::<(), google_ai_rs::Error>(())
+    /// ```
+    pub fn youtube(url: &str) -> Result<Self, Error> {
+        let id = youtube_video_id(url).ok_or_else(|| {
+            Error::InvalidArgument(format!("not a YouTube video URL: {url}").into())
+        })?;
+
+        Ok(Self::file_data(
+            "",
+            &format!("https://www.youtube.com/watch?v={id}"),
+        ))
+    }
+
+    /// Restricts a video part to the segment between `start` and `end`
+    pub fn clip(mut self, start: Duration, end: Duration) -> Self {
+        let metadata = self.video_metadata_mut();
+        metadata.start_offset = Some(to_proto_duration(start));
+        metadata.end_offset = Some(to_proto_duration(end));
+        self
+    }
+
+    /// Sets the frame rate at which a video part is sampled
+    ///
+    /// The API defaults to 1 frame per second and accepts values in `(0.0, 24.0]`.
+    pub fn fps(mut self, fps: f64) -> Self {
+        self.video_metadata_mut().fps = Some(fps);
+        self
+    }
+
+    fn video_metadata_mut(&mut self) -> &mut VideoClipMetadata {
+        let Metadata::VideoMetadata(metadata) = self
+            .metadata
+            .get_or_insert(Metadata::VideoMetadata(Default::default()));
+        metadata
+    }
+}
+
+/// Extracts the 11 character video id from a YouTube URL.
+fn youtube_video_id(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    let (host, path) = rest.split_once('/')?;
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .or_else(|| host.strip_prefix("music."))
+        .unwrap_or(host);
+
+    let id = match host {
+        "youtu.be" => path.split(['?', '#', '/']).next()?,
+        "youtube.com" => {
+            let (path, query) = path.split_once('?').unwrap_or((path, ""));
+            match path.split_once('/') {
+                Some(("shorts" | "embed" | "live", id)) => id.split(['/', '#']).next()?,
+                None if path == "watch" => query
+                    .split(['&', '#'])
+                    .find_map(|pair| pair.strip_prefix("v="))?,
+                _ => return None,
+            }
         }
+        _ => return None,
+    };
+
+    let valid = id.len() == 11
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    valid.then_some(id)
+}
+
+fn to_proto_duration(d: Duration) -> prost_types::Duration {
+    prost_types::Duration {
+        seconds: d.as_secs().min(i64::MAX as u64) as i64,
+        nanos: d.subsec_nanos() as i32,
     }
 }
 
@@ -590,6 +757,7 @@ impl From<String> for Part {
     fn from(text: String) -> Self {
         Part {
             data: Some(Data::Text(text)),
+            metadata: None,
         }
     }
 }
@@ -711,6 +879,7 @@ impl Candidate {
             for p in &content.parts {
                 if let Part {
                     data: Some(Data::FunctionCall(ref fc)),
+                    ..
                 } = p
                 {
                     out.push(fc.clone());
@@ -720,6 +889,79 @@ impl Candidate {
         }
         None
     }
+
+    /// Returns the average log probability of the tokens in the candidate.
+    ///
+    /// Computed from `logprobs_result` when the request enabled logprobs
+    /// (see [`GenerativeModel::logprobs`](crate::GenerativeModel::logprobs)),
+    /// otherwise falls back to the server-reported `avg_logprobs`.
+    ///
+    /// Returns `None` if the response carries no log probability information.
+    pub fn avg_logprob(&self) -> Option<f64> {
+        if let Some(result) = &self.logprobs_result {
+            let (sum, n) = result
+                .chosen_candidates
+                .iter()
+                .filter_map(|c| c.log_probability)
+                .fold((0.0, 0usize), |(sum, n), lp| (sum + lp as f64, n + 1));
+
+            if n > 0 {
+                return Some(sum / n as f64);
+            }
+        }
+
+        if self.avg_logprobs != 0.0 {
+            Some(self.avg_logprobs)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a confidence score in `[0, 1]` for the candidate.
+    ///
+    /// This is the geometric mean of the per-token probabilities, i.e.
+    /// `exp(avg_logprob)`. Useful to route low-confidence outputs to
+    /// human review.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::Candidate;
+    /// let candidate = Candidate {
+    ///     avg_logprobs: -0.05,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let confidence = candidate.confidence().unwrap();
+    /// assert!(confidence > 0.95 && confidence < 1.0);
+    /// ```
+    pub fn confidence(&self) -> Option<f64> {
+        self.avg_logprob().map(|lp| lp.exp().clamp(0.0, 1.0))
+    }
+}
+
+impl CachedContent {
+    /// Sets the system instruction cached alongside the contents
+    ///
+    /// A cache may hold nothing but a system instruction.
+    pub fn with_system_instruction<I: IntoContent>(mut self, instruction: I) -> Self {
+        self.system_instruction = Some(instruction.into_content());
+        self
+    }
+
+    /// Sets the tools cached alongside the contents
+    pub fn with_tools<I>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = Tool>,
+    {
+        self.tools = tools.into_iter().collect();
+        self
+    }
+
+    /// Sets how long the cache lives, replacing any expiration time
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.expiration = Some(cached_content::Expiration::Ttl(to_proto_duration(ttl)));
+        self
+    }
 }
 
 // Response processing implementation
@@ -859,7 +1101,7 @@ impl fmt::Display for Response {
     }
 }
 
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use base64::engine::general_purpose::NO_PAD;
 use prost_types::FieldMask;
@@ -868,8 +1110,11 @@ use crate::{
     full_model_name,
     genai::Response,
     proto::{
-        cached_content, part::Data, tuned_model::SourceModel, Blob, CachedContent, Candidate,
-        Content, FileData, FunctionCall, Part, TunedModel,
+        cached_content,
+        part::{Data, Metadata},
+        tuned_model::SourceModel,
+        Blob, CachedContent, Candidate, Content, FileData, FunctionCall, Part, Tool, TunedModel,
+        VideoClipMetadata,
     },
     Error,
 };
@@ -932,3 +1177,104 @@ mod sealed {
 }
 
 impl<T> sealed::Sealed for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn youtube_video_id() {
+        let tests = [
+            (
+                "https://www.youtube.com/watch?v=9hE5-98ZeCg",
+                Some("9hE5-98ZeCg"),
+            ),
+            (
+                "youtube.com/watch?feature=share&v=9hE5-98ZeCg#t=1",
+                Some("9hE5-98ZeCg"),
+            ),
+            ("https://youtu.be/9hE5-98ZeCg?t=10", Some("9hE5-98ZeCg")),
+            (
+                "https://m.youtube.com/shorts/9hE5-98ZeCg",
+                Some("9hE5-98ZeCg"),
+            ),
+            (
+                "https://www.youtube.com/embed/9hE5-98ZeCg",
+                Some("9hE5-98ZeCg"),
+            ),
+            ("https://www.youtube.com/watch?v=short", None),
+            ("https://www.youtube.com/channel/UC123", None),
+            ("https://vimeo.com/9hE5-98ZeCg", None),
+        ];
+
+        for (url, want) in tests {
+            assert_eq!(super::youtube_video_id(url), want, "{url}");
+        }
+    }
+
+    #[test]
+    fn option_into_parts() {
+        let none: Option<String> = None;
+        assert_eq!(none.size_hint(), (0, Some(0)));
+        assert!(none.into_parts().is_empty());
+
+        let some = Some("hello");
+        assert_eq!(some.size_hint(), (1, Some(1)));
+        assert_eq!(some.into_parts(), vec![Part::from("hello")]);
+    }
+
+    #[test]
+    fn cached_content_ttl_mask() {
+        let cc = CachedContent::default()
+            .with_system_instruction("You are a librarian")
+            .with_ttl(Duration::from_millis(1500));
+
+        assert_eq!(
+            cc.expiration,
+            Some(cached_content::Expiration::Ttl(prost_types::Duration {
+                seconds: 1,
+                nanos: 500_000_000,
+            }))
+        );
+        assert_eq!(cc.field_mask().paths, vec!["ttl"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn parses_text_beside_other_parts() {
+        let candidate = |parts| Candidate {
+            content: Some(Content {
+                role: "model".to_owned(),
+                parts,
+            }),
+            ..Default::default()
+        };
+        let response = crate::TypedResponse {
+            t: (),
+            raw: Response {
+                candidates: vec![
+                    candidate(vec![
+                        Part::text(r#"{"name": "#),
+                        Part::blob("image/png", vec![0x89, b'P', b'N', b'G']),
+                        Part::text(r#""Pancakes"}"#),
+                    ]),
+                    candidate(vec![Part::text("{}")]),
+                ],
+                ..Default::default()
+            },
+            confidence: None,
+            warnings: Vec::new(),
+        };
+
+        let value = serde_json::Value::try_from_candidates(&response.raw.candidates[..1]).unwrap();
+        assert_eq!(value, serde_json::json!({"name": "Pancakes"}));
+        let extra = response.extra_parts();
+        assert_eq!(extra.len(), 2);
+        assert!(matches!(
+            extra[0].as_slice(),
+            [Part { data: Some(Data::InlineData(Blob { mime_type, .. })), .. }]
+                if mime_type == "image/png"
+        ));
+        assert!(extra[1].is_empty());
+    }
+}
@@ -0,0 +1,391 @@
+//! Keeping prompts within a model's context window
+//!
+//! [`ClientBuilder::check_context_window`] checks prompts against the
+//! model's input token limit before sending. An oversized prompt is
+//! otherwise rejected by the server with a generic `INVALID_ARGUMENT` that
+//! doesn't say what to trim.
+//!
+//! [`GenerativeModel::with_compression`] shrinks prompts before they're
+//! sent, e.g. with [`Dedup`] to elide boilerplate repeated across turns.
+//!
+//! [`ClientBuilder::check_context_window`]: crate::client::ClientBuilder::check_context_window
+//! [`GenerativeModel::with_compression`]: crate::GenerativeModel::with_compression
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use std::cmp::Reverse;
+
+use prost::Message as _;
+use tonic::IntoRequest;
+
+use crate::client::Client;
+use crate::error::{status_into_error, Error, PartSize};
+use crate::proto::{
+    part::Data, Content, CountTokensRequest, CountTokensResponse, GenerateContentRequest,
+};
+
+/// Rough size of a token in characters, for estimating savings
+const CHARS_PER_TOKEN: usize = 4;
+
+/// A transformation that shrinks a prompt's contents before sending
+///
+/// The system instruction isn't passed; compressing it would defeat
+/// implicit caching.
+pub trait Compress: Debug + Send + Sync {
+    /// Compresses `contents` in place
+    fn compress(&self, contents: &mut [Content]) -> Compressed;
+}
+
+/// What a [`Compress`] pass saved
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Compressed {
+    /// Number of parts changed
+    pub parts: usize,
+    /// Estimated tokens removed, at four characters per token
+    pub tokens_saved: usize,
+}
+
+/// Elides text blocks repeated across turns
+///
+/// Text is split into blocks at blank lines. A block of at least
+/// [`min_chars`](Self::min_chars) characters that already appeared in an
+/// earlier turn is replaced with a short marker pointing at that turn, so
+/// instructions or tool descriptions pasted into every message are sent
+/// once.
+///
+/// # Example
+/// ```
+/// use google_ai_rs::context::{Compress, Dedup};
+/// use google_ai_rs::Content;
+///
+/// let rules = "Answer in English. Cite your sources. Keep it short.";
+/// let mut history = vec![
+///     Content::user(format!("{rules}\n\nWhat is Rust?")),
+///     Content::model("A systems programming language."),
+///     Content::user(format!("{rules}\n\nWho made it?")),
+/// ];
+///
+/// let saved = Dedup::new().min_chars(20).compress(&mut history);
+/// assert_eq!(saved.parts, 1);
+/// assert_eq!(
+///     history[2].parts[0].to_text(),
+///     "[repeated text from turn 0 elided]\n\nWho made it?"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Dedup {
+    min_chars: usize,
+}
+
+impl Dedup {
+    /// Creates a deduplicator eliding blocks of 200 characters or more
+    pub fn new() -> Self {
+        Self { min_chars: 200 }
+    }
+
+    /// Sets the length under which repeated blocks are kept
+    ///
+    /// Markers cost tokens too; short blocks aren't worth replacing.
+    pub fn min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars;
+        self
+    }
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compress for Dedup {
+    fn compress(&self, contents: &mut [Content]) -> Compressed {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut compressed = Compressed::default();
+
+        for (turn, content) in contents.iter_mut().enumerate() {
+            for part in &mut content.parts {
+                let Some(Data::Text(text)) = &mut part.data else {
+                    continue;
+                };
+
+                let mut saved = 0;
+                let blocks: Vec<String> = text
+                    .split("\n\n")
+                    .map(|block| {
+                        if block.chars().count() < self.min_chars {
+                            return block.to_owned();
+                        }
+                        match seen.get(block) {
+                            Some(&first) if first < turn => {
+                                let marker = format!("[repeated text from turn {first} elided]");
+                                saved += block.len().saturating_sub(marker.len());
+                                marker
+                            }
+                            _ => {
+                                seen.entry(block.to_owned()).or_insert(turn);
+                                block.to_owned()
+                            }
+                        }
+                    })
+                    .collect();
+
+                if saved > 0 {
+                    *text = blocks.join("\n\n");
+                    compressed.parts += 1;
+                    compressed.tokens_saved += saved / CHARS_PER_TOKEN;
+                }
+            }
+        }
+        compressed
+    }
+}
+
+/// How many parts a [`Error::ContextOverflow`] names
+const REPORTED_PARTS: usize = 3;
+
+/// Fails with [`Error::ContextOverflow`] if `request`'s prompt has more
+/// tokens than its model accepts
+///
+/// Tuned models don't report a limit and aren't checked.
+pub(crate) async fn check(client: &Client, request: &GenerateContentRequest) -> Result<(), Error> {
+    if !request.model.starts_with("models/") {
+        return Ok(());
+    }
+
+    let limit = client.cached_model(&request.model).await?.input_token_limit;
+    if limit <= 0 {
+        return Ok(());
+    }
+
+    let count = CountTokensRequest {
+        model: request.model.clone(),
+        contents: vec![],
+        generate_content_request: Some(request.clone()),
+    };
+    let used = count_tokens(client, count).await?.total_tokens;
+
+    if used > limit {
+        return Err(Error::ContextOverflow {
+            used,
+            limit,
+            largest_parts: largest_parts(request, REPORTED_PARTS),
+        });
+    }
+    Ok(())
+}
+
+/// How many token counts a client remembers
+const TOKEN_COUNTS: usize = 256;
+
+/// Size over which a request's count isn't remembered
+///
+/// Counts are keyed by the whole encoded request; prompts carrying inline
+/// media would otherwise keep megabytes alive per entry.
+const MAX_COUNTED_REQUEST: usize = 1024 * 1024;
+
+/// Token counts of recent requests, keyed by the encoded request
+///
+/// Prompts are often counted again unchanged, e.g. a system prompt and
+/// history checked before every message. Requests encode their schemas'
+/// properties in no particular order, so those may miss.
+#[derive(Debug, Default)]
+pub(crate) struct TokenCounts {
+    counts: HashMap<Vec<u8>, (CountTokensResponse, u64)>,
+    /// Incremented on every use, to find the least recently used count
+    clock: u64,
+}
+
+impl TokenCounts {
+    fn get(&mut self, request: &[u8]) -> Option<CountTokensResponse> {
+        self.clock += 1;
+        let (count, last_used) = self.counts.get_mut(request)?;
+        *last_used = self.clock;
+        Some(*count)
+    }
+
+    fn insert(&mut self, request: Vec<u8>, count: CountTokensResponse) {
+        if request.len() > MAX_COUNTED_REQUEST {
+            return;
+        }
+        self.clock += 1;
+        self.counts.insert(request, (count, self.clock));
+        if self.counts.len() > TOKEN_COUNTS {
+            let oldest = self
+                .counts
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(request, _)| request.clone());
+            if let Some(oldest) = oldest {
+                self.counts.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Counts the tokens of `request`, remembering the count per client
+pub(crate) async fn count_tokens(
+    client: &Client,
+    request: CountTokensRequest,
+) -> Result<CountTokensResponse, Error> {
+    let key = request.encode_to_vec();
+    let cached = client
+        .token_counts
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&key);
+    if let Some(count) = cached {
+        return Ok(count);
+    }
+
+    let model = request.model.clone();
+    let cached_content = request
+        .generate_content_request
+        .as_ref()
+        .and_then(|request| request.cached_content.clone());
+    let call = async {
+        client
+            .route_to(cached_content.as_deref(), async |region| {
+                region.gc.clone().count_tokens(request.into_request()).await
+            })
+            .await?
+            .map_err(status_into_error)
+            .map(|r| r.into_inner())
+    };
+    let count = client.config.call(&model, call).await?;
+    client
+        .token_counts
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, count);
+    Ok(count)
+}
+
+/// Returns the `n` largest parts of a request's prompt, largest first
+pub(crate) fn largest_parts(request: &GenerateContentRequest, n: usize) -> Vec<PartSize> {
+    let system = request
+        .system_instruction
+        .iter()
+        .flat_map(|content| content.parts.iter().enumerate().map(|(i, p)| (None, i, p)));
+    let contents = request
+        .contents
+        .iter()
+        .enumerate()
+        .flat_map(|(c, content)| {
+            content
+                .parts
+                .iter()
+                .enumerate()
+                .map(move |(i, p)| (Some(c), i, p))
+        });
+
+    let mut parts: Vec<PartSize> = system
+        .chain(contents)
+        .map(|(content, part, p)| PartSize {
+            content,
+            part,
+            bytes: p.encoded_len(),
+        })
+        .collect();
+    // Stable, so equal sizes keep prompt order
+    parts.sort_by_key(|p| Reverse(p.bytes));
+    parts.truncate(n);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_parts_by_size() {
+        let request = GenerateContentRequest {
+            system_instruction: Some(Content::user("be brief")),
+            contents: vec![
+                Content::user(("short", "x".repeat(500))),
+                Content::model("y".repeat(100)),
+                Content::user("z".repeat(1000)),
+            ],
+            ..Default::default()
+        };
+
+        let parts: Vec<_> = largest_parts(&request, 3)
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            parts,
+            [
+                "contents[2].parts[0] (1003 bytes)",
+                "contents[0].parts[1] (503 bytes)",
+                "contents[1].parts[0] (102 bytes)",
+            ]
+        );
+    }
+
+    #[test]
+    fn token_counts_evict_least_recently_used() {
+        let count = |total_tokens| CountTokensResponse {
+            total_tokens,
+            cached_content_token_count: 0,
+        };
+        let key = |n: u64| n.to_le_bytes().to_vec();
+        let mut counts = TokenCounts::default();
+        for n in 0..TOKEN_COUNTS as u64 {
+            counts.insert(key(n), count(n as i32));
+        }
+        assert_eq!(counts.get(&key(0)), Some(count(0)));
+
+        counts.insert(key(TOKEN_COUNTS as u64), count(-1));
+        assert_eq!(counts.get(&key(1)), None);
+        assert_eq!(counts.get(&key(0)), Some(count(0)));
+        assert_eq!(counts.counts.len(), TOKEN_COUNTS);
+    }
+
+    #[test]
+    fn token_counts_skip_large_requests() {
+        let count = CountTokensResponse {
+            total_tokens: 1,
+            cached_content_token_count: 0,
+        };
+        let mut counts = TokenCounts::default();
+        counts.insert(vec![0; MAX_COUNTED_REQUEST + 1], count);
+        assert!(counts.counts.is_empty());
+
+        counts.insert(vec![0; MAX_COUNTED_REQUEST], count);
+        assert_eq!(counts.get(&vec![0; MAX_COUNTED_REQUEST]), Some(count));
+        assert_eq!(counts.get(&vec![0; MAX_COUNTED_REQUEST - 1]), None);
+    }
+
+    #[test]
+    fn dedup_keeps_first_occurrence() {
+        let schema = "s".repeat(300);
+        let mut contents = vec![
+            Content::user(format!("{schema}\n\nfirst")),
+            Content::model(format!("{schema}\n\nshort\n\nshort")),
+            Content::user((format!("second\n\n{schema}"), schema.clone())),
+        ];
+
+        let saved = Dedup::new().compress(&mut contents);
+
+        let marker = "[repeated text from turn 0 elided]";
+        assert_eq!(contents[0].parts[0].to_text(), format!("{schema}\n\nfirst"));
+        assert_eq!(
+            contents[1].parts[0].to_text(),
+            format!("{marker}\n\nshort\n\nshort")
+        );
+        assert_eq!(
+            contents[2].parts[0].to_text(),
+            format!("second\n\n{marker}")
+        );
+        assert_eq!(contents[2].parts[1].to_text(), marker);
+        assert_eq!(
+            saved,
+            Compressed {
+                parts: 3,
+                tokens_saved: 3 * ((300 - marker.len()) / 4),
+            }
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Deadlines for calls made on behalf of an incoming request
+//!
+//! Calls made inside [`scope`] are abandoned once its deadline passes and
+//! fail with [`NetError::DeadlineExceeded`]. Abandoning a call resets its
+//! HTTP/2 stream, so the upstream stops working on it too. Response streams
+//! keep the deadline that was in scope when they were opened.
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::{deadline, GenerativeModel};
+//!
+//! # async fn f(model: GenerativeModel<'_>, request: tonic::Request<()>) -> Result<(), Box<dyn std::error::Error>> {
+//! // In a tonic handler: give the model whatever time our caller gave us
+//! let timeout = deadline::from_metadata(request.metadata());
+//! let response = match timeout {
+//!     Some(timeout) => {
+//!         deadline::scope_timeout(timeout, model.generate_content("Hello")).await?
+//!     }
+//!     None => model.generate_content("Hello").await?,
+//! };
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`NetError::DeadlineExceeded`]: crate::error::NetError::DeadlineExceeded
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tonic::metadata::MetadataMap;
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Runs `fut` with calls made inside it bounded by `deadline`
+///
+/// Nested scopes can only shorten the deadline.
+pub async fn scope<F: Future>(deadline: Instant, fut: F) -> F::Output {
+    let deadline = current().map_or(deadline, |outer| outer.min(deadline));
+    DEADLINE.scope(deadline, fut).await
+}
+
+/// Runs `fut` with calls made inside it bounded by `timeout` from now
+pub async fn scope_timeout<F: Future>(timeout: Duration, fut: F) -> F::Output {
+    scope(Instant::now() + timeout, fut).await
+}
+
+/// Returns the deadline of the enclosing [`scope`], if any
+pub fn current() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// Reads the `grpc-timeout` an incoming gRPC request was sent with
+pub fn from_metadata(metadata: &MetadataMap) -> Option<Duration> {
+    parse_grpc_timeout(metadata.get("grpc-timeout")?.to_str().ok()?)
+}
+
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    // At most 8 digits followed by a unit
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    if !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+
+    Some(match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grpc_timeout() {
+        let tests = [
+            ("3S", Some(Duration::from_secs(3))),
+            ("250m", Some(Duration::from_millis(250))),
+            ("2H", Some(Duration::from_secs(7200))),
+            ("10u", Some(Duration::from_micros(10))),
+            ("99999999n", Some(Duration::from_nanos(99999999))),
+            ("123456789S", None),
+            ("S", None),
+            ("-1S", None),
+            ("1s", None),
+        ];
+
+        for (value, want) in tests {
+            assert_eq!(parse_grpc_timeout(value), want, "{value}");
+        }
+    }
+
+    #[tokio::test]
+    async fn nested_scopes_only_shorten() {
+        assert_eq!(current(), None);
+
+        let now = Instant::now();
+        let short = now + Duration::from_secs(1);
+        let long = now + Duration::from_secs(10);
+
+        scope(short, async {
+            assert_eq!(current(), Some(short));
+            scope(long, async { assert_eq!(current(), Some(short)) }).await;
+        })
+        .await;
+
+        scope(long, async {
+            scope(short, async { assert_eq!(current(), Some(short)) }).await;
+            assert_eq!(current(), Some(long));
+        })
+        .await;
+    }
+}
@@ -0,0 +1,186 @@
+//! Dropping repeated text from streams
+//!
+//! Stream chunks are meant to carry new text only, but some repeat what
+//! came before: a chunk holding everything so far, or a resumed stream
+//! restating its last sentence. [`TextDeltas`] remembers what each
+//! candidate already emitted and cuts the repeated start off every chunk,
+//! so whatever prints chunks can simply append them.
+//!
+//! Give it to a model with
+//! [`with_text_deltas`](crate::GenerativeModel::with_text_deltas) to apply
+//! it to all of the model's streams, chats included.
+//!
+//! # Example
+//! ```
+//! # use google_ai_rs::{delta::TextDeltas, GenerativeModel};
+//! # async fn f(model: GenerativeModel<'_>) -> Result<(), google_ai_rs::Error> {
+//! let model = model.with_text_deltas(TextDeltas::new());
+//! let mut stream = model.stream_generate_content("Write a long story").await?;
+//! while let Some(chunk) = stream.next().await? {
+//!     print!("{}", chunk.to_text());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::proto::{part::Data, GenerateContentResponse};
+
+/// Cuts text repeating what was already emitted off stream chunks
+///
+/// A chunk's start is taken as repeated when the output so far ends with
+/// it. Short overlaps are common in genuine text, e.g. a space after a
+/// space, so those below [`min_overlap`](Self::min_overlap) bytes, or made
+/// only of whitespace, count as new text.
+///
+/// Models hold an unused one, which each of their streams starts from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextDeltas {
+    min_overlap: usize,
+    /// Text emitted so far, by candidate index
+    emitted: Vec<String>,
+}
+
+impl Default for TextDeltas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextDeltas {
+    /// Drops repeats of at least 32 bytes
+    pub fn new() -> Self {
+        Self {
+            min_overlap: 32,
+            emitted: Vec::new(),
+        }
+    }
+
+    /// Sets how many bytes a repeat needs to be dropped
+    ///
+    /// Lower values catch shorter repeats but may drop genuine text.
+    pub fn min_overlap(mut self, min_overlap: usize) -> Self {
+        self.min_overlap = min_overlap;
+        self
+    }
+
+    /// Returns the text emitted so far by the candidate at `index`
+    pub fn text(&self, index: usize) -> &str {
+        self.emitted.get(index).map_or("", String::as_str)
+    }
+
+    /// Returns the part of `text` the candidate at `index` hasn't emitted
+    /// yet, counting it as emitted
+    pub fn delta<'t>(&mut self, index: usize, text: &'t str) -> &'t str {
+        if self.emitted.len() <= index {
+            self.emitted.resize_with(index + 1, String::new);
+        }
+        let delta = &text[self.repeated(index, text)..];
+        self.emitted[index].push_str(delta);
+        delta
+    }
+
+    /// Cuts repeated text off the text parts of each candidate of `response`
+    pub fn apply(&mut self, response: &mut GenerateContentResponse) {
+        for (position, candidate) in response.candidates.iter_mut().enumerate() {
+            let Some(content) = &mut candidate.content else {
+                continue;
+            };
+            let index = candidate
+                .index
+                .and_then(|index| usize::try_from(index).ok())
+                .unwrap_or(position);
+
+            let text: String = content
+                .parts
+                .iter()
+                .filter_map(|part| match &part.data {
+                    Some(Data::Text(text)) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let mut repeated = text.len() - self.delta(index, &text).len();
+            if repeated == 0 {
+                continue;
+            }
+
+            // The repeat spans the first text parts
+            for part in &mut content.parts {
+                let Some(Data::Text(text)) = &mut part.data else {
+                    continue;
+                };
+                let cut = repeated.min(text.len());
+                text.drain(..cut);
+                repeated -= cut;
+                if repeated == 0 {
+                    break;
+                }
+            }
+            content
+                .parts
+                .retain(|part| !matches!(&part.data, Some(Data::Text(text)) if text.is_empty()));
+        }
+    }
+
+    /// Length of the longest start of `text` the output ends with, if it's
+    /// long enough to be a repeat
+    fn repeated(&self, index: usize, text: &str) -> usize {
+        let emitted = &self.emitted[index];
+        let longest = text.len().min(emitted.len());
+        (self.min_overlap.max(1)..=longest)
+            .rev()
+            .filter(|&len| text.is_char_boundary(len))
+            .map(|len| &text[..len])
+            .find(|overlap| emitted.ends_with(overlap))
+            .filter(|overlap| !overlap.trim().is_empty())
+            .map_or(0, str::len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{Candidate, Content};
+
+    #[test]
+    fn drops_repeats() {
+        let mut deltas = TextDeltas::new().min_overlap(5);
+        assert_eq!(deltas.delta(0, "Once upon"), "Once upon");
+        // A chunk holding everything so far
+        assert_eq!(deltas.delta(0, "Once upon a time"), " a time");
+        // A restated tail
+        assert_eq!(deltas.delta(0, "a time, there was"), ", there was");
+        // Short and whitespace overlaps are genuine
+        assert_eq!(deltas.delta(0, "was"), "was");
+        assert_eq!(deltas.delta(0, "       "), "       ");
+        assert_eq!(deltas.delta(0, "       x"), "       x");
+        assert_eq!(
+            deltas.text(0),
+            "Once upon a time, there waswas              x"
+        );
+        assert_eq!(deltas.text(1), "");
+    }
+
+    #[test]
+    fn cuts_parts() {
+        let mut deltas = TextDeltas::new().min_overlap(5);
+        let chunk = |parts: &[&str]| GenerateContentResponse {
+            candidates: vec![Candidate {
+                content: Some(Content::model(parts.to_vec())),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let mut first = chunk(&["Hello, ", "world"]);
+        deltas.apply(&mut first);
+        assert_eq!(first.to_text(), "Hello, world");
+
+        let mut second = chunk(&["Hello, ", "world! Bye"]);
+        deltas.apply(&mut second);
+        assert_eq!(second.to_text(), "! Bye");
+        assert_eq!(
+            second.candidates[0].content.as_ref().unwrap().parts.len(),
+            1
+        );
+    }
+}
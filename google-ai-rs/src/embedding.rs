@@ -1,4 +1,6 @@
-use std::borrow::Cow;
+pub mod index;
+
+use std::{borrow::Cow, fmt::Debug, marker::PhantomData, ops::Deref};
 
 use tonic::IntoRequest;
 
@@ -6,7 +8,6 @@ use crate::{
     client::CClient,
     content::{IntoContent, TryIntoContent},
     error::status_into_error,
-    full_model_name,
     proto::{BatchEmbedContentsResponse, Content, EmbedContentResponse, Model as Info, TaskType},
 };
 
@@ -67,9 +68,10 @@ impl<'c> Model<'c> {
     }
 
     fn new_inner(client: impl Into<CClient<'c>>, name: &str) -> Self {
+        let client = client.into();
         Self {
-            client: client.into(),
-            name: full_model_name(name).into(),
+            name: client.model_name(name).into(),
+            client,
             task_type: None,
         }
     }
@@ -135,13 +137,15 @@ impl<'c> Model<'c> {
         let request = self
             .build_request(title, content.try_into_content()?)
             .await?;
-        self.client
-            .gc
-            .clone()
-            .embed_content(request)
-            .await
-            .map_err(status_into_error)
-            .map(|response| response.into_inner())
+        let client = &self.client;
+        let call = async {
+            client
+                .route(async |region| region.gc.clone().embed_content(request).await)
+                .await?
+                .map_err(status_into_error)
+                .map(|response| response.into_inner())
+        };
+        client.config.call(&self.name, call).await
     }
 
     /// Creates a new batch embedding context
@@ -266,15 +270,15 @@ impl Batch<'_> {
         let expected = self.req.requests.len();
         let request = self.req.into_request();
 
-        let response = self
-            .m
-            .client
-            .gc
-            .clone()
-            .batch_embed_contents(request)
-            .await
-            .map_err(status_into_error)
-            .map(|response| response.into_inner())?;
+        let client = &self.m.client;
+        let call = async {
+            client
+                .route(async |region| region.gc.clone().batch_embed_contents(request).await)
+                .await?
+                .map_err(status_into_error)
+                .map(|response| response.into_inner())
+        };
+        let response = client.config.call(&self.m.name, call).await?;
 
         if response.embeddings.len() != expected {
             return Err(Error::Service(ServiceError::InvalidResponse(
@@ -291,6 +295,119 @@ impl Batch<'_> {
     }
 }
 
+/// Conversion into a document for embedding
+///
+/// Usually derived: the field marked `#[embed(title)]` becomes the title and
+/// the remaining fields, in declaration order, form the content.
+///
+/// # Example
+/// ```rust,no_run
+/// use google_ai_rs::{Client, EmbedInput};
+///
+/// #[derive(EmbedInput)]
+/// struct Article {
+///     #[embed(title)]
+///     headline: String,
+///     body: String,
+///     #[embed(skip)]
+///     id: u64,
+/// }
+///
+/// # async fn f(article: Article) -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Client::new("YOUR-API-KEY").await?;
+/// let embedder = client.typed_embedding_model::<Article>("embedding-001");
+/// let embedding = embedder.embed(article).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait EmbedInput {
+    /// Returns the document title, if any, and the content to embed.
+    fn into_embed_input(self) -> Result<(Option<String>, Content), Error>;
+}
+
+/// Type-safe wrapper for an embedding [`Model`] accepting documents of type `T`.
+///
+/// The counterpart of [`TypedModel`](crate::TypedModel) for embeddings: inputs
+/// declare how they flatten into a title and content through [`EmbedInput`].
+pub struct TypedEmbedder<'c, T> {
+    inner: Model<'c>,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T> Debug for TypedEmbedder<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<'c, T> TypedEmbedder<'c, T>
+where
+    T: EmbedInput,
+{
+    /// Creates a new typed embedding model.
+    ///
+    /// # Arguments
+    /// * `client` - Configured API client
+    /// * `name` - Model identifier (e.g., "embedding-001")
+    pub fn new(client: &'c Client, name: &str) -> Self {
+        Model::new(client, name).into()
+    }
+
+    /// Sets the task type used for documents without a title.
+    ///
+    /// See [`Model::task_type`].
+    pub fn task_type(mut self, task_type: TaskType) -> Self {
+        self.inner = self.inner.task_type(task_type);
+        self
+    }
+
+    /// Embeds a single document.
+    pub async fn embed(&self, input: T) -> Result<EmbedContentResponse, Error> {
+        let (title, content) = input.into_embed_input()?;
+        self.inner
+            .embed_content_with_title(title.as_deref().unwrap_or_default(), content)
+            .await
+    }
+
+    /// Embeds multiple documents in a single batch request.
+    pub async fn embed_batch<I>(&self, inputs: I) -> Result<BatchEmbedContentsResponse, Error>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut batch = self.inner.new_batch();
+        for input in inputs {
+            let (title, content) = input.into_embed_input()?;
+            batch = batch.add_content_with_title(title.as_deref().unwrap_or_default(), content);
+        }
+        batch.embed().await
+    }
+
+    /// Consumes the `TypedEmbedder`, returning the underlying [`Model`].
+    pub fn into_inner(self) -> Model<'c> {
+        self.inner
+    }
+}
+
+impl<'c, T> Deref for TypedEmbedder<'c, T> {
+    type Target = Model<'c>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'c, T> From<Model<'c>> for TypedEmbedder<'c, T>
+where
+    T: EmbedInput,
+{
+    fn from(inner: Model<'c>) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
 impl Client {
     /// Creates a new embedding model interface
     ///
@@ -298,4 +415,44 @@ impl Client {
     pub fn embedding_model<'c>(&'c self, name: &str) -> Model<'c> {
         Model::new(self, name)
     }
+
+    /// Creates a new typed embedding model interface
+    ///
+    /// Shorthand for `TypedEmbedder::new()`
+    pub fn typed_embedding_model<'c, T: EmbedInput>(&'c self, name: &str) -> TypedEmbedder<'c, T> {
+        TypedEmbedder::new(self, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EmbedInput, Part};
+
+    #[derive(EmbedInput)]
+    #[embed(crate_path = "crate")]
+    struct Article {
+        #[embed(title)]
+        headline: String,
+        summary: String,
+        body: String,
+        #[embed(skip)]
+        _id: u64,
+    }
+
+    #[test]
+    fn derives_title_and_content() {
+        let article = Article {
+            headline: "Rust 2.0".into(),
+            summary: "Not yet".into(),
+            body: "Maybe later".into(),
+            _id: 7,
+        };
+
+        let (title, content) = article.into_embed_input().unwrap();
+        assert_eq!(title.as_deref(), Some("Rust 2.0"));
+        assert_eq!(
+            content.parts,
+            [Part::text("Not yet"), Part::text("Maybe later")]
+        );
+    }
 }
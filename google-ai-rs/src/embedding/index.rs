@@ -0,0 +1,422 @@
+//! In-memory similarity search over embeddings
+//!
+//! A [`VectorIndex`] holds embedding vectors under an id, each with
+//! metadata like its source document or language, and returns the
+//! vectors closest to a query by cosine similarity. [`top_k_where`]
+//! narrows the search to vectors whose metadata passes a predicate, e.g.
+//! one tenant's documents, while it scans rather than after it, so the
+//! `k` results all pass.
+//!
+//! With the `serde` feature, an index can be [saved](VectorIndex::save)
+//! to disk and [loaded](VectorIndex::load) back, so embeddings don't have
+//! to be computed again on every start.
+//!
+//! [`top_k_where`]: VectorIndex::top_k_where
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::embedding::index::{Metadata, VectorIndex};
+//!
+//! let mut index = VectorIndex::new();
+//! let meta = |lang: &str| Metadata::from([("lang".to_owned(), lang.to_owned())]);
+//! index.insert("hello", vec![1.0, 0.0], meta("en"))?;
+//! index.insert("bonjour", vec![0.9, 0.1], meta("fr"))?;
+//! index.insert("goodbye", vec![0.0, 1.0], meta("en"))?;
+//!
+//! let query = [1.0, 0.05];
+//! let best = index.top_k(&query, 1);
+//! assert_eq!(best[0].entry.id, "hello");
+//!
+//! let english = index.top_k_where(&query, 2, |meta| meta["lang"] == "en");
+//! let ids: Vec<_> = english.iter().map(|m| m.entry.id.as_str()).collect();
+//! assert_eq!(ids, ["hello", "goodbye"]);
+//! # Ok::<(), google_ai_rs::Error>(())
+//! ```
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{error::Error, proto::ContentEmbedding};
+
+/// Metadata stored with a vector, for filtering and for looking up what it
+/// was computed from
+pub type Metadata = HashMap<String, String>;
+
+/// A vector stored in a [`VectorIndex`]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    pub id: String,
+    pub values: Vec<f32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: Metadata,
+}
+
+/// An entry found by a search, with its cosine similarity to the query
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Match<'a> {
+    pub entry: &'a Entry,
+    /// From -1 to 1, higher being closer
+    pub score: f32,
+}
+
+/// Embedding vectors searchable by cosine similarity
+///
+/// All vectors have the length of the first one inserted. See the
+/// [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct VectorIndex {
+    entries: Vec<Entry>,
+    // Parallel to `entries`, so searches don't recompute them
+    norms: Vec<f32>,
+    positions: HashMap<String, usize>,
+}
+
+impl VectorIndex {
+    /// Creates an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of vectors in the index
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index has no vectors
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Length of the index's vectors, or `None` while it's empty
+    pub fn dimensions(&self) -> Option<usize> {
+        self.entries.first().map(|entry| entry.values.len())
+    }
+
+    /// Adds a vector under `id`, replacing any already there
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidArgument`] if the vector is empty or its
+    /// length differs from the index's vectors.
+    pub fn insert(
+        &mut self,
+        id: impl Into<String>,
+        values: Vec<f32>,
+        metadata: Metadata,
+    ) -> Result<(), Error> {
+        let entry = Entry {
+            id: id.into(),
+            values,
+            metadata,
+        };
+        self.check(&entry)?;
+        let norm = norm(&entry.values);
+        match self.positions.get(&entry.id) {
+            Some(&position) => {
+                self.entries[position] = entry;
+                self.norms[position] = norm;
+            }
+            None => {
+                self.positions.insert(entry.id.clone(), self.entries.len());
+                self.entries.push(entry);
+                self.norms.push(norm);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds an embedding returned by the API under `id`
+    ///
+    /// See [`insert`](Self::insert).
+    pub fn insert_embedding(
+        &mut self,
+        id: impl Into<String>,
+        embedding: ContentEmbedding,
+        metadata: Metadata,
+    ) -> Result<(), Error> {
+        self.insert(id, embedding.values, metadata)
+    }
+
+    /// Returns the entry stored under `id`
+    pub fn get(&self, id: &str) -> Option<&Entry> {
+        self.positions
+            .get(id)
+            .map(|&position| &self.entries[position])
+    }
+
+    /// Removes the entry stored under `id`, returning it
+    pub fn remove(&mut self, id: &str) -> Option<Entry> {
+        let position = self.positions.remove(id)?;
+        let entry = self.entries.swap_remove(position);
+        self.norms.swap_remove(position);
+        if let Some(moved) = self.entries.get(position) {
+            self.positions.insert(moved.id.clone(), position);
+        }
+        Some(entry)
+    }
+
+    /// Returns the entries, in no particular order
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Returns the `k` entries most similar to `query`, most similar first
+    ///
+    /// A query of another length than the index's vectors, or of all
+    /// zeros, matches nothing.
+    pub fn top_k(&self, query: &[f32], k: usize) -> Vec<Match<'_>> {
+        self.top_k_where(query, k, |_| true)
+    }
+
+    /// Returns the `k` entries most similar to `query` among those whose
+    /// metadata passes `filter`, most similar first
+    pub fn top_k_where<F>(&self, query: &[f32], k: usize, filter: F) -> Vec<Match<'_>>
+    where
+        F: Fn(&Metadata) -> bool,
+    {
+        let query_norm = norm(query);
+        if k == 0 || query_norm == 0.0 || self.dimensions() != Some(query.len()) {
+            return Vec::new();
+        }
+
+        // The k best so far, the worst on top to be pushed out
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        for (position, entry) in self.entries.iter().enumerate() {
+            if !filter(&entry.metadata) {
+                continue;
+            }
+            let norm = self.norms[position];
+            let score = if norm == 0.0 {
+                0.0
+            } else {
+                dot(query, &entry.values) / (query_norm * norm)
+            };
+            best.push(Reverse(Scored { score, position }));
+            if best.len() > k {
+                best.pop();
+            }
+        }
+
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(scored)| Match {
+                entry: &self.entries[scored.position],
+                score: scored.score,
+            })
+            .collect()
+    }
+
+    fn check(&self, entry: &Entry) -> Result<(), Error> {
+        if entry.values.is_empty() {
+            return Err(Error::InvalidArgument(
+                format!("vector {:?} is empty", entry.id).into(),
+            ));
+        }
+        // A replaced vector may be the one setting the length
+        let others = self
+            .entries
+            .iter()
+            .find(|other| other.id != entry.id)
+            .map(|other| other.values.len());
+        match others {
+            Some(len) if len != entry.values.len() => Err(Error::InvalidArgument(
+                format!(
+                    "vector {:?} has {} dimensions, the index {len}",
+                    entry.id,
+                    entry.values.len()
+                )
+                .into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An entry's score and position, ordered by score and then position so
+/// ties keep insertion order
+struct Scored {
+    score: f32,
+    position: usize,
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.position.cmp(&self.position))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn norm(values: &[f32]) -> f32 {
+    dot(values, values).sqrt()
+}
+
+#[cfg(feature = "serde")]
+mod file {
+    use std::path::Path;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{Entry, VectorIndex};
+    use crate::error::{Error, SetupError};
+
+    /// The version [`VectorIndex::save`] writes
+    const VERSION: u32 = 1;
+
+    #[derive(Serialize, Deserialize)]
+    struct File<E> {
+        version: u32,
+        entries: E,
+    }
+
+    impl VectorIndex {
+        /// Writes the index to a JSON file at `path`
+        ///
+        /// Write failures are reported as [`Error::Setup`].
+        pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+            let path = path.as_ref();
+            let file = File {
+                version: VERSION,
+                entries: &self.entries,
+            };
+            let json = serde_json::to_vec(&file).map_err(|e| Error::InvalidContent(Box::new(e)))?;
+
+            tokio::fs::write(path, json)
+                .await
+                .map_err(|e| SetupError::new(format!("failed to write {}", path.display()), e))
+        }
+
+        /// Reads an index written by [`save`](Self::save)
+        ///
+        /// Read failures are reported as [`Error::Setup`]; files that aren't
+        /// indexes, or hold vectors of differing lengths, as
+        /// [`Error::InvalidContent`].
+        pub async fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+            let path = path.as_ref();
+            let json = tokio::fs::read(path)
+                .await
+                .map_err(|e| SetupError::new(format!("failed to read {}", path.display()), e))?;
+            let file: File<Vec<Entry>> =
+                serde_json::from_slice(&json).map_err(|e| Error::InvalidContent(Box::new(e)))?;
+            if file.version > VERSION {
+                return Err(Error::InvalidContent(
+                    format!(
+                        "vector index version {} is newer than the supported {VERSION}",
+                        file.version
+                    )
+                    .into(),
+                ));
+            }
+
+            let mut index = VectorIndex::new();
+            for entry in file.entries {
+                index
+                    .insert(entry.id, entry.values, entry.metadata)
+                    .map_err(|e| match e {
+                        Error::InvalidArgument(e) => Error::InvalidContent(e),
+                        e => e,
+                    })?;
+            }
+            Ok(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(pairs: &[(&str, &str)]) -> Metadata {
+        pairs
+            .iter()
+            .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+            .collect()
+    }
+
+    fn ids<'a>(matches: &[Match<'a>]) -> Vec<&'a str> {
+        matches.iter().map(|m| m.entry.id.as_str()).collect()
+    }
+
+    #[test]
+    fn searches_with_filters() {
+        let mut index = VectorIndex::new();
+        index
+            .insert("a", vec![1.0, 0.0, 0.0], meta(&[("tenant", "x")]))
+            .unwrap();
+        index
+            .insert("b", vec![0.8, 0.6, 0.0], meta(&[("tenant", "y")]))
+            .unwrap();
+        index
+            .insert("c", vec![0.0, 1.0, 0.0], meta(&[("tenant", "x")]))
+            .unwrap();
+        index
+            .insert("d", vec![0.0, 0.0, 1.0], Metadata::new())
+            .unwrap();
+
+        let query = [1.0, 0.1, 0.0];
+        let matches = index.top_k(&query, 3);
+        assert_eq!(ids(&matches), ["a", "b", "c"]);
+        assert!(matches[0].score > 0.99 && matches[0].score <= 1.0);
+
+        let x = index.top_k_where(&query, 3, |m| m.get("tenant").is_some_and(|t| t == "x"));
+        assert_eq!(ids(&x), ["a", "c"]);
+
+        // Mismatched queries match nothing
+        assert!(index.top_k(&[1.0, 0.0], 3).is_empty());
+        assert!(index.top_k(&[0.0; 3], 3).is_empty());
+        assert!(index.insert("e", vec![1.0], Metadata::new()).is_err());
+
+        // Replacing and removing keep lookups right
+        index
+            .insert("a", vec![0.0, 0.0, 2.0], Metadata::new())
+            .unwrap();
+        assert_eq!(ids(&index.top_k(&[0.0, 0.0, 1.0], 2)), ["a", "d"]);
+        assert_eq!(index.remove("a").unwrap().id, "a");
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get("d").unwrap().values, [0.0, 0.0, 1.0]);
+        assert!(index.get("a").is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn saves_and_loads() {
+        let mut index = VectorIndex::new();
+        index
+            .insert("a", vec![1.0, 0.5], meta(&[("lang", "en")]))
+            .unwrap();
+        index.insert("b", vec![-0.5, 1.0], Metadata::new()).unwrap();
+
+        let path = std::env::temp_dir().join(format!("vector-index-{}.json", std::process::id()));
+        index.save(&path).await.unwrap();
+        let loaded = VectorIndex::load(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.entries(), index.entries());
+        assert_eq!(ids(&loaded.top_k(&[1.0, 0.4], 1)), ["a"]);
+
+        std::fs::write(&path, r#"{"version": 1, "entries": [{"id": "a", "values": [1.0]}, {"id": "b", "values": [1.0, 2.0]}]}"#).unwrap();
+        let err = VectorIndex::load(&path).await.unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(err, Error::InvalidContent(_)), "{err}");
+    }
+}
@@ -20,6 +20,36 @@ pub enum Error {
     InvalidArgument(Box<dyn StdError + Send + Sync>),
     /// Malformed or unsupported content structure
     InvalidContent(Box<dyn StdError + Send + Sync>),
+    /// The prompt has more tokens than the model accepts
+    ContextOverflow {
+        /// Tokens in the prompt
+        used: i32,
+        /// The model's input token limit
+        limit: i32,
+        /// The prompt's largest parts, largest first
+        largest_parts: Vec<PartSize>,
+    },
+}
+
+/// Where a part sits in a prompt and roughly how big it is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartSize {
+    /// Index of the part's content, `None` for the system instruction
+    pub content: Option<usize>,
+    /// Index of the part within its content
+    pub part: usize,
+    /// Encoded size of the part in bytes
+    pub bytes: usize,
+}
+
+impl fmt::Display for PartSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.content {
+            Some(content) => write!(f, "contents[{content}]")?,
+            None => f.write_str("system_instruction")?,
+        }
+        write!(f, ".parts[{}] ({} bytes)", self.part, self.bytes)
+    }
 }
 
 impl Error {
@@ -33,6 +63,7 @@ impl Error {
             Error::Auth(e) => e.source().unwrap_or(e),
             Error::InvalidArgument(_) => self,
             Error::InvalidContent(_) => self,
+            Error::ContextOverflow { .. } => self,
         }
     }
 }
@@ -47,6 +78,21 @@ impl fmt::Display for Error {
             Error::Auth(e) => write!(f, "Authentication Error: {e}"),
             Error::InvalidArgument(msg) => write!(f, "Invalid argument: {msg}"),
             Error::InvalidContent(msg) => write!(f, "Invalid content: {msg}"),
+            Error::ContextOverflow {
+                used,
+                limit,
+                largest_parts,
+            } => {
+                write!(
+                    f,
+                    "Context overflow: prompt has {used} tokens, model accepts {limit}"
+                )?;
+                for (i, part) in largest_parts.iter().enumerate() {
+                    let sep = if i == 0 { "; largest parts: " } else { ", " };
+                    write!(f, "{sep}{part}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -61,6 +107,7 @@ impl StdError for Error {
             Error::Auth(e) => e.source(),
             Error::InvalidArgument(e) => e.source(),
             Error::InvalidContent(e) => e.source(),
+            Error::ContextOverflow { .. } => None,
         }
     }
 }
@@ -163,6 +210,13 @@ pub enum ActionErrorBlame {
 pub enum NetError {
     TransportFailure(TonicTransportError),
     ServiceUnavailable(TonicStatus),
+    /// The client was shut down before or during the call
+    Shutdown,
+    /// The circuit breaker for this model (or, if empty, the client) is open
+    CircuitOpen(String),
+    /// The call outlived its [`deadline::scope`](crate::deadline::scope), or
+    /// an operation its [`Backoff::timeout`](crate::operation::Backoff::timeout)
+    DeadlineExceeded,
 }
 
 impl fmt::Display for NetError {
@@ -170,6 +224,10 @@ impl fmt::Display for NetError {
         match self {
             NetError::TransportFailure(e) => write!(f, "Transport failure: {e}"),
             NetError::ServiceUnavailable(e) => write!(f, "Service unavailable: {e}"),
+            NetError::Shutdown => f.write_str("Client is shut down"),
+            NetError::CircuitOpen(scope) if scope.is_empty() => f.write_str("Circuit open"),
+            NetError::CircuitOpen(scope) => write!(f, "Circuit open for {scope}"),
+            NetError::DeadlineExceeded => f.write_str("Deadline exceeded"),
         }
     }
 }
@@ -179,6 +237,7 @@ impl StdError for NetError {
         match self {
             NetError::TransportFailure(e) => Some(e),
             NetError::ServiceUnavailable(e) => Some(e),
+            NetError::Shutdown | NetError::CircuitOpen(_) | NetError::DeadlineExceeded => None,
         }
     }
 }
@@ -0,0 +1,464 @@
+//! Failing over between regional endpoints
+//!
+//! Give [`ClientBuilder::failover`](crate::client::ClientBuilder::failover)
+//! an ordered list of endpoints and the client sends calls to the first.
+//! Once it answers `UNAVAILABLE` several times in a row, calls move to the
+//! next endpoint, wrapping around after the last. While away from the
+//! primary, one call every [`probe_every`](Failover::probe_every) is sent
+//! to it instead, and calls move back as soon as one succeeds.
+//!
+//! Each move is reported to [`on_event`](Failover::on_event) and, with the
+//! `tracing` feature, logged.
+//!
+//! Cached contents, files and operations exist only in the region that
+//! created them. Calls about those the client created, including
+//! generations using a cached content, keep going to that region.
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::{failover::FailoverEvent, Client, Failover};
+//!
+//! # async fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = Client::builder()
+//!     .failover(
+//!         Failover::new([
+//!             "https://us-gemini.example.com",
+//!             "https://eu-gemini.example.com",
+//!         ])
+//!         .on_event(|event| {
+//!             if let FailoverEvent::FailedOver { from, to } = event {
+//!                 eprintln!("{from} is unavailable, using {to}");
+//!             }
+//!         }),
+//!     )
+//!     .build("your-api-key")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+use tonic::{transport::Channel, Code, Status};
+
+use crate::error::{Error, NetError};
+use crate::proto::{
+    cache_service_client::CacheServiceClient, file_service_client::FileServiceClient,
+    generative_service_client::GenerativeServiceClient,
+    longrunning::operations_client::OperationsClient, model_service_client::ModelServiceClient,
+};
+
+/// Ordered endpoints a client fails over between
+///
+/// See the [module docs](self).
+#[derive(Clone)]
+pub struct Failover {
+    /// Endpoints in order of preference; the first is the primary
+    pub endpoints: Vec<String>,
+    /// Consecutive `UNAVAILABLE` answers that make calls move on
+    pub unavailable_after: u32,
+    /// How often the primary is tried while calls go elsewhere
+    pub probe_every: Duration,
+    on_event: Option<EventHook>,
+}
+
+type EventHook = Arc<dyn Fn(FailoverEvent<'_>) + Send + Sync>;
+
+impl fmt::Debug for Failover {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Failover")
+            .field("endpoints", &self.endpoints)
+            .field("unavailable_after", &self.unavailable_after)
+            .field("probe_every", &self.probe_every)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Failover {
+    /// Fails over between `endpoints` after 3 `UNAVAILABLE` answers in a
+    /// row, probing the primary every 30 seconds
+    pub fn new<I>(endpoints: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            endpoints: endpoints.into_iter().map(Into::into).collect(),
+            unavailable_after: 3,
+            probe_every: Duration::from_secs(30),
+            on_event: None,
+        }
+    }
+
+    /// Sets how many `UNAVAILABLE` answers in a row make calls move on
+    pub fn unavailable_after(mut self, unavailable_after: u32) -> Self {
+        self.unavailable_after = unavailable_after;
+        self
+    }
+
+    /// Sets how often the primary is tried while calls go elsewhere
+    pub fn probe_every(mut self, probe_every: Duration) -> Self {
+        self.probe_every = probe_every;
+        self
+    }
+
+    /// Calls `f` on every [`FailoverEvent`], e.g. to count them as metrics
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(FailoverEvent<'_>) + Send + Sync + 'static,
+    {
+        self.on_event = Some(Arc::new(f));
+        self
+    }
+}
+
+/// A change of the endpoint a client sends calls to
+///
+/// See [`Failover::on_event`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FailoverEvent<'a> {
+    /// `from` kept answering `UNAVAILABLE`, so calls now go to `to`
+    FailedOver { from: &'a str, to: &'a str },
+    /// The primary was tried and is still unavailable
+    ProbeFailed { primary: &'a str },
+    /// The primary answered again, so calls went back to it from `from`
+    Recovered { primary: &'a str, from: &'a str },
+}
+
+/// The service clients of one endpoint
+#[derive(Clone, Debug)]
+pub(crate) struct Region {
+    pub(crate) uri: String,
+    pub(crate) gc: GenerativeServiceClient<Channel>,
+    pub(crate) cc: CacheServiceClient<Channel>,
+    pub(crate) mc: ModelServiceClient<Channel>,
+    pub(crate) fc: FileServiceClient<Channel>,
+    pub(crate) oc: OperationsClient<Channel>,
+}
+
+impl Region {
+    pub(crate) fn new(uri: String, channel: Channel) -> Self {
+        Self {
+            uri,
+            gc: GenerativeServiceClient::new(channel.clone()),
+            cc: CacheServiceClient::new(channel.clone()),
+            mc: ModelServiceClient::new(channel.clone()),
+            fc: FileServiceClient::new(channel.clone()),
+            oc: OperationsClient::new(channel),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Route {
+    /// Index of the region calls go to
+    active: usize,
+    /// `UNAVAILABLE` answers in a row from the active region
+    unavailable: u32,
+    /// When the primary is tried next, while away from it
+    probe_at: Option<Instant>,
+    /// Whether a call is trying the primary
+    probing: bool,
+}
+
+/// How many resources' regions a client remembers
+const ORIGINS: usize = 1024;
+
+/// The regions region-scoped resources were created in, by name
+#[derive(Debug, Default)]
+struct Origins {
+    regions: HashMap<String, usize>,
+    /// Names in the order they were pinned, to forget the oldest
+    order: VecDeque<String>,
+}
+
+/// The regions of a client and which one calls go to, shared by clones
+#[derive(Debug)]
+pub(crate) struct Regions {
+    /// Endpoints in order of preference
+    uris: Vec<String>,
+    /// Service clients by endpoint, dropped by [`Self::close`]
+    clients: RwLock<Vec<Arc<Region>>>,
+    failover: Option<Failover>,
+    route: Mutex<Route>,
+    origins: Mutex<Origins>,
+}
+
+impl Regions {
+    /// `regions` must not be empty; without `failover` only the first is used
+    pub(crate) fn new(regions: Vec<Region>, failover: Option<Failover>) -> Self {
+        assert!(!regions.is_empty(), "a client needs an endpoint");
+        Self {
+            uris: regions.iter().map(|region| region.uri.clone()).collect(),
+            clients: RwLock::new(regions.into_iter().map(Arc::new).collect()),
+            failover,
+            route: Mutex::default(),
+            origins: Mutex::default(),
+        }
+    }
+
+    /// Picks the region for a call, returning its index for [`Self::record`]
+    pub(crate) fn pick(&self) -> Result<(usize, Arc<Region>), Error> {
+        let index = match &self.failover {
+            Some(failover) => {
+                let mut route = self.route.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                match route.probe_at {
+                    // One call per period tries the primary, in case an
+                    // earlier one never finished
+                    Some(at) if now >= at => {
+                        route.probing = true;
+                        route.probe_at = Some(now + failover.probe_every);
+                        0
+                    }
+                    _ => route.active,
+                }
+            }
+            None => 0,
+        };
+        Ok((index, self.region(index)?))
+    }
+
+    /// The service clients of region `index`, unless they were closed
+    pub(crate) fn region(&self, index: usize) -> Result<Arc<Region>, Error> {
+        let clients = self.clients.read().unwrap_or_else(|e| e.into_inner());
+        clients
+            .get(index)
+            .cloned()
+            .ok_or(Error::Net(NetError::Shutdown))
+    }
+
+    /// Drops the service clients, so connections close once the calls still
+    /// using them end
+    pub(crate) fn close(&self) {
+        self.clients
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// The region calls go to, not counting probes of the primary
+    #[cfg(feature = "file-upload")]
+    pub(crate) fn active(&self) -> (usize, &str) {
+        let route = self.route.lock().unwrap_or_else(|e| e.into_inner());
+        (route.active, &self.uris[route.active])
+    }
+
+    /// The region that created the resource `name`, if the client did
+    pub(crate) fn origin(&self, name: &str) -> Option<usize> {
+        let origins = self.origins.lock().unwrap_or_else(|e| e.into_inner());
+        origins.regions.get(name).copied()
+    }
+
+    /// Remembers that region `index` created the resource `name`
+    pub(crate) fn pin(&self, name: &str, index: usize) {
+        if self.uris.len() < 2 || name.is_empty() {
+            return;
+        }
+        let mut origins = self.origins.lock().unwrap_or_else(|e| e.into_inner());
+        if origins.regions.insert(name.to_owned(), index).is_none() {
+            origins.order.push_back(name.to_owned());
+        }
+        if origins.order.len() > ORIGINS {
+            if let Some(oldest) = origins.order.pop_front() {
+                origins.regions.remove(&oldest);
+            }
+        }
+    }
+
+    /// Forgets the region of a deleted resource
+    pub(crate) fn forget(&self, name: &str) {
+        let mut origins = self.origins.lock().unwrap_or_else(|e| e.into_inner());
+        if origins.regions.remove(name).is_some() {
+            origins.order.retain(|pinned| pinned != name);
+        }
+    }
+
+    /// Counts the outcome of a call sent to region `index`
+    pub(crate) fn record(&self, index: usize, status: Option<&Status>) {
+        let Some(failover) = &self.failover else {
+            return;
+        };
+        let unavailable = status.is_some_and(|status| status.code() == Code::Unavailable);
+        // Emitted once the lock is released, so handlers may call the client
+        if let Some(event) = self.advance(failover, index, unavailable) {
+            emit(failover, event);
+        }
+    }
+
+    fn advance(
+        &self,
+        failover: &Failover,
+        index: usize,
+        unavailable: bool,
+    ) -> Option<FailoverEvent<'_>> {
+        let mut route = self.route.lock().unwrap_or_else(|e| e.into_inner());
+        let primary = &self.uris[0];
+
+        if index == 0 && route.probing {
+            route.probing = false;
+            if unavailable {
+                return Some(FailoverEvent::ProbeFailed { primary });
+            }
+            let from = &self.uris[route.active];
+            *route = Route::default();
+            return Some(FailoverEvent::Recovered { primary, from });
+        }
+        // Answers from a region calls already left don't count
+        if index != route.active {
+            return None;
+        }
+
+        if !unavailable {
+            route.unavailable = 0;
+            return None;
+        }
+        route.unavailable += 1;
+        if route.unavailable < failover.unavailable_after.max(1) || self.uris.len() < 2 {
+            return None;
+        }
+
+        let from = route.active;
+        let to = (from + 1) % self.uris.len();
+        *route = Route {
+            active: to,
+            probe_at: (to != 0).then(|| Instant::now() + failover.probe_every),
+            ..Route::default()
+        };
+        Some(FailoverEvent::FailedOver {
+            from: &self.uris[from],
+            to: &self.uris[to],
+        })
+    }
+}
+
+fn emit(failover: &Failover, event: FailoverEvent<'_>) {
+    #[cfg(feature = "tracing")]
+    match event {
+        FailoverEvent::FailedOver { from, to } => {
+            tracing::warn!(from, to, "endpoint unavailable, failing over")
+        }
+        FailoverEvent::ProbeFailed { primary } => {
+            tracing::debug!(primary, "primary endpoint still unavailable")
+        }
+        FailoverEvent::Recovered { primary, from } => {
+            tracing::info!(primary, from, "primary endpoint recovered")
+        }
+    }
+    if let Some(on_event) = &failover.on_event {
+        on_event(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions(failover: Failover) -> Regions {
+        let regions = failover
+            .endpoints
+            .iter()
+            .map(|uri| {
+                let channel = Channel::from_shared(uri.clone()).unwrap().connect_lazy();
+                Region::new(uri.clone(), channel)
+            })
+            .collect();
+        Regions::new(regions, Some(failover))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fails_over_and_recovers() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        let regions = regions(
+            Failover::new(["http://a.test", "http://b.test"])
+                .unavailable_after(2)
+                .probe_every(Duration::from_millis(50))
+                .on_event(move |event| log.lock().unwrap().push(format!("{event:?}"))),
+        );
+        let unavailable = Status::unavailable("down");
+        let internal = Status::internal("oops");
+
+        // Other errors and successes break the streak
+        regions.record(0, Some(&unavailable));
+        regions.record(0, Some(&internal));
+        regions.record(0, Some(&unavailable));
+        assert_eq!(regions.pick().unwrap().0, 0);
+        regions.record(0, Some(&unavailable));
+
+        assert_eq!(regions.pick().unwrap().1.uri, "http://b.test");
+
+        // One call per period tries the primary
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(regions.pick().unwrap().0, 0);
+        assert_eq!(regions.pick().unwrap().0, 1);
+        regions.record(0, Some(&unavailable));
+        assert_eq!(regions.pick().unwrap().0, 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(regions.pick().unwrap().0, 0);
+        regions.record(0, None);
+        assert_eq!(regions.pick().unwrap().0, 0);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                r#"FailedOver { from: "http://a.test", to: "http://b.test" }"#,
+                r#"ProbeFailed { primary: "http://a.test" }"#,
+                r#"Recovered { primary: "http://a.test", from: "http://b.test" }"#,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn wraps_around() {
+        let regions = regions(
+            Failover::new(["http://a.test", "http://b.test", "http://c.test"]).unavailable_after(1),
+        );
+        let unavailable = Status::unavailable("down");
+        for want in [1, 2, 0] {
+            let (index, _) = regions.pick().unwrap();
+            regions.record(index, Some(&unavailable));
+            assert_eq!(regions.pick().unwrap().0, want);
+        }
+    }
+
+    #[tokio::test]
+    async fn pins_resources_to_their_origin() {
+        let regions = regions(Failover::new(["http://a.test", "http://b.test"]));
+        let origin = |name| {
+            regions
+                .origin(name)
+                .map(|index| regions.uris[index].as_str())
+        };
+
+        regions.pin("cachedContents/a", 0);
+        regions.pin("files/b", 1);
+        assert_eq!(origin("cachedContents/a"), Some("http://a.test"));
+        assert_eq!(origin("files/b"), Some("http://b.test"));
+        assert_eq!(origin("files/c"), None);
+
+        regions.forget("cachedContents/a");
+        assert_eq!(origin("cachedContents/a"), None);
+
+        for i in 0..ORIGINS {
+            regions.pin(&format!("operations/{i}"), 0);
+        }
+        assert_eq!(origin("files/b"), None);
+        assert_eq!(origin("operations/0"), Some("http://a.test"));
+    }
+
+    #[tokio::test]
+    async fn single_endpoints_pin_nothing() {
+        let regions = regions(Failover::new(["http://a.test"]));
+        regions.pin("files/a", 0);
+        assert!(regions.origin("files/a").is_none());
+    }
+}
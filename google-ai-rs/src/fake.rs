@@ -0,0 +1,143 @@
+//! A gRPC server on localhost answering from a closure, for tests that
+//! need the client to get responses without the API
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bytes::Bytes;
+use h2::{server::SendResponse, RecvStream};
+use http::{HeaderMap, HeaderValue, Request, Response};
+use tokio::net::TcpListener;
+use tonic::Status;
+
+use crate::{auth::Auth, client::Client, failover::Failover};
+
+type Handler = Arc<dyn Fn(&str, Bytes) -> (Vec<Vec<u8>>, Option<Status>) + Send + Sync>;
+
+/// Answers calls with what its handler returns for the method path and
+/// encoded request
+pub(crate) struct FakeServer {
+    addr: SocketAddr,
+    calls: Arc<AtomicUsize>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl FakeServer {
+    /// Answers unary calls with one message or a status
+    pub(crate) async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&str, Bytes) -> Result<Vec<u8>, Status> + Send + Sync + 'static,
+    {
+        Self::start_streaming(move |path, body| match handler(path, body) {
+            Ok(reply) => (vec![reply], None),
+            Err(status) => (Vec::new(), Some(status)),
+        })
+        .await
+    }
+
+    /// Answers with the messages, then fails with the status if there's one
+    pub(crate) async fn start_streaming<F>(handler: F) -> Self
+    where
+        F: Fn(&str, Bytes) -> (Vec<Vec<u8>>, Option<Status>) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handler: Handler = Arc::new(handler);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let (counter, open) = (calls.clone(), connections.clone());
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let (handler, counter, open) = (handler.clone(), counter.clone(), open.clone());
+                tokio::spawn(async move {
+                    let Ok(mut connection) = h2::server::handshake(socket).await else {
+                        return;
+                    };
+                    open.fetch_add(1, Ordering::SeqCst);
+                    while let Some(Ok((request, respond))) = connection.accept().await {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        tokio::spawn(answer(handler.clone(), request, respond));
+                    }
+                    open.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Self {
+            addr,
+            calls,
+            connections,
+        }
+    }
+
+    /// The endpoint to send calls to
+    pub(crate) fn uri(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A client sending every call here
+    pub(crate) async fn client(&self) -> Client {
+        Client::builder()
+            .failover(Failover::new([self.uri()]))
+            .build(Auth::new("fake"))
+            .await
+            .unwrap()
+    }
+
+    /// Calls received so far
+    pub(crate) fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Connections currently open
+    pub(crate) fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+}
+
+async fn answer(handler: Handler, request: Request<RecvStream>, mut respond: SendResponse<Bytes>) {
+    let path = request.uri().path().to_owned();
+    let mut body = request.into_body();
+    let mut message = Vec::new();
+    while let Some(Ok(chunk)) = body.data().await {
+        let _ = body.flow_control().release_capacity(chunk.len());
+        message.extend_from_slice(&chunk);
+    }
+    // Past the compression flag and length prefix
+    let message = Bytes::from(message).slice(5..);
+
+    let response = Response::builder()
+        .header("content-type", "application/grpc")
+        .body(())
+        .unwrap();
+    let Ok(mut stream) = respond.send_response(response, false) else {
+        return;
+    };
+    let mut trailers = HeaderMap::new();
+    let (replies, status) = handler(&path, message);
+    for reply in replies {
+        let mut frame = Vec::with_capacity(reply.len() + 5);
+        frame.push(0);
+        frame.extend_from_slice(&(reply.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&reply);
+        let _ = stream.send_data(frame.into(), false);
+    }
+    match status {
+        None => {
+            trailers.insert("grpc-status", HeaderValue::from(0));
+        }
+        Some(status) => {
+            trailers.insert("grpc-status", HeaderValue::from(status.code() as i32));
+            if let Ok(message) = HeaderValue::from_str(status.message()) {
+                trailers.insert("grpc-message", message);
+            }
+        }
+    }
+    let _ = stream.send_trailers(trailers);
+}
@@ -0,0 +1,209 @@
+//! Fetching remote resources into [`Part`]s
+//!
+//! Enabled by the `url-fetch` feature. Downloads are bounded in size and
+//! restricted to MIME types the Gemini API understands, so a bad link fails
+//! early with a descriptive error instead of at request time.
+//!
+//! # Example
+//! ```rust,no_run
+//! use google_ai_rs::Part;
+//!
+//! # async fn f() -> Result<(), google_ai_rs::Error> {
+//! let image = Part::from_url("https://example.com/cat.png").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{error::Error as StdError, fmt, time::Duration};
+
+use crate::{
+    error::{Error, SetupError},
+    Part,
+};
+
+/// Limits applied when fetching a URL with [`Part::from_url_with`].
+#[derive(Clone, Debug)]
+pub struct FetchOptions {
+    /// Maximum accepted body size in bytes.
+    pub max_bytes: usize,
+    /// Accepted MIME types. Entries may end in `/*` to match a whole
+    /// top-level type (e.g. `image/*`).
+    pub allowed_mime_types: Vec<String>,
+    /// Timeout for the whole request.
+    pub timeout: Duration,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            // Inline data is capped at 20MB per request
+            max_bytes: 20 * 1024 * 1024,
+            allowed_mime_types: [
+                "image/*",
+                "audio/*",
+                "video/*",
+                "text/*",
+                "application/pdf",
+                "application/json",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl FetchOptions {
+    /// Sets the maximum accepted body size in bytes.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Replaces the accepted MIME types.
+    pub fn allowed_mime_types<I, S>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_mime_types = types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn allows(&self, mime_type: &str) -> bool {
+        self.allowed_mime_types
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(top) => mime_type
+                    .split_once('/')
+                    .is_some_and(|(t, _)| t.eq_ignore_ascii_case(top)),
+                None => allowed.eq_ignore_ascii_case(mime_type),
+            })
+    }
+}
+
+/// Reasons a fetched resource was rejected.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FetchError {
+    /// The server responded with a non-success status.
+    Status(u16),
+    /// The response had no `Content-Type` header.
+    MissingMimeType,
+    /// The response's MIME type is not in [`FetchOptions::allowed_mime_types`].
+    UnsupportedMimeType(String),
+    /// The body exceeds [`FetchOptions::max_bytes`].
+    TooLarge { limit: usize },
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Status(code) => write!(f, "server responded with status {code}"),
+            FetchError::MissingMimeType => write!(f, "response has no content type"),
+            FetchError::UnsupportedMimeType(mime) => write!(f, "unsupported content type {mime}"),
+            FetchError::TooLarge { limit } => write!(f, "response exceeds {limit} bytes"),
+        }
+    }
+}
+
+impl StdError for FetchError {}
+
+impl Part {
+    /// Downloads `url` and wraps its body in an inline data part, using the
+    /// default [`FetchOptions`].
+    pub async fn from_url(url: &str) -> Result<Self, Error> {
+        Self::from_url_with(url, &FetchOptions::default()).await
+    }
+
+    /// Downloads `url` and wraps its body in an inline data part.
+    ///
+    /// Transport failures are reported as [`Error::Setup`]; rejected
+    /// responses as [`Error::InvalidContent`] wrapping a [`FetchError`].
+    pub async fn from_url_with(url: &str, options: &FetchOptions) -> Result<Self, Error> {
+        let context = || format!("failed to fetch {url}");
+
+        let client = reqwest::Client::builder()
+            .timeout(options.timeout)
+            .build()
+            .map_err(|e| SetupError::new(context(), e))?;
+
+        let mut response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| SetupError::new(context(), e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(rejected(FetchError::Status(status.as_u16())));
+        }
+
+        let mime_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split(';').next().unwrap_or(v).trim().to_ascii_lowercase())
+            .ok_or_else(|| rejected(FetchError::MissingMimeType))?;
+
+        if !options.allows(&mime_type) {
+            return Err(rejected(FetchError::UnsupportedMimeType(mime_type)));
+        }
+
+        let too_large = || {
+            rejected(FetchError::TooLarge {
+                limit: options.max_bytes,
+            })
+        };
+
+        if response
+            .content_length()
+            .is_some_and(|len| len > options.max_bytes as u64)
+        {
+            return Err(too_large());
+        }
+
+        // Content-Length can be absent or wrong, so enforce the limit while reading.
+        let mut data = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| SetupError::new(context(), e))?
+        {
+            if data.len() + chunk.len() > options.max_bytes {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(Part::blob(&mime_type, data))
+    }
+}
+
+fn rejected(err: FetchError) -> Error {
+    Error::InvalidContent(Box::new(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows() {
+        let options = FetchOptions::default();
+        assert!(options.allows("image/png"));
+        assert!(options.allows("application/pdf"));
+        assert!(!options.allows("application/octet-stream"));
+
+        let options = options.allowed_mime_types(["image/png"]);
+        assert!(options.allows("image/png"));
+        assert!(!options.allows("image/jpeg"));
+    }
+}
@@ -0,0 +1,122 @@
+//! Saving responses as test fixtures
+//!
+//! Enabled by the `fixtures` feature. [`Response::save_json`] writes a
+//! response to a JSON file that can be checked in, and
+//! [`Response::load_json`] reads it back, so parsing code can be tested
+//! through [`TryFromCandidates`](crate::TryFromCandidates) without calling
+//! the API.
+//!
+//! The file holds the response's exact protobuf encoding, plus its text
+//! and model version for reviewers; only the encoding is read back.
+//!
+//! # Example
+#![cfg_attr(feature = "serde", doc = "```rust,no_run")]
+#![cfg_attr(not(feature = "serde"), doc = "```rust,ignore")]
+//! use google_ai_rs::{genai::Response, TryFromCandidates};
+//!
+//! # async fn record(model: google_ai_rs::GenerativeModel<'_>) -> Result<(), google_ai_rs::Error> {
+//! // Once, against the API
+//! let response = model.generate_content("List three colors as JSON").await?;
+//! response.save_json("tests/fixtures/colors.json").await?;
+//! # Ok(())
+//! # }
+//!
+//! # async fn replay() -> Result<(), google_ai_rs::Error> {
+//! // In tests
+//! let response = Response::load_json("tests/fixtures/colors.json").await?;
+//! let colors = Vec::<String>::try_from_candidates(&response.candidates)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, SetupError},
+    genai::Response,
+};
+
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    /// Not read back; shows what the response says in diffs
+    #[serde(default)]
+    model_version: String,
+    #[serde(default)]
+    text: String,
+    /// Base64 of the protobuf-encoded response
+    protobuf: String,
+}
+
+impl Response {
+    /// Writes the response to a JSON fixture at `path`
+    ///
+    /// Write failures are reported as [`Error::Setup`].
+    pub async fn save_json(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let fixture = Fixture {
+            model_version: self.model_version.clone(),
+            text: self.to_text(),
+            protobuf: STANDARD.encode(self.encode_to_vec()),
+        };
+        let json =
+            serde_json::to_vec_pretty(&fixture).map_err(|e| Error::InvalidContent(Box::new(e)))?;
+
+        tokio::fs::write(path, json)
+            .await
+            .map_err(|e| SetupError::new(format!("failed to write {}", path.display()), e))
+    }
+
+    /// Reads a response written by [`save_json`](Self::save_json)
+    ///
+    /// Read failures are reported as [`Error::Setup`]; files that aren't
+    /// fixtures as [`Error::InvalidContent`].
+    pub async fn load_json(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json = tokio::fs::read(path)
+            .await
+            .map_err(|e| SetupError::new(format!("failed to read {}", path.display()), e))?;
+
+        Self::from_fixture(&json)
+    }
+
+    fn from_fixture(json: &[u8]) -> Result<Self, Error> {
+        let fixture: Fixture =
+            serde_json::from_slice(json).map_err(|e| Error::InvalidContent(Box::new(e)))?;
+        let bytes = STANDARD
+            .decode(fixture.protobuf)
+            .map_err(|e| Error::InvalidContent(Box::new(e)))?;
+        Self::decode(bytes.as_slice()).map_err(|e| Error::InvalidContent(Box::new(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Candidate, Content};
+
+    #[tokio::test]
+    async fn round_trips() {
+        let response = Response {
+            candidates: vec![Candidate {
+                content: Some(Content::model("[\"red\", \"green\"]")),
+                ..Default::default()
+            }],
+            model_version: "gemini-2.5-flash".into(),
+            ..Default::default()
+        };
+
+        let path = std::env::temp_dir().join(format!("fixture-{}.json", std::process::id()));
+        response.save_json(&path).await.unwrap();
+        let loaded = Response::load_json(&path).await;
+        let json = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), response);
+        assert!(json.contains("red"), "{json}");
+        assert!(Response::from_fixture(b"{}").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+//! Function calling helpers
+//!
+//! When a model turn asks for several function calls, the follow-up turn has
+//! to answer each of them with a `FunctionResponse` part, in the order the
+//! calls were made. [`FunctionResponses`] pairs results with calls and
+//! reports the calls left unanswered. It can also cap the size of each
+//! response so one verbose function doesn't fill the context window.
+//!
+//! With the `serde` feature, [`transcript`] exports finished runs for
+//! evaluation and observability tools.
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::function::FunctionResponses;
+//! use google_ai_rs::{Content, FunctionCall};
+//! use prost_types::Struct;
+//!
+//! let call = |name: &str| FunctionCall {
+//!     name: name.into(),
+//!     ..Default::default()
+//! };
+//! let turn = Content::model((call("get_weather"), call("get_time")));
+//!
+//! // Results can come back in any order
+//! let follow_up = FunctionResponses::for_turn(&turn)
+//!     .respond("get_time", Struct::default())?
+//!     .respond("get_weather", Struct::default())?
+//!     .into_content()?;
+//!
+//! assert_eq!(follow_up.parts.len(), 2);
+//! # Ok::<(), google_ai_rs::Error>(())
+//! ```
+
+#[cfg(feature = "builtin-tools")]
+pub mod builtin;
+#[cfg(feature = "sandbox-tools")]
+pub mod sandbox;
+#[cfg(feature = "serde")]
+pub mod transcript;
+
+use std::{error::Error as StdError, fmt};
+
+use prost_types::{value::Kind, Struct, Value};
+
+use crate::{
+    error::Error,
+    proto::{part::Data, Content, FunctionCall, FunctionResponse, Part},
+};
+
+/// Responses to the function calls of one model turn
+#[derive(Clone, Debug)]
+pub struct FunctionResponses<'a> {
+    calls: Vec<&'a FunctionCall>,
+    responses: Vec<Option<Struct>>,
+    max_tokens: Option<usize>,
+}
+
+/// Rough size of a token in characters, for budgeting without a round trip
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Appended to strings shortened by [`FunctionResponses::max_response_tokens`]
+pub const TRUNCATED: &str = "…[truncated]";
+
+impl<'a> FunctionResponses<'a> {
+    /// Collects the function calls of a model turn, in order
+    pub fn for_turn(turn: &'a Content) -> Self {
+        let calls: Vec<_> = turn
+            .parts
+            .iter()
+            .filter_map(|part| match &part.data {
+                Some(Data::FunctionCall(call)) => Some(call),
+                _ => None,
+            })
+            .collect();
+        Self {
+            responses: vec![None; calls.len()],
+            calls,
+            max_tokens: None,
+        }
+    }
+
+    /// Caps each response at roughly `max_tokens` tokens
+    ///
+    /// Responses over the limit have their longest strings shortened, each
+    /// ending in [`TRUNCATED`], until the strings fit. Tokens are estimated
+    /// at four characters each.
+    pub fn max_response_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Records the result of a call
+    ///
+    /// `key` is the call's id or, for calls without one, its function name.
+    /// Repeated calls to the same function are answered in order.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidArgument`] if no unanswered call matches `key`.
+    pub fn respond(mut self, key: &str, response: Struct) -> Result<Self, Error> {
+        let pending = self
+            .calls
+            .iter()
+            .zip(&self.responses)
+            .position(|(call, response)| {
+                response.is_none()
+                    && if call.id.is_empty() {
+                        call.name == key
+                    } else {
+                        call.id == key
+                    }
+            });
+
+        match pending {
+            Some(i) => {
+                self.responses[i] = Some(response);
+                Ok(self)
+            }
+            None => Err(Error::InvalidArgument(
+                format!("no unanswered function call matches {key:?}").into(),
+            )),
+        }
+    }
+
+    /// Records results from an iterator of `(key, response)` pairs
+    ///
+    /// See [`respond`](Self::respond).
+    pub fn respond_all<I, K>(self, results: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, Struct)>,
+        K: AsRef<str>,
+    {
+        results.into_iter().try_fold(self, |this, (key, response)| {
+            this.respond(key.as_ref(), response)
+        })
+    }
+
+    /// Returns the calls without a response yet
+    pub fn missing(&self) -> Vec<&'a FunctionCall> {
+        self.calls
+            .iter()
+            .zip(&self.responses)
+            .filter(|(_, response)| response.is_none())
+            .map(|(call, _)| *call)
+            .collect()
+    }
+
+    /// Builds the user turn answering every call, in call order
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidContent`] holding [`MissingResponses`] if a
+    /// call has no response.
+    pub fn into_content(self) -> Result<Content, Error> {
+        let missing = self.missing();
+        if !missing.is_empty() {
+            return Err(Error::InvalidContent(Box::new(MissingResponses {
+                calls: missing.into_iter().cloned().collect(),
+            })));
+        }
+
+        let max_chars = self.max_tokens.map(|t| t.saturating_mul(CHARS_PER_TOKEN));
+        let parts = self
+            .calls
+            .into_iter()
+            .zip(self.responses)
+            .map(|(call, mut response)| {
+                if let (Some(max_chars), Some(response)) = (max_chars, &mut response) {
+                    truncate(response, max_chars);
+                }
+                Part {
+                    data: Some(Data::FunctionResponse(FunctionResponse {
+                        id: call.id.clone(),
+                        name: call.name.clone(),
+                        response,
+                    })),
+                    metadata: None,
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(Content::user(parts))
+    }
+}
+
+/// Shortens the longest strings of a response until they fit in `max_chars`
+fn truncate(response: &mut Struct, max_chars: usize) {
+    fn strings<'s>(value: &'s mut Value, out: &mut Vec<&'s mut String>) {
+        match &mut value.kind {
+            Some(Kind::StringValue(s)) => out.push(s),
+            Some(Kind::StructValue(s)) => s.fields.values_mut().for_each(|v| strings(v, out)),
+            Some(Kind::ListValue(l)) => l.values.iter_mut().for_each(|v| strings(v, out)),
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    response
+        .fields
+        .values_mut()
+        .for_each(|v| strings(v, &mut out));
+
+    let lens: Vec<usize> = out.iter().map(|s| s.chars().count()).collect();
+    if lens.iter().sum::<usize>() <= max_chars {
+        return;
+    }
+
+    // The largest length every string can keep while the total still fits
+    let mut sorted = lens.clone();
+    sorted.sort_unstable();
+    let mut remaining = max_chars;
+    let mut cap = usize::MAX;
+    for (i, len) in sorted.iter().enumerate() {
+        let share = remaining / (sorted.len() - i);
+        if *len > share {
+            cap = share;
+            break;
+        }
+        remaining -= len;
+    }
+
+    for (s, len) in out.into_iter().zip(lens) {
+        if len > cap {
+            let end = s.char_indices().nth(cap).map_or(s.len(), |(i, _)| i);
+            s.truncate(end);
+            s.push_str(TRUNCATED);
+        }
+    }
+}
+
+/// Function calls that were left without a response
+#[derive(Clone, Debug)]
+pub struct MissingResponses {
+    pub calls: Vec<FunctionCall>,
+}
+
+impl fmt::Display for MissingResponses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no response to function call")?;
+        if self.calls.len() > 1 {
+            write!(f, "s")?;
+        }
+        for (i, call) in self.calls.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{sep}{}", call.name)?;
+            if !call.id.is_empty() {
+                write!(f, " ({})", call.id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StdError for MissingResponses {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, id: &str) -> FunctionCall {
+        FunctionCall {
+            id: id.into(),
+            name: name.into(),
+            args: None,
+        }
+    }
+
+    fn response(key: &str) -> Struct {
+        Struct {
+            fields: [(
+                "key".to_owned(),
+                prost_types::Value {
+                    kind: Some(prost_types::value::Kind::StringValue(key.into())),
+                },
+            )]
+            .into(),
+        }
+    }
+
+    #[test]
+    fn orders_responses_by_call() {
+        let turn = Content::model((
+            "Let me check",
+            call("weather", "a"),
+            call("weather", ""),
+            call("weather", ""),
+        ));
+
+        let content = FunctionResponses::for_turn(&turn)
+            .respond_all([
+                ("weather", response("second")),
+                ("a", response("first")),
+                ("weather", response("third")),
+            ])
+            .unwrap()
+            .into_content()
+            .unwrap();
+
+        assert_eq!(content.role, "user");
+        let keys: Vec<_> = content
+            .parts
+            .iter()
+            .map(|part| match &part.data {
+                Some(Data::FunctionResponse(r)) => {
+                    match &r.response.as_ref().unwrap().fields["key"].kind {
+                        Some(prost_types::value::Kind::StringValue(key)) => key.as_str(),
+                        _ => unreachable!(),
+                    }
+                }
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(keys, ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn truncates_longest_strings() {
+        let turn = Content::model(call("search", ""));
+        let text = |s: String| Value {
+            kind: Some(Kind::StringValue(s)),
+        };
+        let result = Struct {
+            fields: [
+                ("title".to_owned(), text("t".repeat(10))),
+                ("body".to_owned(), text("é".repeat(1000))),
+            ]
+            .into(),
+        };
+
+        let content = FunctionResponses::for_turn(&turn)
+            .max_response_tokens(25)
+            .respond("search", result)
+            .unwrap()
+            .into_content()
+            .unwrap();
+
+        let Some(Data::FunctionResponse(r)) = &content.parts[0].data else {
+            panic!("expected a function response");
+        };
+        let fields = &r.response.as_ref().unwrap().fields;
+        assert_eq!(fields["title"], text("t".repeat(10)));
+        assert_eq!(fields["body"], text("é".repeat(90) + TRUNCATED));
+    }
+
+    #[test]
+    fn reports_missing_and_unknown() {
+        let turn = Content::model((call("weather", ""), call("time", "t1")));
+
+        let err = FunctionResponses::for_turn(&turn)
+            .respond("stocks", response("x"))
+            .unwrap_err();
+        assert!(err.to_string().contains("\"stocks\""), "{err}");
+
+        let responses = FunctionResponses::for_turn(&turn)
+            .respond("weather", response("x"))
+            .unwrap();
+        assert_eq!(responses.missing(), [&call("time", "t1")]);
+
+        let Err(Error::InvalidContent(err)) = responses.into_content() else {
+            panic!("expected missing responses");
+        };
+        let missing = err.downcast_ref::<MissingResponses>().unwrap();
+        assert_eq!(missing.calls, [call("time", "t1")]);
+        assert_eq!(
+            missing.to_string(),
+            "no response to function call time (t1)"
+        );
+    }
+}
@@ -0,0 +1,609 @@
+//! Reference functions that run locally
+//!
+//! A calculator, a clock and a unit converter: small, pure Rust functions
+//! for the things models tend to get wrong on their own. Hand [`tool`] to a
+//! model and answer its calls with [`FunctionResponses::respond_builtin`].
+//!
+//! | Function        | Arguments                                    |
+//! |-----------------|----------------------------------------------|
+//! | `calculate`     | `expression`                                 |
+//! | `current_time`  | `utc_offset_minutes` (optional)              |
+//! | `convert_units` | `value`, `from`, `to`                        |
+//!
+//! Failures, such as a malformed expression, are reported back to the model
+//! as an `error` field so it can correct itself.
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::function::{builtin, FunctionResponses};
+//!
+//! # async fn f(client: google_ai_rs::Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let model = client
+//!     .generative_model("gemini-2.5-flash")
+//!     .tools([builtin::tool()]);
+//! let mut session = model.start_chat();
+//!
+//! let response = session.send_message("How many pounds is 17% of 2,340 kg?").await?;
+//! if let Some(turn) = response.candidates.first().and_then(|c| c.content.as_ref()) {
+//!     let follow_up = FunctionResponses::for_turn(turn)
+//!         .respond_builtin()
+//!         .into_content()?;
+//!     session.send_message(follow_up).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`FunctionResponses::respond_builtin`]: super::FunctionResponses::respond_builtin
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use prost_types::{value::Kind, Struct, Value};
+
+use super::FunctionResponses;
+use crate::{
+    civil::civil_from_days,
+    proto::{FunctionCall, FunctionDeclaration, Schema, Tool},
+};
+
+/// Declarations of every built-in function
+pub fn tool() -> Tool {
+    let units: Vec<&str> = UNITS
+        .iter()
+        .map(|(unit, _, _)| *unit)
+        .chain(TEMPERATURES)
+        .collect();
+
+    Tool {
+        function_declarations: vec![
+            FunctionDeclaration {
+                name: "calculate".into(),
+                description: "Evaluates an arithmetic expression. Supports + - * / % ^, \
+                              parentheses, pi, e and sqrt, abs, ln, log, log2, exp, sin, cos, \
+                              tan, asin, acos, atan, floor, ceil and round."
+                    .into(),
+                parameters: Some(
+                    Schema::new_object()
+                        .property(
+                            "expression",
+                            Schema::new_string().description("e.g. (2 + 3) * sqrt(16)"),
+                        )
+                        .required(["expression"]),
+                ),
+                response: None,
+            },
+            FunctionDeclaration {
+                name: "current_time".into(),
+                description: "Returns the current date and time.".into(),
+                parameters: Some(Schema::new_object().property(
+                    "utc_offset_minutes",
+                    Schema::new_integer().description("Offset from UTC, defaults to 0"),
+                )),
+                response: None,
+            },
+            FunctionDeclaration {
+                name: "convert_units".into(),
+                description: "Converts a quantity between units of length, mass, time, \
+                              volume, speed, data or temperature."
+                    .into(),
+                parameters: Some(
+                    Schema::new_object()
+                        .property("value", Schema::new_number())
+                        .property("from", Schema::new_string().into_enum(units.clone()))
+                        .property("to", Schema::new_string().into_enum(units))
+                        .required(["value", "from", "to"]),
+                ),
+                response: None,
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+/// Runs a call to a built-in function
+///
+/// Returns `None` if `call` isn't to one.
+pub fn call(call: &FunctionCall) -> Option<Struct> {
+    let run = match call.name.as_str() {
+        "calculate" => run_calculate,
+        "current_time" => run_current_time,
+        "convert_units" => run_convert_units,
+        _ => return None,
+    };
+    let result = run(&call.args.clone().unwrap_or_default());
+
+    let fields = match result {
+        Ok(fields) => fields,
+        Err(err) => vec![("error", text(&err))],
+    };
+    Some(Struct {
+        fields: fields.into_iter().map(|(k, v)| (k.to_owned(), v)).collect(),
+    })
+}
+
+impl FunctionResponses<'_> {
+    /// Answers every unanswered call to a built-in function
+    pub fn respond_builtin(mut self) -> Self {
+        for (call, response) in self.calls.iter().zip(&mut self.responses) {
+            if response.is_none() {
+                *response = crate::function::builtin::call(call);
+            }
+        }
+        self
+    }
+}
+
+type Fields = Vec<(&'static str, Value)>;
+
+fn run_calculate(args: &Struct) -> Result<Fields, String> {
+    let result = calculate(string(args, "expression")?)?;
+    Ok(vec![("result", number(result))])
+}
+
+fn run_current_time(args: &Struct) -> Result<Fields, String> {
+    let offset = match args.fields.get("utc_offset_minutes") {
+        None => 0,
+        Some(_) => float(args, "utc_offset_minutes")? as i64,
+    };
+    current_time(offset)
+}
+
+fn run_convert_units(args: &Struct) -> Result<Fields, String> {
+    let value = float(args, "value")?;
+    let to = string(args, "to")?;
+    let converted = convert_units(value, string(args, "from")?, to)?;
+    Ok(vec![("value", number(converted)), ("unit", text(to))])
+}
+
+fn string<'a>(args: &'a Struct, name: &str) -> Result<&'a str, String> {
+    match args.fields.get(name).and_then(|v| v.kind.as_ref()) {
+        Some(Kind::StringValue(s)) => Ok(s),
+        _ => Err(format!("missing string argument {name}")),
+    }
+}
+
+fn float(args: &Struct, name: &str) -> Result<f64, String> {
+    match args.fields.get(name).and_then(|v| v.kind.as_ref()) {
+        Some(Kind::NumberValue(n)) => Ok(*n),
+        _ => Err(format!("missing number argument {name}")),
+    }
+}
+
+fn number(n: f64) -> Value {
+    Value {
+        kind: Some(Kind::NumberValue(n)),
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value {
+        kind: Some(Kind::StringValue(s.to_owned())),
+    }
+}
+
+// Calculator
+// -----------------------------------------------------------------
+
+fn calculate(expression: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        input: expression.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    parser.skip_space();
+    if parser.pos < parser.input.len() {
+        return Err(format!("unexpected input at {}", parser.pos));
+    }
+    if !value.is_finite() {
+        return Err("result is not a finite number".into());
+    }
+    Ok(value)
+}
+
+/// How deep parentheses, signs and powers may nest, so a hostile
+/// expression can't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn take_while(&mut self, f: fn(&u8) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.input.get(self.pos).is_some_and(f) {
+            self.pos += 1;
+        }
+        // Only ASCII is taken
+        std::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default()
+    }
+
+    fn skip_space(&mut self) {
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_space();
+        if self.input.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat(b'+') {
+                value += self.term()?;
+            } else if self.eat(b'-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat(b'*') {
+                value *= self.unary()?;
+            } else if self.eat(b'/') {
+                value /= self.unary()?;
+            } else if self.eat(b'%') {
+                value %= self.unary()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    // Every cycle of the grammar passes through here
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("expression nests deeper than {MAX_DEPTH} levels"));
+        }
+        self.depth += 1;
+        let value = self.unary_inner();
+        self.depth -= 1;
+        value
+    }
+
+    fn unary_inner(&mut self) -> Result<f64, String> {
+        if self.eat(b'-') {
+            Ok(-self.unary()?)
+        } else if self.eat(b'+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.atom()?;
+        if self.eat(b'^') {
+            // Right associative, and binds tighter than a leading minus
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<f64, String> {
+        self.skip_space();
+        let start = self.pos;
+
+        if self.eat(b'(') {
+            let value = self.expr()?;
+            return if self.eat(b')') {
+                Ok(value)
+            } else {
+                Err(format!("unclosed parenthesis at {start}"))
+            };
+        }
+
+        match self.input.get(self.pos) {
+            Some(c) if c.is_ascii_digit() || *c == b'.' => {
+                let number = self.take_while(|c| c.is_ascii_digit() || *c == b'.');
+                number
+                    .parse()
+                    .map_err(|_| format!("invalid number {number:?}"))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(u8::is_ascii_alphanumeric);
+                match name {
+                    "pi" => return Ok(std::f64::consts::PI),
+                    "e" => return Ok(std::f64::consts::E),
+                    _ => {}
+                }
+
+                let function: fn(f64) -> f64 = match name {
+                    "sqrt" => f64::sqrt,
+                    "abs" => f64::abs,
+                    "ln" => f64::ln,
+                    "log" => f64::log10,
+                    "log2" => f64::log2,
+                    "exp" => f64::exp,
+                    "sin" => f64::sin,
+                    "cos" => f64::cos,
+                    "tan" => f64::tan,
+                    "asin" => f64::asin,
+                    "acos" => f64::acos,
+                    "atan" => f64::atan,
+                    "floor" => f64::floor,
+                    "ceil" => f64::ceil,
+                    "round" => f64::round,
+                    _ => return Err(format!("unknown name {name:?}")),
+                };
+                if !self.eat(b'(') {
+                    return Err(format!("expected ( after {name}"));
+                }
+                let arg = self.expr()?;
+                if !self.eat(b')') {
+                    return Err(format!("unclosed parenthesis after {name}"));
+                }
+                Ok(function(arg))
+            }
+            Some(c) => Err(format!("unexpected {:?} at {start}", *c as char)),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+}
+
+// Clock
+// -----------------------------------------------------------------
+
+fn current_time(utc_offset_minutes: i64) -> Result<Fields, String> {
+    // Real offsets stay within a day
+    if utc_offset_minutes.abs() >= 24 * 60 {
+        return Err(format!("utc offset {utc_offset_minutes} is out of range"));
+    }
+    let unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| "system clock is before 1970".to_owned())?
+        .as_secs() as i64;
+
+    let (iso8601, weekday) = format_time(unix, utc_offset_minutes);
+    Ok(vec![
+        ("iso8601", text(&iso8601)),
+        ("weekday", text(weekday)),
+        ("unix_seconds", number(unix as f64)),
+    ])
+}
+
+fn format_time(unix: i64, utc_offset_minutes: i64) -> (String, &'static str) {
+    const WEEKDAYS: [&str; 7] = [
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+    ];
+
+    let local = unix + utc_offset_minutes * 60;
+    let days = local.div_euclid(86400);
+    let secs = local.rem_euclid(86400);
+
+    let (year, month, day) = civil_from_days(days);
+
+    let offset = if utc_offset_minutes == 0 {
+        "Z".to_owned()
+    } else {
+        let sign = if utc_offset_minutes < 0 { '-' } else { '+' };
+        let abs = utc_offset_minutes.abs();
+        format!("{sign}{:02}:{:02}", abs / 60, abs % 60)
+    };
+
+    let iso8601 = format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{offset}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    );
+    (iso8601, WEEKDAYS[days.rem_euclid(7) as usize])
+}
+
+// Unit conversion
+// -----------------------------------------------------------------
+
+/// Unit, quantity and size in the quantity's base unit
+const UNITS: &[(&str, &str, f64)] = &[
+    ("m", "length", 1.0),
+    ("km", "length", 1000.0),
+    ("cm", "length", 0.01),
+    ("mm", "length", 0.001),
+    ("mi", "length", 1609.344),
+    ("yd", "length", 0.9144),
+    ("ft", "length", 0.3048),
+    ("in", "length", 0.0254),
+    ("nmi", "length", 1852.0),
+    ("kg", "mass", 1.0),
+    ("g", "mass", 0.001),
+    ("mg", "mass", 1e-6),
+    ("t", "mass", 1000.0),
+    ("lb", "mass", 0.45359237),
+    ("oz", "mass", 0.028349523125),
+    ("s", "time", 1.0),
+    ("ms", "time", 0.001),
+    ("min", "time", 60.0),
+    ("h", "time", 3600.0),
+    ("d", "time", 86400.0),
+    ("wk", "time", 604800.0),
+    ("l", "volume", 1.0),
+    ("ml", "volume", 0.001),
+    ("m3", "volume", 1000.0),
+    ("gal", "volume", 3.785411784),
+    ("qt", "volume", 0.946352946),
+    ("pt", "volume", 0.473176473),
+    ("cup", "volume", 0.2365882365),
+    ("floz", "volume", 0.0295735295625),
+    ("m/s", "speed", 1.0),
+    ("km/h", "speed", 1.0 / 3.6),
+    ("mph", "speed", 0.44704),
+    ("kn", "speed", 1852.0 / 3600.0),
+    ("B", "data", 1.0),
+    ("KB", "data", 1e3),
+    ("MB", "data", 1e6),
+    ("GB", "data", 1e9),
+    ("TB", "data", 1e12),
+    ("KiB", "data", 1024.0),
+    ("MiB", "data", 1048576.0),
+    ("GiB", "data", 1073741824.0),
+];
+
+const TEMPERATURES: [&str; 3] = ["C", "F", "K"];
+
+fn convert_units(value: f64, from: &str, to: &str) -> Result<f64, String> {
+    if TEMPERATURES.contains(&from) || TEMPERATURES.contains(&to) {
+        let kelvin = match from {
+            "C" => value + 273.15,
+            "F" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+            "K" => value,
+            _ => return Err(format!("can't convert {from} to {to}")),
+        };
+        return match to {
+            "C" => Ok(kelvin - 273.15),
+            "F" => Ok((kelvin - 273.15) * 9.0 / 5.0 + 32.0),
+            "K" => Ok(kelvin),
+            _ => Err(format!("can't convert {from} to {to}")),
+        };
+    }
+
+    let unit = |name: &str| {
+        UNITS
+            .iter()
+            .find(|(unit, _, _)| *unit == name)
+            .ok_or_else(|| format!("unknown unit {name}"))
+    };
+    let (_, from_quantity, from_size) = unit(from)?;
+    let (_, to_quantity, to_size) = unit(to)?;
+    if from_quantity != to_quantity {
+        return Err(format!(
+            "can't convert {from} ({from_quantity}) to {to} ({to_quantity})"
+        ));
+    }
+    Ok(value * from_size / to_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculator() {
+        let tests = [
+            ("1 + 2 * 3", Ok(7.0)),
+            ("(1 + 2) * 3", Ok(9.0)),
+            ("-2 ^ 2", Ok(-4.0)),
+            ("2 ^ 3 ^ 2", Ok(512.0)),
+            ("sqrt(16) + abs(-1.5)", Ok(5.5)),
+            ("10 % 4", Ok(2.0)),
+            ("round(e * 100) / 100", Ok(2.72)),
+            ("1 / 0", Err("result is not a finite number")),
+            ("2 +", Err("unexpected end of expression")),
+            ("foo(1)", Err("unknown name \"foo\"")),
+            ("(1 + 2", Err("unclosed parenthesis at 0")),
+            ("1 2", Err("unexpected input at 2")),
+            (&format!("{}1{}", "(".repeat(60), ")".repeat(60)), Ok(1.0)),
+            (
+                &format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+                Err("expression nests deeper than 64 levels"),
+            ),
+            (
+                &"-".repeat(100_000),
+                Err("expression nests deeper than 64 levels"),
+            ),
+            (
+                &"2^".repeat(100_000),
+                Err("expression nests deeper than 64 levels"),
+            ),
+        ];
+
+        for (expression, want) in tests {
+            let got = calculate(expression);
+            match want {
+                Ok(want) => assert_eq!(got, Ok(want), "{expression}"),
+                Err(want) => assert_eq!(got.unwrap_err(), want, "{expression}"),
+            }
+        }
+    }
+
+    #[test]
+    fn clock() {
+        let tests = [
+            (0, 0, "1970-01-01T00:00:00Z", "Thursday"),
+            (951782400, 0, "2000-02-29T00:00:00Z", "Tuesday"),
+            (1792324800, 330, "2026-10-18T17:30:00+05:30", "Sunday"),
+            (0, -60, "1969-12-31T23:00:00-01:00", "Wednesday"),
+        ];
+
+        for (unix, offset, iso8601, weekday) in tests {
+            assert_eq!(
+                format_time(unix, offset),
+                (iso8601.to_owned(), weekday),
+                "{unix} {offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn units() {
+        let close = |got: Result<f64, String>, want: f64| {
+            let got = got.unwrap();
+            assert!((got - want).abs() < 1e-9, "{got} != {want}");
+        };
+
+        close(convert_units(1.0, "mi", "km"), 1.609344);
+        close(convert_units(100.0, "C", "F"), 212.0);
+        close(convert_units(0.0, "K", "C"), -273.15);
+        close(convert_units(1.0, "GiB", "MB"), 1073.741824);
+        assert_eq!(
+            convert_units(1.0, "kg", "m").unwrap_err(),
+            "can't convert kg (mass) to m (length)"
+        );
+        assert_eq!(
+            convert_units(1.0, "C", "m").unwrap_err(),
+            "can't convert C to m"
+        );
+    }
+
+    #[test]
+    fn dispatch() {
+        let call = FunctionCall {
+            name: "calculate".into(),
+            args: Some(Struct {
+                fields: [("expression".to_owned(), text("6 * 7"))].into(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(super::call(&call).unwrap().fields["result"], number(42.0));
+
+        let call = FunctionCall {
+            name: "calculate".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            super::call(&call).unwrap().fields["error"],
+            text("missing string argument expression")
+        );
+
+        let call = FunctionCall {
+            name: "weather".into(),
+            ..Default::default()
+        };
+        assert_eq!(super::call(&call), None);
+    }
+}
@@ -0,0 +1,524 @@
+//! Guarded shell and HTTP functions
+//!
+//! Enabled by the `sandbox-tools` feature. A [`Sandbox`] declares a
+//! `run_command` and an `http_get` function for a model to call, and runs
+//! those calls under the [`ShellPolicy`] and [`HttpPolicy`] it was given.
+//! Neither is enabled by default, and each refuses anything its allow-list
+//! doesn't name.
+//!
+//! | Function      | Arguments                 |
+//! |---------------|---------------------------|
+//! | `run_command` | `program`, `args`         |
+//! | `http_get`    | `url`                     |
+//!
+//! Commands run without a shell, so pipes, globs, `;` and `$(...)` are
+//! passed as plain arguments rather than interpreted. They get no stdin, an
+//! environment reduced to `PATH` and a time limit, and their output is cut
+//! at a size limit. Requests are `GET`s to allow-listed hosts only, over
+//! HTTPS unless allowed otherwise, and don't follow redirects.
+//!
+//! This is defense in depth, not isolation: an allowed program runs with
+//! the permissions of this process. Allow programs whose every argument is
+//! safe to leave to the model, and run the whole thing in a container when
+//! that isn't enough.
+//!
+//! Failures, including refusals, are reported back to the model as an
+//! `error` field.
+//!
+//! # Example
+//! ```rust,no_run
+//! use google_ai_rs::function::sandbox::{HttpPolicy, Sandbox, ShellPolicy};
+//! use google_ai_rs::function::FunctionResponses;
+//!
+//! # async fn f(client: google_ai_rs::Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let sandbox = Sandbox::new()
+//!     .shell(ShellPolicy::allow(["git", "ls"]).working_dir("/srv/repo"))
+//!     .http(HttpPolicy::allow(["docs.rs", "*.github.com"]));
+//!
+//! let model = client
+//!     .generative_model("gemini-2.5-flash")
+//!     .tools([sandbox.tool()]);
+//! let mut session = model.start_chat();
+//!
+//! let response = session.send_message("What changed in the last commit?").await?;
+//! if let Some(turn) = response.candidates.first().and_then(|c| c.content.as_ref()) {
+//!     let follow_up = FunctionResponses::for_turn(turn)
+//!         .respond_sandboxed(&sandbox)
+//!         .await
+//!         .into_content()?;
+//!     session.send_message(follow_up).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{path::PathBuf, process::Stdio, time::Duration};
+
+use prost_types::{value::Kind, Struct, Value};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    process::Command,
+};
+
+use super::FunctionResponses;
+use crate::proto::{FunctionCall, FunctionDeclaration, Schema, Tool};
+
+/// What `run_command` may run
+#[derive(Clone, Debug)]
+pub struct ShellPolicy {
+    programs: Vec<String>,
+    working_dir: Option<PathBuf>,
+    timeout: Duration,
+    max_output_bytes: usize,
+}
+
+impl ShellPolicy {
+    /// Allows running exactly these programs, looked up in `PATH`
+    ///
+    /// Programs are matched by the name the model gives, so a path like
+    /// `./git` or `/tmp/git` is refused even when `git` is allowed.
+    pub fn allow<I, S>(programs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            programs: programs.into_iter().map(Into::into).collect(),
+            working_dir: None,
+            timeout: Duration::from_secs(10),
+            max_output_bytes: 16 * 1024,
+        }
+    }
+
+    /// Runs commands in `dir` instead of the current directory
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets how long a command may run before it's killed. Defaults to 10
+    /// seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how much of stdout and of stderr is kept. Defaults to 16KiB.
+    pub fn max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    fn allows(&self, program: &str) -> bool {
+        !program.contains(['/', '\\']) && self.programs.iter().any(|p| p == program)
+    }
+}
+
+/// What `http_get` may fetch
+#[derive(Clone, Debug)]
+pub struct HttpPolicy {
+    hosts: Vec<String>,
+    allow_http: bool,
+    timeout: Duration,
+    max_response_bytes: usize,
+}
+
+impl HttpPolicy {
+    /// Allows fetching from these hosts
+    ///
+    /// A host starting with `*.` matches its subdomains, but not itself.
+    pub fn allow<I, S>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            hosts: hosts
+                .into_iter()
+                .map(|host| host.into().to_ascii_lowercase())
+                .collect(),
+            allow_http: false,
+            timeout: Duration::from_secs(10),
+            max_response_bytes: 64 * 1024,
+        }
+    }
+
+    /// Allows plain `http://` URLs too
+    pub fn allow_http(mut self, allow_http: bool) -> Self {
+        self.allow_http = allow_http;
+        self
+    }
+
+    /// Sets the timeout for the whole request. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how much of the body is kept. Defaults to 64KiB.
+    pub fn max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    fn allows(&self, scheme: &str, host: &str) -> bool {
+        let scheme_allowed = scheme == "https" || (self.allow_http && scheme == "http");
+        let host = host.to_ascii_lowercase();
+        scheme_allowed
+            && self
+                .hosts
+                .iter()
+                .any(|allowed| match allowed.strip_prefix("*.") {
+                    Some(domain) => host
+                        .strip_suffix(domain)
+                        .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+                    None => *allowed == host,
+                })
+    }
+}
+
+/// Shell and HTTP functions, each run under a policy
+#[derive(Clone, Debug, Default)]
+pub struct Sandbox {
+    shell: Option<ShellPolicy>,
+    http: Option<HttpPolicy>,
+}
+
+impl Sandbox {
+    /// Creates a sandbox with no function enabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables `run_command` under `policy`
+    pub fn shell(mut self, policy: ShellPolicy) -> Self {
+        self.shell = Some(policy);
+        self
+    }
+
+    /// Enables `http_get` under `policy`
+    pub fn http(mut self, policy: HttpPolicy) -> Self {
+        self.http = Some(policy);
+        self
+    }
+
+    /// Declarations of the enabled functions
+    ///
+    /// The allow-lists are part of the descriptions, so the model doesn't
+    /// waste calls on what would be refused.
+    pub fn tool(&self) -> Tool {
+        let mut function_declarations = Vec::new();
+
+        if let Some(shell) = &self.shell {
+            function_declarations.push(FunctionDeclaration {
+                name: "run_command".into(),
+                description: format!(
+                    "Runs a program without a shell and returns its exit code and output. \
+                     Allowed programs: {}.",
+                    shell.programs.join(", ")
+                ),
+                parameters: Some(
+                    Schema::new_object()
+                        .property(
+                            "program",
+                            Schema::new_string().into_enum(shell.programs.clone()),
+                        )
+                        .property(
+                            "args",
+                            Schema::new_array()
+                                .items(Schema::new_string())
+                                .description("Passed as is; nothing is expanded"),
+                        )
+                        .required(["program"]),
+                ),
+                response: None,
+            });
+        }
+
+        if let Some(http) = &self.http {
+            function_declarations.push(FunctionDeclaration {
+                name: "http_get".into(),
+                description: format!(
+                    "Fetches a URL with a GET request and returns the status and body. \
+                     Allowed hosts: {}.",
+                    http.hosts.join(", ")
+                ),
+                parameters: Some(
+                    Schema::new_object()
+                        .property("url", Schema::new_string())
+                        .required(["url"]),
+                ),
+                response: None,
+            });
+        }
+
+        Tool {
+            function_declarations,
+            ..Default::default()
+        }
+    }
+
+    /// Runs a call to an enabled function
+    ///
+    /// Returns `None` if `call` isn't to one.
+    pub async fn call(&self, call: &FunctionCall) -> Option<Struct> {
+        let args = call.args.clone().unwrap_or_default();
+        let result = match call.name.as_str() {
+            "run_command" => run_command(self.shell.as_ref()?, &args).await,
+            "http_get" => http_get(self.http.as_ref()?, &args).await,
+            _ => return None,
+        };
+
+        let fields = match result {
+            Ok(fields) => fields,
+            Err(err) => vec![("error", text(err))],
+        };
+        Some(Struct {
+            fields: fields.into_iter().map(|(k, v)| (k.to_owned(), v)).collect(),
+        })
+    }
+}
+
+impl FunctionResponses<'_> {
+    /// Answers every unanswered call to a function enabled in `sandbox`
+    ///
+    /// Calls run one after the other, in call order.
+    pub async fn respond_sandboxed(mut self, sandbox: &Sandbox) -> Self {
+        for (call, response) in self.calls.iter().zip(&mut self.responses) {
+            if response.is_none() {
+                *response = sandbox.call(call).await;
+            }
+        }
+        self
+    }
+}
+
+type Fields = Vec<(&'static str, Value)>;
+
+async fn run_command(policy: &ShellPolicy, args: &Struct) -> Result<Fields, String> {
+    let program = match args.fields.get("program").and_then(|v| v.kind.as_ref()) {
+        Some(Kind::StringValue(s)) => s,
+        _ => return Err("missing string argument program".into()),
+    };
+    if !policy.allows(program) {
+        return Err(format!("program {program:?} is not allowed"));
+    }
+
+    let command_args = match args.fields.get("args").and_then(|v| v.kind.as_ref()) {
+        None | Some(Kind::NullValue(_)) => Vec::new(),
+        Some(Kind::ListValue(list)) => list
+            .values
+            .iter()
+            .map(|v| match &v.kind {
+                Some(Kind::StringValue(s)) => Ok(s.as_str()),
+                _ => Err("args must be strings".to_owned()),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => return Err("args must be a list of strings".into()),
+    };
+
+    let mut command = Command::new(program);
+    command
+        .args(command_args)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Ok(path) = std::env::var("PATH") {
+        command.env("PATH", path);
+    }
+    if let Some(dir) = &policy.working_dir {
+        command.current_dir(dir);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("failed to start {program}: {e}"))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    let run = async {
+        let (stdout, stderr) = tokio::try_join!(
+            read_capped(stdout, policy.max_output_bytes),
+            read_capped(stderr, policy.max_output_bytes),
+        )?;
+        Ok::<_, std::io::Error>((child.wait().await?, stdout, stderr))
+    };
+    // Dropping the child on timeout kills it
+    let (status, (stdout, stdout_cut), (stderr, stderr_cut)) =
+        tokio::time::timeout(policy.timeout, run)
+            .await
+            .map_err(|_| format!("{program} timed out after {:?}", policy.timeout))?
+            .map_err(|e| format!("failed to run {program}: {e}"))?;
+
+    let exit_code = match status.code() {
+        Some(code) => number(code as f64),
+        None => Value {
+            kind: Some(Kind::NullValue(0)),
+        },
+    };
+    Ok(vec![
+        ("exit_code", exit_code),
+        ("stdout", text(stdout)),
+        ("stderr", text(stderr)),
+        ("truncated", boolean(stdout_cut || stderr_cut)),
+    ])
+}
+
+async fn http_get(policy: &HttpPolicy, args: &Struct) -> Result<Fields, String> {
+    let url = match args.fields.get("url").and_then(|v| v.kind.as_ref()) {
+        Some(Kind::StringValue(s)) => s,
+        _ => return Err("missing string argument url".into()),
+    };
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid url: {e}"))?;
+    if !policy.allows(parsed.scheme(), parsed.host_str().unwrap_or_default()) {
+        return Err(format!("fetching {url} is not allowed"));
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(policy.timeout)
+        // A redirect could leave the allowed hosts
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| format!("failed to fetch {url}: {e}"))?;
+    let mut response = client
+        .get(parsed)
+        .send()
+        .await
+        .map_err(|e| format!("failed to fetch {url}: {e}"))?;
+
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+
+    // Content-Length can be absent or wrong, so enforce the limit while reading.
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("failed to fetch {url}: {e}"))?
+    {
+        let room = policy.max_response_bytes - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(vec![
+        ("status", number(status.into())),
+        ("content_type", text(content_type)),
+        ("body", text(String::from_utf8_lossy(&body).into_owned())),
+        ("truncated", boolean(truncated)),
+    ])
+}
+
+/// Reads up to `max` bytes, discarding the rest so the writer isn't blocked
+async fn read_capped<R: AsyncRead + Unpin>(
+    mut reader: R,
+    max: usize,
+) -> std::io::Result<(String, bool)> {
+    let mut kept = Vec::new();
+    (&mut reader)
+        .take(max as u64)
+        .read_to_end(&mut kept)
+        .await?;
+    let rest = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await?;
+    Ok((String::from_utf8_lossy(&kept).into_owned(), rest > 0))
+}
+
+fn number(n: f64) -> Value {
+    Value {
+        kind: Some(Kind::NumberValue(n)),
+    }
+}
+
+fn text(s: String) -> Value {
+    Value {
+        kind: Some(Kind::StringValue(s)),
+    }
+}
+
+fn boolean(b: bool) -> Value {
+    Value {
+        kind: Some(Kind::BoolValue(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::ListValue;
+
+    use super::*;
+
+    #[test]
+    fn matches_hosts() {
+        let policy = HttpPolicy::allow(["docs.rs", "*.github.com"]);
+        assert!(policy.allows("https", "docs.rs"));
+        assert!(policy.allows("https", "API.github.com"));
+        assert!(!policy.allows("https", "github.com"));
+        assert!(!policy.allows("https", "evilgithub.com"));
+        assert!(!policy.allows("https", "docs.rs.evil.com"));
+        assert!(!policy.allows("http", "docs.rs"));
+        assert!(policy.allow_http(true).allows("http", "docs.rs"));
+    }
+
+    #[tokio::test]
+    async fn refuses_unlisted_programs() {
+        let sandbox = Sandbox::new().shell(ShellPolicy::allow(["echo"]));
+        let call = |program: &str| FunctionCall {
+            name: "run_command".into(),
+            args: Some(Struct {
+                fields: [("program".to_owned(), text(program.into()))].into(),
+            }),
+            ..Default::default()
+        };
+
+        for program in ["rm", "/bin/echo", "./echo"] {
+            let response = sandbox.call(&call(program)).await.unwrap();
+            assert!(response.fields.contains_key("error"), "{program}");
+        }
+        assert_eq!(
+            Sandbox::new().call(&call("echo")).await,
+            None,
+            "disabled functions aren't answered"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn caps_output() {
+        let policy = ShellPolicy::allow(["printf"]).max_output_bytes(4);
+        let args = Struct {
+            fields: [
+                ("program".to_owned(), text("printf".into())),
+                (
+                    "args".to_owned(),
+                    Value {
+                        kind: Some(Kind::ListValue(ListValue {
+                            values: vec![text("hello; $(rm -rf /)".into())],
+                        })),
+                    },
+                ),
+            ]
+            .into(),
+        };
+
+        let fields: std::collections::HashMap<_, _> = run_command(&policy, &args)
+            .await
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(fields["exit_code"], number(0.0));
+        assert_eq!(fields["stdout"], text("hell".into()));
+        assert_eq!(fields["truncated"], boolean(true));
+    }
+}
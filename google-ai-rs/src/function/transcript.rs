@@ -0,0 +1,596 @@
+//! Exporting function-calling transcripts
+//!
+//! Enabled by the `serde` feature. Evaluation and observability tools
+//! mostly read runs in one of two shapes: the chat messages of OpenAI's
+//! tools API, or a trace of one JSON event per line. A [`Transcript`]
+//! writes a history of contents, e.g. a [`Session`](crate::chat::Session)'s,
+//! in both.
+//!
+//! Gemini only sometimes gives function calls an id, while OpenAI's
+//! messages pair results with calls by id. Calls without one get
+//! `call_1`, `call_2` and so on in the order they were made, and responses
+//! without one take the id of the oldest unanswered call to the same
+//! function, like [`FunctionResponses::respond`](super::FunctionResponses::respond)
+//! matches them.
+//!
+//! Only text, function calls and function responses carry over; other
+//! parts, like inline images, are left out.
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::function::transcript::Transcript;
+//! use google_ai_rs::proto::part::Data;
+//! use google_ai_rs::{Content, FunctionCall, FunctionResponse, Part};
+//!
+//! let history = [
+//!     Content::user("What's the weather in Paris?"),
+//!     Content::model(FunctionCall {
+//!         name: "get_weather".into(),
+//!         ..Default::default()
+//!     }),
+//!     Content::user(Part {
+//!         data: Some(Data::FunctionResponse(FunctionResponse {
+//!             name: "get_weather".into(),
+//!             ..Default::default()
+//!         })),
+//!         metadata: None,
+//!     }),
+//!     Content::model("It's sunny."),
+//! ];
+//!
+//! let openai = Transcript::new(&history).to_openai();
+//! assert_eq!(openai["messages"][2]["tool_call_id"], "call_1");
+//!
+//! let mut trace = Vec::new();
+//! Transcript::new(&history).write_jsonl(&mut trace)?;
+//! assert_eq!(trace.iter().filter(|&&b| b == b'\n').count(), 4);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::io::{self, Write};
+
+use prost_types::Struct;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    genai::GenerativeModel,
+    proto::{part::Data, Content, Schema, Tool},
+    schema::{canonical::from_proto, SchemaType},
+};
+
+/// A history of contents to export, with the system instruction and tools
+/// it ran under
+#[derive(Clone, Copy, Debug)]
+pub struct Transcript<'a> {
+    system_instruction: Option<&'a Content>,
+    tools: &'a [Tool],
+    contents: &'a [Content],
+}
+
+impl<'a> Transcript<'a> {
+    /// Exports `contents` alone
+    pub fn new(contents: &'a [Content]) -> Self {
+        Self {
+            system_instruction: None,
+            tools: &[],
+            contents,
+        }
+    }
+
+    /// Exports `contents` with the system instruction and tools of `model`
+    pub fn with_model(model: &'a GenerativeModel<'_>, contents: &'a [Content]) -> Self {
+        Self {
+            system_instruction: model.system_instruction.as_ref(),
+            tools: model.tools.as_deref().unwrap_or_default(),
+            contents,
+        }
+    }
+
+    /// Sets the system instruction the contents ran under
+    pub fn system_instruction(mut self, instruction: &'a Content) -> Self {
+        self.system_instruction = Some(instruction);
+        self
+    }
+
+    /// Sets the tools the model was offered
+    pub fn tools(mut self, tools: &'a [Tool]) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Returns the transcript as an OpenAI chat completion request
+    ///
+    /// The object has a `messages` array and, if any functions were
+    /// declared, a `tools` array with their parameters as JSON Schema.
+    /// Model turns become `assistant` messages carrying their function
+    /// calls as `tool_calls`, and each function response a `tool` message.
+    /// Arguments and results are JSON-encoded strings, as the API has them.
+    pub fn to_openai(&self) -> Value {
+        let mut messages = Vec::new();
+        if let Some(text) = self.system_text() {
+            messages.push(json!({"role": "system", "content": text}));
+        }
+
+        for turn in self.turns() {
+            if turn.role == "model" {
+                if turn.text.is_none() && turn.calls.is_empty() {
+                    continue;
+                }
+                let mut message = json!({"role": "assistant", "content": turn.text});
+                if !turn.calls.is_empty() {
+                    message["tool_calls"] = turn
+                        .calls
+                        .iter()
+                        .map(|call| {
+                            json!({
+                                "id": call.id,
+                                "type": "function",
+                                "function": {
+                                    "name": call.name,
+                                    "arguments": call.payload.to_string(),
+                                },
+                            })
+                        })
+                        .collect();
+                }
+                messages.push(message);
+            } else {
+                // Results answer the previous turn, so they come first
+                for result in &turn.results {
+                    messages.push(json!({
+                        "role": "tool",
+                        "tool_call_id": result.id,
+                        "content": result.payload.to_string(),
+                    }));
+                }
+                if let Some(text) = turn.text {
+                    messages.push(json!({"role": "user", "content": text}));
+                }
+            }
+        }
+
+        let mut request = json!({"messages": messages});
+        let tools: Vec<_> = self
+            .tools
+            .iter()
+            .flat_map(|tool| &tool.function_declarations)
+            .map(|declaration| {
+                let parameters = declaration
+                    .parameters
+                    .as_ref()
+                    .map_or_else(|| json!({"type": "object", "properties": {}}), json_schema);
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": declaration.name,
+                        "description": declaration.description,
+                        "parameters": parameters,
+                    },
+                })
+            })
+            .collect();
+        if !tools.is_empty() {
+            request["tools"] = Value::Array(tools);
+        }
+        request
+    }
+
+    /// Returns the transcript as trace events, in order
+    ///
+    /// Every event has a `step`, counting from 0, and a `type`:
+    /// - `system`, with the system instruction's `text`
+    /// - `message`, with the `turn` it's from, its `role` and `text`
+    /// - `tool_call`, with its `turn`, the call's `id`, `name` and
+    ///   `arguments`
+    /// - `tool_result`, with its `turn`, the `id` and `name` of the call it
+    ///   answers and the `result`
+    /// - `final_answer`, in place of the `message` of the last turn, if
+    ///   it's the model's and calls no functions
+    ///
+    /// Turns count contents from 0. Arguments and results are JSON objects.
+    pub fn trace(&self) -> Vec<Value> {
+        let mut events = Vec::new();
+        if let Some(text) = self.system_text() {
+            events.push(json!({"type": "system", "text": text}));
+        }
+
+        let turns = self.turns();
+        let last = turns.len().checked_sub(1);
+        for (index, turn) in turns.into_iter().enumerate() {
+            for result in turn.results {
+                events.push(json!({
+                    "turn": index,
+                    "type": "tool_result",
+                    "id": result.id,
+                    "name": result.name,
+                    "result": result.payload,
+                }));
+            }
+            if let Some(text) = turn.text {
+                let kind = if Some(index) == last && turn.role == "model" && turn.calls.is_empty() {
+                    "final_answer"
+                } else {
+                    "message"
+                };
+                events.push(json!({
+                    "turn": index,
+                    "type": kind,
+                    "role": turn.role,
+                    "text": text,
+                }));
+            }
+            for call in turn.calls {
+                events.push(json!({
+                    "turn": index,
+                    "type": "tool_call",
+                    "id": call.id,
+                    "name": call.name,
+                    "arguments": call.payload,
+                }));
+            }
+        }
+
+        for (step, event) in events.iter_mut().enumerate() {
+            event["step"] = step.into();
+        }
+        events
+    }
+
+    /// Writes the [trace](Self::trace) as JSON Lines, one event per line
+    pub fn write_jsonl(&self, mut writer: impl Write) -> io::Result<()> {
+        for event in self.trace() {
+            serde_json::to_writer(&mut writer, &event)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Returns the [trace](Self::trace) as JSON Lines
+    pub fn to_jsonl(&self) -> String {
+        let mut jsonl = Vec::new();
+        self.write_jsonl(&mut jsonl)
+            .expect("writing to a Vec doesn't fail");
+        String::from_utf8(jsonl).expect("JSON is UTF-8")
+    }
+
+    fn system_text(&self) -> Option<String> {
+        self.system_instruction.and_then(text)
+    }
+
+    /// Reads the contents, giving every call and response an id
+    fn turns(&self) -> Vec<Turn<'a>> {
+        let mut next_id = 0;
+        let mut pending: Vec<(&str, String)> = Vec::new();
+
+        self.contents
+            .iter()
+            .map(|content| {
+                let mut turn = Turn {
+                    role: &content.role,
+                    text: text(content),
+                    calls: Vec::new(),
+                    results: Vec::new(),
+                };
+                for part in &content.parts {
+                    match &part.data {
+                        Some(Data::FunctionCall(call)) => {
+                            let id = if call.id.is_empty() {
+                                next_id += 1;
+                                format!("call_{next_id}")
+                            } else {
+                                call.id.clone()
+                            };
+                            pending.push((&call.name, id.clone()));
+                            turn.calls.push(Exchange {
+                                id,
+                                name: &call.name,
+                                payload: object(call.args.as_ref()),
+                            });
+                        }
+                        Some(Data::FunctionResponse(response)) => {
+                            let answered = pending.iter().position(|(name, id)| {
+                                if response.id.is_empty() {
+                                    *name == response.name
+                                } else {
+                                    *id == response.id
+                                }
+                            });
+                            let id = match answered {
+                                Some(i) => pending.remove(i).1,
+                                None if !response.id.is_empty() => response.id.clone(),
+                                None => {
+                                    next_id += 1;
+                                    format!("call_{next_id}")
+                                }
+                            };
+                            turn.results.push(Exchange {
+                                id,
+                                name: &response.name,
+                                payload: object(response.response.as_ref()),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+                turn
+            })
+            .collect()
+    }
+}
+
+struct Turn<'a> {
+    role: &'a str,
+    text: Option<String>,
+    calls: Vec<Exchange<'a>>,
+    results: Vec<Exchange<'a>>,
+}
+
+/// A function call or response, with its id settled
+struct Exchange<'a> {
+    id: String,
+    name: &'a str,
+    payload: Value,
+}
+
+/// Joins the text parts of a content
+fn text(content: &Content) -> Option<String> {
+    let texts: Vec<&str> = content
+        .parts
+        .iter()
+        .filter_map(|part| match &part.data {
+            Some(Data::Text(text)) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    (!texts.is_empty()).then(|| texts.concat())
+}
+
+fn object(fields: Option<&Struct>) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .flat_map(|s| &s.fields)
+            .map(|(name, value)| (name.clone(), from_proto(value)))
+            .collect(),
+    )
+}
+
+/// Writes a schema as the JSON Schema it stands for
+fn json_schema(schema: &Schema) -> Value {
+    let mut object = Map::new();
+    let r#type = match schema.r#type() {
+        SchemaType::Unspecified => None,
+        SchemaType::String => Some("string"),
+        SchemaType::Number => Some("number"),
+        SchemaType::Integer => Some("integer"),
+        SchemaType::Boolean => Some("boolean"),
+        SchemaType::Array => Some("array"),
+        SchemaType::Object => Some("object"),
+    };
+    match r#type {
+        Some(r#type) if schema.nullable => {
+            object.insert("type".into(), json!([r#type, "null"]));
+        }
+        Some(r#type) => {
+            object.insert("type".into(), r#type.into());
+        }
+        None => {}
+    }
+
+    let mut string = |name: &str, value: &str| {
+        if !value.is_empty() {
+            object.insert(name.into(), value.into());
+        }
+    };
+    string("title", &schema.title);
+    string("description", &schema.description);
+    string("pattern", &schema.pattern);
+    // Gemini marks enums with a format JSON Schema doesn't have
+    if schema.format != "enum" {
+        string("format", &schema.format);
+    }
+
+    if !schema.r#enum.is_empty() {
+        object.insert("enum".into(), json!(schema.r#enum));
+    }
+    for (name, count) in [
+        ("minLength", schema.min_length),
+        ("maxLength", schema.max_length),
+        ("minItems", schema.min_items),
+        ("maxItems", schema.max_items),
+    ] {
+        if count > 0 {
+            object.insert(name.into(), count.into());
+        }
+    }
+    if let Some(minimum) = schema.minimum {
+        object.insert("minimum".into(), minimum.into());
+    }
+    if let Some(maximum) = schema.maximum {
+        object.insert("maximum".into(), maximum.into());
+    }
+    if let Some(items) = &schema.items {
+        object.insert("items".into(), json_schema(items));
+    }
+    if !schema.properties.is_empty() || schema.r#type() == SchemaType::Object {
+        let properties: Map<_, _> = schema
+            .properties
+            .iter()
+            .map(|(name, property)| (name.clone(), json_schema(property)))
+            .collect();
+        object.insert("properties".into(), properties.into());
+    }
+    if !schema.required.is_empty() {
+        object.insert("required".into(), json!(schema.required));
+    }
+    if !schema.any_of.is_empty() {
+        let any_of: Vec<_> = schema.any_of.iter().map(json_schema).collect();
+        object.insert("anyOf".into(), any_of.into());
+    }
+    if let Some(default) = &schema.default {
+        object.insert("default".into(), from_proto(default));
+    }
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proto::{FunctionCall, FunctionDeclaration, FunctionResponse, Part};
+
+    use super::*;
+
+    fn fields(value: Value) -> Option<Struct> {
+        let Value::Object(map) = value else {
+            unreachable!()
+        };
+        Some(Struct {
+            fields: map
+                .into_iter()
+                .map(|(name, value)| (name, crate::schema::canonical::to_proto(value)))
+                .collect(),
+        })
+    }
+
+    fn history() -> Vec<Content> {
+        let call = |id: &str, city: &str| Part {
+            data: Some(Data::FunctionCall(FunctionCall {
+                id: id.into(),
+                name: "get_weather".into(),
+                args: fields(json!({"city": city})),
+            })),
+            metadata: None,
+        };
+        let response = |id: &str, temp: i32| Part {
+            data: Some(Data::FunctionResponse(FunctionResponse {
+                id: id.into(),
+                name: "get_weather".into(),
+                response: fields(json!({"temp": temp})),
+            })),
+            metadata: None,
+        };
+        vec![
+            Content::user("Weather in Paris and Oslo?"),
+            Content::model(vec![
+                Part::text("Checking both."),
+                call("", "Paris"),
+                call("w-2", "Oslo"),
+            ]),
+            Content::user(vec![response("w-2", -3), response("", 21)]),
+            Content::model("Paris is at 21°C, Oslo at -3°C."),
+        ]
+    }
+
+    #[test]
+    fn exports_openai_messages() {
+        let history = history();
+        let tools = [Tool {
+            function_declarations: vec![FunctionDeclaration {
+                name: "get_weather".into(),
+                description: "Current weather".into(),
+                parameters: Some(
+                    Schema::new_object()
+                        .property("city", Schema::new_string())
+                        .property(
+                            "unit",
+                            Schema {
+                                nullable: true,
+                                ..Schema::new_string().into_enum(["c", "f"])
+                            },
+                        )
+                        .required(["city"]),
+                ),
+                response: None,
+            }],
+            ..Default::default()
+        }];
+        let system = Content::new("Be brief.");
+
+        let openai = Transcript::new(&history)
+            .system_instruction(&system)
+            .tools(&tools)
+            .to_openai();
+
+        assert_eq!(
+            openai["messages"],
+            json!([
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Weather in Paris and Oslo?"},
+                {
+                    "role": "assistant",
+                    "content": "Checking both.",
+                    "tool_calls": [
+                        {
+                            "id": "call_1",
+                            "type": "function",
+                            "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"},
+                        },
+                        {
+                            "id": "w-2",
+                            "type": "function",
+                            "function": {"name": "get_weather", "arguments": "{\"city\":\"Oslo\"}"},
+                        },
+                    ],
+                },
+                {"role": "tool", "tool_call_id": "w-2", "content": "{\"temp\":-3}"},
+                {"role": "tool", "tool_call_id": "call_1", "content": "{\"temp\":21}"},
+                {"role": "assistant", "content": "Paris is at 21°C, Oslo at -3°C."},
+            ])
+        );
+        assert_eq!(
+            openai["tools"],
+            json!([{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Current weather",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            "city": {"type": "string"},
+                            "unit": {"type": ["string", "null"], "enum": ["c", "f"]},
+                        },
+                        "required": ["city"],
+                    },
+                },
+            }])
+        );
+    }
+
+    #[test]
+    fn exports_trace() {
+        let history = history();
+        let jsonl = Transcript::new(&history).to_jsonl();
+        let events: Vec<Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let kinds: Vec<_> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "message",
+                "message",
+                "tool_call",
+                "tool_call",
+                "tool_result",
+                "tool_result",
+                "final_answer"
+            ]
+        );
+        assert_eq!(
+            events[2],
+            json!({
+                "step": 2,
+                "turn": 1,
+                "type": "tool_call",
+                "id": "call_1",
+                "name": "get_weather",
+                "arguments": {"city": "Paris"},
+            })
+        );
+        assert_eq!(events[5]["id"], "call_1");
+        assert_eq!(events[5]["result"], json!({"temp": 21}));
+        assert_eq!(events[6]["text"], "Paris is at 21°C, Oslo at -3°C.");
+    }
+}
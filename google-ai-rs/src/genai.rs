@@ -1,20 +1,29 @@
 use std::{
-    fmt::Debug,
+    collections::HashMap,
+    fmt::{self, Debug},
     io::Write,
     ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
 };
 use tokio::io::AsyncWrite;
+use tokio::time::Instant;
 use tonic::{IntoRequest, Streaming};
 
 use crate::{
-    client::{CClient, Client, SharedClient},
+    citations::FieldCitations,
+    client::{CClient, Client, InFlight, SharedClient},
+    config::{is_upstream_failure, Split},
     content::{IntoContent, TryFromCandidates, TryIntoContents},
-    error::{status_into_error, ActionError, Error},
-    full_model_name,
-    schema::AsSchema,
+    context::Compress,
+    delta::TextDeltas,
+    error::{status_into_error, ActionError, Error, ServiceError},
+    proto::{part::Data, Part},
+    schema::{AsSchema, SchemaType},
+    shadow::Shadow,
 };
 
 pub use crate::proto::{
+    generate_content_response::prompt_feedback::BlockReason, safety_rating::HarmProbability,
     safety_setting::HarmBlockThreshold, CachedContent, Content, CountTokensRequest,
     CountTokensResponse, GenerateContentRequest, GenerateContentResponse, GenerationConfig,
     HarmCategory, Model, SafetySetting, Schema, Tool, ToolConfig, TunedModel,
@@ -47,9 +56,13 @@ pub use crate::proto::{
 /// # }
 pub struct TypedModel<'c, T> {
     inner: GenerativeModel<'c>,
+    validators: Vec<Validator<T>>,
+    repair_attempts: u32,
     _marker: PhantomInvariant<T>,
 }
 
+type Validator<T> = Arc<dyn Fn(&T) -> Result<(), Vec<String>> + Send + Sync>;
+
 impl<T> Debug for TypedModel<'_, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.inner.fmt(f)
@@ -60,6 +73,8 @@ impl<T> Clone for TypedModel<'_, T> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            validators: self.validators.clone(),
+            repair_attempts: self.repair_attempts,
             _marker: PhantomInvariant(std::marker::PhantomData),
         }
     }
@@ -81,6 +96,8 @@ where
         let inner = GenerativeModel::new(client, name).as_response_schema::<T>();
         Self {
             inner,
+            validators: Vec::new(),
+            repair_attempts: 0,
             _marker: PhantomInvariant(std::marker::PhantomData),
         }
     }
@@ -89,6 +106,8 @@ where
         let inner = GenerativeModel::new_inner(client, name).as_response_schema::<T>();
         Self {
             inner,
+            validators: Vec::new(),
+            repair_attempts: 0,
             _marker: PhantomInvariant(std::marker::PhantomData),
         }
     }
@@ -137,9 +156,22 @@ where
         I: TryIntoContents + Send,
         T: TryFromCandidates + Send,
     {
-        let response = self.inner.generate_content_consuming(contents).await?;
-        let t = T::try_from_candidates(&response.candidates)?;
-        Ok(TypedResponse { t, raw: response })
+        if !self.validators.is_empty() || self.repair_attempts > 0 {
+            return self.generate_checked(contents).await;
+        }
+
+        let stats = self.inner.client.parse_stats.clone();
+        let (response, warnings) = self.inner.generate_with_warnings(contents).await?;
+        let t = T::try_from_candidates(&response.candidates);
+        record_parse::<T>(&stats, &response, t.is_ok(), 0);
+        let t = t?;
+        let confidence = response.candidates.first().and_then(|c| c.confidence());
+        Ok(TypedResponse {
+            t,
+            raw: response,
+            confidence,
+            warnings,
+        })
     }
 
     /// Generates content and parses it directly into type `T`.
@@ -212,9 +244,149 @@ where
         I: TryIntoContents + Send,
         T: TryFromCandidates + Send,
     {
+        if !self.validators.is_empty() || self.repair_attempts > 0 {
+            return self.generate_checked(contents).await.map(|r| r.t);
+        }
+
+        let stats = self.inner.client.parse_stats.clone();
         let response = self.inner.generate_content_consuming(contents).await?;
-        let t = T::try_from_candidates(&response.candidates)?;
-        Ok(t)
+        let t = T::try_from_candidates(&response.candidates);
+        record_parse::<T>(&stats, &response, t.is_ok(), 0);
+        t
+    }
+
+    /// Adds a check that parsed responses must pass
+    ///
+    /// The validator returns the problems it finds, e.g. `"confidence must
+    /// be 0..=1"`. A response with problems fails with [`Violations`] as an
+    /// invalid response, unless [repair attempts](Self::repair_attempts)
+    /// remain.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::{AsSchema, Client, TypedModel};
+    /// # #[derive(AsSchema)] struct Sentiment { label: String, confidence: f64 }
+    /// # fn f(client: &Client) {
+    /// let model = TypedModel::<Sentiment>::new(client, "gemini-2.5-flash")
+    ///     .validate_with(|s| match s.confidence {
+    ///         0.0..=1.0 => Ok(()),
+    ///         _ => Err(vec!["confidence must be 0..=1".into()]),
+    ///     })
+    ///     .repair_attempts(1);
+    /// # }
+    /// ```
+    pub fn validate_with<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&T) -> Result<(), Vec<String>> + Send + Sync + 'static,
+    {
+        self.validators.push(Arc::new(validator));
+        self
+    }
+
+    /// Checks parsed responses with `T`'s [`Validate`] implementation
+    ///
+    /// See [`validate_with`](Self::validate_with).
+    pub fn validated(self) -> Self
+    where
+        T: Validate + 'static,
+    {
+        self.validate_with(T::validate)
+    }
+
+    /// Sets how many times a response that fails to parse or validate is
+    /// sent back to the model to be fixed
+    ///
+    /// The model sees its previous output and what was wrong with it.
+    /// Defaults to 0.
+    pub fn repair_attempts(mut self, repair_attempts: u32) -> Self {
+        self.repair_attempts = repair_attempts;
+        self
+    }
+
+    /// Duplicates a share of calls to a candidate model and reports how it
+    /// compares, including whether its responses parse as `T`
+    ///
+    /// See [`Shadow`].
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self
+    where
+        T: TryFromCandidates,
+    {
+        self.inner.shadow = Some(shadow.parsing::<T>());
+        self
+    }
+
+    /// Generates, parses and validates, repairing until it passes or the
+    /// attempts run out
+    async fn generate_checked<I>(&self, contents: I) -> Result<TypedResponse<T>, Error>
+    where
+        I: TryIntoContents + Send,
+        T: TryFromCandidates + Send,
+    {
+        let (system, mut contents) = contents.try_into_system_and_contents()?;
+        let mut attempt = 0;
+        let mut warnings = Vec::new();
+        loop {
+            let (response, new_warnings) = self
+                .inner
+                .generate_with_warnings(Prepared(system.clone(), contents.clone()))
+                .await?;
+            warnings.extend(new_warnings);
+
+            let violations = match T::try_from_candidates(&response.candidates) {
+                Ok(t) => {
+                    let violations: Vec<String> = self
+                        .validators
+                        .iter()
+                        .filter_map(|validate| validate(&t).err())
+                        .flatten()
+                        .collect();
+                    if violations.is_empty() {
+                        self.record_parse(&response, true, attempt);
+                        let confidence = response.candidates.first().and_then(|c| c.confidence());
+                        return Ok(TypedResponse {
+                            t,
+                            raw: response,
+                            confidence,
+                            warnings,
+                        });
+                    }
+                    violations
+                }
+                Err(Error::Service(ServiceError::InvalidResponse(err)))
+                    if attempt < self.repair_attempts =>
+                {
+                    vec![err.to_string()]
+                }
+                Err(err) => {
+                    self.record_parse(&response, false, attempt);
+                    return Err(err);
+                }
+            };
+
+            if attempt == self.repair_attempts {
+                self.record_parse(&response, false, attempt);
+                return Err(Error::Service(ServiceError::InvalidResponse(Box::new(
+                    Violations(violations),
+                ))));
+            }
+            attempt += 1;
+
+            contents.push(Content::model(response.to_text()));
+            contents.push(Content::user(format!(
+                "Your previous response had these problems:\n- {}\n\
+                 Reply again with the corrected response only.",
+                violations.join("\n- ")
+            )));
+        }
+    }
+
+    fn record_parse(&self, response: &GenerateContentResponse, parsed: bool, repair_rounds: u32) {
+        record_parse::<T>(
+            &self.inner.client.parse_stats,
+            response,
+            parsed,
+            repair_rounds,
+        )
     }
 
     /// Consumes the `TypedModel`, returning the underlying `GenerativeModel`.
@@ -237,6 +409,8 @@ where
     pub unsafe fn from_inner_unchecked(inner: GenerativeModel<'c>) -> Self {
         Self {
             inner,
+            validators: Vec::new(),
+            repair_attempts: 0,
             _marker: PhantomInvariant(std::marker::PhantomData),
         }
     }
@@ -244,6 +418,8 @@ where
     fn cloned(&self) -> TypedModel<'_, T> {
         TypedModel {
             inner: self.inner.cloned(),
+            validators: self.validators.clone(),
+            repair_attempts: self.repair_attempts,
             _marker: PhantomInvariant(std::marker::PhantomData),
         }
     }
@@ -265,11 +441,141 @@ where
         let inner = value.as_response_schema::<T>();
         TypedModel {
             inner,
+            validators: Vec::new(),
+            repair_attempts: 0,
             _marker: PhantomInvariant(std::marker::PhantomData),
         }
     }
 }
 
+/// Checks on a parsed response beyond what its schema can express
+///
+/// Enable them on a model with [`TypedModel::validated`].
+///
+/// # Example
+/// ```
+/// use google_ai_rs::Validate;
+///
+/// struct Sentiment {
+///     label: String,
+///     confidence: f64,
+/// }
+///
+/// impl Validate for Sentiment {
+///     fn validate(&self) -> Result<(), Vec<String>> {
+///         let mut violations = Vec::new();
+///         if !(0.0..=1.0).contains(&self.confidence) {
+///             violations.push("confidence must be 0..=1".into());
+///         }
+///         if self.label.is_empty() {
+///             violations.push("label must not be empty".into());
+///         }
+///         if violations.is_empty() {
+///             Ok(())
+///         } else {
+///             Err(violations)
+///         }
+///     }
+/// }
+/// ```
+pub trait Validate {
+    /// Returns every problem found with the response
+    fn validate(&self) -> Result<(), Vec<String>>;
+}
+
+/// Problems a response still had after its repair attempts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violations(pub Vec<String>);
+
+impl fmt::Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join("; "))
+    }
+}
+
+impl std::error::Error for Violations {}
+
+/// How typed parsing of one response type has gone
+///
+/// See [`Client::parse_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Responses that parsed and passed validation, possibly after repairs
+    pub parsed: u64,
+    /// Responses that didn't, even after repairs
+    pub failed: u64,
+    /// Requests that needed at least one repair round
+    pub repaired: u64,
+    /// Repair rounds across all requests
+    pub repair_rounds: u64,
+}
+
+impl ParseStats {
+    /// Returns the share of requests that needed repairs, from 0 to 1
+    pub fn repair_rate(&self) -> f64 {
+        let total = self.parsed + self.failed;
+        if total == 0 {
+            return 0.0;
+        }
+        self.repaired as f64 / total as f64
+    }
+
+    /// Returns the average number of rounds of requests that needed repairs
+    pub fn average_repair_rounds(&self) -> f64 {
+        if self.repaired == 0 {
+            return 0.0;
+        }
+        self.repair_rounds as f64 / self.repaired as f64
+    }
+}
+
+fn record_parse<T>(
+    stats: &Mutex<HashMap<&'static str, ParseStats>>,
+    _response: &GenerateContentResponse,
+    parsed: bool,
+    repair_rounds: u32,
+) {
+    let type_name = std::any::type_name::<T>();
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        type_name,
+        parsed,
+        repair_rounds,
+        model_version = _response.model_version(),
+        response_id = _response.response_id(),
+        "typed response parse"
+    );
+
+    let mut stats = stats.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = stats.entry(type_name).or_default();
+    if parsed {
+        stats.parsed += 1;
+    } else {
+        stats.failed += 1;
+    }
+    if repair_rounds > 0 {
+        stats.repaired += 1;
+        stats.repair_rounds += u64::from(repair_rounds);
+    }
+}
+
+/// Contents already split into a system instruction and turns
+struct Prepared(Option<Content>, Vec<Content>);
+
+impl TryIntoContents for Prepared {
+    fn try_into_contents(self) -> Result<Vec<Content>, Error> {
+        let Prepared(system, mut contents) = self;
+        if let Some(system) = system {
+            contents.insert(0, system);
+        }
+        Ok(contents)
+    }
+
+    fn try_into_system_and_contents(self) -> Result<(Option<Content>, Vec<Content>), Error> {
+        Ok((self.0, self.1))
+    }
+}
+
 /// Container for typed responses with raw API data.
 ///
 /// Preserves full response details while providing parsed content.
@@ -278,6 +584,14 @@ pub struct TypedResponse<T> {
     pub t: T,
     /// Raw API response structure    
     pub raw: GenerateContentResponse,
+    /// Confidence score of the first candidate in `[0, 1]`, if the
+    /// response carries log probabilities.
+    ///
+    /// See [`Candidate::confidence`](crate::Candidate::confidence).
+    pub confidence: Option<f64>,
+    /// Things that went wrong but didn't fail the request, e.g. a response
+    /// schema the model rejected and that was retried without constraints
+    pub warnings: Vec<String>,
 }
 
 impl<T> Debug for TypedResponse<T>
@@ -303,6 +617,51 @@ impl<T> DerefMut for TypedResponse<T> {
     }
 }
 
+impl<T> TypedResponse<T> {
+    /// Returns which grounding sources back which values of `t`
+    ///
+    /// Empty unless grounding, e.g. through Google Search, was on. See
+    /// [`FieldCitations`].
+    pub fn field_citations(&self) -> FieldCitations<T> {
+        match self.raw.candidates.first() {
+            Some(candidate) => FieldCitations::new(candidate),
+            None => FieldCitations::from(Vec::new()),
+        }
+    }
+
+    /// Returns the parts besides text of each candidate, e.g. images
+    /// generated alongside the structured data
+    ///
+    /// One list per candidate, in order. `t` is parsed from the text parts
+    /// only, so these are left for the caller.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::{Data, TypedResponse};
+    /// # fn f<Recipe>(recipe: TypedResponse<Recipe>) {
+    /// for part in recipe.extra_parts().into_iter().flatten() {
+    ///     if let Some(Data::InlineData(image)) = &part.data {
+    ///         println!("{} image of {} bytes", image.mime_type, image.data.len());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn extra_parts(&self) -> Vec<Vec<&Part>> {
+        self.raw
+            .candidates
+            .iter()
+            .map(|candidate| {
+                candidate
+                    .content
+                    .iter()
+                    .flat_map(|content| &content.parts)
+                    .filter(|part| !matches!(part.data, Some(Data::Text(_))))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
 /// Configured interface for a specific generative AI model
 ///
 /// # Example
@@ -337,6 +696,20 @@ pub struct GenerativeModel<'c> {
     /// Fullname of the cached content to use as context
     /// (e.g., "cachedContents/NAME")
     pub cached_content: Option<Box<str>>,
+    /// Name of the client profile to fall back to
+    pub profile: Option<Box<str>>,
+    /// How streams recover from failing midway
+    pub stream_resume: Option<StreamResume>,
+    /// Drops text stream chunks repeat
+    pub text_deltas: Option<TextDeltas>,
+    /// Shrinks prompt contents before they're sent
+    pub compression: Option<Arc<dyn Compress>>,
+    /// Candidate model a share of calls is duplicated to
+    pub shadow: Option<Shadow>,
+    /// Models requests are split between instead of this one
+    pub split: Option<Split>,
+    /// Keeps requests of one conversation on the same model of a split
+    pub conversation_id: Option<Box<str>>,
 }
 
 impl<'c> GenerativeModel<'c> {
@@ -352,15 +725,23 @@ impl<'c> GenerativeModel<'c> {
     }
 
     fn new_inner(client: impl Into<CClient<'c>>, name: &str) -> Self {
+        let client = client.into();
         Self {
-            client: client.into(),
-            model_name: full_model_name(name).into(),
+            model_name: client.model_name(name).into(),
+            client,
             system_instruction: None,
             tools: None,
             tool_config: None,
             safety_settings: None,
             generation_config: None,
             cached_content: None,
+            profile: None,
+            stream_resume: None,
+            text_deltas: None,
+            compression: None,
+            shadow: None,
+            split: None,
+            conversation_id: None,
         }
     }
 
@@ -405,7 +786,11 @@ impl<'c> GenerativeModel<'c> {
     where
         T: TryIntoContents,
     {
-        self.cloned().generate_content_consuming(contents).await
+        // Fallback warnings are logged with the `tracing` feature; the typed
+        // responses of `TypedModel` carry them too
+        self.generate_with_warnings(contents)
+            .await
+            .map(|(response, _)| response)
     }
 
     /// Generates content by consuming the model instance.
@@ -420,12 +805,153 @@ impl<'c> GenerativeModel<'c> {
     where
         T: TryIntoContents,
     {
-        let mut gc = self.client.gc.clone();
-        let request = self.build_request(contents)?;
-        gc.generate_content(request)
+        // Fallback warnings are logged with the `tracing` feature; the typed
+        // responses of `TypedModel` carry them too
+        self.generate_with_warnings(contents)
             .await
-            .map_err(status_into_error)
-            .map(|r| r.into_inner())
+            .map(|(response, _)| response)
+    }
+
+    /// Generates content with some settings overridden for this call only
+    ///
+    /// Overrides apply on top of the settings the call would otherwise use,
+    /// including those of the model's profile and the client.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::{genai::RequestOptions, Client};
+    /// # async fn f(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let model = client.generative_model("gemini-2.0-flash").temperature(0.2);
+    ///
+    /// let strict = model.generate_content("Summarize the report").await?;
+    /// let creative = model
+    ///     .generate_content_with(
+    ///         "Suggest a title for the report",
+    ///         RequestOptions::new().temperature(1.2).max_output_tokens(32),
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn generate_content_with<T>(
+        &self,
+        contents: T,
+        options: RequestOptions,
+    ) -> Result<GenerateContentResponse, Error>
+    where
+        T: TryIntoContents,
+    {
+        let overrides = Overrides {
+            options: Some(options),
+            ..Default::default()
+        };
+        let request = self.build_request(contents, Some(&overrides))?;
+        self.send_with_warnings(request)
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Builds the request [`generate_content`](Self::generate_content) would
+    /// send, with the model's and client's settings applied
+    ///
+    /// Useful for sending it another way, e.g. through the client's
+    /// `tower` service impl.
+    pub fn to_request<T>(&self, contents: T) -> Result<GenerateContentRequest, Error>
+    where
+        T: TryIntoContents,
+    {
+        self.build_request(contents, None)
+    }
+
+    /// Generates content, retrying once without schema constraints if the
+    /// server rejects the response schema
+    ///
+    /// Returns warnings about such fallbacks along with the response.
+    async fn generate_with_warnings<T>(
+        &self,
+        contents: T,
+    ) -> Result<(GenerateContentResponse, Vec<String>), Error>
+    where
+        T: TryIntoContents,
+    {
+        let request = self.build_request(contents, None)?;
+        self.send_with_warnings(request).await
+    }
+
+    /// Sends a request built by [`Self::build_request`], as
+    /// [`Self::generate_with_warnings`] does
+    async fn send_with_warnings(
+        &self,
+        request: GenerateContentRequest,
+    ) -> Result<(GenerateContentResponse, Vec<String>), Error> {
+        let client = &self.client;
+        if client.config().check_context_window {
+            crate::context::check(client, &request).await?;
+        }
+        let shadowed = self
+            .shadow
+            .clone()
+            .filter(Shadow::sample)
+            .map(|shadow| (shadow, request.clone()));
+
+        // Older models reject some constraints with INVALID_ARGUMENT naming
+        // the response schema
+        let schema = request
+            .generation_config
+            .as_ref()
+            .and_then(|config| config.response_schema.as_ref());
+        let fallback = schema
+            .map(Schema::without_constraints)
+            .filter(|plain| Some(plain) != schema)
+            .map(|plain| {
+                let mut fallback = request.clone();
+                if let Some(config) = &mut fallback.generation_config {
+                    config.response_schema = Some(plain);
+                }
+                fallback
+            });
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "generate_content",
+            model = &*request.model,
+            model_version = tracing::field::Empty,
+            response_id = tracing::field::Empty,
+        );
+
+        let started = Instant::now();
+        let sending = async {
+            match (send(client, request).await, fallback) {
+                (Err(err), Some(fallback)) if is_schema_rejection(&err) => {
+                    let response = send(client, fallback).await?;
+                    let warning =
+                        format!("response schema was rejected, retried without constraints: {err}");
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("{warning}");
+                    Ok((response, vec![warning]))
+                }
+                (result, _) => result.map(|response| (response, Vec::new())),
+            }
+        };
+        #[cfg(feature = "tracing")]
+        let sending = tracing::Instrument::instrument(sending, span.clone());
+        let result = sending.await;
+
+        #[cfg(feature = "tracing")]
+        if let Ok((response, _)) = &result {
+            span.record("model_version", response.model_version());
+            span.record("response_id", response.response_id());
+        }
+
+        if let (Some((shadow, request)), Ok((response, _))) = (shadowed, &result) {
+            shadow.spawn(
+                client.to_shared(),
+                request,
+                response.clone(),
+                started.elapsed(),
+            );
+        }
+        result
     }
 
     /// A convenience method to generate a structured response of type `T`.
@@ -505,17 +1031,58 @@ impl<'c> GenerativeModel<'c> {
     where
         T: TryIntoContents,
     {
-        let mut gc = self.client.gc.clone();
-        let request = self.build_request(contents)?;
-        gc.stream_generate_content(request)
-            .await
-            .map_err(status_into_error)
-            .map(|s| ResponseStream(s.into_inner()))
+        let request = self.build_request(contents, None)?;
+        self.stream_request(request).await
+    }
+
+    /// Streams a request built by [`Self::build_request`]
+    async fn stream_request(
+        &self,
+        request: GenerateContentRequest,
+    ) -> Result<ResponseStream, Error> {
+        let client = &self.client;
+        if client.config().check_context_window {
+            crate::context::check(client, &request).await?;
+        }
+        let model = request.model.clone();
+
+        let resume = self.stream_resume.clone().map(|options| Resume {
+            options,
+            request: request.clone(),
+            partial: String::new(),
+            attempts: 0,
+            client: client.to_shared(),
+        });
+
+        let cached_content = request.cached_content.clone();
+        let cached_content = cached_content.as_deref();
+
+        // The stream stays in flight until it's dropped
+        let mut in_flight = client.lifecycle.enter()?;
+        let call = async {
+            client
+                .route_in(&mut in_flight, cached_content, async |region| {
+                    region.gc.clone().stream_generate_content(request).await
+                })
+                .await?
+                .map_err(status_into_error)
+        };
+        let inner = client.config.call(&model, call).await?.into_inner();
+        Ok(ResponseStream {
+            inner,
+            in_flight,
+            resume,
+            text_deltas: self.text_deltas.clone(),
+            #[cfg(feature = "chaos")]
+            chaos_left: chaos_left(&client.config()),
+        })
     }
 
     /// Estimates token usage for given content
     ///
-    /// Useful for cost estimation and validation before full generation
+    /// Useful for cost estimation and validation before full generation.
+    /// The client remembers recent counts, so counting an unchanged prompt
+    /// again doesn't make another call.
     ///
     /// # Arguments
     /// * `parts` - Content input that can be converted to parts
@@ -538,19 +1105,14 @@ impl<'c> GenerativeModel<'c> {
     where
         T: TryIntoContents,
     {
-        let mut gc = self.client.gc.clone();
-
         // Builds token counting request
         let request = CountTokensRequest {
             model: self.model_name.to_string(),
             contents: vec![],
-            generate_content_request: Some(self.clone().build_request(contents)?),
+            generate_content_request: Some(self.build_request(contents, None)?),
         };
 
-        gc.count_tokens(request)
-            .await
-            .map_err(status_into_error)
-            .map(|r| r.into_inner())
+        crate::context::count_tokens(&self.client, request).await
     }
 
     /// info returns information about the model.
@@ -569,7 +1131,7 @@ impl<'c> GenerativeModel<'c> {
 
     /// Changes the model identifier for this instance in place.
     pub fn change_model(&mut self, to: &str) {
-        self.model_name = full_model_name(to).into()
+        self.model_name = self.client.model_name(to).into()
     }
 
     /// Returns the full identifier of the model, including any `models/` prefix.
@@ -622,6 +1184,74 @@ impl<'c> GenerativeModel<'c> {
         Ok(self)
     }
 
+    /// Falls back to a named profile of the client's configuration
+    ///
+    /// The profile is looked up when each request is sent, so it follows
+    /// [`Client::update_config`]. Requests fail with
+    /// [`Error::InvalidArgument`] while no profile has this name.
+    pub fn with_profile(mut self, name: &str) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Lets streams pick up where they stopped when they fail midway
+    ///
+    /// See [`StreamResume`].
+    pub fn with_stream_resume(mut self, stream_resume: StreamResume) -> Self {
+        self.stream_resume = Some(stream_resume);
+        self
+    }
+
+    /// Cuts text repeating earlier chunks off stream chunks, so they can be
+    /// appended as they come
+    ///
+    /// Resumed streams, which tend to restate their last words, are covered
+    /// too. See [`TextDeltas`].
+    pub fn with_text_deltas(mut self, text_deltas: TextDeltas) -> Self {
+        self.text_deltas = Some(text_deltas);
+        self
+    }
+
+    /// Compresses every prompt's contents before sending, e.g. with
+    /// [`Dedup`](crate::context::Dedup)
+    ///
+    /// Compression runs on the full contents of each request, so a chat
+    /// session's history is compressed as sent while the session keeps the
+    /// original.
+    pub fn with_compression<C: Compress + 'static>(mut self, compression: C) -> Self {
+        self.compression = Some(Arc::new(compression));
+        self
+    }
+
+    /// Duplicates a share of calls to a candidate model and reports how it
+    /// compares
+    ///
+    /// See [`Shadow`]. On a [`TypedModel`], use
+    /// [`TypedModel::with_shadow`] to also compare parsing.
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    /// Splits requests between models by weight instead of sending them to
+    /// this one
+    ///
+    /// Takes precedence over a profile's split. See [`Split`].
+    pub fn split<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = (S, u32)>,
+        S: Into<String>,
+    {
+        self.split = Some(Split::new(models));
+        self
+    }
+
+    /// Sends every request of a conversation to the same model of a split
+    pub fn with_conversation_id(mut self, id: &str) -> Self {
+        self.conversation_id = Some(id.into());
+        self
+    }
+
     /// Specifies expected response format (e.g., "application/json")
     pub fn with_response_format(mut self, mime_type: &str) -> Self {
         self.generation_config
@@ -691,7 +1321,11 @@ impl<'c> GenerativeModel<'c> {
         if c.response_mime_type.is_empty() {
             c.response_mime_type = "application/json".into();
         }
-        c.response_schema = Some(schema);
+        // An unspecified schema stands for any JSON, which the API only
+        // takes as a bare JSON response
+        c.response_schema = (schema.r#type != SchemaType::Unspecified as i32
+            || !schema.any_of.is_empty())
+        .then_some(schema);
         self
     }
 
@@ -801,6 +1435,15 @@ impl<'c> GenerativeModel<'c> {
         self
     }
 
+    /// Requests log probabilities in the response.
+    ///
+    /// `x` is the number of top alternatives returned at each decoding step.
+    /// Enables [`Candidate::confidence`](crate::Candidate::confidence) scoring.
+    pub fn logprobs(mut self, x: i32) -> Self {
+        self.set_logprobs(x);
+        self
+    }
+
     /// Sets the number of candidates to generate.
     ///
     /// This parameter specifies how many different response candidates the model should generate
@@ -848,22 +1491,114 @@ impl<'c> GenerativeModel<'c> {
         self.generation_config.get_or_insert_default().top_k = Some(x)
     }
 
-    #[inline(always)]
+    /// Requests log probabilities in the response.
+    ///
+    /// `x` is the number of top alternatives returned at each decoding step.
+    pub fn set_logprobs(&mut self, x: i32) {
+        let c = self.generation_config.get_or_insert_default();
+        c.response_logprobs = Some(true);
+        c.logprobs = Some(x);
+    }
+
+    /// Builds the request for `contents`, with an overlay's settings over
+    /// the model's
     fn build_request(
-        self,
+        &self,
         contents: impl TryIntoContents,
+        overrides: Option<&Overrides>,
     ) -> Result<GenerateContentRequest, Error> {
-        let contents = contents.try_into_contents()?;
-        Ok(GenerateContentRequest {
-            model: self.model_name.into(),
+        let config = self.client.config();
+        let profile = match &self.profile {
+            Some(name) => Some(config.profiles.get(&**name).ok_or_else(|| {
+                Error::InvalidArgument(format!("no client profile named {name}").into())
+            })?),
+            None => None,
+        };
+
+        // The model's own settings win over its profile's, which win over
+        // the client's.
+        // Cached content carries its own instruction and tools, which
+        // requests can't repeat, so with it they're only sent if the model
+        // sets them itself
+        let uncached = profile.filter(|_| self.cached_content.is_none());
+        let model_instruction = self
+            .system_instruction
+            .clone()
+            .or_else(|| uncached.and_then(|p| p.system_instruction.clone()));
+        let mut safety_settings = self
+            .safety_settings
+            .clone()
+            .or_else(|| profile.and_then(|p| p.safety_settings.clone()))
+            .or_else(|| config.safety_settings.clone());
+        // Field by field, so a model setting only its temperature keeps the
+        // profile's output limit
+        let mut generation_config = overrides
+            .and_then(|o| o.generation_config.as_ref())
+            .or(self.generation_config.as_ref())
+            .cloned();
+        let inherited = [
+            profile.and_then(|p| p.generation_config.as_ref()),
+            config.generation_config.as_ref(),
+        ];
+        for fallback in inherited.into_iter().flatten() {
+            match &mut generation_config {
+                Some(generation_config) => inherit(generation_config, fallback),
+                None => generation_config = Some(fallback.clone()),
+            }
+        }
+        if let Some(options) = overrides.and_then(|o| o.options.as_ref()) {
+            options.apply(&mut generation_config, &mut safety_settings);
+        }
+        let split = self
+            .split
+            .as_ref()
+            .or_else(|| profile.and_then(|p| p.split.as_ref()));
+        let conversation_id = overrides
+            .and_then(|o| o.conversation_id.as_deref())
+            .or(self.conversation_id.as_deref());
+        let model = match split.and_then(|split| split.pick(conversation_id)) {
+            Some(name) => {
+                let model = self.client.resolve_model(name);
+                #[cfg(feature = "tracing")]
+                tracing::debug!(from = &*self.model_name, to = &*model, "split request");
+                model
+            }
+            None => self.model_name.to_string(),
+        };
+
+        let (system, mut contents) = contents.try_into_system_and_contents()?;
+        if let Some(compression) = &self.compression {
+            let _compressed = compression.compress(&mut contents);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                model = &*model,
+                parts = _compressed.parts,
+                tokens_saved = _compressed.tokens_saved,
+                "compressed prompt"
+            );
+        }
+        // The input's own system instruction follows the model's
+        let system_instruction = match (model_instruction, system) {
+            (Some(mut instruction), Some(system)) => {
+                instruction.parts.extend(system.parts);
+                Some(instruction)
+            }
+            (instruction, system) => instruction.or(system),
+        };
+        let request = GenerateContentRequest {
+            model,
             contents,
-            system_instruction: self.system_instruction,
-            tools: self.tools.unwrap_or_default(),
-            tool_config: self.tool_config,
-            safety_settings: self.safety_settings.unwrap_or_default(),
-            generation_config: self.generation_config,
-            cached_content: self.cached_content.map(|c| c.into()),
-        })
+            system_instruction,
+            tools: self.tools.clone().unwrap_or_default(),
+            tool_config: self.tool_config.clone(),
+            safety_settings: safety_settings.unwrap_or_default(),
+            generation_config,
+            cached_content: self.cached_content.as_deref().map(Into::into),
+        };
+        if config.strict_validation {
+            crate::validate::request(&request)?;
+        }
+        Ok(request)
     }
 
     // This is to avoid the performance overhead while cloning
@@ -876,6 +1611,253 @@ impl<'c> GenerativeModel<'c> {
     }
 }
 
+/// Sends a generation request through the client's limits
+pub(crate) async fn send(
+    client: &CClient<'_>,
+    request: GenerateContentRequest,
+) -> Result<GenerateContentResponse, Error> {
+    let model = request.model.clone();
+    let cached_content = request.cached_content.clone();
+    let call = async {
+        client
+            .route_to(cached_content.as_deref(), async |region| {
+                region.gc.clone().generate_content(request).await
+            })
+            .await?
+            .map_err(status_into_error)
+            .map(|r| r.into_inner())
+    };
+    let response = client.config.call(&model, call).await;
+    #[cfg(feature = "chaos")]
+    let response = response.map(|mut response| {
+        if let Some(chaos) = &client.config().chaos {
+            chaos.corrupt(&mut response);
+        }
+        response
+    });
+    response
+}
+
+/// Fills the fields `config` leaves unset from `fallback`
+fn inherit(config: &mut GenerationConfig, fallback: &GenerationConfig) {
+    let GenerationConfig {
+        candidate_count,
+        stop_sequences,
+        max_output_tokens,
+        temperature,
+        top_p,
+        top_k,
+        response_mime_type,
+        response_schema,
+        presence_penalty,
+        frequency_penalty,
+        response_logprobs,
+        logprobs,
+        enable_enhanced_civic_answers,
+        response_modalities,
+        speech_config,
+    } = fallback;
+
+    config.candidate_count = config.candidate_count.or(*candidate_count);
+    if config.stop_sequences.is_empty() {
+        config.stop_sequences.clone_from(stop_sequences);
+    }
+    config.max_output_tokens = config.max_output_tokens.or(*max_output_tokens);
+    config.temperature = config.temperature.or(*temperature);
+    config.top_p = config.top_p.or(*top_p);
+    config.top_k = config.top_k.or(*top_k);
+    if config.response_mime_type.is_empty() {
+        config.response_mime_type.clone_from(response_mime_type);
+    }
+    if config.response_schema.is_none() {
+        config.response_schema.clone_from(response_schema);
+    }
+    config.presence_penalty = config.presence_penalty.or(*presence_penalty);
+    config.frequency_penalty = config.frequency_penalty.or(*frequency_penalty);
+    config.response_logprobs = config.response_logprobs.or(*response_logprobs);
+    config.logprobs = config.logprobs.or(*logprobs);
+    config.enable_enhanced_civic_answers = config
+        .enable_enhanced_civic_answers
+        .or(*enable_enhanced_civic_answers);
+    if config.response_modalities.is_empty() {
+        config.response_modalities.clone_from(response_modalities);
+    }
+    if config.speech_config.is_none() {
+        config.speech_config.clone_from(speech_config);
+    }
+}
+
+/// Whether the server rejected the request's response schema, rather than
+/// something else about it
+fn is_schema_rejection(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Service(ServiceError::ApiError(status))
+            if status.0.code() == tonic::Code::InvalidArgument
+                && ["response_schema", "responseSchema"]
+                    .iter()
+                    .any(|field| status.0.message().contains(field))
+    )
+}
+
+/// A model shared read-only by many requests
+///
+/// Cloning a handle or making an [overlay](Self::overlay) copies an `Arc`,
+/// not the model's instruction, tools and settings, so a server can keep
+/// one handle and derive a cheap overlay per request. The base is copied
+/// once, into the request itself, when an overlay generates.
+///
+/// # Example
+/// ```
+/// # use google_ai_rs::{genai::ModelHandle, SharedClient};
+/// # async fn f(client: SharedClient) -> Result<(), google_ai_rs::Error> {
+/// let handle = client
+///     .generative_model("gemini-2.5-flash")
+///     .with_system_instruction("You are a support agent for ACME.")
+///     .into_handle();
+///
+/// // Per request
+/// let response = handle
+///     .overlay()
+///     .temperature(0.2)
+///     .generate_content("Where is my order?")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ModelHandle<'c> {
+    base: Arc<GenerativeModel<'c>>,
+}
+
+impl<'c> GenerativeModel<'c> {
+    /// Freezes the model into a [`ModelHandle`] for sharing
+    pub fn into_handle(self) -> ModelHandle<'c> {
+        ModelHandle {
+            base: Arc::new(self),
+        }
+    }
+}
+
+impl<'c> ModelHandle<'c> {
+    /// Returns the shared model
+    pub fn base(&self) -> &GenerativeModel<'c> {
+        &self.base
+    }
+
+    /// Starts a per-request overlay with the base's settings
+    pub fn overlay(&self) -> ModelOverlay<'c> {
+        ModelOverlay {
+            base: self.base.clone(),
+            overrides: Overrides::default(),
+        }
+    }
+}
+
+/// Per-request overrides on a [`ModelHandle`]
+///
+/// Only the overridden generation config is owned; everything else is
+/// read from the shared base.
+#[derive(Clone, Debug)]
+pub struct ModelOverlay<'c> {
+    base: Arc<GenerativeModel<'c>>,
+    overrides: Overrides,
+}
+
+/// What an overlay sets over its base model
+#[derive(Clone, Debug, Default)]
+struct Overrides {
+    generation_config: Option<GenerationConfig>,
+    conversation_id: Option<Box<str>>,
+    /// Overrides for a single call, applied last
+    options: Option<RequestOptions>,
+}
+
+impl<'c> ModelOverlay<'c> {
+    /// Overrides the temperature
+    pub fn temperature(mut self, x: f32) -> Self {
+        self.config().temperature = Some(x);
+        self
+    }
+
+    /// Overrides the maximum number of output tokens
+    pub fn max_output_tokens(mut self, x: i32) -> Self {
+        self.config().max_output_tokens = Some(x);
+        self
+    }
+
+    /// Replaces the generation config
+    pub fn generation_config(mut self, config: GenerationConfig) -> Self {
+        self.overrides.generation_config = Some(config);
+        self
+    }
+
+    /// Sets the conversation, to stay on one model of a split
+    pub fn conversation_id(mut self, id: &str) -> Self {
+        self.overrides.conversation_id = Some(id.into());
+        self
+    }
+
+    /// Returns a copy of the base model with the overrides applied
+    ///
+    /// Generating through the overlay itself doesn't copy the base.
+    pub fn model(&self) -> GenerativeModel<'_> {
+        let mut model = self.base.cloned();
+        if let Some(config) = &self.overrides.generation_config {
+            model.generation_config = Some(config.clone());
+        }
+        if let Some(id) = &self.overrides.conversation_id {
+            model.conversation_id = Some(id.clone());
+        }
+        model
+    }
+
+    /// Builds the request [`generate_content`](Self::generate_content)
+    /// would send
+    ///
+    /// See [`GenerativeModel::to_request`].
+    pub fn to_request<T>(&self, contents: T) -> Result<GenerateContentRequest, Error>
+    where
+        T: TryIntoContents,
+    {
+        self.base.build_request(contents, Some(&self.overrides))
+    }
+
+    /// Generates content with the overrides applied
+    ///
+    /// See [`GenerativeModel::generate_content`].
+    pub async fn generate_content<T>(&self, contents: T) -> Result<GenerateContentResponse, Error>
+    where
+        T: TryIntoContents,
+    {
+        let request = self.to_request(contents)?;
+        // Fallback warnings are logged with the `tracing` feature
+        self.base
+            .send_with_warnings(request)
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Streams content with the overrides applied
+    ///
+    /// See [`GenerativeModel::stream_generate_content`].
+    pub async fn stream_generate_content<T>(&self, contents: T) -> Result<ResponseStream, Error>
+    where
+        T: TryIntoContents,
+    {
+        let request = self.to_request(contents)?;
+        self.base.stream_request(request).await
+    }
+
+    /// The overridden config, copied from the base on first override
+    fn config(&mut self) -> &mut GenerationConfig {
+        let base = &self.base;
+        self.overrides
+            .generation_config
+            .get_or_insert_with(|| base.generation_config.clone().unwrap_or_default())
+    }
+}
+
 impl SafetySetting {
     /// Creates a new [`SafetySetting`] with default values
     pub fn new() -> Self {
@@ -909,10 +1891,295 @@ impl Response {
             meta.total_token_count as f64 + meta.cached_content_token_count as f64
         })
     }
+
+    /// Model version that generated the response, if the API reported one
+    pub fn model_version(&self) -> Option<&str> {
+        Some(self.model_version.as_str()).filter(|v| !v.is_empty())
+    }
+
+    /// Identifier of the response, if the API reported one
+    ///
+    /// Every chunk of a streamed response carries the same ID.
+    pub fn response_id(&self) -> Option<&str> {
+        Some(self.response_id.as_str()).filter(|id| !id.is_empty())
+    }
+
+    /// How the prompt was judged, if the API said
+    pub fn prompt_feedback(&self) -> Option<PromptFeedback> {
+        self.prompt_feedback
+            .as_ref()
+            .map(|feedback| PromptFeedback {
+                block_reason: Some(feedback.block_reason())
+                    .filter(|reason| *reason != BlockReason::Unspecified),
+                safety_ratings: feedback
+                    .safety_ratings
+                    .iter()
+                    .map(|rating| HarmRating {
+                        category: rating.category(),
+                        probability: rating.probability(),
+                        blocked: rating.blocked,
+                    })
+                    .collect(),
+            })
+    }
+
+    /// Returns the response, or an error if the prompt was blocked
+    ///
+    /// A blocked prompt gets no candidates, so this is worth checking
+    /// before reading any.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidContent`] holding a [`PromptBlocked`].
+    pub fn ok_or_feedback(self) -> Result<Self, Error> {
+        match self.prompt_feedback() {
+            Some(PromptFeedback {
+                block_reason: Some(reason),
+                safety_ratings,
+            }) => Err(Error::InvalidContent(Box::new(PromptBlocked {
+                reason,
+                safety_ratings,
+            }))),
+            _ => Ok(self),
+        }
+    }
+}
+
+/// How a prompt was judged
+#[derive(Clone, Debug, PartialEq)]
+pub struct PromptFeedback {
+    /// Why the prompt was blocked, `None` if it wasn't
+    pub block_reason: Option<BlockReason>,
+    /// Safety ratings of the prompt, at most one per category
+    pub safety_ratings: Vec<HarmRating>,
+}
+
+/// How likely some content is to be harmful in one category
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HarmRating {
+    pub category: HarmCategory,
+    pub probability: HarmProbability,
+    /// Whether the content was blocked because of this rating
+    pub blocked: bool,
+}
+
+/// A prompt the API refused to answer
+#[derive(Clone, Debug, PartialEq)]
+pub struct PromptBlocked {
+    pub reason: BlockReason,
+    pub safety_ratings: Vec<HarmRating>,
+}
+
+impl fmt::Display for PromptBlocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "prompt blocked ({})", self.reason.as_str_name())?;
+        let blocked = self.safety_ratings.iter().filter(|rating| rating.blocked);
+        for (i, rating) in blocked.enumerate() {
+            let sep = if i == 0 { ": " } else { ", " };
+            write!(
+                f,
+                "{sep}{} is {}",
+                rating.category.as_str_name(),
+                rating.probability.as_str_name()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PromptBlocked {}
+
+/// Recovery for streams that fail midway
+///
+/// When the upstream fails after a stream has started, the request is sent
+/// again with the text received so far as a model turn, followed by
+/// `instruction` as a user turn. The continuation then carries on in the
+/// same [`ResponseStream`]. Failures that aren't the upstream's (invalid
+/// requests, deadlines, shutdown) are returned as usual.
+///
+/// # Example
+/// ```
+/// use google_ai_rs::genai::StreamResume;
+///
+/// # async fn f(client: google_ai_rs::Client) -> Result<(), Box<dyn std::error::Error>> {
+/// let model = client
+///     .generative_model("gemini-pro")
+///     .with_stream_resume(StreamResume::new().max_attempts(3));
+///
+/// let mut stream = model.stream_generate_content("Write a long story").await?;
+/// while let Some(chunk) = stream.next().await? {
+///     print!("{}", chunk.to_text());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamResume {
+    /// How many times a single stream may be resumed
+    pub max_attempts: u32,
+    /// What the model is asked to do with its partial output
+    pub instruction: String,
+}
+
+impl Default for StreamResume {
+    fn default() -> Self {
+        Self {
+            max_attempts: 2,
+            instruction: "Continue exactly where you left off. Don't repeat anything you \
+                          already wrote."
+                .into(),
+        }
+    }
+}
+
+impl StreamResume {
+    /// Creates a recovery allowing two resumes per stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many times a single stream may be resumed
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets what the model is asked to do with its partial output
+    pub fn instruction(mut self, instruction: impl Into<String>) -> Self {
+        self.instruction = instruction.into();
+        self
+    }
+}
+
+/// Settings overridden for a single call
+///
+/// See [`GenerativeModel::generate_content_with`]. Unset fields keep the
+/// model's settings.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct RequestOptions {
+    /// Overrides the temperature
+    pub temperature: Option<f32>,
+    /// Overrides the top-p sampling parameter
+    pub top_p: Option<f32>,
+    /// Overrides the top-k sampling parameter
+    pub top_k: Option<i32>,
+    /// Overrides the maximum number of output tokens
+    pub max_output_tokens: Option<i32>,
+    /// Replaces the safety settings
+    pub safety_settings: Option<Vec<SafetySetting>>,
+}
+
+impl RequestOptions {
+    /// Creates options overriding nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the temperature
+    pub fn temperature(mut self, x: f32) -> Self {
+        self.temperature = Some(x);
+        self
+    }
+
+    /// Overrides the top-p sampling parameter
+    pub fn top_p(mut self, x: f32) -> Self {
+        self.top_p = Some(x);
+        self
+    }
+
+    /// Overrides the top-k sampling parameter
+    pub fn top_k(mut self, x: i32) -> Self {
+        self.top_k = Some(x);
+        self
+    }
+
+    /// Overrides the maximum number of output tokens
+    pub fn max_output_tokens(mut self, x: i32) -> Self {
+        self.max_output_tokens = Some(x);
+        self
+    }
+
+    /// Replaces the safety settings
+    pub fn safety_settings<I>(mut self, safety_settings: I) -> Self
+    where
+        I: IntoIterator<Item = SafetySetting>,
+    {
+        self.safety_settings = Some(safety_settings.into_iter().collect());
+        self
+    }
+
+    fn apply(
+        &self,
+        generation_config: &mut Option<GenerationConfig>,
+        safety_settings: &mut Option<Vec<SafetySetting>>,
+    ) {
+        // Left unset when nothing is overridden, so the server's defaults hold
+        if self.temperature.is_some()
+            || self.top_p.is_some()
+            || self.top_k.is_some()
+            || self.max_output_tokens.is_some()
+        {
+            let config = generation_config.get_or_insert_default();
+            config.temperature = self.temperature.or(config.temperature);
+            config.top_p = self.top_p.or(config.top_p);
+            config.top_k = self.top_k.or(config.top_k);
+            config.max_output_tokens = self.max_output_tokens.or(config.max_output_tokens);
+        }
+        if self.safety_settings.is_some() {
+            safety_settings.clone_from(&self.safety_settings);
+        }
+    }
+}
+
+/// State for resuming a stream
+struct Resume {
+    options: StreamResume,
+    /// The request as first sent
+    request: GenerateContentRequest,
+    /// Text received so far
+    partial: String,
+    attempts: u32,
+    client: SharedClient,
+}
+
+impl Resume {
+    fn record(&mut self, response: &GenerateContentResponse) {
+        self.partial.push_str(&response.to_text());
+    }
+
+    fn continuation(&self) -> GenerateContentRequest {
+        let mut request = self.request.clone();
+        // Nothing to continue from if it failed before any text arrived
+        if !self.partial.is_empty() {
+            request.contents.push(Content::model(self.partial.clone()));
+            request
+                .contents
+                .push(Content::user(self.options.instruction.clone()));
+        }
+        request
+    }
 }
 
 /// Streaming response handler implementing async iteration
-pub struct ResponseStream(Streaming<GenerateContentResponse>);
+///
+/// Counts as an in-flight call for [`Client::shutdown`] until dropped.
+pub struct ResponseStream {
+    inner: Streaming<GenerateContentResponse>,
+    in_flight: InFlight,
+    resume: Option<Resume>,
+    text_deltas: Option<TextDeltas>,
+    /// Chunks left before chaos cuts the stream
+    #[cfg(feature = "chaos")]
+    chaos_left: Option<usize>,
+}
+
+#[cfg(feature = "chaos")]
+fn chaos_left(config: &crate::ClientConfig) -> Option<usize> {
+    config
+        .chaos
+        .as_ref()
+        .and_then(|chaos| chaos.truncate_after())
+}
 
 impl ResponseStream {
     /// Streams content chunks to any `Write` implementer
@@ -970,7 +2237,74 @@ impl ResponseStream {
 
     /// Fetches next response chunk
     pub async fn next(&mut self) -> Result<Option<GenerateContentResponse>, Error> {
-        self.0.message().await.map_err(status_into_error)
+        loop {
+            let mut result = match self.cut() {
+                Some(err) => Err(err),
+                None => self
+                    .in_flight
+                    .run(self.inner.message())
+                    .await
+                    .and_then(|r| r.map_err(status_into_error)),
+            };
+            if let (Some(text_deltas), Ok(Some(response))) = (&mut self.text_deltas, &mut result) {
+                text_deltas.apply(response);
+            }
+
+            let Some(resume) = &mut self.resume else {
+                return result;
+            };
+
+            match result {
+                Ok(Some(response)) => {
+                    resume.record(&response);
+                    return Ok(Some(response));
+                }
+                Err(err)
+                    if resume.attempts < resume.options.max_attempts
+                        && is_upstream_failure(&err) =>
+                {
+                    resume.attempts += 1;
+                    let request = resume.continuation();
+                    let model = request.model.clone();
+                    let cached_content = request.cached_content.clone();
+                    let client = &resume.client;
+                    let in_flight = &mut self.in_flight;
+                    let call = async {
+                        client
+                            .route_in(in_flight, cached_content.as_deref(), async |region| {
+                                region.gc.clone().stream_generate_content(request).await
+                            })
+                            .await?
+                            .map_err(status_into_error)
+                    };
+                    self.inner = client.config.call(&model, call).await?.into_inner();
+                    #[cfg(feature = "chaos")]
+                    {
+                        self.chaos_left = chaos_left(&client.config());
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the error a stream cut short by chaos fails with
+    fn cut(&mut self) -> Option<Error> {
+        #[cfg(feature = "chaos")]
+        if let Some(left) = &mut self.chaos_left {
+            if *left == 0 {
+                return Some(crate::chaos::Chaos::truncated());
+            }
+            *left -= 1;
+        }
+        None
+    }
+
+    /// Returns how many times the stream was resumed
+    ///
+    /// Always `0` without [`GenerativeModel::with_stream_resume`].
+    pub fn resumed(&self) -> u32 {
+        self.resume.as_ref().map_or(0, |r| r.attempts)
     }
 }
 
@@ -1013,3 +2347,544 @@ pub enum Info {
     Tuned(TunedModel),
     Model(Model),
 }
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::{
+        config::{ClientConfig, Profile},
+        content::TryFromContents,
+        fake::FakeServer,
+        proto::Candidate,
+    };
+
+    #[tokio::test]
+    async fn inherits_generation_config_by_field() {
+        let server = FakeServer::start(|_, _| unreachable!()).await;
+        let client = server.client().await;
+        client.set_config(
+            ClientConfig::new()
+                .generation_config(GenerationConfig {
+                    temperature: Some(0.1),
+                    max_output_tokens: Some(100),
+                    top_k: Some(5),
+                    ..Default::default()
+                })
+                .profile(
+                    "short",
+                    Profile::new().generation_config(GenerationConfig {
+                        max_output_tokens: Some(50),
+                        stop_sequences: vec!["END".into()],
+                        ..Default::default()
+                    }),
+                ),
+        );
+
+        let model = client
+            .generative_model("gemini-test")
+            .with_profile("short")
+            .temperature(0.9);
+        let config = model.to_request("hi").unwrap().generation_config.unwrap();
+        assert_eq!(config.temperature, Some(0.9));
+        assert_eq!(config.max_output_tokens, Some(50));
+        assert_eq!(config.stop_sequences, ["END"]);
+        assert_eq!(config.top_k, Some(5));
+        assert_eq!(config.top_p, None);
+        assert_eq!(server.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn request_options_apply_to_one_call() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let server = FakeServer::start({
+            let sent = sent.clone();
+            move |_, body| {
+                let request = GenerateContentRequest::decode(body).unwrap();
+                sent.lock()
+                    .unwrap()
+                    .push(request.generation_config.unwrap());
+                Ok(GenerateContentResponse::default().encode_to_vec())
+            }
+        })
+        .await;
+        let client = server.client().await;
+        let model = client.generative_model("gemini-test").temperature(0.2);
+
+        model
+            .generate_content_with(
+                "hi",
+                RequestOptions::new().temperature(1.2).max_output_tokens(32),
+            )
+            .await
+            .unwrap();
+        model.generate_content("hi").await.unwrap();
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(
+            (sent[0].temperature, sent[0].max_output_tokens),
+            (Some(1.2), Some(32))
+        );
+        assert_eq!(
+            (sent[1].temperature, sent[1].max_output_tokens),
+            (Some(0.2), None)
+        );
+        assert_eq!(
+            model.generation_config.as_ref().unwrap().temperature,
+            Some(0.2)
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_content_inherits_no_instruction() {
+        let server = FakeServer::start(|_, _| unreachable!()).await;
+        let client = server.client().await;
+        client.set_config(
+            ClientConfig::new().profile("agent", Profile::new().system_instruction("Be brief.")),
+        );
+        let model = client.generative_model("gemini-test").with_profile("agent");
+        assert!(model.to_request("hi").unwrap().system_instruction.is_some());
+
+        let cache = CachedContent {
+            name: Some("cachedContents/history".into()),
+            ..Default::default()
+        };
+        let request = model
+            .with_cached_content(&cache)
+            .unwrap()
+            .to_request("hi")
+            .unwrap();
+        assert_eq!(
+            request.cached_content.as_deref(),
+            Some("cachedContents/history")
+        );
+        assert_eq!(request.system_instruction, None);
+        assert!(request.tools.is_empty());
+        assert_eq!(request.tool_config, None);
+    }
+
+    #[tokio::test]
+    async fn overlay_leaves_base_alone() {
+        let server = FakeServer::start(|_, _| unreachable!()).await;
+        let client = server.client().await;
+        let handle = client
+            .generative_model("gemini-test")
+            .with_system_instruction("Be brief.")
+            .temperature(0.5)
+            .top_k(3)
+            .into_handle();
+
+        let request = handle.overlay().temperature(0.9).to_request("hi").unwrap();
+        let config = request.generation_config.unwrap();
+        assert_eq!(config.temperature, Some(0.9));
+        assert_eq!(config.top_k, Some(3));
+        assert_eq!(
+            request.system_instruction.unwrap().parts[0].to_text(),
+            "Be brief."
+        );
+
+        let base = handle.base().generation_config.as_ref().unwrap();
+        assert_eq!(base.temperature, Some(0.5));
+        let request = handle.base().to_request("hi").unwrap();
+        assert_eq!(request.generation_config.unwrap().temperature, Some(0.5));
+    }
+
+    #[test]
+    fn retries_only_schema_rejections() {
+        let rejection = |code, message: &str| status_into_error(tonic::Status::new(code, message));
+        assert!(is_schema_rejection(&rejection(
+            tonic::Code::InvalidArgument,
+            "* GenerateContentRequest.generation_config.response_schema.properties[id].maximum: \
+             unsupported"
+        )));
+        assert!(!is_schema_rejection(&rejection(
+            tonic::Code::InvalidArgument,
+            "* GenerateContentRequest.contents: contents is not specified"
+        )));
+        assert!(!is_schema_rejection(&rejection(
+            tonic::Code::Internal,
+            "response_schema"
+        )));
+    }
+
+    #[tokio::test]
+    async fn decodes_blocked_prompts() {
+        use crate::proto::{
+            generate_content_response::PromptFeedback as RawFeedback, SafetyRating,
+        };
+
+        let server = FakeServer::start(|_, _| {
+            let response = GenerateContentResponse {
+                prompt_feedback: Some(RawFeedback {
+                    block_reason: BlockReason::Safety as i32,
+                    safety_ratings: vec![
+                        SafetyRating {
+                            category: HarmCategory::Harassment as i32,
+                            probability: HarmProbability::High as i32,
+                            blocked: true,
+                        },
+                        SafetyRating {
+                            category: HarmCategory::HateSpeech as i32,
+                            probability: HarmProbability::Low as i32,
+                            blocked: false,
+                        },
+                    ],
+                }),
+                ..Default::default()
+            };
+            Ok(response.encode_to_vec())
+        })
+        .await;
+        let client = server.client().await;
+
+        let response = client
+            .generative_model("gemini-test")
+            .generate_content("hi")
+            .await
+            .unwrap();
+        let feedback = response.prompt_feedback().unwrap();
+        assert_eq!(feedback.block_reason, Some(BlockReason::Safety));
+        assert_eq!(
+            feedback.safety_ratings,
+            [
+                HarmRating {
+                    category: HarmCategory::Harassment,
+                    probability: HarmProbability::High,
+                    blocked: true,
+                },
+                HarmRating {
+                    category: HarmCategory::HateSpeech,
+                    probability: HarmProbability::Low,
+                    blocked: false,
+                },
+            ]
+        );
+
+        let Err(Error::InvalidContent(err)) = response.ok_or_feedback() else {
+            panic!("blocked prompts should fail");
+        };
+        let blocked = err.downcast_ref::<PromptBlocked>().unwrap();
+        assert_eq!(blocked.reason, BlockReason::Safety);
+        assert_eq!(blocked.safety_ratings, feedback.safety_ratings);
+        assert_eq!(
+            blocked.to_string(),
+            "prompt blocked (SAFETY): HARM_CATEGORY_HARASSMENT is HIGH"
+        );
+
+        // Feedback without a reason isn't a block
+        let unblocked = GenerateContentResponse {
+            prompt_feedback: Some(RawFeedback::default()),
+            ..Default::default()
+        };
+        assert_eq!(unblocked.prompt_feedback().unwrap().block_reason, None);
+        assert!(unblocked.ok_or_feedback().is_ok());
+    }
+
+    struct Count(u32);
+
+    impl AsSchema for Count {
+        fn as_schema() -> Schema {
+            Schema {
+                minimum: Some(0.0),
+                ..Schema::new_integer()
+            }
+        }
+    }
+
+    impl TryFromContents for Count {
+        fn try_from_contents<'a, I>(contents: I) -> Result<Self, Error>
+        where
+            I: Iterator<Item = &'a Content>,
+        {
+            let text: String = contents.flat_map(|c| &c.parts).map(Part::to_text).collect();
+            text.parse()
+                .map(Count)
+                .map_err(|err| Error::Service(ServiceError::InvalidResponse(Box::new(err))))
+        }
+    }
+
+    #[tokio::test]
+    async fn records_parse_outcomes() {
+        // Answers "good" with a number right away, "repairable" once told
+        // what was wrong, and "bad" never
+        let server = FakeServer::start(|_, body| {
+            let request = GenerateContentRequest::decode(body).unwrap();
+            let prompt = request.contents[0].parts[0].to_text();
+            let reply = match (prompt, request.contents.len()) {
+                ("good", _) | ("repairable", 3) => "7",
+                _ => "seven",
+            };
+            let response = GenerateContentResponse {
+                candidates: vec![Candidate {
+                    content: Some(Content::model(reply)),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            Ok(response.encode_to_vec())
+        })
+        .await;
+        let client = server.client().await;
+        let model = client.typed_model::<Count>("gemini-test");
+        let repairing = model.clone().repair_attempts(1);
+
+        assert_eq!(repairing.generate_content("good").await.unwrap().0, 7);
+        assert_eq!(repairing.generate_content("repairable").await.unwrap().0, 7);
+        assert!(repairing.generate_content("bad").await.is_err());
+        assert!(model.generate_content("bad").await.is_err());
+
+        let stats = client.parse_stats()[std::any::type_name::<Count>()];
+        assert_eq!(
+            stats,
+            ParseStats {
+                parsed: 2,
+                failed: 2,
+                repaired: 2,
+                repair_rounds: 2,
+            }
+        );
+        assert_eq!(stats.repair_rate(), 0.5);
+        assert_eq!(stats.average_repair_rounds(), 1.0);
+    }
+
+    fn violations(result: Result<Count, Error>) -> Vec<String> {
+        let Err(Error::Service(ServiceError::InvalidResponse(err))) = result else {
+            panic!("the response should be invalid");
+        };
+        err.downcast_ref::<Violations>().unwrap().0.clone()
+    }
+
+    #[tokio::test]
+    async fn validates_and_repairs() {
+        // Answers too many, then a fixed count if it's told so and "fixable"
+        let server = FakeServer::start(|_, body| {
+            let request = GenerateContentRequest::decode(body).unwrap();
+            let prompt = request.contents[0].parts[0].to_text();
+            let reply = match &request.contents[..] {
+                [_, _, problems] if prompt == "fixable" => {
+                    assert!(problems.parts[0].to_text().contains("at most 10"));
+                    "5"
+                }
+                _ => "12",
+            };
+            Ok(chunk(reply))
+        })
+        .await;
+        let client = server.client().await;
+        let model = client
+            .typed_model::<Count>("gemini-test")
+            .validate_with(|count| match count.0 {
+                0..=10 => Ok(()),
+                _ => Err(vec!["count must be at most 10".into()]),
+            });
+
+        // Without repairs, the first answer must pass
+        let failed = model.generate_content("fixable").await;
+        assert_eq!(violations(failed), ["count must be at most 10"]);
+        assert_eq!(server.calls(), 1);
+
+        let repairing = model.repair_attempts(2);
+        assert_eq!(repairing.generate_content("fixable").await.unwrap().0, 5);
+        assert_eq!(server.calls(), 3);
+
+        // Each round is sent back until they run out
+        let exhausted = repairing.generate_content("stubborn").await;
+        assert_eq!(violations(exhausted), ["count must be at most 10"]);
+        assert_eq!(server.calls(), 6);
+    }
+
+    // Rejects the schema constraints older models don't support
+    async fn constraint_rejecting_server() -> FakeServer {
+        FakeServer::start(|_, body| {
+            let request = GenerateContentRequest::decode(body).unwrap();
+            let schema = request
+                .generation_config
+                .and_then(|config| config.response_schema);
+            if schema.is_some_and(|schema| schema.minimum.is_some()) {
+                return Err(tonic::Status::invalid_argument(
+                    "response_schema: minimum is not supported",
+                ));
+            }
+            let response = GenerateContentResponse {
+                candidates: vec![Candidate {
+                    content: Some(Content::model("7")),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            Ok(response.encode_to_vec())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn reports_schema_fallbacks() {
+        let server = constraint_rejecting_server().await;
+        let client = server.client().await;
+
+        let response = client
+            .typed_model::<Count>("gemini-test")
+            .generate_typed_content("how many?")
+            .await
+            .unwrap();
+        assert_eq!(response.t.0, 7);
+        let [warning] = &response.warnings[..] else {
+            panic!("one fallback should warn: {:?}", response.warnings);
+        };
+        assert!(warning.contains("retried without constraints"), "{warning}");
+        assert_eq!(server.calls(), 2);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn logs_schema_fallbacks() {
+        use std::{
+            fmt::Debug,
+            sync::{Arc, Mutex},
+        };
+        use tracing::{field::Field, span, Event, Level, Metadata};
+
+        // Keeps the messages of warnings
+        #[derive(Clone, Default)]
+        struct Warnings(Arc<Mutex<Vec<String>>>);
+
+        impl tracing::Subscriber for Warnings {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, event: &Event<'_>) {
+                if *event.metadata().level() != Level::WARN {
+                    return;
+                }
+                let mut message = String::new();
+                event.record(&mut |field: &Field, value: &dyn Debug| {
+                    if field.name() == "message" {
+                        message = format!("{value:?}");
+                    }
+                });
+                self.0.lock().unwrap().push(message);
+            }
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let warnings = Warnings::default();
+        let _guard = tracing::dispatcher::set_default(&tracing::Dispatch::new(warnings.clone()));
+
+        let server = constraint_rejecting_server().await;
+        let client = server.client().await;
+        let response = client
+            .generative_model("gemini-test")
+            .with_response_schema(Count::as_schema())
+            .generate_content("how many?")
+            .await
+            .unwrap();
+        assert_eq!(response.text(), "7");
+
+        let warnings = warnings.0.lock().unwrap();
+        assert!(
+            warnings
+                .iter()
+                .any(|warning| warning.contains("retried without constraints")),
+            "{warnings:?}"
+        );
+    }
+
+    fn chunk(text: &str) -> Vec<u8> {
+        GenerateContentResponse {
+            candidates: vec![Candidate {
+                content: Some(Content::model(text)),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    async fn collect(stream: &mut ResponseStream) -> Result<String, Error> {
+        let mut text = String::new();
+        while let Some(response) = stream.next().await? {
+            text.push_str(&response.to_text());
+        }
+        Ok(text)
+    }
+
+    #[tokio::test]
+    async fn resumes_dropped_streams() {
+        // Drops the first stream after its first chunk
+        let server = FakeServer::start_streaming(|_, body| {
+            let request = GenerateContentRequest::decode(body).unwrap();
+            match &request.contents[..] {
+                [_] => (
+                    vec![chunk("Hello, ")],
+                    Some(tonic::Status::unavailable("connection reset")),
+                ),
+                [_, partial, instruction] => {
+                    assert_eq!(partial.parts[0].to_text(), "Hello, ");
+                    assert_eq!(
+                        instruction.parts[0].to_text(),
+                        StreamResume::default().instruction
+                    );
+                    (vec![chunk("world!")], None)
+                }
+                contents => panic!("unexpected contents: {contents:?}"),
+            }
+        })
+        .await;
+        let client = server.client().await;
+        let model = client
+            .generative_model("gemini-test")
+            .with_stream_resume(StreamResume::new());
+
+        let mut stream = model.stream_generate_content("Say hello").await.unwrap();
+        assert_eq!(collect(&mut stream).await.unwrap(), "Hello, world!");
+        assert_eq!(stream.resumed(), 1);
+        assert_eq!(server.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn caps_stream_resumes() {
+        let server = FakeServer::start_streaming(|_, _| {
+            (
+                vec![chunk("again ")],
+                Some(tonic::Status::unavailable("connection reset")),
+            )
+        })
+        .await;
+        let client = server.client().await;
+        let model = client
+            .generative_model("gemini-test")
+            .with_stream_resume(StreamResume::new().max_attempts(2));
+
+        let mut stream = model.stream_generate_content("Say hello").await.unwrap();
+        let Err(Error::Service(ServiceError::ApiError(status))) = collect(&mut stream).await else {
+            panic!("the stream should fail once out of resumes");
+        };
+        assert_eq!(status.0.code(), tonic::Code::Unavailable);
+        assert_eq!(stream.resumed(), 2);
+        assert_eq!(server.calls(), 3);
+
+        // Failures of the request itself aren't resumed
+        let server = FakeServer::start_streaming(|_, _| {
+            (
+                vec![chunk("half ")],
+                Some(tonic::Status::deadline_exceeded("took too long")),
+            )
+        })
+        .await;
+        let client = server.client().await;
+        let model = client
+            .generative_model("gemini-test")
+            .with_stream_resume(StreamResume::new());
+        let mut stream = model.stream_generate_content("Say hello").await.unwrap();
+        assert!(collect(&mut stream).await.is_err());
+        assert_eq!(stream.resumed(), 0);
+        assert_eq!(server.calls(), 1);
+    }
+}
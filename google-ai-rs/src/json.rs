@@ -0,0 +1,233 @@
+//! Schema-less JSON responses
+//!
+//! Enabled by the `serde` feature. Before settling on a struct, ask for a
+//! [`JsonResponse`] and dig into whatever the model returned with JSON
+//! pointers.
+//!
+//! Typed responses also give back the JSON they were parsed from, laid out
+//! by [`JsonFormat`] for storage or hashing, with
+//! [`TypedResponse::json`](crate::TypedResponse::json).
+//!
+//! # Example
+//! ```rust,no_run
+//! use google_ai_rs::{json::JsonResponse, Client};
+//!
+//! # async fn f(client: Client) -> Result<(), google_ai_rs::Error> {
+//! let model = client.typed_model::<JsonResponse>("gemini-2.5-flash");
+//! let response = model
+//!     .generate_content("Describe Paris as JSON, with its population under /stats")
+//!     .await?;
+//!
+//! let population: u64 = response.get_as("/stats/population")?;
+//! println!("{population} people in {:?}", response.pointer("/name"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::{Deref, Range};
+
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize, Serialize, Serializer,
+};
+use serde_json::Value;
+
+use crate::{
+    error::{Error, ServiceError},
+    genai::TypedResponse,
+    proto::Schema,
+    schema::AsSchema,
+};
+
+/// Any JSON the model returns, queried by JSON pointer
+///
+/// No response schema is sent, only a request for JSON output. Derefs to
+/// the underlying [`serde_json::Value`].
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct JsonResponse(pub Value);
+
+impl JsonResponse {
+    /// Looks up a value by JSON pointer, e.g. `"/items/0/name"`
+    ///
+    /// The empty pointer `""` is the whole value.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        self.0.pointer(pointer)
+    }
+
+    /// Deserializes the value at `pointer` into a `T`
+    ///
+    /// A missing value or one of the wrong shape is reported as
+    /// [`ServiceError::InvalidResponse`], like any other response that
+    /// doesn't parse.
+    pub fn get_as<T: DeserializeOwned>(&self, pointer: &str) -> Result<T, Error> {
+        let value = self.pointer(pointer).ok_or_else(|| {
+            Error::Service(ServiceError::InvalidResponse(
+                format!("no value at {pointer:?}").into(),
+            ))
+        })?;
+        T::deserialize(value)
+            .map_err(|err| Error::Service(ServiceError::InvalidResponse(err.into())))
+    }
+
+    /// Returns the underlying value
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+}
+
+impl Deref for JsonResponse {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        &self.0
+    }
+}
+
+impl From<Value> for JsonResponse {
+    fn from(value: Value) -> Self {
+        Self(value)
+    }
+}
+
+impl AsSchema for JsonResponse {
+    fn as_schema() -> Schema {
+        Value::as_schema()
+    }
+}
+
+/// How [`TypedResponse::json`] lays out JSON
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonFormat {
+    /// Indented, one value per line
+    Pretty,
+    /// Without whitespace
+    Minified,
+    /// Without whitespace, keys sorted, so equal values give equal strings,
+    /// e.g. for hashing
+    Canonical,
+}
+
+impl<T> TypedResponse<T> {
+    /// Returns the JSON `t` was parsed from, laid out as `format`
+    ///
+    /// # Example
+    /// ```
+    /// use google_ai_rs::json::JsonFormat;
+    /// # use google_ai_rs::{Error, TypedResponse};
+    /// # fn sha256(_: String) {}
+    ///
+    /// # fn f<T>(response: TypedResponse<T>) -> Result<(), Error> {
+    /// let key = sha256(response.json(JsonFormat::Canonical)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn json(&self, format: JsonFormat) -> Result<String, Error> {
+        let invalid =
+            |err: serde_json::Error| Error::Service(ServiceError::InvalidResponse(err.into()));
+        let value: Value = serde_json::from_slice(&self.raw.to_bytes()).map_err(invalid)?;
+        match format {
+            JsonFormat::Pretty => serde_json::to_string_pretty(&value),
+            JsonFormat::Minified => serde_json::to_string(&value),
+            JsonFormat::Canonical => serde_json::to_string(&Sorted(&value)),
+        }
+        .map_err(invalid)
+    }
+
+    /// Returns the bytes of the response's output, [`to_bytes`], that hold
+    /// the JSON `t` was parsed from
+    ///
+    /// Whitespace around the JSON is left out. `None` if the output doesn't
+    /// start with JSON.
+    ///
+    /// [`to_bytes`]: crate::proto::GenerateContentResponse::to_bytes
+    pub fn json_span(&self) -> Option<Range<usize>> {
+        json_span(&self.raw.to_bytes())
+    }
+}
+
+fn json_span(output: &[u8]) -> Option<Range<usize>> {
+    let start = output.iter().position(|b| !b.is_ascii_whitespace())?;
+    let mut values =
+        serde_json::Deserializer::from_slice(&output[start..]).into_iter::<IgnoredAny>();
+    values.next()?.ok()?;
+    Some(start..start + values.byte_offset())
+}
+
+/// Serializes a value with the keys of its objects sorted
+struct Sorted<'a>(&'a Value);
+
+impl Serialize for Sorted<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Array(values) => serializer.collect_seq(values.iter().map(Sorted)),
+            Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                serializer.collect_map(entries.into_iter().map(|(key, value)| (key, Sorted(value))))
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn queries_by_pointer() {
+        let response = JsonResponse(json!({
+            "name": "Paris",
+            "stats": { "population": 2102650, "districts": [1, 2, 3] },
+        }));
+
+        assert_eq!(response.pointer("/name"), Some(&json!("Paris")));
+        assert_eq!(
+            response.get_as::<u64>("/stats/population").unwrap(),
+            2102650
+        );
+        assert_eq!(
+            response.get_as::<Vec<u8>>("/stats/districts").unwrap(),
+            [1, 2, 3]
+        );
+        assert!(response.get_as::<u64>("/stats/area").is_err());
+        assert!(response.get_as::<u64>("/name").is_err());
+    }
+
+    #[test]
+    fn formats_and_spans() {
+        let response = TypedResponse {
+            t: (),
+            raw: crate::proto::GenerateContentResponse {
+                candidates: vec![crate::proto::Candidate {
+                    content: Some(crate::proto::Content::model(
+                        "\n {\"b\": [1, {\"d\": 2, \"c\": 3}], \"a\": null}\n",
+                    )),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            confidence: None,
+            warnings: Vec::new(),
+        };
+
+        assert_eq!(
+            response.json(JsonFormat::Canonical).unwrap(),
+            r#"{"a":null,"b":[1,{"c":3,"d":2}]}"#
+        );
+        let pretty = response.json(JsonFormat::Pretty).unwrap();
+        let minified = response.json(JsonFormat::Minified).unwrap();
+        assert!(pretty.lines().count() > 1);
+        assert!(!minified.contains([' ', '\n']));
+        assert_eq!(
+            serde_json::from_str::<Value>(&pretty).unwrap(),
+            serde_json::from_str::<Value>(&minified).unwrap()
+        );
+        assert_eq!(response.json_span(), Some(2..41));
+        assert_eq!(json_span(b"  "), None);
+        assert_eq!(json_span(b"{\"a\": "), None);
+    }
+}
@@ -13,7 +13,7 @@
 //! - **Fluent API**: Builder-style configuration for temperature, safety settings, tools, etc.
 //! - **Type-Safe Schemas**: Use `AsSchema` to validate responses at compile-time.
 //! - **Stateful Chat**: The `Session` struct handles conversation history for you.
-//! - **Multi-Modal Input**: Mix text and images with `Part` or your own `TryIntoContents` impl.
+//! - **Multi-Modal Input**: Mix text and images with `Part` or `#[derive(IntoContents)]` on your own structs.
 //!
 //! ## 🚀 Quickstart (Chat Session)
 //!
@@ -45,26 +45,19 @@
 //! }
 //! ```
 //!
-//! ## 📎 Multi-modal Input with `TryIntoContents`
+//! ## 📎 Multi-modal Input with `IntoContents`
 //!
-//! Build your own structs that can be turned into model input. Great for combining text + images.
+//! Derive model input from your own structs. Great for combining text + images.
 //!
 //! ```rust,no_run
-//! use google_ai_rs::{Client, Part, Error, content::TryIntoContents, Content};
+//! use google_ai_rs::{Client, IntoContents, Part};
 //!
+//! #[derive(IntoContents)]
 //! struct UserQuery {
 //!     text: String,
 //!     attachments: Vec<Part>,
 //! }
 //!
-//! impl TryIntoContents for UserQuery {
-//!     fn try_into_contents(self) -> Result<Vec<Content>, Error> {
-//!         let mut parts = vec![Part::from(self.text)];
-//!         parts.extend(self.attachments);
-//!         Ok(vec![Content { role: "user".into(), parts }])
-//!     }
-//! }
-//!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let client = Client::new("YOUR_API_KEY").await?;
@@ -122,33 +115,69 @@
 //! ```
 
 pub mod auth;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod chat;
+pub mod citations;
+#[cfg(any(feature = "serde", feature = "builtin-tools"))]
+mod civil;
 pub mod client;
+pub mod config;
 pub mod content;
+pub mod context;
+pub mod deadline;
+pub mod delta;
 pub mod embedding;
 pub mod error;
+pub mod failover;
+#[cfg(test)]
+mod fake;
+#[cfg(feature = "url-fetch")]
+pub mod fetch;
+#[cfg(feature = "fixtures")]
+pub mod fixture;
+pub mod function;
 pub mod genai;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod operation;
+pub mod pipeline;
+pub mod prepare;
+mod retired;
+pub mod router;
 pub mod schema;
+#[cfg(feature = "tower")]
+pub mod service;
+pub mod shadow;
+#[cfg(feature = "file-upload")]
+pub mod upload;
+mod validate;
+pub mod vision;
 pub use auth::Auth;
 pub use client::{Client, SharedClient};
+pub use config::{
+    BreakerScope, CircuitBreaker, ClientConfig, Profile, RateLimit, RetiredModels, Split,
+};
+pub use embedding::{EmbedInput, TypedEmbedder};
 pub use error::Error;
-pub use genai::{GenerativeModel, TypedModel, TypedResponse};
+pub use failover::Failover;
+pub use genai::{GenerativeModel, ParseStats, TypedModel, TypedResponse, Validate};
 
 pub use crate::proto::Schema;
-pub use crate::schema::{AsSchema, Map, MapTrait, SchemaType, Tuple};
+pub use crate::schema::{AsSchema, BigIntString, Map, MapTrait, SchemaType, Tuple};
 
 pub use content::{
     IntoContent, IntoContents, IntoParts, TryFromCandidates, TryFromContents, TryIntoContent,
     TryIntoContents,
 };
 pub use proto::{
-    part::Data, CachedContent, Candidate, Content, FunctionCall, GenerationConfig, Part, TaskType,
-    Tool,
+    part::Data, CachedContent, Candidate, Content, FunctionCall, FunctionResponse,
+    GenerationConfig, Part, TaskType, Tool,
 };
 
 extern crate google_ai_schema_derive;
 
-pub use google_ai_schema_derive::AsSchema;
+pub use google_ai_schema_derive::{AsSchema, EmbedInput, IntoContents};
 
 #[cfg(feature = "serde")]
 pub use google_ai_schema_derive::AsSchemaWithSerde;
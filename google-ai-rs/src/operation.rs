@@ -0,0 +1,413 @@
+//! Long-running operations
+//!
+//! Some API calls, like tuning a model, don't complete immediately. They
+//! return an [`Operation`] that can be polled until the server reports it as
+//! done.
+//!
+//! # Example
+//! ```rust,no_run
+//! use google_ai_rs::{Client, operation::Backoff};
+//! # use google_ai_rs::proto::TunedModel;
+//!
+//! # async fn f(tuned_model: TunedModel) -> Result<(), google_ai_rs::Error> {
+//! let client = Client::new("YOUR-API-KEY").await?;
+//! let mut op = client.create_tuned_model(tuned_model, None).await?;
+//!
+//! if let Some(progress) = op.metadata()? {
+//!     println!("{}% done", progress.completed_percent);
+//! }
+//!
+//! let model = op.wait_with_backoff(Backoff::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{marker::PhantomData, time::Duration};
+
+use prost::Message;
+use tonic::IntoRequest;
+
+use crate::{
+    client::CClient,
+    error::{status_into_error, Error, NetError, ServiceError, TonicStatus},
+    proto::longrunning::{
+        operation::Result as OpResult, CancelOperationRequest, GetOperationRequest,
+        Operation as RawOperation,
+    },
+};
+
+/// A handle to a long-running operation.
+///
+/// `T` is the type the operation resolves to and `M` the type of the progress
+/// metadata the server attaches to it.
+#[derive(Debug)]
+pub struct Operation<'c, T, M = ()> {
+    client: CClient<'c>,
+    raw: RawOperation,
+    _marker: PhantomData<fn() -> (T, M)>,
+}
+
+impl<'c, T, M> Operation<'c, T, M>
+where
+    T: Message + Default,
+    M: Message + Default,
+{
+    pub(crate) fn new(client: impl Into<CClient<'c>>, raw: RawOperation) -> Self {
+        Self {
+            client: client.into(),
+            raw,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the server-assigned name of the operation.
+    ///
+    /// It can be stored and used with [`Client::get_operation`](crate::Client::get_operation)
+    /// to resume tracking it later.
+    pub fn name(&self) -> &str {
+        &self.raw.name
+    }
+
+    /// Returns whether the operation had completed as of the last poll.
+    pub fn is_done(&self) -> bool {
+        self.raw.done
+    }
+
+    /// Decodes the progress metadata reported as of the last poll.
+    pub fn metadata(&self) -> Result<Option<M>, Error> {
+        self.raw.metadata.as_ref().map(decode).transpose()
+    }
+
+    /// Returns the result of the operation if it has completed as of the last
+    /// poll.
+    pub fn result(&self) -> Option<Result<T, Error>> {
+        if !self.raw.done {
+            return None;
+        }
+
+        Some(match &self.raw.result {
+            Some(OpResult::Response(any)) => decode(any),
+            Some(OpResult::Error(status)) => Err(Error::Service(ServiceError::ApiError(
+                TonicStatus(Box::new(tonic::Status::new(
+                    status.code.into(),
+                    status.message.clone(),
+                ))),
+            ))),
+            // Operations without a response, e.g. deletions
+            None => Ok(T::default()),
+        })
+    }
+
+    /// Returns the raw operation as of the last poll.
+    pub fn raw(&self) -> &RawOperation {
+        &self.raw
+    }
+
+    /// Fetches the latest state of the operation.
+    ///
+    /// Returns the result if the operation is done.
+    pub async fn poll(&mut self) -> Result<Option<T>, Error> {
+        if !self.raw.done {
+            let request = GetOperationRequest {
+                name: self.raw.name.clone(),
+            }
+            .into_request();
+
+            self.raw = self
+                .client
+                .route_to(Some(&self.raw.name), async |region| {
+                    region.oc.clone().get_operation(request).await
+                })
+                .await?
+                .map_err(status_into_error)?
+                .into_inner();
+        }
+
+        self.result().transpose()
+    }
+
+    /// Polls the operation until it completes, sleeping between polls as
+    /// described by `backoff`.
+    ///
+    /// # Errors
+    /// Returns the operation's error if it failed, or
+    /// [`NetError::DeadlineExceeded`] if it didn't complete within
+    /// [`Backoff::timeout`].
+    pub async fn wait_with_backoff(mut self, backoff: Backoff) -> Result<T, Error> {
+        let start = tokio::time::Instant::now();
+        let mut delay = backoff.initial;
+
+        loop {
+            if let Some(result) = self.poll().await? {
+                return Ok(result);
+            }
+
+            if let Some(timeout) = backoff.timeout {
+                let elapsed = start.elapsed();
+                if elapsed >= timeout {
+                    return Err(Error::Net(NetError::DeadlineExceeded));
+                }
+                delay = delay.min(timeout - elapsed);
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = delay.mul_f64(backoff.multiplier).min(backoff.max);
+        }
+    }
+
+    /// Polls the operation until it completes using the default [`Backoff`].
+    pub async fn wait(self) -> Result<T, Error> {
+        self.wait_with_backoff(Backoff::default()).await
+    }
+
+    /// Requests cancellation of the operation.
+    ///
+    /// Cancellation is best-effort: the operation may still complete. Poll it
+    /// to observe the final state.
+    pub async fn cancel(&self) -> Result<(), Error> {
+        self.client.check_writable("cancel_operation")?;
+        let request = CancelOperationRequest {
+            name: self.raw.name.clone(),
+        }
+        .into_request();
+
+        self.client
+            .route_to(Some(&self.raw.name), async |region| {
+                region.oc.clone().cancel_operation(request).await
+            })
+            .await?
+            .map_err(status_into_error)
+            .map(|r| r.into_inner())
+    }
+}
+
+/// Polling schedule used by [`Operation::wait_with_backoff`].
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    /// Delay before the second poll.
+    pub initial: Duration,
+    /// Upper bound for the delay between polls.
+    pub max: Duration,
+    /// Factor the delay grows by after each poll.
+    pub multiplier: f64,
+    /// Total time to wait before giving up. `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            multiplier: 2.0,
+            timeout: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// Sets the delay before the second poll.
+    pub fn initial(mut self, initial: Duration) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    /// Sets the upper bound for the delay between polls.
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Sets the factor the delay grows by after each poll.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the total time to wait before giving up.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+fn decode<T: Message + Default>(any: &prost_types::Any) -> Result<T, Error> {
+    T::decode(any.value.as_slice())
+        .map_err(|err| Error::Service(ServiceError::InvalidResponse(err.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::{
+        fake::FakeServer,
+        proto::{rpc::Status, CreateTunedModelMetadata, TunedModel},
+    };
+
+    fn any(message: &impl Message) -> prost_types::Any {
+        prost_types::Any {
+            type_url: String::new(),
+            value: message.encode_to_vec(),
+        }
+    }
+
+    /// Serves an operation that's done from the `done_at`th poll on
+    async fn poller(done_at: usize, result: Option<OpResult>) -> FakeServer {
+        let polls = Arc::new(AtomicUsize::new(0));
+        FakeServer::start(move |path, _| {
+            assert_eq!(path, "/google.longrunning.Operations/GetOperation");
+            let done = polls.fetch_add(1, Ordering::SeqCst) + 1 >= done_at;
+            let operation = RawOperation {
+                name: "operations/tune".into(),
+                metadata: Some(any(&CreateTunedModelMetadata {
+                    completed_percent: if done { 100.0 } else { 50.0 },
+                    ..Default::default()
+                })),
+                done,
+                result: result.clone().filter(|_| done),
+            };
+            Ok(operation.encode_to_vec())
+        })
+        .await
+    }
+
+    fn tuned() -> OpResult {
+        OpResult::Response(any(&TunedModel {
+            name: "tunedModels/tuned".into(),
+            ..Default::default()
+        }))
+    }
+
+    fn quick() -> Backoff {
+        Backoff::default()
+            .initial(Duration::from_millis(1))
+            .max(Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn waits_until_done() {
+        let server = poller(3, Some(tuned())).await;
+        let client = server.client().await;
+
+        let op = client
+            .get_operation::<TunedModel, CreateTunedModelMetadata>("operations/tune")
+            .await
+            .unwrap();
+        assert_eq!(op.name(), "operations/tune");
+        assert!(!op.is_done());
+        assert!(op.result().is_none());
+        assert_eq!(op.metadata().unwrap().unwrap().completed_percent, 50.0);
+
+        let model = op.wait_with_backoff(quick()).await.unwrap();
+        assert_eq!(model.name, "tunedModels/tuned");
+        assert_eq!(server.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn decodes_results() {
+        let server = poller(1, Some(tuned())).await;
+        let client = server.client().await;
+        let mut op = client
+            .get_operation::<TunedModel, CreateTunedModelMetadata>("operations/tune")
+            .await
+            .unwrap();
+        assert!(op.is_done());
+        assert_eq!(op.metadata().unwrap().unwrap().completed_percent, 100.0);
+        assert_eq!(op.result().unwrap().unwrap().name, "tunedModels/tuned");
+        // Done operations aren't fetched again
+        assert_eq!(op.poll().await.unwrap().unwrap().name, "tunedModels/tuned");
+        assert_eq!(server.calls(), 1);
+
+        let failed = OpResult::Error(Status {
+            code: tonic::Code::NotFound as i32,
+            message: "no such base model".into(),
+            details: Vec::new(),
+        });
+        let server = poller(1, Some(failed)).await;
+        let client = server.client().await;
+        let op = client
+            .get_operation::<TunedModel, ()>("operations/tune")
+            .await
+            .unwrap();
+        match op.wait().await {
+            Err(Error::Service(ServiceError::ApiError(TonicStatus(status)))) => {
+                assert_eq!(status.code(), tonic::Code::NotFound);
+                assert_eq!(status.message(), "no such base model");
+            }
+            other => panic!("expected the operation's error, got {other:?}"),
+        }
+
+        // Operations without a response resolve to the default
+        let server = poller(1, None).await;
+        let client = server.client().await;
+        let op = client
+            .get_operation::<(), ()>("operations/delete")
+            .await
+            .unwrap();
+        op.wait().await.unwrap();
+
+        let server = poller(
+            1,
+            Some(OpResult::Response(prost_types::Any {
+                type_url: String::new(),
+                value: vec![0xff],
+            })),
+        )
+        .await;
+        let client = server.client().await;
+        let op = client
+            .get_operation::<TunedModel, ()>("operations/tune")
+            .await
+            .unwrap();
+        assert!(matches!(
+            op.result(),
+            Some(Err(Error::Service(ServiceError::InvalidResponse(_))))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn times_out() {
+        let server = poller(usize::MAX, None).await;
+        let client = server.client().await;
+        let op = client
+            .get_operation::<TunedModel, ()>("operations/tune")
+            .await
+            .unwrap();
+
+        // The last sleep is cut short to end at the timeout
+        let start = tokio::time::Instant::now();
+        let backoff = Backoff::default()
+            .initial(Duration::from_secs(60))
+            .timeout(Duration::from_secs(5));
+        assert!(matches!(
+            op.wait_with_backoff(backoff).await,
+            Err(Error::Net(NetError::DeadlineExceeded))
+        ));
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+        assert_eq!(server.calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_between_polls() {
+        let server = poller(usize::MAX, None).await;
+        let client = server.client().await;
+        let op = client
+            .get_operation::<TunedModel, ()>("operations/tune")
+            .await
+            .unwrap();
+
+        // Polls after 0, 2, 6, 14 and 30s, then at the timeout
+        let backoff = Backoff::default()
+            .initial(Duration::from_secs(2))
+            .max(Duration::from_secs(100))
+            .timeout(Duration::from_secs(40));
+        assert!(op.wait_with_backoff(backoff).await.is_err());
+        // One call more for the initial fetch
+        assert_eq!(server.calls(), 7);
+    }
+}
@@ -0,0 +1,404 @@
+//! Chaining model calls and plain functions into typed workflows
+//!
+//! A [`Pipeline`] runs [`Step`]s in order, each taking the previous one's
+//! output as input: [`extract`] asks a [`TypedModel`] for structured
+//! output, [`generate`] asks a [`GenerativeModel`] for text, and closures
+//! transform values in between. Mismatched steps don't compile.
+//!
+//! # Example
+#![cfg_attr(feature = "serde", doc = "```rust,no_run")]
+#![cfg_attr(not(feature = "serde"), doc = "```rust,ignore")]
+//! use google_ai_rs::pipeline::{extract, generate, Pipeline};
+//! use google_ai_rs::{AsSchema, SharedClient};
+//! use serde::Deserialize;
+//!
+//! #[derive(AsSchema, Deserialize)]
+//! struct Claims {
+//!     claims: Vec<String>,
+//! }
+//!
+//! # async fn f(client: SharedClient, article: &str) -> Result<(), google_ai_rs::Error> {
+//! let fact_check = Pipeline::new()
+//!     .step(extract(client.typed_model::<Claims>("gemini-2.5-flash")))
+//!     .retries(2)
+//!     .step(|c: Claims| format!("Fact-check each claim:\n- {}", c.claims.join("\n- ")))
+//!     .step(generate(client.generative_model("gemini-2.5-pro")));
+//!
+//! let report: String = fact_check.run(article).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    future::{ready, Future},
+    time::Duration,
+};
+
+use crate::config::is_upstream_failure;
+use crate::content::{TryFromCandidates, TryIntoContents};
+use crate::error::Error;
+use crate::genai::{GenerativeModel, TypedModel};
+use crate::schema::AsSchema;
+
+/// One stage of a [`Pipeline`], turning an `I` into an [`Output`](Self::Output)
+///
+/// Implemented for model calls made with [`extract`] and [`generate`], for
+/// closures `Fn(I) -> O`, and for pipelines themselves, so they nest.
+pub trait Step<I>: Send + Sync {
+    /// The value handed to the next step
+    type Output: Send;
+
+    /// Runs the step
+    fn run(&self, input: I) -> impl Future<Output = Result<Self::Output, Error>> + Send;
+
+    /// Names the step in traces
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Steps run in order, built with [`step`](Self::step)
+///
+/// See the [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct Pipeline<S = Start> {
+    steps: S,
+}
+
+impl Pipeline {
+    /// Creates a pipeline passing its input through unchanged
+    pub fn new() -> Self {
+        Self { steps: Start }
+    }
+}
+
+impl<S> Pipeline<S> {
+    /// Appends a step taking this pipeline's output
+    pub fn step<T>(self, step: T) -> Pipeline<Then<S, T>> {
+        Pipeline {
+            steps: Then {
+                first: self.steps,
+                second: step,
+            },
+        }
+    }
+
+    /// Runs the steps in order on `input`, stopping at the first error
+    pub async fn run<I>(&self, input: I) -> Result<S::Output, Error>
+    where
+        S: Step<I>,
+    {
+        self.steps.run(input).await
+    }
+}
+
+impl<A, B> Pipeline<Then<A, B>> {
+    /// Retries the last step up to `retries` more times when it fails
+    ///
+    /// Each attempt gets a clone of the step's input. Only failures of the
+    /// service or the network are retried, after waiting 500ms, then twice
+    /// as long before each further attempt; other errors would repeat.
+    pub fn retries(self, retries: u32) -> Pipeline<Then<A, Retry<B>>> {
+        let Then { first, second } = self.steps;
+        Pipeline {
+            steps: Then {
+                first,
+                second: Retry {
+                    step: second,
+                    retries,
+                },
+            },
+        }
+    }
+}
+
+impl<I, S: Step<I>> Step<I> for Pipeline<S> {
+    type Output = S::Output;
+
+    fn run(&self, input: I) -> impl Future<Output = Result<Self::Output, Error>> + Send {
+        self.steps.run(input)
+    }
+
+    fn name(&self) -> &str {
+        "pipeline"
+    }
+}
+
+/// The empty [`Pipeline`], passing its input through
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Start;
+
+impl<I: Send> Step<I> for Start {
+    type Output = I;
+
+    fn run(&self, input: I) -> impl Future<Output = Result<I, Error>> + Send {
+        ready(Ok(input))
+    }
+}
+
+/// Two steps run in order
+#[derive(Clone, Debug)]
+pub struct Then<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<I, A, B> Step<I> for Then<A, B>
+where
+    I: Send,
+    A: Step<I>,
+    B: Step<A::Output>,
+{
+    type Output = B::Output;
+
+    async fn run(&self, input: I) -> Result<B::Output, Error> {
+        let input = self.first.run(input).await?;
+
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        let result = self.second.run(input).await;
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(_) => tracing::debug!(
+                step = self.second.name(),
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                "pipeline step finished"
+            ),
+            Err(err) => tracing::warn!(
+                step = self.second.name(),
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                "pipeline step failed: {err}"
+            ),
+        }
+        result
+    }
+}
+
+/// Wait before the first retry of a step, doubled for each one after
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// A step retried on failure, made with [`Pipeline::retries`]
+#[derive(Clone, Debug)]
+pub struct Retry<S> {
+    step: S,
+    retries: u32,
+}
+
+impl<I, S> Step<I> for Retry<S>
+where
+    I: Clone + Send,
+    S: Step<I>,
+{
+    type Output = S::Output;
+
+    async fn run(&self, input: I) -> Result<S::Output, Error> {
+        let mut attempt = 0;
+        loop {
+            match self.step.run(input.clone()).await {
+                Err(_err) if attempt < self.retries && is_upstream_failure(&_err) => {
+                    attempt += 1;
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        step = self.step.name(),
+                        attempt,
+                        "retrying pipeline step: {_err}"
+                    );
+                    tokio::time::sleep(RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.step.name()
+    }
+}
+
+impl<I, O, F> Step<I> for F
+where
+    F: Fn(I) -> O + Send + Sync,
+    O: Send,
+{
+    type Output = O;
+
+    fn run(&self, input: I) -> impl Future<Output = Result<O, Error>> + Send {
+        ready(Ok(self(input)))
+    }
+
+    fn name(&self) -> &str {
+        "transform"
+    }
+}
+
+/// A step asking a [`TypedModel`] for a `T`, made with [`extract`]
+#[derive(Debug)]
+pub struct Extract<'c, T> {
+    model: TypedModel<'c, T>,
+}
+
+/// Asks `model` for structured output, from prompts like `&str`, `String`
+/// or anything else [`TryIntoContents`]
+///
+/// The model's validators and repair attempts apply.
+pub fn extract<T>(model: TypedModel<'_, T>) -> Extract<'_, T> {
+    Extract { model }
+}
+
+impl<I, T> Step<I> for Extract<'_, T>
+where
+    I: TryIntoContents + Send,
+    T: AsSchema + TryFromCandidates + Send,
+{
+    type Output = T;
+
+    fn run(&self, input: I) -> impl Future<Output = Result<T, Error>> + Send {
+        self.model.generate_content(input)
+    }
+
+    fn name(&self) -> &str {
+        "extract"
+    }
+}
+
+/// A step asking a [`GenerativeModel`] for text, made with [`generate`]
+#[derive(Clone, Debug)]
+pub struct Generate<'c> {
+    model: GenerativeModel<'c>,
+}
+
+/// Asks `model` for free-form text
+///
+/// Use [`extract`] for structured output.
+pub fn generate(model: GenerativeModel<'_>) -> Generate<'_> {
+    Generate { model }
+}
+
+impl<I> Step<I> for Generate<'_>
+where
+    I: TryIntoContents + Send,
+{
+    type Output = String;
+
+    async fn run(&self, input: I) -> Result<String, Error> {
+        Ok(self.model.generate_content(input).await?.to_text())
+    }
+
+    fn name(&self) -> &str {
+        "generate"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use prost::Message;
+
+    use tokio::time::Instant;
+
+    use crate::{
+        content::TryFromContents,
+        error::status_into_error,
+        fake::FakeServer,
+        proto::{
+            Candidate, Content, GenerateContentRequest, GenerateContentResponse, Part, Schema,
+        },
+    };
+
+    /// Fails with `status` until it has failed `failures` times
+    struct Flaky {
+        failures: AtomicU32,
+        status: fn(&'static str) -> tonic::Status,
+    }
+
+    fn flaky(failures: u32, status: fn(&'static str) -> tonic::Status) -> Flaky {
+        Flaky {
+            failures: AtomicU32::new(failures),
+            status,
+        }
+    }
+
+    impl Step<u32> for Flaky {
+        type Output = u32;
+
+        async fn run(&self, input: u32) -> Result<u32, Error> {
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(status_into_error((self.status)("flaky")));
+            }
+            Ok(input + 1)
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runs_steps_in_order_with_retries() {
+        let started = Instant::now();
+        let pipeline = Pipeline::new()
+            .step(|n: u32| n * 10)
+            .step(flaky(2, tonic::Status::unavailable))
+            .retries(2)
+            .step(|n: u32| n.to_string());
+        assert_eq!(pipeline.run(4).await.unwrap(), "41");
+        // Backed off 500ms, then 1s
+        assert_eq!(started.elapsed(), Duration::from_millis(1500));
+
+        let pipeline = Pipeline::new()
+            .step(flaky(2, tonic::Status::unavailable))
+            .retries(1);
+        assert!(pipeline.run(0).await.is_err());
+
+        // Rejected requests would fail again, so a retry that would've
+        // worked here isn't made
+        let pipeline = Pipeline::new()
+            .step(flaky(1, tonic::Status::invalid_argument))
+            .retries(2);
+        assert!(pipeline.run(0).await.is_err());
+    }
+
+    /// Uppercases the response's text
+    struct Shout(String);
+
+    impl AsSchema for Shout {
+        fn as_schema() -> Schema {
+            Schema::new_string()
+        }
+    }
+
+    impl TryFromContents for Shout {
+        fn try_from_contents<'a, I>(contents: I) -> Result<Self, Error>
+        where
+            I: Iterator<Item = &'a Content>,
+        {
+            let text: String = contents.flat_map(|c| &c.parts).map(Part::to_text).collect();
+            Ok(Shout(text.to_uppercase()))
+        }
+    }
+
+    #[tokio::test]
+    async fn extracts_through_a_model() {
+        let server = FakeServer::start(|path, request| {
+            assert!(path.ends_with("/GenerateContent"), "{path}");
+            let request = GenerateContentRequest::decode(request).unwrap();
+            let prompt = request.contents[0].parts[0].to_text().to_owned();
+            let response = GenerateContentResponse {
+                candidates: vec![Candidate {
+                    content: Some(Content::model(format!("echo {prompt}"))),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            Ok(response.encode_to_vec())
+        })
+        .await;
+        let client = server.client().await;
+
+        let pipeline = Pipeline::new()
+            .step(|n: u32| format!("{n} items"))
+            .step(extract(client.typed_model::<Shout>("gemini-test")))
+            .step(|shout: Shout| shout.0);
+        assert_eq!(pipeline.run(3).await.unwrap(), "ECHO 3 ITEMS");
+        assert_eq!(server.calls(), 1);
+    }
+}
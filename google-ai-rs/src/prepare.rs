@@ -0,0 +1,126 @@
+//! Preparing many heavy parts concurrently
+//!
+//! Media-heavy prompts spend most of their build time reading files and
+//! fetching URLs. [`join_parts`] runs those part-producing futures side by
+//! side, at most `concurrency` at a time, and hands back the parts in input
+//! order, ready for any [`TryIntoContents`](crate::TryIntoContents) input.
+//!
+//! # Example
+//! ```rust,no_run
+//! use google_ai_rs::{prepare::join_parts, Part};
+//!
+//! # async fn f(model: google_ai_rs::GenerativeModel<'_>) -> Result<(), google_ai_rs::Error> {
+//! let pages = ["scan-1.png", "scan-2.png", "scan-3.png"];
+//! let parts = join_parts(4, pages.iter().map(|p| Part::from_file("image/png", p))).await?;
+//!
+//! let response = model
+//!     .generate_content(("Transcribe these pages.", parts))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Futures are polled on the calling task, so CPU-bound work such as image
+//! re-encoding should be moved off it with `tokio::task::spawn_blocking`
+//! inside the future to actually run in parallel.
+
+use std::{
+    future::{poll_fn, Future},
+    path::Path,
+    pin::Pin,
+    task::Poll,
+};
+
+use crate::{
+    error::{Error, SetupError},
+    Part,
+};
+
+/// Runs `tasks` with at most `concurrency` in flight, collecting their parts
+/// in input order
+///
+/// Stops at the first error; tasks still in flight are dropped. A
+/// `concurrency` of 0 is treated as 1.
+pub async fn join_parts<I, F>(concurrency: usize, tasks: I) -> Result<Vec<Part>, Error>
+where
+    I: IntoIterator<Item = F>,
+    F: Future<Output = Result<Part, Error>>,
+{
+    let concurrency = concurrency.max(1);
+    let mut tasks = tasks.into_iter().enumerate();
+    let mut running: Vec<(usize, Pin<Box<F>>)> = Vec::with_capacity(concurrency);
+    let mut parts: Vec<Option<Part>> = Vec::new();
+
+    loop {
+        while running.len() < concurrency {
+            let Some((index, task)) = tasks.next() else {
+                break;
+            };
+            running.push((index, Box::pin(task)));
+            parts.push(None);
+        }
+
+        if running.is_empty() {
+            break;
+        }
+
+        let (slot, result) = poll_fn(|cx| {
+            for (slot, (_, task)) in running.iter_mut().enumerate() {
+                if let Poll::Ready(result) = task.as_mut().poll(cx) {
+                    return Poll::Ready((slot, result));
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+
+        let (index, _) = running.swap_remove(slot);
+        parts[index] = Some(result?);
+    }
+
+    Ok(parts.into_iter().flatten().collect())
+}
+
+impl Part {
+    /// Reads the file at `path` into an inline data part
+    ///
+    /// Read failures are reported as [`Error::Setup`].
+    pub async fn from_file(mime_type: &str, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = tokio::fs::read(path)
+            .await
+            .map_err(|e| SetupError::new(format!("failed to read {}", path.display()), e))?;
+        Ok(Part::blob(mime_type, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn keeps_input_order() {
+        let delays = [30, 10, 20, 0];
+        let tasks = delays.map(|ms| async move {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(Part::text(ms.to_string()))
+        });
+
+        let parts = join_parts(2, tasks).await.unwrap();
+        let parts: Vec<_> = parts.iter().map(Part::to_text).collect();
+        assert_eq!(parts, ["30", "10", "20", "0"]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_first_error() {
+        let tasks = (0..3).map(|i| async move {
+            if i == 1 {
+                Err(Error::InvalidContent("bad part".into()))
+            } else {
+                Ok(Part::text("ok"))
+            }
+        });
+        assert!(join_parts(0, tasks).await.is_err());
+    }
+}
@@ -97,6 +97,9 @@ pub struct Content {
 pub struct Part {
     #[prost(oneof = "part::Data", tags = "2, 3, 4, 5, 6, 9, 10")]
     pub data: ::core::option::Option<part::Data>,
+    /// Controls extra preprocessing of data.
+    #[prost(oneof = "part::Metadata", tags = "14")]
+    pub metadata: ::core::option::Option<part::Metadata>,
 }
 
 /// Raw media bytes.
@@ -127,6 +130,23 @@ pub struct FileData {
     #[prost(string, tag = "2")]
     pub file_uri: ::prost::alloc::string::String,
 }
+/// Describes how a video part should be processed.
+///
+/// Named `VideoMetadata` in the API; renamed here to avoid clashing with the
+/// metadata of video `File`s.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct VideoClipMetadata {
+    /// Optional. The start offset of the video.
+    #[prost(message, optional, tag = "1")]
+    pub start_offset: ::core::option::Option<::prost_types::Duration>,
+    /// Optional. The end offset of the video.
+    #[prost(message, optional, tag = "2")]
+    pub end_offset: ::core::option::Option<::prost_types::Duration>,
+    /// Optional. The frame rate of the video sent to the model. Defaults to 1.0
+    /// if not specified. The fps range is (0.0, 24.0].
+    #[prost(double, optional, tag = "3")]
+    pub fps: ::core::option::Option<f64>,
+}
 /// Code generated by the model that is meant to be executed, and the result
 /// returned to the model.
 ///
@@ -324,6 +344,9 @@ pub struct Schema {
     ///   for STRING type: enum
     #[prost(string, tag = "2")]
     pub format: ::prost::alloc::string::String,
+    /// Optional. The title of the schema.
+    #[prost(string, tag = "24")]
+    pub title: ::prost::alloc::string::String,
     /// Optional. A brief description of the parameter. This could contain examples
     /// of use. Parameter description may be formatted as Markdown.
     #[prost(string, tag = "3")]
@@ -351,6 +374,35 @@ pub struct Schema {
     /// Optional. Required properties of Type.OBJECT.
     #[prost(string, repeated, tag = "8")]
     pub required: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Optional. The order of the properties.
+    /// Not a standard field in open api spec. Used to determine the order of the
+    /// properties in the response.
+    #[prost(string, repeated, tag = "23")]
+    pub property_ordering: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Optional. Minimum value of the Type.INTEGER and Type.NUMBER
+    #[prost(double, optional, tag = "11")]
+    pub minimum: ::core::option::Option<f64>,
+    /// Optional. Maximum value of the Type.INTEGER and Type.NUMBER
+    #[prost(double, optional, tag = "12")]
+    pub maximum: ::core::option::Option<f64>,
+    /// Optional. Minimum length of the Type.STRING
+    #[prost(int64, tag = "13")]
+    pub min_length: i64,
+    /// Optional. Maximum length of the Type.STRING
+    #[prost(int64, tag = "14")]
+    pub max_length: i64,
+    /// Optional. Pattern of the Type.STRING to restrict a string to a regular
+    /// expression.
+    #[prost(string, tag = "15")]
+    pub pattern: ::prost::alloc::string::String,
+    /// Optional. The value should be validated against any (one or more) of the
+    /// subschemas in the list.
+    #[prost(message, repeated, tag = "18")]
+    pub any_of: ::prost::alloc::vec::Vec<Schema>,
+    /// Optional. Default value of the field. Per JSON Schema, this field is
+    /// intended for documentation generators and doesn't affect validation.
+    #[prost(message, optional, tag = "25")]
+    pub default: ::core::option::Option<::prost_types::Value>,
 }
 /// Passage included inline with a grounding configuration.
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -983,6 +1035,9 @@ pub struct GenerateContentResponse {
     /// Output only. The model version used to generate the response.
     #[prost(string, tag = "4")]
     pub model_version: ::prost::alloc::string::String,
+    /// Output only. response_id is used to identify each response.
+    #[prost(string, tag = "7")]
+    pub response_id: ::prost::alloc::string::String,
 }
 /// A response candidate generated from the model.
 #[derive(Clone, PartialEq, ::prost::Message)]
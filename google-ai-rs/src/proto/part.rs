@@ -27,3 +27,11 @@ pub enum Data {
     #[prost(message, tag = "10")]
     CodeExecutionResult(super::CodeExecutionResult),
 }
+/// Controls extra preprocessing of data.
+#[derive(Clone, Copy, PartialEq, ::prost::Oneof)]
+pub enum Metadata {
+    /// Optional. Video metadata. The metadata should only be specified while the
+    /// video data is presented in inline_data or file_data.
+    #[prost(message, tag = "14")]
+    VideoMetadata(super::VideoClipMetadata),
+}
@@ -0,0 +1,91 @@
+//! Models Google has shut down
+//!
+//! Requests to a retired model fail with a bare `NOT_FOUND`. Knowing the
+//! names up front lets us point at the replacement instead.
+
+use crate::config::{ClientConfig, RetiredModels};
+use crate::error::Error;
+
+/// Retired model families and what to use instead, longest name first
+/// where one is a prefix of another
+const RETIRED: &[(&str, &str)] = &[
+    ("gemini-1.0-pro-vision", "gemini-2.5-flash"),
+    ("gemini-1.0-pro", "gemini-2.5-flash"),
+    ("gemini-pro-vision", "gemini-2.5-flash"),
+    ("gemini-pro", "gemini-2.5-flash"),
+    ("gemini-1.5-flash-8b", "gemini-2.5-flash-lite"),
+    ("gemini-1.5-flash", "gemini-2.5-flash"),
+    ("gemini-1.5-pro", "gemini-2.5-pro"),
+    ("chat-bison-001", "gemini-2.5-flash"),
+    ("text-bison-001", "gemini-2.5-flash"),
+    ("embedding-gecko-001", "gemini-embedding-001"),
+    ("embedding-001", "gemini-embedding-001"),
+    ("text-embedding-004", "gemini-embedding-001"),
+];
+
+/// Returns the replacement for `name` if it's a retired model
+///
+/// Versions of a retired model (`gemini-1.5-pro-002`,
+/// `gemini-1.5-flash-latest`) are retired with it.
+pub(crate) fn replacement(name: &str) -> Option<&'static str> {
+    let id = name.strip_prefix("models/").unwrap_or(name);
+    RETIRED.iter().find_map(|(family, replacement)| {
+        let matches = id
+            .strip_prefix(family)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'));
+        matches.then_some(*replacement)
+    })
+}
+
+/// Warns about a retired model when a model is created
+pub(crate) fn warn(config: &ClientConfig, name: &str) {
+    if config.retired_models != RetiredModels::Warn {
+        return;
+    }
+    if let Some(_replacement) = replacement(name) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            model = name,
+            "model has been retired, use {_replacement} instead"
+        );
+    }
+}
+
+/// Fails requests to a retired model when the configuration asks for it
+pub(crate) fn check(config: &ClientConfig, name: &str) -> Result<(), Error> {
+    match replacement(name) {
+        Some(replacement) if config.retired_models == RetiredModels::Error => {
+            Err(Error::InvalidArgument(
+                format!("model {name} has been retired, use {replacement} instead").into(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retired_names() {
+        let tests = [
+            ("models/gemini-pro", Some("gemini-2.5-flash")),
+            ("gemini-pro-vision", Some("gemini-2.5-flash")),
+            ("gemini-1.5-pro-002", Some("gemini-2.5-pro")),
+            (
+                "models/gemini-1.5-flash-8b-latest",
+                Some("gemini-2.5-flash-lite"),
+            ),
+            ("embedding-001", Some("gemini-embedding-001")),
+            ("models/gemini-embedding-001", None),
+            ("gemini-2.5-pro", None),
+            ("gemini-proton", None),
+            ("tunedModels/gemini-pro", None),
+        ];
+
+        for (name, want) in tests {
+            assert_eq!(replacement(name), want, "{name}");
+        }
+    }
+}
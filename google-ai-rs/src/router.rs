@@ -0,0 +1,329 @@
+//! Routing calls to the cheapest capable model
+//!
+//! Fill a [`PricingTable`] with the models you're willing to use, their
+//! prices and what they can do, then give a [`Router`] the
+//! [`Requirements`] of a kind of call: whether it sends images, how much
+//! context it needs, and what it may cost. [`choose`](Router::choose)
+//! returns the cheapest model meeting them.
+//!
+//! Prices change, so the table is shared: update it in place, e.g. from a
+//! config reload, and every router sharing it picks from the new prices on
+//! its next call. Models without a declared context window have theirs
+//! looked up through the API, once per client.
+//!
+//! # Example
+//! ```
+//! use google_ai_rs::router::{ModelOffer, PricingTable, Requirements, Router};
+//!
+//! # async fn f(client: google_ai_rs::SharedClient) -> Result<(), google_ai_rs::Error> {
+//! let prices = PricingTable::new([
+//!     ModelOffer::new("gemini-2.5-flash-lite", 0.10, 0.40).vision(true),
+//!     ModelOffer::new("gemini-2.5-flash", 0.30, 2.50).vision(true),
+//!     ModelOffer::new("gemini-2.5-pro", 1.25, 10.00).vision(true),
+//! ]);
+//!
+//! // Calls of about 2,000 tokens in and 500 out
+//! let router = Router::new(
+//!     client,
+//!     prices.clone(),
+//!     Requirements::new(2_000, 500)
+//!         .vision()
+//!         .min_context(128_000)
+//!         .max_cost_per_1k_calls(2.0),
+//! );
+//! let model = router.model().await?;
+//! let response = model.generate_content("Describe this chart").await?;
+//!
+//! // A price drop is picked up by the next call
+//! prices.set(ModelOffer::new("gemini-2.5-flash", 0.05, 0.20).vision(true));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{client::SharedClient, error::Error, genai::GenerativeModel};
+
+/// A model's prices and capabilities
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ModelOffer {
+    /// Model name, as given to [`generative_model`](crate::Client::generative_model)
+    pub model: String,
+    /// Dollars per million input tokens
+    pub input_per_million: f64,
+    /// Dollars per million output tokens
+    pub output_per_million: f64,
+    /// Whether the model takes images
+    pub vision: bool,
+    /// Input tokens the model takes, or `None` to look it up
+    pub context_window: Option<u32>,
+}
+
+impl ModelOffer {
+    /// Prices `model` in dollars per million input and output tokens
+    pub fn new(model: impl Into<String>, input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            model: model.into(),
+            input_per_million,
+            output_per_million,
+            vision: false,
+            context_window: None,
+        }
+    }
+
+    /// Sets whether the model takes images
+    pub fn vision(mut self, vision: bool) -> Self {
+        self.vision = vision;
+        self
+    }
+
+    /// Declares how many input tokens the model takes, sparing the lookup
+    pub fn context_window(mut self, tokens: u32) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    /// Dollars a call of this many tokens costs
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (f64::from(input_tokens) * self.input_per_million
+            + f64::from(output_tokens) * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Models to route between, shared by clones and updated in place
+///
+/// Earlier offers win ties in price.
+#[derive(Clone, Debug, Default)]
+pub struct PricingTable {
+    offers: Arc<RwLock<Vec<ModelOffer>>>,
+}
+
+impl PricingTable {
+    /// Creates a table of `offers`
+    pub fn new(offers: impl IntoIterator<Item = ModelOffer>) -> Self {
+        let table = Self::default();
+        for offer in offers {
+            table.set(offer);
+        }
+        table
+    }
+
+    /// Adds an offer, or replaces the one for the same model in place
+    pub fn set(&self, offer: ModelOffer) {
+        let mut offers = self.offers.write().unwrap_or_else(|e| e.into_inner());
+        match offers.iter_mut().find(|o| o.model == offer.model) {
+            Some(existing) => *existing = offer,
+            None => offers.push(offer),
+        }
+    }
+
+    /// Removes the offer for `model`, returning it
+    pub fn remove(&self, model: &str) -> Option<ModelOffer> {
+        let mut offers = self.offers.write().unwrap_or_else(|e| e.into_inner());
+        let index = offers.iter().position(|o| o.model == model)?;
+        Some(offers.remove(index))
+    }
+
+    /// Returns the current offers
+    pub fn offers(&self) -> Vec<ModelOffer> {
+        self.offers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// What a kind of call needs from a model
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Requirements {
+    /// Input tokens of a typical call, which costs are estimated from
+    pub input_tokens: u32,
+    /// Output tokens of a typical call
+    pub output_tokens: u32,
+    /// Whether calls send images
+    pub vision: bool,
+    /// Input tokens the model must take
+    pub min_context: u32,
+    /// Most dollars 1,000 typical calls may cost
+    pub max_cost_per_1k_calls: Option<f64>,
+}
+
+impl Requirements {
+    /// Requires nothing of calls of about `input_tokens` in and
+    /// `output_tokens` out
+    pub fn new(input_tokens: u32, output_tokens: u32) -> Self {
+        Self {
+            input_tokens,
+            output_tokens,
+            vision: false,
+            min_context: 0,
+            max_cost_per_1k_calls: None,
+        }
+    }
+
+    /// Requires models taking images
+    pub fn vision(mut self) -> Self {
+        self.vision = true;
+        self
+    }
+
+    /// Requires models taking at least `tokens` input tokens
+    pub fn min_context(mut self, tokens: u32) -> Self {
+        self.min_context = tokens;
+        self
+    }
+
+    /// Requires 1,000 typical calls to cost at most `dollars`
+    pub fn max_cost_per_1k_calls(mut self, dollars: f64) -> Self {
+        self.max_cost_per_1k_calls = Some(dollars);
+        self
+    }
+
+    /// Dollars 1,000 typical calls to `offer` cost
+    pub fn cost_per_1k_calls(&self, offer: &ModelOffer) -> f64 {
+        offer.cost(self.input_tokens, self.output_tokens) * 1_000.0
+    }
+
+    /// Whether `offer` qualifies on everything but its context window
+    fn allows(&self, offer: &ModelOffer) -> bool {
+        (offer.vision || !self.vision)
+            && self
+                .max_cost_per_1k_calls
+                .is_none_or(|max| self.cost_per_1k_calls(offer) <= max)
+    }
+}
+
+/// Picks the cheapest model of a [`PricingTable`] meeting [`Requirements`]
+///
+/// See the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct Router {
+    client: SharedClient,
+    table: PricingTable,
+    requirements: Requirements,
+}
+
+impl Router {
+    /// Routes calls meeting `requirements` between the models of `table`
+    pub fn new(client: SharedClient, table: PricingTable, requirements: Requirements) -> Self {
+        Self {
+            client,
+            table,
+            requirements,
+        }
+    }
+
+    /// Returns the table routed from, to update prices through
+    pub fn table(&self) -> &PricingTable {
+        &self.table
+    }
+
+    /// Returns the name of the cheapest qualifying model under current prices
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidArgument`] if no model qualifies, or the
+    /// error of looking up a context window.
+    pub async fn choose(&self) -> Result<String, Error> {
+        let offers = self.table.offers();
+        let mut windows = HashMap::new();
+        if self.requirements.min_context > 0 {
+            for offer in offers.iter().filter(|o| self.requirements.allows(o)) {
+                if offer.context_window.is_none() {
+                    let model = self.client.cached_model(&offer.model).await?;
+                    let window = u32::try_from(model.input_token_limit).unwrap_or(0);
+                    windows.insert(offer.model.clone(), window);
+                }
+            }
+        }
+
+        cheapest(&offers, &self.requirements, |offer| {
+            offer
+                .context_window
+                .or_else(|| windows.get(&offer.model).copied())
+                .unwrap_or(0)
+        })
+        .map(|offer| offer.model.clone())
+        .ok_or_else(|| {
+            Error::InvalidArgument(
+                format!(
+                    "no model in the pricing table meets {:?}",
+                    self.requirements
+                )
+                .into(),
+            )
+        })
+    }
+
+    /// Returns the cheapest qualifying model under current prices
+    pub async fn model(&self) -> Result<GenerativeModel<'static>, Error> {
+        let name = self.choose().await?;
+        Ok(self.client.generative_model(&name))
+    }
+}
+
+fn cheapest<'o>(
+    offers: &'o [ModelOffer],
+    requirements: &Requirements,
+    window: impl Fn(&ModelOffer) -> u32,
+) -> Option<&'o ModelOffer> {
+    offers
+        .iter()
+        .filter(|offer| requirements.allows(offer))
+        .filter(|offer| requirements.min_context == 0 || window(offer) >= requirements.min_context)
+        .min_by(|a, b| {
+            requirements
+                .cost_per_1k_calls(a)
+                .total_cmp(&requirements.cost_per_1k_calls(b))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_cheapest_qualifying() {
+        let table = PricingTable::new([
+            ModelOffer::new("text", 0.05, 0.10).context_window(1_000_000),
+            ModelOffer::new("small", 0.10, 0.40)
+                .vision(true)
+                .context_window(32_000),
+            ModelOffer::new("flash", 0.30, 2.50)
+                .vision(true)
+                .context_window(1_000_000),
+            ModelOffer::new("pro", 1.25, 10.00)
+                .vision(true)
+                .context_window(2_000_000),
+        ]);
+        let pick = |requirements: &Requirements| {
+            cheapest(&table.offers(), requirements, |o| o.context_window.unwrap())
+                .map(|o| o.model.clone())
+        };
+
+        let requirements = Requirements::new(2_000, 500);
+        assert_eq!(pick(&requirements).as_deref(), Some("text"));
+        let requirements = requirements.vision();
+        assert_eq!(pick(&requirements).as_deref(), Some("small"));
+        let requirements = requirements.min_context(128_000);
+        assert_eq!(pick(&requirements).as_deref(), Some("flash"));
+        // flash costs $1.85 per 1k calls
+        assert_eq!(pick(&requirements.clone().max_cost_per_1k_calls(1.0)), None);
+
+        // Re-evaluated with new prices
+        table.set(
+            ModelOffer::new("pro", 0.10, 0.10)
+                .vision(true)
+                .context_window(2_000_000),
+        );
+        assert_eq!(pick(&requirements).as_deref(), Some("pro"));
+        assert_eq!(table.offers().len(), 4);
+        table.remove("pro");
+        assert_eq!(pick(&requirements).as_deref(), Some("flash"));
+    }
+}
@@ -16,13 +16,34 @@ use std::{
             AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16,
             AtomicU32, AtomicU64, AtomicU8, AtomicUsize,
         },
-        Arc, Mutex, RwLock, Weak as ArcWeak,
+        Arc, Mutex, OnceLock, RwLock, Weak as ArcWeak,
     },
+    time::{Duration, SystemTime},
 };
 use tokio::sync::{Mutex as TMutex, RwLock as TRwLock};
 
 use crate::proto::{Schema, Type};
 
+#[cfg(feature = "serde")]
+pub mod as_string;
+#[cfg(feature = "serde")]
+pub mod big_int;
+#[cfg(feature = "serde")]
+pub(crate) mod canonical;
+#[cfg(feature = "serde")]
+mod infer;
+#[cfg(feature = "schemars")]
+mod json_schema;
+#[cfg(feature = "serde")]
+mod mock;
+#[cfg(feature = "serde")]
+pub mod time;
+
+#[cfg(feature = "schemars")]
+pub use json_schema::from_json_schema;
+#[cfg(feature = "serde")]
+pub use mock::{mock, mock_from};
+
 // SchemaType contains the list of OpenAPI data types as defined by
 // https://spec.openapis.org/oas/v3.0.3#data-types
 pub type SchemaType = Type;
@@ -102,6 +123,12 @@ impl Schema {
         self
     }
 
+    /// Sets the title of the schema.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
     /// Sets the description of the schema.
     ///
     /// The description can be formatted as Markdown.
@@ -181,6 +208,124 @@ impl Schema {
         self
     }
 
+    /// Sets the minimum value for a `Number` or `Integer` schema.
+    ///
+    /// This method is only effective when the schema's type is `Number` or `Integer`.
+    /// Named apart from the `minimum()` getter prost generates for the field.
+    pub fn min_value(mut self, minimum: f64) -> Self {
+        if self.is_numeric() {
+            self.minimum = Some(minimum);
+        }
+        self
+    }
+
+    /// Sets the maximum value for a `Number` or `Integer` schema.
+    ///
+    /// This method is only effective when the schema's type is `Number` or `Integer`.
+    /// Named apart from the `maximum()` getter prost generates for the field.
+    pub fn max_value(mut self, maximum: f64) -> Self {
+        if self.is_numeric() {
+            self.maximum = Some(maximum);
+        }
+        self
+    }
+
+    /// Sets the minimum length of a `String` schema.
+    ///
+    /// This method is only effective when the schema's type is `String`.
+    pub fn min_length(mut self, min_length: i64) -> Self {
+        if self.is_string() {
+            self.min_length = min_length;
+        }
+        self
+    }
+
+    /// Sets the maximum length of a `String` schema.
+    ///
+    /// This method is only effective when the schema's type is `String`.
+    pub fn max_length(mut self, max_length: i64) -> Self {
+        if self.is_string() {
+            self.max_length = max_length;
+        }
+        self
+    }
+
+    /// Sets the regular expression a `String` schema must match.
+    ///
+    /// This method is only effective when the schema's type is `String`.
+    pub fn pattern(mut self, pattern: impl Into<String>) -> Self {
+        if self.is_string() {
+            self.pattern = pattern.into();
+        }
+        self
+    }
+
+    /// Sets the value used when the field is missing
+    ///
+    /// Serde fills it in on deserialization with `#[serde(default)]`; the
+    /// model only sees it as documentation.
+    pub fn default_value(mut self, value: impl Into<prost_types::Value>) -> Self {
+        self.default = Some(value.into());
+        self
+    }
+
+    /// Returns a copy without the constraints older models reject
+    ///
+    /// Drops number bounds, string lengths and patterns, array sizes and
+    /// property ordering at every level, and replaces alternatives with the
+    /// first of them; types, formats, descriptions, enums, properties and
+    /// required fields stay.
+    pub fn without_constraints(&self) -> Schema {
+        if let Some(first) = self.any_of.first() {
+            let mut plain = first.without_constraints();
+            if !self.description.is_empty() {
+                plain.description.clone_from(&self.description);
+            }
+            plain.nullable |= self.nullable;
+            return plain;
+        }
+
+        Schema {
+            items: self
+                .items
+                .as_ref()
+                .map(|items| Box::new(items.without_constraints())),
+            max_items: 0,
+            min_items: 0,
+            properties: self
+                .properties
+                .iter()
+                .map(|(name, schema)| (name.clone(), schema.without_constraints()))
+                .collect(),
+            minimum: None,
+            maximum: None,
+            min_length: 0,
+            max_length: 0,
+            pattern: String::new(),
+            property_ordering: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Creates a schema matched by a value matching any of `schemas`
+    ///
+    /// The schema has no type of its own; each alternative has one.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use google_ai_rs::Schema;
+    /// let id = Schema::any_of([Schema::new_integer(), Schema::new_string()]);
+    /// ```
+    pub fn any_of<I>(schemas: I) -> Self
+    where
+        I: IntoIterator<Item = Schema>,
+    {
+        Schema {
+            any_of: schemas.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
     /// Adds a single property to an `Object` schema.
     ///
     /// This method is a convenience for adding a single key-value pair to the properties map.
@@ -244,6 +389,21 @@ impl Schema {
         self
     }
 
+    /// Sets the order the model should produce the properties of an
+    /// `Object` schema in.
+    ///
+    /// This method is only effective when the schema's type is `Object`.
+    pub fn property_ordering<I, S>(mut self, ordering: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if self.is_object() {
+            self.property_ordering = ordering.into_iter().map(Into::into).collect();
+        }
+        self
+    }
+
     fn is_object(&self) -> bool {
         SchemaType::Object as i32 == self.r#type
     }
@@ -252,8 +412,12 @@ impl Schema {
         SchemaType::Array as i32 == self.r#type
     }
 
+    fn is_numeric(&self) -> bool {
+        SchemaType::Number as i32 == self.r#type || SchemaType::Integer as i32 == self.r#type
+    }
+
     fn is_string(&self) -> bool {
-        SchemaType::Object as i32 == self.r#type
+        SchemaType::String as i32 == self.r#type
     }
 }
 
@@ -369,6 +533,68 @@ pub trait AsSchema {
     fn as_schema() -> Schema;
 }
 
+thread_local! {
+    // How many times each recursive type is nested in the schema being built
+    static DEPTHS: RefCell<HashMap<&'static str, usize>> = RefCell::default();
+}
+
+/// Returns the schema in `cache`, building and storing it on first use
+///
+/// Used by `#[derive(AsSchema)]` for types without type or const
+/// parameters, whose schema never changes. While a recursive type is being
+/// expanded, schemas depend on how deep they're nested, so the cache is
+/// bypassed.
+#[doc(hidden)]
+pub fn cached(cache: &'static OnceLock<Schema>, build: impl FnOnce() -> Schema) -> Schema {
+    let expanding = DEPTHS.with_borrow(|depths| depths.values().any(|depth| *depth > 0));
+    if expanding {
+        return build();
+    }
+    if let Some(schema) = cache.get() {
+        return schema.clone();
+    }
+    // Not get_or_init: building may call back into this type's schema
+    let schema = build();
+    let _ = cache.set(schema.clone());
+    schema
+}
+
+/// Builds `T`'s schema unless `T` is already nested `max_depth` times in
+/// the schema being built, in which case it's left unspecified and nullable
+///
+/// Used by `#[derive(AsSchema)]` with `#[schema(max_depth = N)]`.
+#[doc(hidden)]
+pub fn recursion_limited<T: ?Sized>(max_depth: usize, build: impl FnOnce() -> Schema) -> Schema {
+    struct Nested(&'static str);
+
+    impl Drop for Nested {
+        fn drop(&mut self) {
+            DEPTHS.with_borrow_mut(|depths| {
+                if let Some(depth) = depths.get_mut(self.0) {
+                    *depth -= 1;
+                }
+            });
+        }
+    }
+
+    let name = std::any::type_name::<T>();
+    let depth = DEPTHS.with_borrow_mut(|depths| {
+        let depth = depths.entry(name).or_default();
+        *depth += 1;
+        *depth
+    });
+    let _nested = Nested(name);
+
+    if depth > max_depth {
+        Schema {
+            nullable: true,
+            ..Default::default()
+        }
+    } else {
+        build()
+    }
+}
+
 impl<T: AsSchema + ?Sized> AsSchema for &T {
     fn as_schema() -> Schema {
         T::as_schema()
@@ -434,12 +660,13 @@ impl<'a, T: AsSchema + ToOwned + ?Sized + 'a> AsSchema for Cow<'a, T> {
 }
 
 macro_rules! number {
-    ($($n:ident, $ty:ident, $format:ident)*) => {
+    ($($n:ident, $ty:ident, $format:ident $(, $minimum:literal)?)*) => {
         $(impl AsSchema for $n {
             fn as_schema() -> Schema {
                 Schema {
 		            r#type: SchemaType::$ty as i32,
 		            format: SchemaFormat::$format.as_str().into(),
+		            $(minimum: Some($minimum as f64),)?
 		            ..Default::default()
 		        }
             }
@@ -447,24 +674,25 @@ macro_rules! number {
     };
 }
 
+// The bounds keep the model from producing values that fail to convert
 number! {
-    usize, Number, None
-    u8, Number, None
-    u16, Number, None
-    u32, Number, None
-    u64, Number, None
-    u128, Number, None
-    AtomicUsize, Number, None
-    AtomicU8, Number, None
-    AtomicU16, Number, None
-    AtomicU32, Number, None
-    AtomicU64, Number, None
-    NonZeroUsize, Number, None
-    NonZeroU8, Number, None
-    NonZeroU16, Number, None
-    NonZeroU32, Number, None
-    NonZeroU64, Number, None
-    NonZeroU128, Number, None
+    usize, Number, None, 0
+    u8, Number, None, 0
+    u16, Number, None, 0
+    u32, Number, None, 0
+    u64, Number, None, 0
+    u128, Number, None, 0
+    AtomicUsize, Number, None, 0
+    AtomicU8, Number, None, 0
+    AtomicU16, Number, None, 0
+    AtomicU32, Number, None, 0
+    AtomicU64, Number, None, 0
+    NonZeroUsize, Number, None, 1
+    NonZeroU8, Number, None, 1
+    NonZeroU16, Number, None, 1
+    NonZeroU32, Number, None, 1
+    NonZeroU64, Number, None, 1
+    NonZeroU128, Number, None, 1
 }
 
 number! {
@@ -511,7 +739,18 @@ string! {
     String
     Path
     PathBuf
-    char //
+}
+
+impl AsSchema for char {
+    fn as_schema() -> Schema {
+        // Anything longer fails to deserialize
+        Schema {
+            r#type: SchemaType::String as i32,
+            min_length: 1,
+            max_length: 1,
+            ..Default::default()
+        }
+    }
 }
 
 impl AsSchema for bool {
@@ -596,6 +835,38 @@ impl AsSchema for () {
     }
 }
 
+/// A number of seconds; deserialize it with `schema::time::seconds`
+impl AsSchema for Duration {
+    fn as_schema() -> Schema {
+        Schema {
+            r#type: SchemaType::Number as i32,
+            description: "A duration in seconds, e.g. 90.5".into(),
+            minimum: Some(0.0),
+            ..Default::default()
+        }
+    }
+}
+
+/// An RFC 3339 timestamp; deserialize it with `schema::time::rfc3339`
+impl AsSchema for SystemTime {
+    fn as_schema() -> Schema {
+        Schema {
+            r#type: SchemaType::String as i32,
+            format: "date-time".into(),
+            description: "An RFC 3339 timestamp, e.g. 2024-03-01T12:00:00Z".into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Any JSON value. As a response schema, only JSON output is requested
+#[cfg(feature = "serde")]
+impl AsSchema for serde_json::Value {
+    fn as_schema() -> Schema {
+        Schema::default()
+    }
+}
+
 impl<T: AsSchema> AsSchema for Option<T> {
     fn as_schema() -> Schema {
         let mut schema = T::as_schema();
@@ -690,6 +961,7 @@ macro_rules! custom_wrapper_utils {
 custom_wrapper_utils! {
     Tuple
     Map
+    BigIntString
 }
 
 /// A wrapper type to represent maps in Google Schema-friendly format.
@@ -945,6 +1217,66 @@ tuple! {
     (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15)
 }
 
+/// Wrapper type for integers represented as strings of digits
+///
+/// JSON numbers are usually read as doubles, which can't hold every `u64`
+/// or `i64`: large IDs come back silently rounded. This asks the model for
+/// a string of digits instead.
+///
+/// # Example
+///
+/// ```
+/// use google_ai_rs::{AsSchema, BigIntString};
+///
+/// type Id = BigIntString<u64>;
+///
+/// let schema = Id::as_schema();
+/// assert_eq!(schema.pattern, "^[0-9]+$");
+/// ```
+///
+/// On fields, `#[schema(big_int_as_string)]` gives the same schema without
+/// changing the field's type. Pair it with `schema::big_int` for serde.
+///
+/// **Deserialization Note:**  
+/// Requires `serde` feature. Plain JSON integers are accepted as well.
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BigIntString<T: ?Sized> {
+    inner: T,
+}
+
+impl<T: BigInt> AsSchema for BigIntString<T> {
+    fn as_schema() -> Schema {
+        Schema {
+            r#type: SchemaType::String as i32,
+            pattern: T::PATTERN.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for BigIntString<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// Integers that [`BigIntString`] can hold
+pub trait BigInt: std::str::FromStr + std::fmt::Display {
+    /// Regular expression matching the integer's digits
+    const PATTERN: &str;
+}
+
+macro_rules! big_int {
+    ($pattern:literal: $($n:ident)*) => {
+        $(impl BigInt for $n {
+            const PATTERN: &str = $pattern;
+        })*
+    };
+}
+
+big_int!("^[0-9]+$": u8 u16 u32 u64 u128 usize);
+big_int!("^-?[0-9]+$": i8 i16 i32 i64 i128 isize);
+
 #[cfg(feature = "serde")]
 mod serde_support {
     use std::marker::PhantomData;
@@ -1312,6 +1644,98 @@ mod serde_support {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn number_bounds() {
+        assert_eq!(u8::as_schema().minimum, Some(0.0));
+        assert_eq!(AtomicU64::as_schema().minimum, Some(0.0));
+        assert_eq!(NonZeroU8::as_schema().minimum, Some(1.0));
+        assert_eq!(NonZeroI32::as_schema().minimum, None);
+        assert_eq!(f64::as_schema().minimum, None);
+        assert_eq!(Option::<u32>::as_schema().minimum, Some(0.0));
+    }
+
+    #[test]
+    fn bounds_only_apply_to_numbers() {
+        let schema = Schema::new_integer().min_value(-1.0).max_value(1.0);
+        assert_eq!((schema.minimum, schema.maximum), (Some(-1.0), Some(1.0)));
+
+        let schema = Schema::new_string().min_value(0.0);
+        assert_eq!(schema.minimum, None);
+    }
+
+    #[test]
+    fn without_constraints() {
+        let schema = Schema::new_object()
+            .property("id", u32::as_schema())
+            .property("tag", char::as_schema().description("one letter"))
+            .property("kind", Schema::new_string().into_enum(["A", "B"]));
+
+        let plain = schema.without_constraints();
+        assert_eq!(plain.properties["id"], Schema::new_number());
+        assert_eq!(
+            plain.properties["tag"],
+            Schema::new_string().description("one letter")
+        );
+        assert_eq!(plain.properties["kind"], schema.properties["kind"]);
+
+        let schema = Schema::new_object()
+            .property(
+                "ids",
+                Schema::new_array().items(u8::as_schema()).max_items(3),
+            )
+            .property(
+                "id",
+                Schema::any_of([Schema::new_string().max_length(8), Schema::new_integer()])
+                    .description("either"),
+            )
+            .property_ordering(["id", "ids"]);
+        let plain = schema.without_constraints();
+        assert!(plain.property_ordering.is_empty());
+        assert_eq!(
+            plain.properties["ids"],
+            Schema::new_array().items(Schema::new_number())
+        );
+        assert_eq!(
+            plain.properties["id"],
+            Schema::new_string().description("either")
+        );
+    }
+
+    #[test]
+    fn time_types() {
+        let duration = Duration::as_schema();
+        assert_eq!(duration.r#type, SchemaType::Number as i32);
+        assert_eq!(duration.minimum, Some(0.0));
+
+        let time = SystemTime::as_schema();
+        assert_eq!(time.r#type, SchemaType::String as i32);
+        assert_eq!(time.format, "date-time");
+    }
+
+    #[test]
+    fn big_int_string() {
+        let schema = BigIntString::<u64>::as_schema();
+        assert_eq!(schema.r#type, SchemaType::String as i32);
+        assert_eq!(schema.pattern, "^[0-9]+$");
+        assert_eq!(BigIntString::<i128>::as_schema().pattern, "^-?[0-9]+$");
+        assert_eq!(
+            BigIntString::new(u64::MAX).to_string(),
+            "18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn char_is_single_character() {
+        let schema = char::as_schema();
+        assert_eq!((schema.min_length, schema.max_length), (1, 1));
+        assert_eq!(schema, Schema::new_string().min_length(1).max_length(1));
+    }
+}
+
 #[cfg(test)]
 #[allow(dead_code)]
 mod derive_test {
@@ -1359,6 +1783,300 @@ mod derive_test {
         assert_eq!(S::as_schema(), expect)
     }
 
+    #[test]
+    fn max_depth() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate", max_depth = 2)]
+        #[allow(dead_code)]
+        struct Comment {
+            text: String,
+            replies: Vec<Comment>,
+        }
+
+        let schema = Comment::as_schema();
+        let reply = schema.properties["replies"].items.as_deref().unwrap();
+        assert_eq!(reply.r#type, SchemaType::Object as i32);
+        let cut = reply.properties["replies"].items.as_deref().unwrap();
+        assert_eq!(
+            *cut,
+            Schema {
+                nullable: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(Comment::as_schema(), schema);
+    }
+
+    #[test]
+    fn cached_schemas() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate")]
+        #[allow(dead_code)]
+        struct Page<T> {
+            items: Vec<T>,
+        }
+
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate")]
+        #[allow(dead_code)]
+        struct Search {
+            query: String,
+            results: Page<u32>,
+        }
+
+        let schema = Search::as_schema();
+        assert_eq!(Search::as_schema(), schema);
+
+        // Generic types build each instantiation's own
+        let items = |schema: Schema| schema.properties["items"].items.as_deref().unwrap().r#type;
+        assert_eq!(items(Page::<u32>::as_schema()), SchemaType::Number as i32);
+        assert_eq!(
+            items(Page::<String>::as_schema()),
+            SchemaType::String as i32
+        );
+    }
+
+    #[test]
+    fn internally_tagged_enum() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate", tag = "kind", rename_all = "snake_case")]
+        #[allow(dead_code)]
+        enum Shape {
+            Point,
+            Circle {
+                #[schema(description = "In cm")]
+                radius: f64,
+            },
+            Rect {
+                width: f64,
+                height: f64,
+            },
+        }
+
+        let schema = Shape::as_schema();
+        assert_eq!(schema.r#type, SchemaType::Object as i32);
+        assert_eq!(schema.required, ["kind"]);
+        assert_eq!(
+            schema.properties["kind"].r#enum,
+            ["point", "circle", "rect"]
+        );
+        assert_eq!(schema.properties.len(), 4);
+        assert_eq!(
+            schema.properties["radius"].description,
+            "In cm\nOnly when kind is \"circle\""
+        );
+    }
+
+    #[test]
+    fn adjacently_tagged_enum() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate", tag = "t", content = "c")]
+        #[allow(dead_code)]
+        enum Message {
+            Text(String),
+            Move { x: i32, y: i32 },
+            Quit,
+        }
+
+        let schema = Message::as_schema();
+        assert_eq!(schema.required, ["t"]);
+        assert_eq!(schema.properties["t"].r#enum, ["Text", "Move", "Quit"]);
+
+        let content = &schema.properties["c"];
+        assert_eq!(content.r#type, SchemaType::Unspecified as i32);
+        assert!(content.nullable);
+        assert_eq!(
+            content.description,
+            "Depends on t:\nText: String\nMove: an object with x, y\nQuit: none"
+        );
+    }
+
+    #[test]
+    fn untagged_enum() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate", untagged)]
+        enum Id {
+            Number(u64),
+            Name { first: String, last: String },
+            Unknown,
+        }
+
+        let schema = Id::as_schema();
+        assert!(schema.nullable);
+        assert_eq!(schema.r#type, SchemaType::Unspecified as i32);
+        assert_eq!(schema.any_of.len(), 2);
+        assert_eq!(schema.any_of[0], u64::as_schema());
+        assert_eq!(schema.any_of[1].required, ["first", "last"]);
+    }
+
+    #[test]
+    fn numeric_bounds() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate")]
+        struct Review {
+            #[schema(minimum = 1, maximum = 5)]
+            stars: u8,
+            #[schema(minimum = -1.0, maximum = 1.0)]
+            sentiment: Option<f32>,
+        }
+
+        let schema = Review::as_schema();
+        let stars = &schema.properties["stars"];
+        assert_eq!((stars.minimum, stars.maximum), (Some(1.0), Some(5.0)));
+        let sentiment = &schema.properties["sentiment"];
+        assert_eq!(
+            (sentiment.minimum, sentiment.maximum),
+            (Some(-1.0), Some(1.0))
+        );
+        assert!(sentiment.nullable);
+    }
+
+    #[test]
+    fn pattern() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate")]
+        struct Booking {
+            #[schema(pattern = "^[A-Z]{2}\\d{4}$")]
+            flight: String,
+        }
+
+        let schema = Booking::as_schema();
+        assert_eq!(
+            schema.properties["flight"],
+            Schema::new_string().pattern("^[A-Z]{2}\\d{4}$")
+        );
+        assert!(schema.without_constraints().properties["flight"]
+            .pattern
+            .is_empty());
+    }
+
+    #[test]
+    fn example() {
+        #[derive(AsSchema)]
+        #[schema(
+            crate_path = "crate",
+            description = "A booking",
+            example = r#"{"date": "2024-03-01"}"#
+        )]
+        struct Booking {
+            #[schema(example = "2024-03-01")]
+            date: String,
+            #[schema(description = "Number of guests", example = 2)]
+            guests: u32,
+        }
+
+        let schema = Booking::as_schema();
+        assert_eq!(
+            schema.description,
+            "A booking\nExample: {\"date\": \"2024-03-01\"}"
+        );
+        assert_eq!(schema.properties["date"].description, "Example: 2024-03-01");
+        assert_eq!(
+            schema.properties["guests"].description,
+            "Number of guests\nExample: 2"
+        );
+    }
+
+    #[test]
+    fn default() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate")]
+        struct Search {
+            query: String,
+            #[schema(default = 10)]
+            limit: u32,
+        }
+
+        let schema = Search::as_schema();
+        assert_eq!(schema.required, ["query"]);
+        assert_eq!(
+            schema.properties["limit"],
+            u32::as_schema().default_value(10)
+        );
+    }
+
+    #[test]
+    fn title() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate", auto_title)]
+        struct ShippingAddress {
+            postal_code: String,
+            #[schema(title = "ISO country code")]
+            country: String,
+        }
+
+        let schema = ShippingAddress::as_schema();
+        assert_eq!(schema.title, "Shipping address");
+        assert_eq!(
+            schema.properties["postal_code"],
+            Schema::new_string().title("Postal code")
+        );
+        assert_eq!(schema.properties["country"].title, "ISO country code");
+    }
+
+    #[test]
+    fn ordered() {
+        #[derive(AsSchema)]
+        #[schema(crate_path = "crate", ordered, rename_all = "camelCase")]
+        struct Answer {
+            reasoning: String,
+            final_answer: String,
+            #[schema(skip)]
+            _cache: String,
+        }
+
+        let schema = Answer::as_schema();
+        assert_eq!(schema.property_ordering, ["reasoning", "finalAnswer"]);
+    }
+
+    #[test]
+    fn serde_default() {
+        #[derive(AsSchema, serde::Deserialize)]
+        #[schema(crate_path = "crate")]
+        struct Search {
+            query: String,
+            #[serde(default)]
+            exact: bool,
+        }
+
+        let schema = Search::as_schema();
+        assert_eq!(schema.required, ["query"]);
+        assert_eq!(schema.properties["exact"].default, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn with_serde_defaults() {
+        #[derive(crate::AsSchemaWithSerde, Debug, PartialEq)]
+        #[schema(crate_path = "crate")]
+        struct Search {
+            query: String,
+            #[schema(default = 10)]
+            limit: u32,
+            #[schema(default = "en")]
+            language: Option<String>,
+        }
+
+        let search: Search = serde_json::from_str(r#"{"query": "rust"}"#).unwrap();
+        assert_eq!(
+            search,
+            Search {
+                query: "rust".into(),
+                limit: 10,
+                language: Some("en".into()),
+            }
+        );
+
+        #[derive(crate::AsSchemaWithSerde, Debug, PartialEq)]
+        #[schema(crate_path = "crate", all_optional)]
+        enum Filter {
+            Range { min: u32, max: u32 },
+        }
+
+        let filter: Filter = serde_json::from_str(r#"{"Range": {"min": 3}}"#).unwrap();
+        assert_eq!(filter, Filter::Range { min: 3, max: 0 });
+    }
+
     #[test]
     fn as_schema() {
         struct Wrapper<T>(T);
@@ -0,0 +1,168 @@
+//! Serde adapter for types represented as strings
+//!
+//! Enabled by the `serde` feature. Pairs with `#[schema(as_string)]`, which
+//! asks the model for a string in place of a type implementing `FromStr`
+//! and `Display`, like IDs, IP addresses or currencies. `AsSchemaWithSerde`
+//! parses such fields itself; with `AsSchema`, point serde at this module,
+//! or at [`option`] for an `Option`:
+//!
+//! ```rust
+//! use std::net::IpAddr;
+//!
+//! use google_ai_rs::{schema::as_string, AsSchema};
+//!
+//! #[derive(AsSchema, serde::Deserialize)]
+//! struct Server {
+//!     #[schema(as_string)]
+//!     #[serde(with = "as_string")]
+//!     address: IpAddr,
+//!     #[schema(as_string)]
+//!     #[serde(with = "as_string::option", default)]
+//!     backup: Option<IpAddr>,
+//! }
+//! # let server: Server = serde_json::from_str(r#"{"address": "10.0.0.1"}"#).unwrap();
+//! # assert_eq!(server.address, IpAddr::from([10, 0, 0, 1]));
+//! # assert_eq!(server.backup, None);
+//! ```
+
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use serde::{de::Visitor, Deserializer, Serializer};
+
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: fmt::Display,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(FromStrVisitor(PhantomData))
+}
+
+struct FromStrVisitor<T>(PhantomData<T>);
+
+impl<T> Visitor<'_> for FromStrVisitor<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a string")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<T, E> {
+        v.parse()
+            .map_err(|err| E::custom(format!("{v:?} isn't valid: {err}")))
+    }
+}
+
+/// The same for `Option`s, `null` being `None`
+pub mod option {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: fmt::Display,
+        S: Serializer,
+    {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+
+    struct OptionVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for OptionVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        type Value = Option<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a string or null")
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<T>, D::Error> {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        #[serde(with = "super")]
+        address: IpAddr,
+        #[serde(with = "super::option", default)]
+        backup: Option<IpAddr>,
+    }
+
+    #[test]
+    fn round_trips() {
+        let server: Server = serde_json::from_value(json!({
+            "address": "10.0.0.1",
+            "backup": null,
+        }))
+        .unwrap();
+        assert_eq!(server.address, IpAddr::from([10, 0, 0, 1]));
+        assert_eq!(server.backup, None);
+        let server: Server = serde_json::from_value(json!({"address": "::1"})).unwrap();
+        assert_eq!(server.backup, None);
+
+        let server = Server {
+            address: IpAddr::from([10, 0, 0, 1]),
+            backup: Some(IpAddr::from([10, 0, 0, 2])),
+        };
+        let value = serde_json::to_value(&server).unwrap();
+        assert_eq!(value, json!({"address": "10.0.0.1", "backup": "10.0.0.2"}));
+        assert_eq!(serde_json::from_value::<Server>(value).unwrap(), server);
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        let tests = [
+            (json!("10.0.0"), r#""10.0.0" isn't valid"#),
+            (json!(10), "expected a string"),
+        ];
+        for (address, error_like) in tests {
+            let err = serde_json::from_value::<Server>(json!({ "address": address }))
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(error_like), "{err}");
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Serde adapter for integers represented as strings of digits
+//!
+//! Enabled by the `serde` feature. Pairs with `#[schema(big_int_as_string)]`,
+//! which asks the model for a string where serde would expect a number:
+//!
+//! ```rust
+//! use google_ai_rs::{schema::big_int, AsSchema};
+//!
+//! #[derive(AsSchema, serde::Deserialize)]
+//! struct Order {
+//!     #[schema(big_int_as_string)]
+//!     #[serde(with = "big_int")]
+//!     id: u64,
+//! }
+//! # let order: Order = serde_json::from_str(r#"{"id": "18446744073709551615"}"#).unwrap();
+//! # assert_eq!(order.id, u64::MAX);
+//! ```
+//!
+//! Plain JSON integers are accepted too, so values written before the
+//! switch still read back.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{BigInt, BigIntString};
+
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: BigInt,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: BigInt,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(BigIntVisitor(PhantomData))
+}
+
+struct BigIntVisitor<T>(PhantomData<T>);
+
+impl<T: BigInt> BigIntVisitor<T> {
+    fn parse<E: serde::de::Error>(digits: &str) -> Result<T, E> {
+        digits
+            .parse()
+            .map_err(|_| E::custom(format!("{digits:?} isn't an integer in range")))
+    }
+}
+
+impl<T: BigInt> Visitor<'_> for BigIntVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an integer or a string of its digits")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<T, E> {
+        Self::parse(v)
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_u128<E: serde::de::Error>(self, v: u128) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_i128<E: serde::de::Error>(self, v: i128) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<T, E> {
+        // Already rounded if it was ever large
+        Err(E::custom(format!(
+            "{v} isn't exact; big integers must be strings"
+        )))
+    }
+}
+
+impl<'de, T: BigInt> Deserialize<'de> for BigIntString<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl<T: BigInt> Serialize for BigIntString<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.inner, serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_strings() {
+        let id: BigIntString<u64> = serde_json::from_value(json!("18446744073709551615")).unwrap();
+        assert_eq!(*id, u64::MAX);
+        assert_eq!(
+            serde_json::to_value(id).unwrap(),
+            json!("18446744073709551615")
+        );
+
+        let id: BigIntString<i128> = serde_json::from_value(json!("-42")).unwrap();
+        assert_eq!(id.into_inner(), -42);
+    }
+
+    #[test]
+    fn accepts_integers_only() {
+        let id: BigIntString<u64> = serde_json::from_value(json!(7)).unwrap();
+        assert_eq!(*id, 7);
+
+        for value in [json!(1e20), json!("-1"), json!("12a"), json!(null)] {
+            assert!(
+                serde_json::from_value::<BigIntString<u64>>(value.clone()).is_err(),
+                "{value}"
+            );
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::BTreeMap;
+
+use prost_types::value::Kind;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+
+use super::{Schema, SchemaType};
+use crate::error::Error;
+
+/// The version [`Schema::to_canonical_json`] writes
+const VERSION: u32 = 1;
+
+impl Schema {
+    /// Returns the schema as canonical JSON, for storing it
+    ///
+    /// The form is documented and versioned, independent of the protobuf
+    /// definitions this crate is generated from, so stored schemas can be
+    /// read back by later versions with
+    /// [`from_canonical_json`](Self::from_canonical_json). Equal schemas
+    /// give equal strings, which makes them fit for hashing and snapshots.
+    ///
+    /// The JSON is minified with keys sorted, `{"schema":{...},"version":1}`.
+    /// A schema is an object of the following keys, left out when unset:
+    ///
+    /// | Key | Value |
+    /// |---|---|
+    /// | `anyOf` | Array of schemas |
+    /// | `default` | Any JSON value |
+    /// | `description`, `format`, `pattern`, `title` | String |
+    /// | `enum`, `propertyOrdering`, `required` | Array of strings |
+    /// | `items` | Schema |
+    /// | `maxItems`, `maxLength`, `minItems`, `minLength` | Integer |
+    /// | `maximum`, `minimum` | Number |
+    /// | `nullable` | `true` |
+    /// | `properties` | Object of schemas |
+    /// | `type` | `"string"`, `"number"`, `"integer"`, `"boolean"`, `"array"` or `"object"` |
+    ///
+    /// Enabled by the `serde` feature.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use google_ai_rs::Schema;
+    /// let schema = Schema::new_object()
+    ///     .property("name", Schema::new_string())
+    ///     .required(["name"]);
+    ///
+    /// let json = schema.to_canonical_json();
+    /// assert_eq!(
+    ///     json,
+    ///     r#"{"schema":{"properties":{"name":{"type":"string"}},"required":["name"],"type":"object"},"version":1}"#
+    /// );
+    /// assert_eq!(Schema::from_canonical_json(&json).unwrap(), schema);
+    /// ```
+    pub fn to_canonical_json(&self) -> String {
+        let document = Document {
+            schema: Canonical::from(self),
+            version: VERSION,
+        };
+        serde_json::to_string(&document).expect("schemas serialize to JSON")
+    }
+
+    /// Reads a schema written by [`to_canonical_json`](Self::to_canonical_json)
+    ///
+    /// JSON of another form, or written by a newer version of the form, is
+    /// reported as [`Error::InvalidContent`].
+    ///
+    /// Enabled by the `serde` feature.
+    pub fn from_canonical_json(json: &str) -> Result<Schema, Error> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } =
+            serde_json::from_str(json).map_err(|e| Error::InvalidContent(Box::new(e)))?;
+        if version > VERSION {
+            return Err(Error::InvalidContent(
+                format!("canonical schema version {version} is newer than the supported {VERSION}")
+                    .into(),
+            ));
+        }
+
+        let document: Document =
+            serde_json::from_str(json).map_err(|e| Error::InvalidContent(Box::new(e)))?;
+        Ok(document.schema.into())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document {
+    schema: Canonical,
+    version: u32,
+}
+
+// Fields are declared in the order of their keys, which serde writes them
+// in
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+struct Canonical {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    any_of: Vec<Canonical>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default: Option<Value>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    r#enum: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    format: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<Box<Canonical>>,
+    #[serde(skip_serializing_if = "is_zero")]
+    max_items: i64,
+    #[serde(skip_serializing_if = "is_zero")]
+    max_length: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<f64>,
+    #[serde(skip_serializing_if = "is_zero")]
+    min_items: i64,
+    #[serde(skip_serializing_if = "is_zero")]
+    min_length: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<f64>,
+    #[serde(skip_serializing_if = "is_false")]
+    nullable: bool,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pattern: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, Canonical>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    property_ordering: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    required: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    title: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    r#type: Option<CanonicalType>,
+}
+
+/// Named apart from the protobuf enum, whose numbering isn't part of the form
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CanonicalType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+fn is_zero(n: &i64) -> bool {
+    *n == 0
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+impl From<&Schema> for Canonical {
+    fn from(schema: &Schema) -> Self {
+        Self {
+            any_of: schema.any_of.iter().map(Canonical::from).collect(),
+            default: schema.default.as_ref().map(from_proto),
+            description: schema.description.clone(),
+            r#enum: schema.r#enum.clone(),
+            format: schema.format.clone(),
+            items: schema
+                .items
+                .as_deref()
+                .map(|items| Box::new(Canonical::from(items))),
+            max_items: schema.max_items,
+            max_length: schema.max_length,
+            maximum: schema.maximum,
+            min_items: schema.min_items,
+            min_length: schema.min_length,
+            minimum: schema.minimum,
+            nullable: schema.nullable,
+            pattern: schema.pattern.clone(),
+            properties: schema
+                .properties
+                .iter()
+                .map(|(name, schema)| (name.clone(), Canonical::from(schema)))
+                .collect(),
+            property_ordering: schema.property_ordering.clone(),
+            required: schema.required.clone(),
+            title: schema.title.clone(),
+            r#type: match schema.r#type() {
+                SchemaType::Unspecified => None,
+                SchemaType::String => Some(CanonicalType::String),
+                SchemaType::Number => Some(CanonicalType::Number),
+                SchemaType::Integer => Some(CanonicalType::Integer),
+                SchemaType::Boolean => Some(CanonicalType::Boolean),
+                SchemaType::Array => Some(CanonicalType::Array),
+                SchemaType::Object => Some(CanonicalType::Object),
+            },
+        }
+    }
+}
+
+impl From<Canonical> for Schema {
+    fn from(canonical: Canonical) -> Self {
+        let r#type = match canonical.r#type {
+            None => SchemaType::Unspecified,
+            Some(CanonicalType::String) => SchemaType::String,
+            Some(CanonicalType::Number) => SchemaType::Number,
+            Some(CanonicalType::Integer) => SchemaType::Integer,
+            Some(CanonicalType::Boolean) => SchemaType::Boolean,
+            Some(CanonicalType::Array) => SchemaType::Array,
+            Some(CanonicalType::Object) => SchemaType::Object,
+        };
+        Self {
+            r#type: r#type as i32,
+            format: canonical.format,
+            title: canonical.title,
+            description: canonical.description,
+            nullable: canonical.nullable,
+            r#enum: canonical.r#enum,
+            items: canonical.items.map(|items| Box::new(Schema::from(*items))),
+            max_items: canonical.max_items,
+            min_items: canonical.min_items,
+            properties: canonical
+                .properties
+                .into_iter()
+                .map(|(name, schema)| (name, Schema::from(schema)))
+                .collect(),
+            required: canonical.required,
+            property_ordering: canonical.property_ordering,
+            minimum: canonical.minimum,
+            maximum: canonical.maximum,
+            min_length: canonical.min_length,
+            max_length: canonical.max_length,
+            pattern: canonical.pattern,
+            any_of: canonical.any_of.into_iter().map(Schema::from).collect(),
+            default: canonical.default.map(to_proto),
+        }
+    }
+}
+
+/// Converts a protobuf value to JSON, whole numbers as integers
+pub(crate) fn from_proto(value: &prost_types::Value) -> Value {
+    match &value.kind {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::NumberValue(n)) => {
+            if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+                Value::from(*n as i64)
+            } else {
+                Number::from_f64(*n).map_or(Value::Null, Value::Number)
+            }
+        }
+        Some(Kind::StringValue(s)) => Value::String(s.clone()),
+        Some(Kind::BoolValue(b)) => Value::Bool(*b),
+        Some(Kind::StructValue(s)) => Value::Object(
+            s.fields
+                .iter()
+                .map(|(name, value)| (name.clone(), from_proto(value)))
+                .collect(),
+        ),
+        Some(Kind::ListValue(l)) => Value::Array(l.values.iter().map(from_proto).collect()),
+    }
+}
+
+/// Converts JSON to a protobuf value
+pub(crate) fn to_proto(value: Value) -> prost_types::Value {
+    let kind = match value {
+        Value::Null => Kind::NullValue(0),
+        Value::Bool(b) => Kind::BoolValue(b),
+        Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        Value::String(s) => Kind::StringValue(s),
+        Value::Array(values) => Kind::ListValue(prost_types::ListValue {
+            values: values.into_iter().map(to_proto).collect(),
+        }),
+        Value::Object(map) => Kind::StructValue(prost_types::Struct {
+            fields: map
+                .into_iter()
+                .map(|(name, value)| (name, to_proto(value)))
+                .collect(),
+        }),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let schema = Schema::new_object()
+            .description("A task")
+            .property(
+                "status",
+                Schema::new_string()
+                    .into_enum(["open", "closed"])
+                    .default_value("open"),
+            )
+            .property(
+                "tags",
+                Schema::new_array()
+                    .items(Schema::new_string().min_length(1).pattern("^#"))
+                    .max_items(5),
+            )
+            .property(
+                "estimate",
+                Schema::any_of([Schema::new_integer().min_value(0.0), Schema::new_string()])
+                    .nullable(true),
+            )
+            .required(["status"])
+            .property_ordering(["status", "tags", "estimate"]);
+
+        let json = schema.to_canonical_json();
+        assert_eq!(Schema::from_canonical_json(&json).unwrap(), schema);
+
+        // Independent of the order properties were added in
+        let reordered = Schema::new_object()
+            .description("A task")
+            .property("estimate", schema.properties["estimate"].clone())
+            .property("tags", schema.properties["tags"].clone())
+            .property("status", schema.properties["status"].clone())
+            .required(["status"])
+            .property_ordering(["status", "tags", "estimate"]);
+        assert_eq!(reordered.to_canonical_json(), json);
+
+        assert_eq!(
+            Schema::default().to_canonical_json(),
+            r#"{"schema":{},"version":1}"#
+        );
+    }
+
+    #[test]
+    fn rejects_other_json() {
+        let tests = [
+            (r#"{"schema":{},"version":2}"#, "version 2 is newer"),
+            (
+                r#"{"schema":{"type":"STRING"},"version":1}"#,
+                "unknown variant",
+            ),
+            (
+                r#"{"schema":{"kind":"string"},"version":1}"#,
+                "unknown field",
+            ),
+            (r#"{"type":"string"}"#, "missing field"),
+        ];
+        for (json, error_like) in tests {
+            let err = Schema::from_canonical_json(json).unwrap_err().to_string();
+            assert!(err.contains(error_like), "{err}");
+        }
+    }
+}
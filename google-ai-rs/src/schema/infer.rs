@@ -0,0 +1,179 @@
+use serde_json::Value;
+
+use super::{Schema, SchemaType};
+
+impl Schema {
+    /// Infers a schema from a sample value
+    ///
+    /// A best effort for prototyping, or for tool parameters captured from
+    /// real traffic: integers and other numbers, strings, booleans, arrays
+    /// and objects are recognized, with every property of an object
+    /// required. The elements of an array are merged into one item schema:
+    /// properties missing from some objects aren't required, `null`s make
+    /// the schema nullable, and values of different types become `any_of`.
+    /// Formats, enums and bounds can't be told from one sample; add them
+    /// afterwards.
+    ///
+    /// Enabled by the `serde` feature.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use google_ai_rs::{Schema, SchemaType};
+    /// let sample = serde_json::json!({
+    ///     "name": "Ada",
+    ///     "tags": [{"id": 1, "label": "x"}, {"id": 2}],
+    ///     "manager": null,
+    /// });
+    /// let schema = Schema::infer_from_json(&sample);
+    ///
+    /// let tag = schema.properties["tags"].items.as_deref().unwrap();
+    /// assert_eq!(tag.required, ["id"]);
+    /// assert!(schema.properties["manager"].nullable);
+    /// ```
+    pub fn infer_from_json(value: &Value) -> Schema {
+        match value {
+            // Only the nullability is known
+            Value::Null => Schema {
+                nullable: true,
+                ..Default::default()
+            },
+            Value::Bool(_) => Schema::new(SchemaType::Boolean),
+            Value::Number(number) if number.is_f64() => Schema::new_number(),
+            Value::Number(_) => Schema::new_integer(),
+            Value::String(_) => Schema::new_string(),
+            Value::Array(values) => {
+                let items = values.iter().map(Schema::infer_from_json).reduce(merge);
+                Schema {
+                    items: items.map(Box::new),
+                    ..Schema::new_array()
+                }
+            }
+            Value::Object(map) => Schema::new_object()
+                .properties(map.iter().map(|(k, v)| (k, Schema::infer_from_json(v))))
+                .required(map.keys()),
+        }
+    }
+}
+
+// A schema matching values of both
+fn merge(a: Schema, b: Schema) -> Schema {
+    let unspecified = SchemaType::Unspecified as i32;
+    let (integer, number) = (SchemaType::Integer as i32, SchemaType::Number as i32);
+
+    // A null only adds nullability
+    if a.r#type == unspecified && a.any_of.is_empty() {
+        return Schema {
+            nullable: a.nullable || b.nullable,
+            ..b
+        };
+    }
+    if b.r#type == unspecified && b.any_of.is_empty() {
+        return merge(b, a);
+    }
+
+    let numeric = |ty: i32| ty == integer || ty == number;
+    let nullable = a.nullable || b.nullable;
+    let mut merged = match (a.r#type, b.r#type) {
+        (x, y) if x == y && x == SchemaType::Object as i32 => merge_objects(a, b),
+        (x, y) if x == y && x == SchemaType::Array as i32 => Schema {
+            items: match (a.items, b.items) {
+                (Some(a), Some(b)) => Some(Box::new(merge(*a, *b))),
+                (a, b) => a.or(b),
+            },
+            ..a
+        },
+        (x, y) if x == y && x != unspecified => a,
+        // Integers are numbers too
+        (x, y) if numeric(x) && numeric(y) => Schema::new_number(),
+        _ => {
+            // Add to the alternatives, merging into one of the same type
+            let mut any_of = if a.r#type == unspecified {
+                a.any_of
+            } else {
+                vec![a]
+            };
+            for schema in if b.r#type == unspecified {
+                b.any_of
+            } else {
+                vec![b]
+            } {
+                match any_of.iter().position(|s| {
+                    s.r#type == schema.r#type || numeric(s.r#type) && numeric(schema.r#type)
+                }) {
+                    Some(i) => {
+                        let existing = any_of.remove(i);
+                        any_of.insert(i, merge(existing, schema));
+                    }
+                    None => any_of.push(schema),
+                }
+            }
+            Schema::any_of(any_of)
+        }
+    };
+    merged.nullable = nullable;
+    merged
+}
+
+// Properties of either, required only if required by both
+fn merge_objects(mut a: Schema, b: Schema) -> Schema {
+    for (name, schema) in b.properties {
+        let merged = match a.properties.remove(&name) {
+            Some(existing) => merge(existing, schema),
+            None => schema,
+        };
+        a.properties.insert(name, merged);
+    }
+    a.required.retain(|name| b.required.contains(name));
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn infers_primitives() {
+        let schema = Schema::infer_from_json(&json!({
+            "name": "Ada",
+            "age": 36,
+            "score": 9.5,
+            "admin": false,
+        }));
+        assert_eq!(schema.r#type, SchemaType::Object as i32);
+        let ty = |name: &str| schema.properties[name].r#type;
+        assert_eq!(ty("name"), SchemaType::String as i32);
+        assert_eq!(ty("age"), SchemaType::Integer as i32);
+        assert_eq!(ty("score"), SchemaType::Number as i32);
+        assert_eq!(ty("admin"), SchemaType::Boolean as i32);
+
+        let mut required = schema.required.clone();
+        required.sort();
+        assert_eq!(required, ["admin", "age", "name", "score"]);
+    }
+
+    #[test]
+    fn merges_array_items() {
+        let schema = Schema::infer_from_json(&json!([
+            {"id": 1, "note": null},
+            {"id": 2.5, "note": "x", "extra": [true]},
+        ]));
+        let item = schema.items.unwrap();
+        assert_eq!(item.required, ["id", "note"]);
+        assert_eq!(item.properties["id"].r#type, SchemaType::Number as i32);
+        let note = &item.properties["note"];
+        assert_eq!(note.r#type, SchemaType::String as i32);
+        assert!(note.nullable);
+        assert_eq!(
+            item.properties["extra"].items.as_deref().unwrap().r#type,
+            SchemaType::Boolean as i32
+        );
+
+        let schema = Schema::infer_from_json(&json!([1, "a", 2.5, null]));
+        let item = schema.items.unwrap();
+        assert_eq!(item.any_of.len(), 2);
+        assert!(item.nullable);
+
+        assert_eq!(Schema::infer_from_json(&json!([])).items, None);
+    }
+}
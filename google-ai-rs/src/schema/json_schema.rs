@@ -0,0 +1,413 @@
+//! Bridge from `schemars::JsonSchema`
+//!
+//! Enabled by the `schemars` feature. Many crates already describe their
+//! types with [`schemars`]; [`from_json_schema`] converts the JSON Schema
+//! it generates into the subset Gemini takes, so those types don't need an
+//! `as_schema` function of their own. In a derived type, mark their fields
+//! `#[schema(via_schemars)]`:
+//!
+//! ```rust
+//! use google_ai_rs::AsSchema;
+//! # mod some_crate {
+//! #     #[derive(schemars::JsonSchema, serde::Deserialize)]
+//! #     pub struct Money {
+//! #         pub cents: i64,
+//! #     }
+//! # }
+//!
+//! #[derive(AsSchema, serde::Deserialize)]
+//! struct Invoice {
+//!     #[schema(via_schemars)]
+//!     total: some_crate::Money,
+//! }
+//! ```
+//!
+//! References are inlined, with recursive ones left unconstrained, and
+//! `allOf` is merged into one schema. Keywords Gemini has no counterpart
+//! for, like `additionalProperties` or `uniqueItems`, and formats it
+//! doesn't know are dropped.
+
+use serde_json::{Map, Value};
+
+use super::{canonical, Schema, SchemaType};
+
+/// Converts the JSON Schema `T` describes itself with
+///
+/// The schema is generated for deserialization, since that's what model
+/// replies go through.
+///
+/// # Example
+/// ```rust
+/// # use google_ai_rs::schema::from_json_schema;
+/// #[derive(schemars::JsonSchema)]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let schema = from_json_schema::<Point>();
+/// assert_eq!(schema.required, ["x", "y"]);
+/// ```
+pub fn from_json_schema<T: schemars::JsonSchema + ?Sized>() -> Schema {
+    let schema = schemars::generate::SchemaSettings::draft2020_12()
+        .for_deserialize()
+        .into_generator()
+        .into_root_schema_for::<T>();
+    Schema::from(&schema)
+}
+
+impl From<&schemars::Schema> for Schema {
+    fn from(schema: &schemars::Schema) -> Self {
+        let root = schema.as_value();
+        Converter {
+            root,
+            expanding: Vec::new(),
+        }
+        .convert(root)
+    }
+}
+
+struct Converter<'a> {
+    root: &'a Value,
+    // References being inlined, to catch recursion
+    expanding: Vec<&'a str>,
+}
+
+impl<'a> Converter<'a> {
+    fn convert(&mut self, value: &'a Value) -> Schema {
+        // `true` and `false` constrain nothing Gemini can express
+        let Some(object) = value.as_object() else {
+            return Schema::default();
+        };
+        let mut schema = self.own_keywords(object);
+
+        if let Some(Value::String(reference)) = object.get("$ref") {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer));
+            if let Some(target) = target {
+                if !self.expanding.contains(&reference.as_str()) {
+                    self.expanding.push(reference);
+                    let referenced = self.convert(target);
+                    self.expanding.pop();
+                    fill(&mut schema, referenced);
+                }
+            }
+        }
+
+        if let Some(Value::Array(all)) = object.get("allOf") {
+            for value in all {
+                let part = self.convert(value);
+                fill(&mut schema, part);
+            }
+        }
+
+        for keyword in ["anyOf", "oneOf"] {
+            let Some(Value::Array(alternatives)) = object.get(keyword) else {
+                continue;
+            };
+            let mut others = Vec::new();
+            for value in alternatives {
+                if is_null(value) {
+                    schema.nullable = true;
+                } else {
+                    others.push(self.convert(value));
+                }
+            }
+            if others.len() == 1 {
+                fill(&mut schema, others.remove(0));
+            } else if schema.any_of.is_empty() {
+                schema.any_of = others;
+            }
+        }
+
+        schema
+    }
+
+    fn own_keywords(&mut self, object: &'a Map<String, Value>) -> Schema {
+        let mut schema = Schema::default();
+
+        match object.get("type") {
+            Some(Value::String(name)) => set_type(&mut schema, name),
+            Some(Value::Array(names)) => {
+                let names: Vec<_> = names.iter().filter_map(Value::as_str).collect();
+                let types: Vec<_> = names.iter().filter(|&&name| name != "null").collect();
+                schema.nullable = types.len() < names.len();
+                match types[..] {
+                    [name] => set_type(&mut schema, name),
+                    _ => {
+                        schema.any_of = types
+                            .into_iter()
+                            .map(|name| {
+                                let mut alternative = Schema::default();
+                                set_type(&mut alternative, name);
+                                alternative
+                            })
+                            .collect();
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Gemini only enumerates strings
+        let values = match object.get("const") {
+            Some(value) => Some(std::slice::from_ref(value)),
+            None => object
+                .get("enum")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice),
+        };
+        if let Some(values) = values {
+            let strings: Vec<_> = values.iter().filter_map(Value::as_str).collect();
+            let nulls = values.iter().filter(|value| value.is_null()).count();
+            if !strings.is_empty() && strings.len() + nulls == values.len() {
+                schema.r#type = SchemaType::String as i32;
+                schema.format = "enum".to_owned();
+                schema.r#enum = strings.into_iter().map(str::to_owned).collect();
+            }
+            schema.nullable |= nulls > 0;
+        }
+
+        if let Some(Value::String(format)) = object.get("format") {
+            let known = match SchemaType::try_from(schema.r#type) {
+                Ok(SchemaType::String) => format == "date-time",
+                Ok(SchemaType::Integer) => format == "int32" || format == "int64",
+                Ok(SchemaType::Number) => format == "float" || format == "double",
+                _ => false,
+            };
+            if known && schema.format.is_empty() {
+                schema.format.clone_from(format);
+            }
+        }
+
+        let string = |name| object.get(name).and_then(Value::as_str).map(str::to_owned);
+        schema.title = string("title").unwrap_or_default();
+        schema.description = string("description").unwrap_or_default();
+        schema.pattern = string("pattern").unwrap_or_default();
+
+        let count = |name| {
+            object
+                .get(name)
+                .and_then(Value::as_u64)
+                .map_or(0, |n| i64::try_from(n).unwrap_or(i64::MAX))
+        };
+        schema.min_length = count("minLength");
+        schema.max_length = count("maxLength");
+        schema.min_items = count("minItems");
+        schema.max_items = count("maxItems");
+        schema.minimum = object.get("minimum").and_then(Value::as_f64);
+        schema.maximum = object.get("maximum").and_then(Value::as_f64);
+
+        if let Some(Value::Object(properties)) = object.get("properties") {
+            schema.properties = properties
+                .iter()
+                .map(|(name, value)| (name.clone(), self.convert(value)))
+                .collect();
+        }
+        if let Some(Value::Array(required)) = object.get("required") {
+            schema.required = required
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect();
+        }
+
+        // Tuples become arrays of a fixed length whose items are any of
+        // the elements
+        if let Some(Value::Array(prefix)) = object.get("prefixItems") {
+            let len = i64::try_from(prefix.len()).unwrap_or(i64::MAX);
+            let mut items: Vec<_> = prefix.iter().map(|value| self.convert(value)).collect();
+            schema.items = Some(Box::new(if items.len() == 1 {
+                items.remove(0)
+            } else {
+                Schema::any_of(items)
+            }));
+            schema.min_items = schema.min_items.max(len);
+            if schema.max_items == 0 {
+                schema.max_items = len;
+            }
+        } else if let Some(items) = object.get("items") {
+            schema.items = Some(Box::new(self.convert(items)));
+        }
+
+        if let Some(default) = object.get("default") {
+            schema.default = Some(canonical::to_proto(default.clone()));
+        }
+
+        schema
+    }
+}
+
+fn set_type(schema: &mut Schema, name: &str) {
+    let r#type = match name {
+        "string" => SchemaType::String,
+        "number" => SchemaType::Number,
+        "integer" => SchemaType::Integer,
+        "boolean" => SchemaType::Boolean,
+        "array" => SchemaType::Array,
+        "object" => SchemaType::Object,
+        "null" => {
+            schema.nullable = true;
+            return;
+        }
+        _ => return,
+    };
+    schema.r#type = r#type as i32;
+}
+
+fn is_null(value: &Value) -> bool {
+    value.get("type").and_then(Value::as_str) == Some("null")
+        || value.get("const").is_some_and(Value::is_null)
+}
+
+// Fills what `schema` leaves unset from `from`
+fn fill(schema: &mut Schema, from: Schema) {
+    macro_rules! fill {
+        ($($field:ident)*) => {
+            $(
+                if is_unset(&schema.$field) {
+                    schema.$field = from.$field;
+                }
+            )*
+        };
+    }
+    fill! {
+        r#type format title description r#enum items max_items min_items
+        property_ordering minimum maximum min_length max_length pattern
+        any_of default
+    }
+    schema.nullable |= from.nullable;
+    for (name, property) in from.properties {
+        schema.properties.entry(name).or_insert(property);
+    }
+    for name in from.required {
+        if !schema.required.contains(&name) {
+            schema.required.push(name);
+        }
+    }
+}
+
+fn is_unset<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn convert(value: Value) -> Schema {
+        Schema::from(&schemars::Schema::try_from(value).unwrap())
+    }
+
+    #[test]
+    fn converts_keywords() {
+        let schema = convert(json!({
+            "type": "object",
+            "title": "Task",
+            "properties": {
+                "status": {"enum": ["open", "closed"], "default": "open"},
+                "due": {"type": ["string", "null"], "format": "date-time"},
+                "tags": {"type": "array", "items": {"type": "string", "minLength": 1}, "uniqueItems": true},
+                "count": {"type": "integer", "format": "uint32", "minimum": 0},
+                "point": {"type": "array", "prefixItems": [{"type": "number"}, {"type": "number"}]},
+            },
+            "required": ["status"],
+        }));
+
+        let expected = Schema::new_object()
+            .title("Task")
+            .property(
+                "status",
+                Schema::new_string()
+                    .into_enum(["open", "closed"])
+                    .default_value("open"),
+            )
+            .property(
+                "due",
+                Schema {
+                    format: "date-time".to_owned(),
+                    nullable: true,
+                    ..Schema::new_string()
+                },
+            )
+            .property(
+                "tags",
+                Schema::new_array().items(Schema::new_string().min_length(1)),
+            )
+            .property("count", Schema::new_integer().min_value(0.0))
+            .property(
+                "point",
+                Schema::new_array()
+                    .items(Schema::any_of([Schema::new_number(), Schema::new_number()]))
+                    .min_items(2)
+                    .max_items(2),
+            )
+            .required(["status"]);
+        assert_eq!(schema, expected);
+    }
+
+    #[test]
+    fn inlines_references() {
+        let schema = convert(json!({
+            "type": "object",
+            "properties": {
+                "head": {"$ref": "#/$defs/Node", "description": "First node"},
+                "owner": {"anyOf": [{"$ref": "#/$defs/User"}, {"type": "null"}]},
+            },
+            "$defs": {
+                "Node": {
+                    "type": "object",
+                    "description": "A node",
+                    "properties": {"next": {"$ref": "#/$defs/Node"}},
+                },
+                "User": {"allOf": [
+                    {"type": "object", "properties": {"name": {"type": "string"}}, "required": ["name"]},
+                    {"properties": {"id": {"type": "integer"}}, "required": ["id"]},
+                ]},
+            },
+        }));
+
+        let head = &schema.properties["head"];
+        assert_eq!(head.description, "First node");
+        assert!(head.is_object());
+        // The recursion stops at a schema taking anything
+        assert_eq!(head.properties["next"], Schema::default());
+
+        let owner = &schema.properties["owner"];
+        assert!(owner.nullable);
+        assert!(owner.is_object());
+        assert_eq!(owner.required, ["name", "id"]);
+        assert_eq!(owner.properties["id"], Schema::new_integer());
+    }
+
+    #[test]
+    fn converts_derived() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        enum Kind {
+            Bug,
+            Feature,
+        }
+
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Issue {
+            /// What's wrong
+            title: String,
+            kind: Kind,
+            assignee: Option<String>,
+        }
+
+        let schema = from_json_schema::<Issue>();
+        assert_eq!(schema.properties["title"].description, "What's wrong");
+        assert_eq!(
+            schema.properties["kind"],
+            Schema::new_string().into_enum(["Bug", "Feature"])
+        );
+        assert!(schema.properties["assignee"].nullable);
+        assert_eq!(schema.required, ["title", "kind"]);
+    }
+}
@@ -0,0 +1,195 @@
+//! Fake values for schemas
+//!
+//! For building a UI, or testing code that handles responses, before
+//! spending a request on the API. [`mock`] returns a value of a type's
+//! schema and [`mock_from`] one of any schema, as JSON to deserialize or
+//! display.
+//!
+//! Values are plausible rather than random, and the same every time: the
+//! default if there is one, else the first enum value or alternative, a
+//! string named after its property, numbers near 1, `true`, and one
+//! element per array, each kept within the schema's bounds. Every property
+//! of an object is filled in, optional or not. Patterns aren't followed,
+//! except the digits of [`BigIntString`](super::BigIntString).
+//!
+//! Enabled by the `serde` feature.
+//!
+//! # Example
+//! ```rust
+//! use google_ai_rs::{schema, AsSchema};
+//! use serde::Deserialize;
+//!
+//! #[derive(AsSchema, Deserialize)]
+//! struct Review {
+//!     title: String,
+//!     #[schema(minimum = 1, maximum = 5)]
+//!     stars: u8,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let value = schema::mock::<Review>();
+//! assert_eq!(value["title"], "title");
+//!
+//! let review: Review = serde_json::from_value(value).unwrap();
+//! assert_eq!(review.stars, 1);
+//! assert_eq!(review.tags, ["tags"]);
+//! ```
+
+use serde_json::{Map, Number, Value};
+
+use super::{canonical::from_proto, AsSchema, Schema, SchemaType};
+
+/// Returns a fake value of `T`'s schema
+pub fn mock<T: AsSchema + ?Sized>() -> Value {
+    mock_from(&T::as_schema())
+}
+
+/// Returns a fake value of `schema`
+pub fn mock_from(schema: &Schema) -> Value {
+    value(schema, "")
+}
+
+// `name` is the property the value is for, which strings are named after
+fn value(schema: &Schema, name: &str) -> Value {
+    if let Some(default) = &schema.default {
+        return from_proto(default);
+    }
+    if let Some(first) = schema.any_of.first() {
+        return value(first, name);
+    }
+
+    match schema.r#type() {
+        SchemaType::String => Value::String(string(schema, name)),
+        SchemaType::Integer => Value::from(integer(schema)),
+        SchemaType::Number => number(schema),
+        SchemaType::Boolean => Value::Bool(true),
+        SchemaType::Array => array(schema, name),
+        SchemaType::Object => object(schema),
+        // Any value, or only null
+        SchemaType::Unspecified => Value::Null,
+    }
+}
+
+fn string(schema: &Schema, name: &str) -> String {
+    if let Some(first) = schema.r#enum.first() {
+        return first.clone();
+    }
+    let text = match (schema.format.as_str(), schema.pattern.as_str()) {
+        ("date-time", _) => "2024-03-01T12:00:00Z",
+        (_, "^[0-9]+$" | "^-?[0-9]+$") => "1",
+        _ if name.is_empty() => "text",
+        _ => name,
+    };
+
+    // Repeat the last character up to the minimum length
+    let mut text: String = match usize::try_from(schema.max_length) {
+        Ok(max) if max > 0 => text.chars().take(max).collect(),
+        _ => text.to_owned(),
+    };
+    let len = text.chars().count();
+    if let (Ok(min), Some(last)) = (usize::try_from(schema.min_length), text.chars().last()) {
+        text.extend(std::iter::repeat_n(last, min.saturating_sub(len)));
+    }
+    text
+}
+
+fn integer(schema: &Schema) -> i64 {
+    let mut value = 1;
+    if let Some(maximum) = schema.maximum {
+        value = value.min(maximum.floor() as i64);
+    }
+    if let Some(minimum) = schema.minimum {
+        value = value.max(minimum.ceil() as i64);
+    }
+    value
+}
+
+fn number(schema: &Schema) -> Value {
+    let mut value = 1.0;
+    if let Some(maximum) = schema.maximum {
+        value = f64::min(value, maximum);
+    }
+    if let Some(minimum) = schema.minimum {
+        value = f64::max(value, minimum);
+    }
+    // Unsigned integers have number schemas, and don't deserialize from 1.0
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        return Value::from(value as i64);
+    }
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn array(schema: &Schema, name: &str) -> Value {
+    let Some(items) = &schema.items else {
+        return Value::Array(Vec::new());
+    };
+    let mut len = schema.min_items.max(1);
+    if schema.max_items > 0 {
+        len = len.min(schema.max_items);
+    }
+    let item = value(items, name);
+    Value::Array(vec![item; len as usize])
+}
+
+fn object(schema: &Schema) -> Value {
+    let map: Map<String, Value> = schema
+        .properties
+        .iter()
+        .map(|(name, schema)| (name.clone(), value(schema, name)))
+        .collect();
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn respects_constraints() {
+        let schema = Schema::new_object()
+            .property("status", Schema::new_string().into_enum(["open", "closed"]))
+            .property("code", Schema::new_string().min_length(6).max_length(8))
+            .property(
+                "at",
+                Schema {
+                    format: "date-time".into(),
+                    ..Schema::new_string()
+                },
+            )
+            .property(
+                "count",
+                Schema::new_integer().min_value(3.2).max_value(10.0),
+            )
+            .property("ratio", Schema::new_number().max_value(0.5))
+            .property(
+                "ids",
+                Schema::new_array()
+                    .items(Schema::new_integer())
+                    .min_items(2)
+                    .max_items(3),
+            )
+            .property(
+                "id",
+                Schema::any_of([Schema::new_integer(), Schema::new_string()]),
+            )
+            .property("limit", Schema::new_integer().default_value(20.0))
+            .property("any", Schema::default());
+
+        assert_eq!(
+            mock_from(&schema),
+            json!({
+                "status": "open",
+                "code": "codeee",
+                "at": "2024-03-01T12:00:00Z",
+                "count": 4,
+                "ratio": 0.5,
+                "ids": [1, 1],
+                "id": 1,
+                "limit": 20,
+                "any": null,
+            })
+        );
+        assert_eq!(mock_from(&Schema::new_string().max_length(2)), json!("te"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Serde adapters matching the schemas of time types
+//!
+//! Enabled by the `serde` feature. Serde's own representations of
+//! [`Duration`] and [`SystemTime`] are structs of seconds and nanoseconds,
+//! which isn't what their schemas ask the model for. Point fields at these
+//! modules with `#[serde(with = "...")]`:
+//!
+//! ```rust
+//! use google_ai_rs::{schema::time, AsSchema};
+//! use std::time::{Duration, SystemTime};
+//!
+//! #[derive(AsSchema, serde::Deserialize)]
+//! struct Meeting {
+//!     #[serde(with = "time::rfc3339")]
+//!     starts_at: SystemTime,
+//!     #[serde(with = "time::seconds")]
+//!     length: Duration,
+//! }
+//! # let json = r#"{"starts_at": "2024-05-01T09:30:00Z", "length": 1800}"#;
+//! # let meeting: Meeting = serde_json::from_str(json).unwrap();
+//! # let epoch = SystemTime::UNIX_EPOCH;
+//! # assert_eq!(meeting.starts_at, epoch + Duration::from_secs(1714555800));
+//! # assert_eq!(meeting.length, Duration::from_secs(1800));
+//! ```
+//!
+//! They give the matching schemas too, so `AsSchemaWithSerde` takes both
+//! from `#[schema(with = "...")]`:
+//!
+//! ```rust
+//! use google_ai_rs::{schema::time, AsSchemaWithSerde};
+//! use std::time::SystemTime;
+//!
+//! #[derive(AsSchemaWithSerde)]
+//! struct Reminder {
+//!     #[schema(with = "time::rfc3339")]
+//!     at: SystemTime,
+//! }
+//! # let reminder: Reminder = serde_json::from_str(r#"{"at": "1970-01-01T00:00:00Z"}"#).unwrap();
+//! # assert_eq!(reminder.at, SystemTime::UNIX_EPOCH);
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    civil::{civil_from_days, days_from_civil, days_in_month},
+    AsSchema, Schema,
+};
+
+/// [`Duration`] as a number of seconds, e.g. `90.5`
+pub mod seconds {
+    use super::*;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn as_schema() -> Schema {
+        Duration::as_schema()
+    }
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = f64::deserialize(deserializer)?;
+        Duration::try_from_secs_f64(secs)
+            .map_err(|_| D::Error::custom(format!("{secs} isn't a valid number of seconds")))
+    }
+}
+
+/// [`SystemTime`] as an RFC 3339 timestamp, e.g. `"2024-03-01T12:00:00Z"`
+///
+/// Timestamps are written in UTC. Any offset is accepted when reading.
+pub mod rfc3339 {
+    use super::*;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn as_schema() -> Schema {
+        SystemTime::as_schema()
+    }
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(*time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse(&text)
+            .ok_or_else(|| D::Error::custom(format!("{text:?} isn't an RFC 3339 timestamp")))
+    }
+}
+
+fn format(time: SystemTime) -> String {
+    // Seconds are floored so that nanoseconds stay positive before 1970
+    let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+        Err(err) => {
+            let before = err.duration();
+            match before.subsec_nanos() {
+                0 => (-(before.as_secs() as i64), 0),
+                nanos => (-(before.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+            }
+        }
+    };
+
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let time_of_day = secs.rem_euclid(86400);
+    let mut text = format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60
+    );
+    if nanos != 0 {
+        let fraction = format!("{nanos:09}");
+        text.push('.');
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+    text.push('Z');
+    text
+}
+
+fn parse(text: &str) -> Option<SystemTime> {
+    let bytes = text.as_bytes();
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = bytes.get(range)?;
+        digits
+            .iter()
+            .all(u8::is_ascii_digit)
+            .then(|| std::str::from_utf8(digits).ok()?.parse().ok())?
+    };
+
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if bytes.get(4) != Some(&b'-')
+        || bytes.get(7) != Some(&b'-')
+        || !matches!(bytes.get(10), Some(b'T' | b't' | b' '))
+        || bytes.get(13) != Some(&b':')
+        || bytes.get(16) != Some(&b':')
+    {
+        return None;
+    }
+    // Leap seconds are folded into the next second
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &text[19..];
+    let mut nanos = 0u32;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return None;
+        }
+        // Digits past nanoseconds are dropped
+        let digits = &fraction[..len.min(9)];
+        nanos = digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32);
+        rest = &fraction[len..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes().first()? {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let offset = &bytes[bytes.len() - rest.len()..];
+            if offset.len() != 6 || offset[3] != b':' {
+                return None;
+            }
+            let start = bytes.len() - 6;
+            let (hours, minutes) = (number(start + 1..start + 3)?, number(start + 4..start + 6)?);
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+
+    let secs =
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset;
+    let since_epoch = Duration::new(secs.unsigned_abs(), 0);
+    let time = if secs >= 0 {
+        UNIX_EPOCH.checked_add(since_epoch)?
+    } else {
+        UNIX_EPOCH.checked_sub(since_epoch)?
+    };
+    time.checked_add(Duration::from_nanos(nanos.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_rfc3339() {
+        let cases = [
+            ("1970-01-01T00:00:00Z", 0, 0),
+            ("2024-02-29T23:59:59.5Z", 1709251199, 500_000_000),
+            ("1969-12-31T23:59:59.25Z", -1, 250_000_000),
+            ("2000-03-01T00:00:00.000000001Z", 951868800, 1),
+        ];
+        for (text, secs, nanos) in cases {
+            let secs: i64 = secs;
+            let time = if secs >= 0 {
+                UNIX_EPOCH + Duration::from_secs(secs as u64)
+            } else {
+                UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+            } + Duration::from_nanos(nanos);
+            assert_eq!(format(time), text);
+            assert_eq!(parse(text), Some(time), "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_offsets() {
+        assert_eq!(
+            parse("2024-03-01T14:30:00+02:30"),
+            parse("2024-03-01T12:00:00Z")
+        );
+        assert_eq!(
+            parse("2024-03-01 12:00:00.123456789123-01:00"),
+            parse("2024-03-01T13:00:00.123456789Z")
+        );
+        for text in [
+            "2024-03-01",
+            "2024-13-01T00:00:00Z",
+            "2024-02-31T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-04-31T00:00:00Z",
+            "2024-03-01T00:00:00",
+            "2024-03-01T00:00:00.Z",
+            "2024-03-01T00:00:00+0200",
+        ] {
+            assert_eq!(parse(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn deserializes_seconds() {
+        let duration = seconds::deserialize(serde_json::json!(90.5)).unwrap();
+        assert_eq!(duration, Duration::from_millis(90_500));
+        assert!(seconds::deserialize(serde_json::json!(-1)).is_err());
+    }
+}
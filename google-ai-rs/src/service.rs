@@ -0,0 +1,143 @@
+//! Sending requests through tower middleware
+//!
+//! Enabled by the `tower` feature. [`Client`] and [`SharedClient`]
+//! implement [`tower_service::Service`] for raw
+//! [`GenerateContentRequest`]s, so standard middleware (timeouts,
+//! concurrency limits, load shedding, retries) can wrap the client instead
+//! of the crate growing its own version of each layer.
+//!
+//! Requests still go through the client's own configuration, rate limit,
+//! circuit breaker and failover included. The client doesn't retry, so
+//! failed calls are only retried by a retry layer. Model-level behavior
+//! such as response schema fallbacks and typed parsing is left out; build
+//! the request with
+//! [`GenerativeModel::to_request`](crate::GenerativeModel::to_request) to
+//! keep the model's settings.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tower::{Service, ServiceBuilder, ServiceExt};
+//!
+//! # async fn f(client: google_ai_rs::Client) -> Result<(), tower::BoxError> {
+//! let mut service = ServiceBuilder::new()
+//!     .concurrency_limit(8)
+//!     .timeout(Duration::from_secs(30))
+//!     .service(client.clone());
+//!
+//! let request = client
+//!     .generative_model("gemini-2.5-flash")
+//!     .to_request("Summarize the tower crate in one line")?;
+//! let response = service.ready().await?.call(request).await?;
+//! println!("{}", response.to_text());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tower_service::Service;
+
+use crate::{
+    client::{CClient, Client, SharedClient},
+    error::Error,
+    genai::send,
+    proto::{GenerateContentRequest, GenerateContentResponse},
+};
+
+/// The future returned by the client's [`Service`] impls
+pub type ResponseFuture =
+    Pin<Box<dyn Future<Output = Result<GenerateContentResponse, Error>> + Send + 'static>>;
+
+impl Service<GenerateContentRequest> for Client {
+    type Response = GenerateContentResponse;
+    type Error = Error;
+    type Future = ResponseFuture;
+
+    /// Always ready; the client queues calls on its own limits
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: GenerateContentRequest) -> Self::Future {
+        let client = self.clone();
+        Box::pin(async move { send(&CClient::Borrowed(&client), request).await })
+    }
+}
+
+impl Service<GenerateContentRequest> for SharedClient {
+    type Response = GenerateContentResponse;
+    type Error = Error;
+    type Future = ResponseFuture;
+
+    /// Always ready; the client queues calls on its own limits
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: GenerateContentRequest) -> Self::Future {
+        let client = CClient::Shared(self.clone());
+        Box::pin(async move { send(&client, request).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::{
+        fake::FakeServer,
+        proto::{Candidate, Content},
+    };
+
+    fn assert_service<S>()
+    where
+        S: Service<GenerateContentRequest, Future = ResponseFuture> + Clone + Send + 'static,
+    {
+    }
+
+    #[test]
+    fn clients_are_services() {
+        assert_service::<Client>();
+        assert_service::<SharedClient>();
+    }
+
+    #[tokio::test]
+    async fn calls_generate_content() {
+        // Echoes the model a request was sent for
+        let server = FakeServer::start(|path, body| {
+            assert!(path.ends_with("/GenerateContent"), "{path}");
+            let request = GenerateContentRequest::decode(body).unwrap();
+            let response = GenerateContentResponse {
+                candidates: vec![Candidate {
+                    content: Some(Content::model(request.model)),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            Ok(response.encode_to_vec())
+        })
+        .await;
+        let mut client = server.client().await;
+        let request = client
+            .generative_model("gemini-test")
+            .to_request("hi")
+            .unwrap();
+
+        std::future::poll_fn(|cx| Service::poll_ready(&mut client, cx))
+            .await
+            .unwrap();
+        let response = client.call(request.clone()).await.unwrap();
+        assert_eq!(response.to_text(), "models/gemini-test");
+
+        let mut shared = client.into_shared();
+        let response = shared.call(request).await.unwrap();
+        assert_eq!(response.to_text(), "models/gemini-test");
+        assert_eq!(server.calls(), 2);
+    }
+}
@@ -0,0 +1,281 @@
+//! Shadowing calls to a candidate model
+//!
+//! Before moving to a new model, give the current one a [`Shadow`]: a share
+//! of its generation calls is sent again to the candidate, in the
+//! background, once the primary answered. The caller gets the primary's
+//! response as usual and never waits for the candidate.
+//!
+//! Each pair is compared in a [`ShadowReport`]: both latencies, whether
+//! each response parses as the [`TypedModel`](crate::TypedModel)'s type,
+//! and optionally a [judge](Shadow::judge)'s score. Reports go to
+//! [`on_report`](Shadow::on_report) and, with the `tracing` feature, are
+//! logged.
+//!
+//! Calls are picked at a fixed spread rather than by chance, e.g. every
+//! 10th at 10%. Only calls the primary answered are shadowed; streams
+//! aren't.
+//!
+//! # Example
+#![cfg_attr(feature = "serde", doc = "```rust,no_run")]
+#![cfg_attr(not(feature = "serde"), doc = "```rust,ignore")]
+//! use google_ai_rs::{shadow::Shadow, AsSchema, Client};
+//!
+//! # #[derive(AsSchema, serde::Deserialize)] struct Invoice { total: f64 }
+//! # fn f(client: &Client) {
+//! let model = client
+//!     .typed_model::<Invoice>("gemini-2.5-flash")
+//!     .with_shadow(
+//!         Shadow::new("gemini-3-flash", 10).on_report(|report| {
+//!             if report.candidate_parsed == Some(false) {
+//!                 eprintln!("candidate broke the schema: {report:?}");
+//!             }
+//!         }),
+//!     );
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{
+    client::SharedClient,
+    content::TryFromCandidates,
+    error::Error,
+    genai::send,
+    proto::{Candidate, GenerateContentRequest, GenerateContentResponse},
+};
+
+type Score = Pin<Box<dyn Future<Output = Result<f64, Error>> + Send>>;
+
+type Judge = Arc<
+    dyn Fn(&GenerateContentRequest, &GenerateContentResponse, &GenerateContentResponse) -> Score
+        + Send
+        + Sync,
+>;
+
+/// A candidate model a share of calls is duplicated to
+///
+/// Clones share their call count. See the [module docs](self).
+#[derive(Clone)]
+pub struct Shadow {
+    /// Model shadowed calls go to
+    pub candidate: String,
+    /// Share of calls shadowed, from 0 to 100
+    pub percent: u8,
+    calls: Arc<AtomicU64>,
+    parses: Option<fn(&[Candidate]) -> bool>,
+    judge: Option<Judge>,
+    on_report: Option<ReportHook>,
+}
+
+type ReportHook = Arc<dyn Fn(&ShadowReport) + Send + Sync>;
+
+impl fmt::Debug for Shadow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shadow")
+            .field("candidate", &self.candidate)
+            .field("percent", &self.percent)
+            .field("calls", &self.calls)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Shadow {
+    /// Shadows `percent` of calls, capped at 100, to `candidate`
+    pub fn new(candidate: impl Into<String>, percent: u8) -> Self {
+        Self {
+            candidate: candidate.into(),
+            percent: percent.min(100),
+            calls: Arc::default(),
+            parses: None,
+            judge: None,
+            on_report: None,
+        }
+    }
+
+    /// Scores the candidate's response against the primary's, e.g. by asking
+    /// another model which is better
+    ///
+    /// `judge` is given the request and the primary's and candidate's
+    /// responses. Higher scores should mean a better candidate.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::{shadow::Shadow, SharedClient};
+    /// # fn f(client: SharedClient) {
+    /// let judge = client.generative_model("gemini-2.5-pro");
+    /// let shadow = Shadow::new("gemini-3-flash", 5).judge(move |_, primary, candidate| {
+    ///     let prompt = format!(
+    ///         "Answer A:\n{}\n\nAnswer B:\n{}\n\n\
+    ///          Reply with 1 if B is better, 0 otherwise.",
+    ///         primary.to_text(),
+    ///         candidate.to_text(),
+    ///     );
+    ///     let judge = judge.clone();
+    ///     async move {
+    ///         let verdict = judge.generate_content(prompt).await?;
+    ///         Ok(if verdict.to_text().trim() == "1" { 1.0 } else { 0.0 })
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn judge<F, Fut>(mut self, judge: F) -> Self
+    where
+        F: Fn(&GenerateContentRequest, &GenerateContentResponse, &GenerateContentResponse) -> Fut
+            + Send
+            + Sync
+            + 'static,
+        Fut: Future<Output = Result<f64, Error>> + Send + 'static,
+    {
+        let judge: Judge = Arc::new(
+            move |request: &GenerateContentRequest,
+                  primary: &GenerateContentResponse,
+                  candidate: &GenerateContentResponse|
+                  -> Score { Box::pin(judge(request, primary, candidate)) },
+        );
+        self.judge = Some(judge);
+        self
+    }
+
+    /// Calls `f` with every [`ShadowReport`], e.g. to aggregate them
+    pub fn on_report<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ShadowReport) + Send + Sync + 'static,
+    {
+        self.on_report = Some(Arc::new(f));
+        self
+    }
+
+    /// Returns how many calls were considered for shadowing so far
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Checks responses by parsing them as `T`
+    pub(crate) fn parsing<T: TryFromCandidates>(mut self) -> Self {
+        let parses: fn(&[Candidate]) -> bool =
+            |candidates| T::try_from_candidates(candidates).is_ok();
+        self.parses = Some(parses);
+        self
+    }
+
+    /// Counts a call, returning whether it's shadowed
+    pub(crate) fn sample(&self) -> bool {
+        let percent = u64::from(self.percent);
+        self.calls.fetch_add(1, Ordering::Relaxed) * percent % 100 < percent
+    }
+
+    /// Sends `request` to the candidate in the background and reports how
+    /// its response compares to `primary`
+    pub(crate) fn spawn(
+        &self,
+        client: SharedClient,
+        mut request: GenerateContentRequest,
+        primary: GenerateContentResponse,
+        primary_latency: Duration,
+    ) {
+        let shadow = self.clone();
+        tokio::spawn(async move {
+            let candidate_model = client.model_name(&shadow.candidate);
+            let primary_model = std::mem::replace(&mut request.model, candidate_model.clone());
+
+            let started = Instant::now();
+            let candidate = send(&client.into(), request.clone()).await;
+            let candidate_latency = started.elapsed();
+
+            let parses = |response: &GenerateContentResponse| {
+                shadow.parses.map(|parses| parses(&response.candidates))
+            };
+            let score = match (&shadow.judge, &candidate) {
+                (Some(judge), Ok(candidate)) => Some(judge(&request, &primary, candidate).await),
+                _ => None,
+            };
+            let report = ShadowReport {
+                primary_model,
+                candidate_model,
+                primary_latency,
+                candidate_latency,
+                primary_parsed: parses(&primary),
+                candidate_parsed: candidate.as_ref().ok().and_then(parses),
+                score,
+                candidate,
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::info!(
+                primary = %report.primary_model,
+                candidate = %report.candidate_model,
+                primary_ms = report.primary_latency.as_millis() as u64,
+                candidate_ms = report.candidate_latency.as_millis() as u64,
+                primary_parsed = report.primary_parsed,
+                candidate_parsed = report.candidate_parsed,
+                score = report.score.as_ref().and_then(|s| s.as_ref().ok()),
+                error = report.candidate.as_ref().err().map(tracing::field::display),
+                "shadow comparison"
+            );
+            if let Some(on_report) = &shadow.on_report {
+                on_report(&report);
+            }
+        });
+    }
+}
+
+/// How a candidate's response compared to the primary's for one call
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ShadowReport {
+    /// Full name of the model the caller got the response of
+    pub primary_model: String,
+    /// Full name of the candidate model
+    pub candidate_model: String,
+    /// How long the primary took to answer
+    pub primary_latency: Duration,
+    /// How long the candidate took to answer or fail
+    pub candidate_latency: Duration,
+    /// The candidate's response, or why it failed
+    pub candidate: Result<GenerateContentResponse, Error>,
+    /// Whether the primary's response parsed, if the model is typed
+    pub primary_parsed: Option<bool>,
+    /// Whether the candidate's response parsed, if the model is typed and
+    /// the candidate answered
+    pub candidate_parsed: Option<bool>,
+    /// The [judge](Shadow::judge)'s score, if one is set and the candidate
+    /// answered
+    pub score: Option<Result<f64, Error>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_at_a_fixed_spread() {
+        let picked = |percent| {
+            let shadow = Shadow::new("candidate", percent);
+            (0..20).filter(|_| shadow.sample()).count()
+        };
+        assert_eq!(picked(0), 0);
+        assert_eq!(picked(10), 2);
+        assert_eq!(picked(25), 5);
+        assert_eq!(picked(100), 20);
+        assert_eq!(picked(255), 20);
+
+        let shadow = Shadow::new("candidate", 25);
+        let picked: Vec<_> = (0..8).map(|_| shadow.sample()).collect();
+        assert_eq!(
+            picked,
+            [true, false, false, false, true, false, false, false]
+        );
+        assert_eq!(shadow.clone().calls(), 8);
+    }
+}
@@ -0,0 +1,466 @@
+//! Uploading media with the File API
+//!
+//! Enabled by the `file-upload` feature. Media too large to send inline,
+//! or reused across requests, is uploaded once and referenced by URI with
+//! [`Part::file_data`](crate::Part::file_data).
+//!
+//! Uploads are sent in chunks with Google's resumable upload protocol, so a
+//! progress callback sees each chunk land, and a transient failure resumes
+//! from the last byte the server received instead of starting over. Inline
+//! blobs go out within a single request and report no progress.
+//!
+//! # Example
+//! ```rust,no_run
+//! use google_ai_rs::{Client, Part};
+//!
+//! # async fn f(client: Client, video: Vec<u8>) -> Result<(), google_ai_rs::Error> {
+//! let file = client
+//!     .upload_file("video/mp4", video)
+//!     .display_name("Keynote")
+//!     .on_progress(|p| println!("{:.0}%", p.fraction() * 100.0))
+//!     .send()
+//!     .await?;
+//!
+//! let model = client.generative_model("gemini-2.5-flash");
+//! let summary = model
+//!     .generate_content(("Summarize this talk", Part::file_data(&file.mime_type, &file.uri)))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{error::Error as StdError, fmt, time::Duration};
+
+use crate::{
+    client::Client,
+    error::{Error, ServiceError, SetupError},
+    proto::File,
+};
+
+/// Where resumable uploads start, under the API's endpoint
+const UPLOAD_PATH: &str = "/upload/v1beta/files";
+/// Size of the chunks sent unless set with [`Upload::chunk_size`]
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+/// Chunk sizes must be multiples of this unless the server says otherwise
+const GRANULARITY: usize = 256 * 1024;
+/// Timeout of each request of an upload
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How much of an upload has been received by the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes received so far
+    pub sent: u64,
+    /// Bytes in the upload
+    pub total: u64,
+}
+
+impl Progress {
+    /// Returns the share of bytes received, from 0 to 1
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.sent as f64 / self.total as f64
+    }
+}
+
+/// Reasons the upload service turned down an upload
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum UploadError {
+    /// The service responded with a non-success status
+    Status { code: u16, message: String },
+    /// The service's response lacked something the protocol requires
+    MalformedResponse(&'static str),
+    /// The service kept taking none of a chunk
+    Stalled { offset: u64 },
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::Status { code, message } => {
+                write!(f, "upload failed with status {code}: {message}")
+            }
+            UploadError::MalformedResponse(missing) => {
+                write!(f, "upload response is missing {missing}")
+            }
+            UploadError::Stalled { offset } => {
+                write!(f, "upload made no progress past byte {offset}")
+            }
+        }
+    }
+}
+
+impl StdError for UploadError {}
+
+/// A pending File API upload, made with [`Client::upload_file`]
+pub struct Upload<'c> {
+    client: &'c Client,
+    mime_type: String,
+    data: Vec<u8>,
+    display_name: Option<String>,
+    chunk_size: usize,
+    retries: u32,
+    on_progress: Option<Box<dyn FnMut(Progress) + Send + Sync + 'c>>,
+}
+
+impl fmt::Debug for Upload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upload")
+            .field("mime_type", &self.mime_type)
+            .field("len", &self.data.len())
+            .field("display_name", &self.display_name)
+            .field("chunk_size", &self.chunk_size)
+            .field("retries", &self.retries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Client {
+    /// Starts uploading `data` of type `mime_type` with the File API
+    ///
+    /// Nothing is sent until [`Upload::send`]. The whole file stays in
+    /// memory until the upload finishes, since chunks are resent from it
+    /// after failures, so a file that doesn't fit in memory must be split.
+    ///
+    /// Uploads go to the endpoint calls are currently sent to, so they
+    /// follow the client's [`Failover`](crate::failover::Failover). Calls
+    /// about the file then keep going there.
+    pub fn upload_file(&self, mime_type: &str, data: impl Into<Vec<u8>>) -> Upload<'_> {
+        Upload {
+            client: self,
+            mime_type: mime_type.to_owned(),
+            data: data.into(),
+            display_name: None,
+            chunk_size: CHUNK_SIZE,
+            retries: 3,
+            on_progress: None,
+        }
+    }
+}
+
+/// A request that failed, and whether trying again might succeed
+struct Failure {
+    error: Error,
+    transient: bool,
+}
+
+impl Failure {
+    fn fatal(error: Error) -> Self {
+        Self {
+            error,
+            transient: false,
+        }
+    }
+
+    fn transport(err: reqwest::Error) -> Self {
+        Self {
+            error: SetupError::new("failed to reach the upload service", err),
+            transient: true,
+        }
+    }
+}
+
+/// What the server holds of an upload
+enum Received {
+    /// The first `n` bytes
+    Bytes(usize),
+    /// All of it, as the named file
+    File(String),
+}
+
+impl<'c> Upload<'c> {
+    /// Sets the file's human-readable name
+    pub fn display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.display_name = Some(display_name.into());
+        self
+    }
+
+    /// Sets the size of the chunks sent, 8MiB by default
+    ///
+    /// Rounded down to a multiple of the server's granularity, usually
+    /// 256KiB. Smaller chunks report progress more often but cost more
+    /// requests.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets how many transient failures in a row are retried, 3 by default
+    ///
+    /// Timeouts, connection failures, rate limiting and server errors are
+    /// transient.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Calls `on_progress` after each chunk the server receives
+    pub fn on_progress(mut self, on_progress: impl FnMut(Progress) + Send + Sync + 'c) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Uploads the file, returning it once all bytes are received
+    ///
+    /// Videos and other media may still be processing; see
+    /// [`File::state`](crate::proto::File::state).
+    ///
+    /// Transport failures are reported as [`Error::Setup`]; rejected
+    /// uploads as [`Error::Service`] wrapping an [`UploadError`].
+    pub async fn send(mut self) -> Result<File, Error> {
+        let client = self.client;
+        client.check_writable("upload_file")?;
+        let name = client.track(self.upload()).await??;
+        client.get_file(&name).await
+    }
+
+    /// Runs the upload protocol, returning the file's name
+    async fn upload(&mut self) -> Result<String, Error> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| SetupError::new("upload client", e))?;
+
+        let (region, endpoint) = self.client.regions.active();
+        let upload_url = upload_url(endpoint);
+        let mut failures = 0;
+        let (url, granularity) = loop {
+            match self.start(&http, &upload_url).await {
+                Ok(session) => break session,
+                Err(failure) => self.back_off(failure, &mut failures).await?,
+            }
+        };
+        let chunk_size = (self.chunk_size / granularity).max(1) * granularity;
+
+        let mut offset = 0;
+        loop {
+            let end = (offset + chunk_size).min(self.data.len());
+            let mut attempt = self.send_chunk(&http, &url, offset, end).await;
+            let received = loop {
+                match attempt {
+                    Ok(received) => break received,
+                    Err(failure) => {
+                        self.back_off(failure, &mut failures).await?;
+                        // The chunk may have landed in part, or in full
+                        attempt = self.query(&http, &url).await;
+                    }
+                }
+            };
+
+            match received {
+                Received::Bytes(n) => {
+                    if n > offset {
+                        failures = 0;
+                    } else {
+                        // Counted like a failure, or a server that takes
+                        // nothing would be sent the chunk forever
+                        let stalled = UploadError::Stalled {
+                            offset: offset as u64,
+                        };
+                        let failure = Failure {
+                            error: rejected(stalled),
+                            transient: true,
+                        };
+                        self.back_off(failure, &mut failures).await?;
+                    }
+                    offset = n;
+                    if let Some(on_progress) = &mut self.on_progress {
+                        on_progress(Progress {
+                            sent: n as u64,
+                            total: self.data.len() as u64,
+                        });
+                    }
+                }
+                Received::File(name) => {
+                    if let Some(on_progress) = &mut self.on_progress {
+                        let total = self.data.len() as u64;
+                        on_progress(Progress { sent: total, total });
+                    }
+                    self.client.regions.pin(&name, region);
+                    return Ok(name);
+                }
+            }
+        }
+    }
+
+    /// Opens an upload session, returning its URL and chunk granularity
+    async fn start(
+        &self,
+        http: &reqwest::Client,
+        upload_url: &str,
+    ) -> Result<(String, usize), Failure> {
+        let metadata = match &self.display_name {
+            Some(name) => serde_json::json!({ "file": { "display_name": name } }),
+            None => serde_json::json!({}),
+        };
+        let response = self
+            .request(http, upload_url)
+            .await
+            .header("X-Goog-Upload-Protocol", "resumable")
+            .header("X-Goog-Upload-Command", "start")
+            .header("X-Goog-Upload-Header-Content-Length", self.data.len())
+            .header("X-Goog-Upload-Header-Content-Type", &self.mime_type)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(metadata.to_string())
+            .send()
+            .await
+            .map_err(Failure::transport)?;
+        let response = checked(response).await?;
+
+        let url = header(&response, "x-goog-upload-url")
+            .ok_or_else(|| malformed("the upload URL"))?
+            .to_owned();
+        let granularity = header(&response, "x-goog-upload-chunk-granularity")
+            .and_then(|g| g.parse().ok())
+            .filter(|&g| g > 0)
+            .unwrap_or(GRANULARITY);
+        Ok((url, granularity))
+    }
+
+    /// Sends `data[offset..end]`, finalizing the upload with the last chunk
+    async fn send_chunk(
+        &self,
+        http: &reqwest::Client,
+        url: &str,
+        offset: usize,
+        end: usize,
+    ) -> Result<Received, Failure> {
+        let last = end == self.data.len();
+        let response = self
+            .request(http, url)
+            .await
+            .header(
+                "X-Goog-Upload-Command",
+                if last { "upload, finalize" } else { "upload" },
+            )
+            .header("X-Goog-Upload-Offset", offset)
+            .body(self.data[offset..end].to_vec())
+            .send()
+            .await
+            .map_err(Failure::transport)?;
+        let response = checked(response).await?;
+
+        if last {
+            file_name(response).await.map(Received::File)
+        } else {
+            Ok(Received::Bytes(end))
+        }
+    }
+
+    /// Asks the server how much of the upload it holds
+    async fn query(&self, http: &reqwest::Client, url: &str) -> Result<Received, Failure> {
+        let response = self
+            .request(http, url)
+            .await
+            .header("X-Goog-Upload-Command", "query")
+            .send()
+            .await
+            .map_err(Failure::transport)?;
+        let response = checked(response).await?;
+
+        if header(&response, "x-goog-upload-status") == Some("final") {
+            return file_name(response).await.map(Received::File);
+        }
+        header(&response, "x-goog-upload-size-received")
+            .and_then(|n| n.parse().ok())
+            .filter(|&n| n <= self.data.len())
+            .map(Received::Bytes)
+            .ok_or_else(|| malformed("the size received"))
+    }
+
+    /// Starts an authenticated request to `url`
+    async fn request(&self, http: &reqwest::Client, url: &str) -> reqwest::RequestBuilder {
+        let (name, value) = self.client.auth_header().await;
+        http.post(url).header(name, value)
+    }
+
+    /// Waits before retrying after a transient failure, or gives up
+    async fn back_off(&self, failure: Failure, failures: &mut u32) -> Result<(), Error> {
+        if !failure.transient || *failures >= self.retries {
+            return Err(failure.error);
+        }
+        *failures += 1;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(attempt = *failures, "retrying upload: {}", failure.error);
+        tokio::time::sleep(Duration::from_millis(500) * 2u32.pow(*failures - 1)).await;
+        Ok(())
+    }
+}
+
+/// Fails on non-success statuses, transiently on those worth retrying
+async fn checked(response: reqwest::Response) -> Result<reqwest::Response, Failure> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let transient = status.is_server_error()
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT;
+    let message = response.text().await.unwrap_or_default();
+    Err(Failure {
+        error: rejected(UploadError::Status {
+            code: status.as_u16(),
+            message,
+        }),
+        transient,
+    })
+}
+
+/// Reads the name of the uploaded file from a final response
+async fn file_name(response: reqwest::Response) -> Result<String, Failure> {
+    let body = response.bytes().await.map_err(Failure::transport)?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|_| malformed("the file"))?;
+    body["file"]["name"]
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| malformed("the file name"))
+}
+
+/// The URL resumable uploads start at for the API at `endpoint`
+fn upload_url(endpoint: &str) -> String {
+    format!("{}{UPLOAD_PATH}", endpoint.trim_end_matches('/'))
+}
+
+fn header<'r>(response: &'r reqwest::Response, name: &str) -> Option<&'r str> {
+    response.headers().get(name)?.to_str().ok()
+}
+
+fn malformed(missing: &'static str) -> Failure {
+    Failure::fatal(rejected(UploadError::MalformedResponse(missing)))
+}
+
+fn rejected(err: UploadError) -> Error {
+    Error::Service(ServiceError::InvalidResponse(Box::new(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_fraction() {
+        let progress = Progress {
+            sent: 256,
+            total: 1024,
+        };
+        assert_eq!(progress.fraction(), 0.25);
+        assert_eq!(Progress { sent: 0, total: 0 }.fraction(), 1.0);
+    }
+
+    #[test]
+    fn upload_url_follows_endpoint() {
+        assert_eq!(
+            upload_url("https://generativelanguage.googleapis.com"),
+            "https://generativelanguage.googleapis.com/upload/v1beta/files"
+        );
+        assert_eq!(
+            upload_url("https://eu.example.com/"),
+            "https://eu.example.com/upload/v1beta/files"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Local checks for requests, mirroring the server's rules
+//!
+//! Enabled with [`ClientBuilder::strict_validation`]. The server remains the
+//! authority; these only catch mistakes we know it would reject, before a
+//! round trip and with a precise message.
+//!
+//! [`ClientBuilder::strict_validation`]: crate::client::ClientBuilder::strict_validation
+
+use crate::error::Error;
+use crate::proto::{Content, GenerateContentRequest, GenerationConfig};
+
+/// Checks a generation request
+pub(crate) fn request(request: &GenerateContentRequest) -> Result<(), Error> {
+    if let Some(config) = &request.generation_config {
+        generation_config(config)?;
+    }
+    roles(&request.contents)
+}
+
+fn generation_config(config: &GenerationConfig) -> Result<(), Error> {
+    in_range("temperature", config.temperature, 0.0, 2.0)?;
+    in_range("top_p", config.top_p, 0.0, 1.0)?;
+    at_least("top_k", config.top_k, 1)?;
+    at_least("max_output_tokens", config.max_output_tokens, 1)?;
+    at_least("candidate_count", config.candidate_count, 1)?;
+    at_most("candidate_count", config.candidate_count, 8)?;
+    in_range("presence_penalty", config.presence_penalty, -2.0, 2.0)?;
+    in_range("frequency_penalty", config.frequency_penalty, -2.0, 2.0)?;
+
+    if let Some(logprobs) = config.logprobs {
+        if config.response_logprobs != Some(true) {
+            return Err(invalid(
+                "generation_config.logprobs requires response_logprobs to be set".into(),
+            ));
+        }
+        at_least("logprobs", Some(logprobs), 0)?;
+        at_most("logprobs", Some(logprobs), 20)?;
+    }
+
+    if config.response_schema.is_some()
+        && !matches!(
+            config.response_mime_type.as_str(),
+            "application/json" | "text/x.enum"
+        )
+    {
+        return Err(invalid(format!(
+            "generation_config.response_schema requires response_mime_type \
+             \"application/json\" or \"text/x.enum\", got {:?}",
+            config.response_mime_type
+        )));
+    }
+
+    Ok(())
+}
+
+/// Turns must alternate between the user and the model
+fn roles(contents: &[Content]) -> Result<(), Error> {
+    let mut previous: Option<&str> = None;
+    for (i, content) in contents.iter().enumerate() {
+        // Function responses come from the user's side
+        let side = match content.role.as_str() {
+            "" | "user" | "function" => "user",
+            "model" => "model",
+            role => {
+                return Err(invalid(format!(
+                    "contents[{i}].role must be \"user\" or \"model\", got {role:?}"
+                )))
+            }
+        };
+
+        if previous == Some(side) {
+            return Err(invalid(format!(
+                "contents[{i}] follows another {side} turn; turns must alternate between user and model"
+            )));
+        }
+        previous = Some(side);
+    }
+    Ok(())
+}
+
+fn in_range(field: &str, value: Option<f32>, min: f32, max: f32) -> Result<(), Error> {
+    match value {
+        Some(value) if !(min..=max).contains(&value) => Err(invalid(format!(
+            "generation_config.{field} must be in [{min:?}, {max:?}], got {value}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn at_least(field: &str, value: Option<i32>, min: i32) -> Result<(), Error> {
+    match value {
+        Some(value) if value < min => Err(invalid(format!(
+            "generation_config.{field} must be at least {min}, got {value}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn at_most(field: &str, value: Option<i32>, max: i32) -> Result<(), Error> {
+    match value {
+        Some(value) if value > max => Err(invalid(format!(
+            "generation_config.{field} must be at most {max}, got {value}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidArgument(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::Schema;
+
+    #[test]
+    fn rejects() {
+        let config = |f: fn(&mut GenerationConfig)| {
+            let mut config = GenerationConfig::default();
+            f(&mut config);
+            GenerateContentRequest {
+                generation_config: Some(config),
+                ..Default::default()
+            }
+        };
+
+        let tests = [
+            (
+                config(|c| c.temperature = Some(2.5)),
+                "temperature must be in",
+            ),
+            (config(|c| c.top_p = Some(-0.1)), "top_p must be in"),
+            (config(|c| c.top_k = Some(0)), "top_k must be at least 1"),
+            (
+                config(|c| c.candidate_count = Some(9)),
+                "candidate_count must be at most 8",
+            ),
+            (
+                config(|c| c.logprobs = Some(3)),
+                "requires response_logprobs",
+            ),
+            (
+                config(|c| c.response_schema = Some(Schema::default())),
+                "requires response_mime_type",
+            ),
+            (
+                GenerateContentRequest {
+                    contents: vec![Content::user("a"), Content::user("b")],
+                    ..Default::default()
+                },
+                "contents[1] follows another user turn",
+            ),
+            (
+                GenerateContentRequest {
+                    contents: vec![Content {
+                        role: "system".into(),
+                        parts: vec![],
+                    }],
+                    ..Default::default()
+                },
+                "contents[0].role must be",
+            ),
+        ];
+
+        for (input, error_like) in tests {
+            let err = request(&input).unwrap_err().to_string();
+            assert!(err.contains(error_like), "{err}");
+        }
+    }
+
+    #[test]
+    fn accepts() {
+        let request = GenerateContentRequest {
+            contents: vec![Content::user("a"), Content::model("b"), Content::user("c")],
+            generation_config: Some(GenerationConfig {
+                temperature: Some(2.0),
+                top_p: Some(0.95),
+                top_k: Some(40),
+                candidate_count: Some(1),
+                response_mime_type: "application/json".into(),
+                response_schema: Some(Schema::default()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(super::request(&request).is_ok());
+    }
+}
@@ -0,0 +1,226 @@
+//! Image understanding helpers
+//!
+//! Gemini models can detect objects in images and report their location as
+//! bounding boxes normalized to a `0..=1000` grid, in `[ymin, xmin, ymax, xmax]`
+//! order. This module provides typed, schema-aware representations of those
+//! results and converts them into pixel coordinates.
+//!
+//! # Example
+#![cfg_attr(feature = "serde", doc = "```rust,no_run")]
+#![cfg_attr(not(feature = "serde"), doc = "```rust,ignore")]
+//! use google_ai_rs::{Client, Part};
+//! use google_ai_rs::vision::DetectedObject;
+//!
+//! # async fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! # let image = vec![];
+//! let client = Client::new("YOUR-API-KEY").await?;
+//! let model = client.typed_model::<Vec<DetectedObject>>("gemini-2.0-flash");
+//!
+//! let objects = model
+//!     .generate_content(("Detect all the cars.", Part::blob("image/jpeg", image)))
+//!     .await?;
+//!
+//! for object in &objects {
+//!     let b = object.box_2d.to_pixels(1920, 1080);
+//!     println!("{} at ({}, {}) - ({}, {})", object.label, b.x_min, b.y_min, b.x_max, b.y_max);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::Deserialize;
+
+use crate::{proto::Schema, schema::AsSchema};
+
+/// Upper bound of the normalized coordinate grid used by Gemini.
+pub const NORMALIZED_SCALE: u32 = 1000;
+
+/// A bounding box normalized to a `0..=1000` grid.
+///
+/// It is (de)serialized as `[ymin, xmin, ymax, xmax]`, which is the layout
+/// Gemini uses for its `box_2d` output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "[i32; 4]")]
+pub struct BoundingBox {
+    pub y_min: i32,
+    pub x_min: i32,
+    pub y_max: i32,
+    pub x_max: i32,
+}
+
+impl BoundingBox {
+    /// Converts the normalized box into pixel coordinates of an image with
+    /// the given dimensions.
+    ///
+    /// Coordinates are clamped to the image and reordered if the model
+    /// returned them inverted.
+    ///
+    /// # Example
+    /// ```
+    /// # use google_ai_rs::vision::BoundingBox;
+    /// let b = BoundingBox { y_min: 0, x_min: 250, y_max: 500, x_max: 1000 };
+    /// let p = b.to_pixels(800, 600);
+    /// assert_eq!((p.x_min, p.y_min, p.x_max, p.y_max), (200, 0, 800, 300));
+    /// ```
+    pub fn to_pixels(&self, width: u32, height: u32) -> PixelBox {
+        let scale = |v: i32, dim: u32| -> u32 {
+            let v = v.clamp(0, NORMALIZED_SCALE as i32) as u64;
+            (v * dim as u64 / NORMALIZED_SCALE as u64) as u32
+        };
+
+        let (x0, x1) = (scale(self.x_min, width), scale(self.x_max, width));
+        let (y0, y1) = (scale(self.y_min, height), scale(self.y_max, height));
+
+        PixelBox {
+            x_min: x0.min(x1),
+            y_min: y0.min(y1),
+            x_max: x0.max(x1),
+            y_max: y0.max(y1),
+        }
+    }
+}
+
+impl From<[i32; 4]> for BoundingBox {
+    fn from([y_min, x_min, y_max, x_max]: [i32; 4]) -> Self {
+        Self {
+            y_min,
+            x_min,
+            y_max,
+            x_max,
+        }
+    }
+}
+
+impl From<BoundingBox> for [i32; 4] {
+    fn from(b: BoundingBox) -> Self {
+        [b.y_min, b.x_min, b.y_max, b.x_max]
+    }
+}
+
+impl AsSchema for BoundingBox {
+    fn as_schema() -> Schema {
+        let mut schema = <[i32; 4]>::as_schema()
+            .description("Bounding box as [ymin, xmin, ymax, xmax], normalized to 0-1000.");
+        schema.nullable = false;
+        schema
+    }
+}
+
+/// A bounding box in pixel coordinates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PixelBox {
+    pub x_min: u32,
+    pub y_min: u32,
+    pub x_max: u32,
+    pub y_max: u32,
+}
+
+impl PixelBox {
+    /// Width of the box in pixels.
+    pub fn width(&self) -> u32 {
+        self.x_max - self.x_min
+    }
+
+    /// Height of the box in pixels.
+    pub fn height(&self) -> u32 {
+        self.y_max - self.y_min
+    }
+}
+
+/// An object detected in an image.
+///
+/// Embed it in your own response types or request `Vec<DetectedObject>`
+/// directly from a [`TypedModel`](crate::TypedModel).
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, crate::AsSchema)]
+#[schema(crate_path = "crate")]
+#[schema(description = "An object detected in an image")]
+pub struct DetectedObject {
+    /// Location of the object.
+    pub box_2d: BoundingBox,
+    /// Label describing the object.
+    #[schema(description = "A short label describing the object")]
+    pub label: String,
+    /// Segmentation mask, when requested.
+    ///
+    /// Gemini encodes it as a base64 PNG probability map covering the
+    /// bounding box. It is kept as-is; decode it with your image library.
+    #[serde(default)]
+    #[schema(description = "Base64 encoded PNG segmentation mask of the object")]
+    pub mask: Option<String>,
+}
+
+impl DetectedObject {
+    /// Converts the object's bounding box into pixel coordinates.
+    ///
+    /// See [`BoundingBox::to_pixels`].
+    pub fn to_pixels(&self, width: u32, height: u32) -> PixelBox {
+        self.box_2d.to_pixels(width, height)
+    }
+}
+
+/// Parses detected objects from the text of a model response.
+///
+/// Unlike plain JSON deserialization, this tolerates the Markdown code fences
+/// models tend to wrap their output in when no response schema is set.
+///
+/// # Example
+/// ```
+/// # use google_ai_rs::vision::parse_detections;
+/// let text = "```json\n[{\"box_2d\": [10, 20, 30, 40], \"label\": \"cat\"}]\n```";
+/// let objects = parse_detections(text).unwrap();
+/// assert_eq!(objects[0].label, "cat");
+/// assert_eq!(objects[0].box_2d.x_min, 20);
+/// ```
+#[cfg(feature = "serde")]
+pub fn parse_detections(text: &str) -> Result<Vec<DetectedObject>, crate::Error> {
+    serde_json::from_str(strip_code_fence(text)).map_err(|err| {
+        crate::Error::Service(crate::error::ServiceError::InvalidResponse(err.into()))
+    })
+}
+
+#[cfg(feature = "serde")]
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+
+    // Skip the language tag
+    let rest = rest.split_once('\n').map_or("", |(_, body)| body);
+    rest.trim_end().strip_suffix("```").unwrap_or(rest).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_pixels() {
+        let tests = [
+            // (normalized, (w, h), want)
+            ([0, 0, 1000, 1000], (640, 480), (0, 0, 640, 480)),
+            ([500, 500, 1000, 1000], (640, 480), (320, 240, 640, 480)),
+            // inverted and out of range
+            ([1200, 1000, -5, 0], (100, 100), (0, 0, 100, 100)),
+        ];
+
+        for (normalized, (w, h), want) in tests {
+            let p = BoundingBox::from(normalized).to_pixels(w, h);
+            assert_eq!((p.x_min, p.y_min, p.x_max, p.y_max), want);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn strip_code_fence() {
+        let tests = [
+            ("[]", "[]"),
+            ("```json\n[]\n```", "[]"),
+            ("  ```\n[1]```  ", "[1]"),
+        ];
+
+        for (input, want) in tests {
+            assert_eq!(super::strip_code_fence(input), want);
+        }
+    }
+}
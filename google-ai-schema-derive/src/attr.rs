@@ -25,9 +25,16 @@ use std::{
     str::FromStr,
 };
 
-use case::Case;
+pub(crate) use case::Case;
 use proc_macro2::Span;
-use syn::{meta::ParseNestedMeta, parse::Parse, Attribute, Error};
+
+use crate::diagnostics::Diagnostic;
+use syn::{
+    meta::ParseNestedMeta,
+    parse::{discouraged::Speculative, Parse},
+    punctuated::Punctuated,
+    Attribute, Error,
+};
 
 // see as a method on SetAttr
 macro_rules! get_attrs {
@@ -51,31 +58,37 @@ macro_rules! get_attrs {
                         let s_attr = s_attr.as_str();
 
                         if !$set.is_finding && $set.is_disallowed(&s_attr) {
-                            return Err(meta.error(format!(
-                                "Disallowed schema attribute {s_attr}. Allowed attributes include: {}",
+                            return Err(meta.error($set.message(Diagnostic::DisallowedAttribute, format!(
+                                "Disallowed {} attribute {s_attr}. Allowed attributes include: {}",
+                                $set.owner,
                                 $set.attr_for_error(&mut [$(get_attrs!(@unwrap_or $($attr_as)?, stringify!($attr))),*])
-                            )))
+                            ))))
                         }
 
                         $(
                             if s_attr == get_attrs!(@unwrap_or $($attr_as)?, stringify!($attr)) {
                                 $attr.0 = ($attr.1)($attr.0.take(), &meta).map_err(|err| {
+                                    // Nested attributes, like `items(...)`, have their own
+                                    if err.to_string().starts_with("schema[") {
+                                        return err;
+                                    }
                                     // FIXME
                                     // let mut prefix = meta.error(format!("Schema attribute {s_attr}: "));
                                     // prefix.combine(err);
                                     // prefix
-                                    let msg = format!("Schema attribute {s_attr}: {err}");
-                                    Error::new(err.span(), msg)
+                                    let msg = format!("{} attribute {s_attr}: {err}", $set.owner);
+                                    Error::new(err.span(), $set.message(Diagnostic::InvalidAttributeValue, msg))
                                 })?;
                                 return Ok(())
                             }
                         )*
 
                         if !$set.is_finding {
-                            Err(meta.error(format!(
-                                "Unsupported schema attribute {s_attr}. Valid attributes include: {}",
+                            Err(meta.error($set.message(Diagnostic::UnsupportedAttribute, format!(
+                                "Unsupported {} attribute {s_attr}. Valid attributes include: {}",
+                                $set.owner,
                                 $set.attr_for_error(&mut [$(get_attrs!(@unwrap_or $($attr_as)?, stringify!($attr))),*])
-                            )))
+                            ))))
                         } else {
                             Ok(())
                         }
@@ -107,69 +120,282 @@ macro_rules! get_attrs {
 /// Top-level type attributes for schema generation
 #[derive(Default)]
 pub(crate) struct TopAttr {
+    pub(crate) title: Option<String>,
+    /// Titles the type and its fields after their identifiers
+    pub(crate) auto_title: bool,
+    /// Records field declaration order in `property_ordering`
+    pub(crate) ordered: bool,
+    /// Leaves fields out of `required` unless they say otherwise
+    pub(crate) all_optional: bool,
+    /// Silences warnings about schemas the API may find too large
+    pub(crate) ignore_limits: bool,
     pub(crate) description: Option<String>,
+    /// Path of a `&str` constant giving the description
+    pub(crate) description_from: Option<syn::ExprPath>,
+    pub(crate) example: Option<String>,
     pub(crate) rename_all: Option<Case>,
     pub(crate) rename_all_with: Option<syn::ExprPath>,
+    /// Renames the fields of every struct variant
+    pub(crate) rename_all_fields: Option<Case>,
     pub(crate) crate_path: Option<syn::Path>,
     pub(crate) nullable: Option<bool>,
-    pub(crate) ignore_serde: Option<bool>,
+    /// Only for tuple structs, which are represented as arrays
+    pub(crate) min_items: Option<Spanned<i64>>,
+    pub(crate) max_items: Option<Spanned<i64>>,
+    /// How a tuple struct of several types is represented
+    pub(crate) tuple: Option<Spanned<TupleRepr>>,
+    /// How many levels a recursive type is expanded to
+    pub(crate) max_depth: Option<Spanned<usize>>,
+    /// Field naming the variant of an internally tagged enum
+    pub(crate) tag: Option<Spanned<String>>,
+    /// Field holding the data of an adjacently tagged enum
+    pub(crate) content: Option<Spanned<String>>,
+    /// Whether an enum is any of its variants' data, untagged
+    pub(crate) untagged: Option<Spanned<bool>>,
+    /// Whether a data enum is any of its variants' objects, one per variant
+    pub(crate) one_of: Option<Spanned<bool>>,
+    /// How a data-less enum's variants are represented
+    pub(crate) repr: Option<Spanned<Repr>>,
+    /// Whether a struct is represented as its only field
+    pub(crate) transparent: bool,
+    /// Function giving the whole type's schema in place of the derived one
+    pub(crate) as_schema: Option<syn::ExprPath>,
+    /// Strings the whole type is one of, in place of the derived schema
+    pub(crate) enum_values: Option<Spanned<Vec<String>>>,
+    /// Where predicates of the impl in place of the inferred ones
+    pub(crate) bound: Option<Bound>,
+    pub(crate) ignore_serde: bool,
+    /// serde's own `rename_all`, kept apart to check that names agree
+    pub(crate) serde_rename_all: Option<Case>,
+    pub(crate) serde_rename_all_fields: Option<Case>,
+    /// Whether any serde attribute is present on the type, its fields or variants
+    pub(crate) has_serde: bool,
+    /// `#[serde(default)]` on the container, defaulting every field
+    pub(crate) serde_default: bool,
 }
 
-pub(crate) fn parse_top(attrs: &[Attribute]) -> Result<TopAttr, Error> {
-    let attrs = SetAttr::new(attrs);
+pub(crate) fn parse_top(input: &syn::DeriveInput) -> Result<TopAttr, Error> {
+    let attrs = SetAttr::new(&input.attrs);
 
     let rename_all_attr = new_attr::<syn::LitStr, Case>();
     get_attrs! {
         attrs => {
+            let title = new_attr::<syn::LitStr, String>();
+            let auto_title = new_attr_bool();
+            let ordered = new_attr_bool();
+            let all_optional = new_attr_bool();
+            let ignore_limits = new_attr_bool();
             let description = new_attr_string_concat();
+            let description_from = new_attr_expr_path();
+            let example = new_attr_lit_text();
             let rename_all = rename_all_attr;
             let rename_all_with = new_attr_expr_path();
+            let rename_all_fields = rename_all_attr;
             let crate_path = new_attr_path();
             let nullable = new_attr_bool();
+            let min_items;
+            let max_items;
+            let tuple;
+            let max_depth;
+            let tag;
+            let content;
+            let untagged = new_attr::<syn::LitBool, Spanned<bool>>();
+            let one_of = new_attr::<syn::LitBool, Spanned<bool>>();
+            let repr;
+            let transparent = new_attr_bool();
+            let as_schema = new_attr_expr_path();
+            let enum_values = new_attr_enum_values();
+            let bound = new_attr::<syn::LitStr, Bound>();
             let ignore_serde = new_attr_bool();
         }
     }
 
+    check_description(&description, &description_from)?;
+
+    // Opting out is per type, so a dependency can't change how another
+    // crate's types are derived
+    let ignore_serde = ignore_serde.unwrap_or_default();
+
     let mut any_rename_all = rename_all;
+    let mut serde_rename_all = None;
+    let mut any_rename_all_fields = rename_all_fields;
+    let mut serde_rename_all_fields = None;
+    let mut tag = tag;
+    let mut content = content;
+    let mut untagged = untagged;
+    let mut transparent = transparent;
+    let mut serde_default = false;
 
-    if ignore_serde.is_none_or(|ignore_serde| !ignore_serde) && any_rename_all.is_none() {
-        // let's use serde's rename
+    if !ignore_serde {
         let attrs = attrs.switch_to_serde();
         get_attrs! {
             attrs => {
                 let rename_all = rename_all_attr;
+                let rename_all_fields = rename_all_attr;
+                let serde_tag as "tag" = new_attr();
+                let serde_content as "content" = new_attr();
+                let serde_untagged as "untagged" = new_attr::<syn::LitBool, Spanned<bool>>();
+                let serde_transparent as "transparent" = new_attr_bool();
+                let default = new_attr::<syn::LitStr, Present>();
             }
         }
-        any_rename_all = rename_all;
+        if transparent.is_none() {
+            transparent = serde_transparent;
+        }
+        serde_rename_all = rename_all;
+        serde_rename_all_fields = rename_all_fields;
+        serde_default = default.is_some();
+
+        if untagged.is_none() {
+            untagged = serde_untagged;
+        }
+        if tag.is_none() {
+            tag = serde_tag;
+            if content.is_none() {
+                content = serde_content;
+            }
+        }
+
+        if any_rename_all.is_none() {
+            // let's use serde's rename
+            any_rename_all = serde_rename_all;
+        }
+        if any_rename_all_fields.is_none() {
+            any_rename_all_fields = serde_rename_all_fields;
+        }
     }
 
     Ok(TopAttr {
+        title,
+        auto_title: auto_title.unwrap_or_default(),
+        ordered: ordered.unwrap_or_default(),
+        all_optional: all_optional.unwrap_or_default(),
+        ignore_limits: ignore_limits.unwrap_or_default(),
         description,
+        description_from,
+        example,
         rename_all: any_rename_all,
         rename_all_with,
+        rename_all_fields: any_rename_all_fields,
         crate_path,
         nullable,
+        min_items,
+        max_items,
+        tuple,
+        max_depth,
+        tag,
+        content,
+        untagged,
+        one_of,
+        repr,
+        transparent: transparent.unwrap_or_default(),
+        as_schema,
+        enum_values,
+        bound,
         ignore_serde,
+        serde_rename_all,
+        serde_rename_all_fields,
+        has_serde: has_serde_attr(input),
+        serde_default,
     })
 }
 
+fn has_serde_attr(input: &syn::DeriveInput) -> bool {
+    let is_serde = |attrs: &[Attribute]| attrs.iter().any(|attr| attr.path().is_ident("serde"));
+    let fields_have_serde = |fields: &syn::Fields| fields.iter().any(|f| is_serde(&f.attrs));
+
+    is_serde(&input.attrs)
+        || match &input.data {
+            syn::Data::Struct(data) => fields_have_serde(&data.fields),
+            syn::Data::Enum(data) => data
+                .variants
+                .iter()
+                .any(|v| is_serde(&v.attrs) || fields_have_serde(&v.fields)),
+            syn::Data::Union(_) => false,
+        }
+}
+
 #[derive(Debug, Default, PartialEq)]
 pub(crate) struct Attr {
+    pub(crate) title: Option<String>,
     pub(crate) description: Option<String>,
+    /// Path of a `&str` constant giving the description
+    pub(crate) description_from: Option<syn::ExprPath>,
+    pub(crate) example: Option<String>,
     pub(crate) format: Option<Spanned<Format>>,
     pub(crate) r#type: Option<Spanned<Type>>,
     pub(crate) as_schema: Option<syn::ExprPath>,
     pub(crate) as_schema_generic: Option<syn::ExprPath>,
+    /// Module giving the field's schema, as `as_schema`, and deserializer
+    pub(crate) with: Option<syn::ExprPath>,
     pub(crate) rename: Option<String>,
     pub(crate) required: Option<bool>,
-    pub(crate) min_items: Option<i64>,
-    pub(crate) max_items: Option<i64>,
+    pub(crate) min_items: Option<Spanned<i64>>,
+    pub(crate) max_items: Option<Spanned<i64>>,
+    /// Only for numbers
+    pub(crate) minimum: Option<Spanned<f64>>,
+    pub(crate) maximum: Option<Spanned<f64>>,
+    /// Only for strings
+    pub(crate) min_length: Option<Spanned<i64>>,
+    pub(crate) max_length: Option<Spanned<i64>>,
+    pub(crate) pattern: Option<Spanned<String>>,
+    /// Only for integers, represented as strings of digits
+    pub(crate) big_int_as_string: Option<bool>,
+    /// Represents the item as a string, parsed with `FromStr`
+    pub(crate) as_string: Option<bool>,
+    /// Takes the schema from `schemars::JsonSchema`
+    pub(crate) via_schemars: Option<bool>,
+    /// Attributes of a list's elements, from `items(...)`
+    pub(crate) items: Option<Box<Attr>>,
+    /// Strings a string item is one of
+    pub(crate) enum_values: Option<Spanned<Vec<String>>>,
+    /// Former names of a variant, from `alias` here or in serde
+    pub(crate) aliases: Vec<String>,
+    pub(crate) default: Option<DefaultValue>,
+    /// `#[serde(default)]`, whatever the default is
+    pub(crate) serde_default: bool,
+    /// `#[serde(skip_serializing_if)]`, leaving the field out of some values
+    pub(crate) serde_skip_serializing_if: bool,
+    /// `#[serde(with)]` or `deserialize_with`, reading the field its own way
+    pub(crate) serde_with: bool,
     pub(crate) nullable: Option<bool>,
     pub(crate) skip: Option<bool>,
+    /// Renames the fields of a struct variant
+    pub(crate) rename_all: Option<Case>,
+    pub(crate) rename_all_with: Option<syn::ExprPath>,
+    /// What serde will name the item, if it renames it explicitly
+    pub(crate) serde_rename: Option<String>,
+    pub(crate) serde_rename_all: Option<Case>,
 }
 
 pub(crate) fn parse_field(attrs: &[Attribute], ignore_serde: bool) -> Result<Attr, Error> {
-    parse_item(attrs, ignore_serde, None)
+    parse_item(
+        attrs,
+        ignore_serde,
+        Some(&["rename_all", "rename_all_with", "alias"]),
+    )
+}
+
+pub(crate) fn parse_variant(attrs: &[Attribute], ignore_serde: bool) -> Result<Attr, Error> {
+    parse_item(
+        attrs,
+        ignore_serde,
+        Some(&[
+            "title",
+            "minimum",
+            "maximum",
+            "min_length",
+            "max_length",
+            "pattern",
+            "big_int_as_string",
+            "as_string",
+            "via_schemars",
+            "enum_values",
+            "example",
+            "default",
+            "with",
+        ]),
+    )
 }
 
 pub(crate) fn parse_plain_enum(attrs: &[Attribute], ignore_serde: bool) -> Result<Attr, Error> {
@@ -177,21 +403,69 @@ pub(crate) fn parse_plain_enum(attrs: &[Attribute], ignore_serde: bool) -> Resul
         attrs,
         ignore_serde,
         Some(&[
+            "title",
             "description",
+            "description_from",
+            "example",
             "format",
             "r#type",
             "as_schema",
             "as_schema_generic",
+            "with",
             "min_items",
             "max_items",
+            "minimum",
+            "maximum",
+            "min_length",
+            "max_length",
+            "pattern",
+            "big_int_as_string",
+            "as_string",
+            "via_schemars",
+            "items",
+            "enum_values",
+            "default",
             "required",
             "nullable",
+            "rename_all",
+            "rename_all_with",
         ]),
     )
 }
 
 pub(crate) fn parse_tuple(attrs: &[Attribute], ignore_serde: bool) -> Result<Attr, Error> {
-    parse_item(attrs, ignore_serde, Some(&["rename"]))
+    parse_item(
+        attrs,
+        ignore_serde,
+        Some(&[
+            "rename",
+            "alias",
+            "rename_all",
+            "rename_all_with",
+            "default",
+        ]),
+    )
+}
+
+// The elements of a list have no name, no field of their own to default,
+// skip or deserialize another way, and no fields to rename
+fn parse_list_items(attrs: &[Attribute]) -> Result<Attr, Error> {
+    parse_item(
+        attrs,
+        true,
+        Some(&[
+            "rename",
+            "alias",
+            "required",
+            "default",
+            "skip",
+            "with",
+            "as_string",
+            "via_schemars",
+            "rename_all",
+            "rename_all_with",
+        ]),
+    )
 }
 
 fn parse_item(
@@ -205,61 +479,323 @@ fn parse_item(
     }
 
     let rename_attr = new_attr();
+    let alias_attr = new_attr_list::<syn::LitStr, String>();
     let skip_attr = new_attr_bool();
+    let rename_all_attr = new_attr::<syn::LitStr, Case>();
     get_attrs! {
         attrs => {
+            let title = new_attr::<syn::LitStr, String>();
             let description = new_attr_string_concat();
+            let description_from = new_attr_expr_path();
+            let example = new_attr_lit_text();
             let format;
             let r#type;
             let as_schema = new_attr_expr_path();
             let as_schema_generic = new_attr_expr_path();
+            let with = new_attr_expr_path();
             let rename = rename_attr;
+            let alias = alias_attr;
             let required = new_attr_bool();
             let min_items;
             let max_items;
+            let minimum = new_attr::<syn::Lit, Spanned<f64>>();
+            let maximum = new_attr::<syn::Lit, Spanned<f64>>();
+            let min_length;
+            let max_length;
+            let pattern = new_attr::<syn::LitStr, Spanned<String>>();
+            let big_int_as_string = new_attr_bool();
+            let as_string = new_attr_bool();
+            let via_schemars = new_attr_bool();
+            let items = new_attr_items();
+            let enum_values = new_attr_enum_values();
+            let default = new_attr::<syn::Lit, DefaultValue>();
             let nullable = new_attr_bool();
             let skip = skip_attr;
+            let rename_all = rename_all_attr;
+            let rename_all_with = new_attr_expr_path();
         }
     }
 
+    check_description(&description, &description_from)?;
+
     let mut any_rename = rename;
+    let mut aliases = alias.unwrap_or_default();
     let mut any_skip = skip;
+    let mut serde_rename = None;
+    let mut serde_rename_all = None;
+    let mut serde_default = false;
+    let mut serde_skip_serializing_if = false;
+    let mut serde_with = false;
+
+    // The module's `as_schema` stands in for the field's
+    let mut as_schema = as_schema;
+    if let Some(with) = &with {
+        if as_schema.is_some() || as_schema_generic.is_some() {
+            return Err(Error::new_spanned(
+                with,
+                Diagnostic::ConflictingWith.message(
+                    "`with` gives the schema through the module's `as_schema`, so it can't be \
+                     combined with `as_schema` or `as_schema_generic`",
+                ),
+            ));
+        }
+        let mut path = with.clone();
+        path.path.segments.push(syn::parse_quote!(as_schema));
+        as_schema = Some(path);
+    }
 
     if !ignore_serde {
-        let get_rename = any_rename.is_none() && !attrs.is_disallowed(&"rename");
-        let get_skip = any_skip.is_none() && !attrs.is_disallowed(&"skip");
-
-        if get_rename || get_skip {
-            attrs = attrs.switch_to_serde();
-            get_attrs! {
-                attrs => {
-                    let rename = rename_attr;
-                    let skip = skip_attr;
-                }
-            };
+        attrs = attrs.switch_to_serde();
+        get_attrs! {
+            attrs => {
+                let rename = rename_attr;
+                let alias = alias_attr;
+                let skip = skip_attr;
+                let skip_deserializing = skip_attr;
+                let rename_all = rename_all_attr;
+                let serde_default_attr as "default" = new_attr::<syn::LitStr, Present>();
+                let skip_serializing_if = new_attr::<syn::LitStr, Present>();
+                let serde_with_attr as "with" = new_attr::<syn::LitStr, Present>();
+                let deserialize_with = new_attr::<syn::LitStr, Present>();
+            }
+        };
+        serde_with = serde_with_attr.is_some() || deserialize_with.is_some();
+
+        if !attrs.is_disallowed(&"default") {
+            serde_default = serde_default_attr.is_some();
+        }
+        serde_skip_serializing_if = skip_serializing_if.is_some();
 
-            if get_rename {
-                any_rename = rename;
+        if !attrs.is_disallowed(&"rename") {
+            serde_rename = rename;
+            if any_rename.is_none() {
+                any_rename = serde_rename.clone();
             }
+        }
 
-            if get_skip {
-                any_skip = skip
+        if !attrs.is_disallowed(&"alias") {
+            for alias in alias.into_iter().flatten() {
+                if !aliases.contains(&alias) {
+                    aliases.push(alias)
+                }
             }
         }
+
+        // Fields serde never reads are filled in from their default, so
+        // the model has no say in them
+        if any_skip.is_none() && !attrs.is_disallowed(&"skip") {
+            any_skip = skip.or(skip_deserializing)
+        }
+
+        if !attrs.is_disallowed(&"rename_all") {
+            serde_rename_all = rename_all;
+        }
     }
 
     Ok(Attr {
+        title,
         description,
+        description_from,
+        example,
         format,
         r#type,
         as_schema,
         as_schema_generic,
+        with,
         rename: any_rename,
         required,
         min_items,
         max_items,
+        minimum,
+        maximum,
+        min_length,
+        max_length,
+        pattern,
+        big_int_as_string,
+        as_string,
+        via_schemars,
+        items,
+        enum_values,
+        aliases,
+        default,
+        serde_default,
+        serde_skip_serializing_if,
+        serde_with,
         nullable,
         skip: any_skip,
+        rename_all,
+        rename_all_with,
+        serde_rename,
+        serde_rename_all,
+    })
+}
+
+// Both give the whole description, which can't be added to a constant's at
+// compile time
+fn check_description(
+    description: &Option<String>,
+    description_from: &Option<syn::ExprPath>,
+) -> Result<(), Error> {
+    match description_from {
+        Some(description_from) if description.is_some() => Err(Error::new_spanned(
+            description_from,
+            Diagnostic::ConflictingDescription
+                .message("description and description_from can't be both set"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// `#[embed(...)]` attributes of a field in the `EmbedInput` derive
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct EmbedAttr {
+    pub(crate) title: Option<bool>,
+    pub(crate) skip: Option<bool>,
+}
+
+pub(crate) fn parse_embed_top(attrs: &[Attribute]) -> Result<Option<syn::Path>, Error> {
+    let attrs = SetAttr::new(attrs).owner("embed");
+    get_attrs! {
+        attrs => {
+            let crate_path = new_attr_path();
+        }
+    }
+    Ok(crate_path)
+}
+
+pub(crate) fn parse_embed_field(attrs: &[Attribute]) -> Result<EmbedAttr, Error> {
+    let attrs = SetAttr::new(attrs).owner("embed");
+    get_attrs! {
+        attrs => {
+            let title = new_attr_bool();
+            let skip = new_attr_bool();
+        }
+    }
+    Ok(EmbedAttr { title, skip })
+}
+
+/// Role of the content a field of the `IntoContents` derive goes into
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Role {
+    #[default]
+    User,
+    Model,
+}
+
+impl Role {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Model => "model",
+        }
+    }
+}
+
+impl TryFromParse<syn::LitStr> for Role {
+    fn try_from_parse(parse: syn::LitStr) -> Result<Self, Error> {
+        match parse.value().as_str() {
+            "user" => Ok(Role::User),
+            "model" => Ok(Role::Model),
+            value => Err(Error::new(
+                parse.span(),
+                unknown_one_of_error(value, &mut ["user", "model"], "Role"),
+            )),
+        }
+    }
+}
+
+/// Representation of a data-less enum
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Repr {
+    /// Variant names, as serde writes them
+    #[default]
+    String,
+    /// Discriminants, as `serde_repr` writes them
+    Integer,
+}
+
+impl TryFromParse<syn::LitStr> for Repr {
+    fn try_from_parse(parse: syn::LitStr) -> Result<Self, Error> {
+        match parse.value().as_str() {
+            "string" => Ok(Repr::String),
+            "integer" => Ok(Repr::Integer),
+            value => Err(Error::new(
+                parse.span(),
+                unknown_one_of_error(value, &mut ["string", "integer"], "Repr"),
+            )),
+        }
+    }
+}
+
+/// Representation of a tuple struct whose fields have different types
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TupleRepr {
+    /// An array of unspecified items, with the fields' descriptions listed
+    /// in the array's
+    Array,
+}
+
+impl TryFromParse<syn::LitStr> for TupleRepr {
+    fn try_from_parse(parse: syn::LitStr) -> Result<Self, Error> {
+        match parse.value().as_str() {
+            "array" => Ok(TupleRepr::Array),
+            value => Err(Error::new(
+                parse.span(),
+                unknown_one_of_error(value, &mut ["array"], "TupleRepr"),
+            )),
+        }
+    }
+}
+
+pub(crate) struct ContentTopAttr {
+    pub(crate) crate_path: Option<syn::Path>,
+    pub(crate) role: Option<Role>,
+}
+
+/// `#[content(...)]` attributes of a field in the `IntoContents` derive
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ContentAttr {
+    pub(crate) role: Option<Role>,
+    pub(crate) prefix: Option<String>,
+    pub(crate) order: Option<i64>,
+    pub(crate) before: Option<Spanned<String>>,
+    pub(crate) system: Option<bool>,
+    pub(crate) skip_if_empty: Option<bool>,
+    pub(crate) skip: Option<bool>,
+}
+
+pub(crate) fn parse_content_top(attrs: &[Attribute]) -> Result<ContentTopAttr, Error> {
+    let attrs = SetAttr::new(attrs).owner("content");
+    get_attrs! {
+        attrs => {
+            let crate_path = new_attr_path();
+            let role;
+        }
+    }
+    Ok(ContentTopAttr { crate_path, role })
+}
+
+pub(crate) fn parse_content_field(attrs: &[Attribute]) -> Result<ContentAttr, Error> {
+    let attrs = SetAttr::new(attrs).owner("content");
+    get_attrs! {
+        attrs => {
+            let role;
+            let prefix = new_attr::<syn::LitStr, String>();
+            let order;
+            let before = new_attr::<syn::LitStr, Spanned<String>>();
+            let system = new_attr_bool();
+            let skip_if_empty = new_attr_bool();
+            let skip = new_attr_bool();
+        }
+    }
+    Ok(ContentAttr {
+        role,
+        prefix,
+        order,
+        before,
+        system,
+        skip_if_empty,
+        skip,
     })
 }
 
@@ -299,6 +835,91 @@ impl TryFromParse<syn::LitInt> for i64 {
     }
 }
 
+impl TryFromParse<syn::LitInt> for usize {
+    fn try_from_parse(parse: syn::LitInt) -> Result<Self, Error> {
+        parse.base10_parse()
+    }
+}
+
+impl TryFromParse<syn::Lit> for f64 {
+    fn try_from_parse(parse: syn::Lit) -> Result<Self, Error> {
+        match parse {
+            syn::Lit::Int(int) => int.base10_parse(),
+            syn::Lit::Float(float) => float.base10_parse(),
+            lit => Err(Error::new(lit.span(), "Expected a number")),
+        }
+    }
+}
+
+/// The `default` of an item, kept typed to be emitted as the same JSON value
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum DefaultValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+}
+
+impl TryFromParse<syn::Lit> for DefaultValue {
+    fn try_from_parse(parse: syn::Lit) -> Result<Self, Error> {
+        match parse {
+            syn::Lit::Str(s) => Ok(DefaultValue::String(s.value())),
+            syn::Lit::Char(c) => Ok(DefaultValue::String(c.value().into())),
+            syn::Lit::Int(int) => int.base10_parse().map(DefaultValue::Number),
+            syn::Lit::Float(float) => float.base10_parse().map(DefaultValue::Number),
+            syn::Lit::Bool(b) => Ok(DefaultValue::Bool(b.value)),
+            lit => Err(Error::new(
+                lit.span(),
+                "Expected a string, number or boolean",
+            )),
+        }
+    }
+}
+
+/// Where predicates written out, e.g. `bound = "T: AsSchema + Clone"`. May
+/// be empty to bound nothing.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Bound(pub(crate) Punctuated<syn::WherePredicate, syn::Token![,]>);
+
+impl TryFromParse<syn::LitStr> for Bound {
+    fn try_from_parse(parse: syn::LitStr) -> Result<Self, Error> {
+        parse.parse_with(Punctuated::parse_terminated).map(Bound)
+    }
+}
+
+/// An attribute whose value doesn't matter, like serde's `default` which
+/// may name a function
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Present;
+
+impl TryFromParse<syn::LitStr> for Present {
+    fn try_from_parse(_: syn::LitStr) -> Result<Self, Error> {
+        Ok(Present)
+    }
+
+    fn try_from_nothing() -> Result<Self, ()> {
+        Ok(Present)
+    }
+}
+
+/// A literal's text, for attributes taking any kind of literal
+struct LitText(String);
+
+impl TryFromParse<syn::Lit> for LitText {
+    fn try_from_parse(parse: syn::Lit) -> Result<Self, Error> {
+        match parse {
+            syn::Lit::Str(s) => Ok(LitText(s.value())),
+            syn::Lit::Char(c) => Ok(LitText(c.value().into())),
+            syn::Lit::Int(int) => Ok(LitText(int.base10_digits().to_owned())),
+            syn::Lit::Float(float) => Ok(LitText(float.base10_digits().to_owned())),
+            syn::Lit::Bool(b) => Ok(LitText(b.value.to_string())),
+            lit => Err(Error::new(
+                lit.span(),
+                "Expected a string, number or boolean",
+            )),
+        }
+    }
+}
+
 impl TryFromParse<syn::LitStr> for String {
     fn try_from_parse(parse: syn::LitStr) -> Result<Self, Error> {
         Ok(parse.value())
@@ -434,6 +1055,22 @@ where
     }
 }
 
+// Collects every occurrence, e.g. `alias = "a", alias = "b"`
+fn new_attr_list<F, T>(
+) -> impl Fn(Option<Vec<T>>, &ParseNestedMeta<'_>) -> Result<Option<Vec<T>>, Error> + Copy
+where
+    F: Parse,
+    T: TryFromParse<F>,
+{
+    let base = new_attr::<F, T>();
+
+    move |former, new_value| {
+        let mut list = former.unwrap_or_default();
+        list.extend(base(None, new_value)?);
+        Ok(Some(list))
+    }
+}
+
 fn new_attr_string_concat(
 ) -> impl Fn(Option<String>, &ParseNestedMeta<'_>) -> Result<Option<String>, Error> {
     let base = new_attr::<syn::LitStr, String>();
@@ -455,32 +1092,95 @@ fn new_attr_string_concat(
     }
 }
 
+fn new_attr_lit_text(
+) -> impl Fn(Option<String>, &ParseNestedMeta<'_>) -> Result<Option<String>, Error> + Copy {
+    let base = new_attr::<syn::Lit, LitText>();
+
+    move |former, new_value| {
+        base(former.map(LitText), new_value).map(|text| text.map(|LitText(text)| text))
+    }
+}
+
+// Takes either form of value, e.g. `"crate::f"` or `crate::f`, trying `V`
+// first
 fn new_attr_any2<V: Parse, V1: Parse, T>(
 ) -> impl Fn(Option<T>, &ParseNestedMeta<'_>) -> Result<Option<T>, Error> + Copy
 where
-    T: Clone,
     T: TryFromParse<V>,
     T: TryFromParse<V1>,
 {
-    let from_v = new_attr::<V, T>();
-    let from_v1 = new_attr::<V1, T>();
+    |former, new_value| {
+        if former.is_some() {
+            return Err(new_value.error("Multiple values not supported"));
+        }
+        let Ok(value) = new_value.value() else {
+            return <T as TryFromParse<V>>::try_from_nothing()
+                .map(Some)
+                .map_err(|_| new_value.error("Argument required"));
+        };
 
-    move |former, new_value| {
-        #[allow(dead_code)]
-        struct ParseNestedMetaE<'a> {
-            pub path: syn::Path,
-            pub input: syn::parse::ParseStream<'a>,
+        // Only the form that parses may move the input on
+        let fork = value.fork();
+        let parsed = match fork.parse::<V>() {
+            Ok(v) => {
+                value.advance_to(&fork);
+                T::try_from_parse(v)
+            }
+            Err(_) => value
+                .parse::<V1>()
+                .map_err(|err| new_value.error(format!("Error parsing value: {err}")))
+                .and_then(T::try_from_parse),
+        };
+        parsed.map(Some)
+    }
+}
+
+// Parses `items(...)` like the attributes of a field of the element type
+fn new_attr_items(
+) -> impl Fn(Option<Box<Attr>>, &ParseNestedMeta<'_>) -> Result<Option<Box<Attr>>, Error> + Copy {
+    |former, new_value| {
+        if former.is_some() {
+            return Err(new_value.error("Multiple values not supported"));
         }
+        let content;
+        syn::parenthesized!(content in new_value.input);
+        let nested: proc_macro2::TokenStream = content.parse()?;
+        let attr: Attribute = syn::parse_quote!(#[schema(#nested)]);
+        parse_list_items(&[attr]).map(|attr| Some(Box::new(attr)))
+    }
+}
 
-        let fork = new_value.input.fork();
-        from_v(former.clone(), new_value).or_else(|_| {
-            from_v1(former, unsafe {
-                &std::mem::transmute::<ParseNestedMetaE, ParseNestedMeta>(ParseNestedMetaE {
-                    path: new_value.path.clone(),
-                    input: &fork,
-                })
-            })
-        })
+type EnumValues = Option<Spanned<Vec<String>>>;
+
+// Parses `enum_values("a", "b")`, which needs distinct values
+fn new_attr_enum_values(
+) -> impl Fn(EnumValues, &ParseNestedMeta<'_>) -> Result<EnumValues, Error> + Copy {
+    |former, new_value| {
+        if former.is_some() {
+            return Err(new_value.error("Multiple values not supported"));
+        }
+        let content;
+        syn::parenthesized!(content in new_value.input);
+        let literals = Punctuated::<syn::LitStr, syn::Token![,]>::parse_terminated(&content)?;
+        if literals.is_empty() {
+            return Err(new_value.error("Expected at least one value"));
+        }
+
+        let mut values = Vec::with_capacity(literals.len());
+        for literal in &literals {
+            let value = literal.value();
+            if values.contains(&value) {
+                return Err(Error::new(
+                    literal.span(),
+                    format!("Value \"{value}\" is listed twice"),
+                ));
+            }
+            values.push(value);
+        }
+        Ok(Some(Spanned {
+            inner: values,
+            span: syn::spanned::Spanned::span(&new_value.path),
+        }))
     }
 }
 
@@ -489,14 +1189,12 @@ fn new_attr_bool(
     new_attr_any2::<syn::LitStr, syn::LitBool, bool>()
 }
 
-// FIXME
 fn new_attr_expr_path(
 ) -> impl Fn(Option<syn::ExprPath>, &ParseNestedMeta<'_>) -> Result<Option<syn::ExprPath>, Error> + Copy
 {
     new_attr_any2::<syn::LitStr, syn::ExprPath, syn::ExprPath>()
 }
 
-// FIXME
 pub(crate) fn new_attr_path(
 ) -> impl Fn(Option<syn::Path>, &ParseNestedMeta<'_>) -> Result<Option<syn::Path>, Error> + Copy {
     new_attr_any2::<syn::LitStr, syn::Path, syn::Path>()
@@ -544,6 +1242,15 @@ impl<'a> SetAttr<'a> {
         self.owner("serde").finding()
     }
 
+    // Codes are for the schema derives, whose attributes are ours and
+    // serde's. Other derives' messages go without.
+    fn message(&self, diagnostic: Diagnostic, message: impl Display) -> String {
+        match self.owner {
+            "schema" | "serde" => diagnostic.message(message),
+            _ => message.to_string(),
+        }
+    }
+
     // filter away the disallowed ones
     fn attr_for_error(&self, all: &mut [&str]) -> impl Display {
         // FIXME: move disallowed down so we can view allowed part while also sorting
@@ -616,7 +1323,7 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::attr::{parse_field, parse_plain_enum, Attr};
+    use crate::attr::{parse_field, parse_plain_enum, parse_top, Attr};
     use syn::{parse_quote, Attribute, Data, DataStruct, Fields};
 
     #[test]
@@ -847,6 +1554,7 @@ mod test {
                 }},
                 want: vec![Attr {
                     rename: Some("TRUE".to_string()),
+                    serde_rename: Some("TRUE".to_string()),
                     skip: Some(true),
                     ..Default::default()
                 }],
@@ -904,22 +1612,30 @@ mod test {
                 }],
             },
             Test {
-                title: "ExprPath - FIXME(tired)",
+                title: "ExprPath",
                 input: parse_quote! {struct S {
                     #[schema(as_schema = "crate::module::function")]
                     rgb: String,
-                    // FIXME:
-                    // #[schema(as_schema_generic = crate::module::function)]
-                    // rgb_: String,
+                    #[schema(as_schema_generic = crate::module::function, description = "Bare")]
+                    rgb_: String,
+                    #[schema(with = module::with)]
+                    rgb__: String,
                 }},
                 want: vec![
                     Attr {
                         as_schema: Some(parse_quote!(crate::module::function)),
                         ..Default::default()
-                    }, /*, Attr {
-                           as_schema_generic: Some(parse_quote!(crate::module::function)),
-                           ..Default::default()
-                       }*/
+                    },
+                    Attr {
+                        as_schema_generic: Some(parse_quote!(crate::module::function)),
+                        description: Some("Bare".to_string()),
+                        ..Default::default()
+                    },
+                    Attr {
+                        as_schema: Some(parse_quote!(module::with::as_schema)),
+                        with: Some(parse_quote!(module::with)),
+                        ..Default::default()
+                    },
                 ],
             },
         ];
@@ -938,6 +1654,60 @@ mod test {
         }
     }
 
+    #[test]
+    fn top_ignore_serde() {
+        let input: syn::DeriveInput = parse_quote! {
+            #[schema(ignore_serde = false)]
+            #[serde(rename_all = "camelCase")]
+            struct S {}
+        };
+        let top = parse_top(&input).unwrap();
+        assert!(!top.ignore_serde);
+        assert!(top.rename_all.is_some());
+
+        let input: syn::DeriveInput = parse_quote! {
+            #[schema(ignore_serde)]
+            #[serde(rename_all = "camelCase")]
+            struct S {}
+        };
+        let top = parse_top(&input).unwrap();
+        assert!(top.ignore_serde);
+        assert!(top.rename_all.is_none());
+
+        let input: syn::DeriveInput = parse_quote! {
+            #[serde(rename_all = "camelCase")]
+            struct S {}
+        };
+        let top = parse_top(&input).unwrap();
+        assert!(!top.ignore_serde);
+        assert!(top.rename_all.is_some());
+    }
+
+    #[test]
+    fn top_bare_paths() {
+        let input: syn::DeriveInput = parse_quote! {
+            #[schema(crate_path = ::google_ai_rs, rename_all_with = crate::case::upper)]
+            struct S {}
+        };
+        let top = parse_top(&input).unwrap();
+        assert_eq!(top.crate_path, Some(parse_quote!(::google_ai_rs)));
+        assert_eq!(top.rename_all_with, Some(parse_quote!(crate::case::upper)));
+
+        let input: syn::DeriveInput = parse_quote! {
+            #[schema(crate_path = "google_ai_rs", rename_all_with = "upper")]
+            struct S {}
+        };
+        let top = parse_top(&input).unwrap();
+        assert_eq!(top.crate_path, Some(parse_quote!(google_ai_rs)));
+        assert_eq!(top.rename_all_with, Some(parse_quote!(upper)));
+
+        let input: syn::DeriveInput = parse_quote! {
+            #[schema(crate_path = 1)]
+            struct S {}
+        };
+        assert!(parse_top(&input).is_err());
+    }
+
     fn get_fields_attrs(i: syn::DeriveInput) -> Vec<Vec<Attribute>> {
         let mut out = Vec::new();
 
@@ -1022,7 +1792,7 @@ mod case {
     }
 
     declare_enum_attr! {
-        #[derive(Copy, Clone, Debug)]
+        #[derive(Copy, Clone, Debug, PartialEq)]
         pub enum Case = Case {
             Camel = "camelCase",
             Snake = "snake_case",
@@ -0,0 +1,244 @@
+// Stable codes of the schema derives' errors
+//
+// Each error message starts with its code, e.g. `schema[E003]: ...`, which
+// users can look up in DIAGNOSTICS.md. That chapter is rendered from the
+// table below (see the test), so the two can't drift apart. Codes are never
+// reused: retire one by leaving it out of messages, not out of the table.
+
+use std::fmt::Display;
+
+macro_rules! diagnostics {
+    ($(
+        $(#[doc = $doc:literal])*
+        $name:ident = $code:literal, $summary:literal;
+    )*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub(crate) enum Diagnostic {
+            $($name,)*
+        }
+
+        #[cfg_attr(not(test), allow(dead_code))]
+        impl Diagnostic {
+            pub(crate) const ALL: &[Diagnostic] = &[$(Self::$name,)*];
+
+            pub(crate) fn code(self) -> &'static str {
+                match self {
+                    $(Self::$name => $code,)*
+                }
+            }
+
+            pub(crate) fn summary(self) -> &'static str {
+                match self {
+                    $(Self::$name => $summary,)*
+                }
+            }
+
+            pub(crate) fn explanation(self) -> &'static str {
+                match self {
+                    $(Self::$name => concat!($($doc, "\n",)*),)*
+                }
+            }
+        }
+    };
+}
+
+diagnostics! {
+    /// The attribute isn't one the derive knows. The message lists the valid
+    /// ones.
+    UnsupportedAttribute = "E001", "Unsupported attribute";
+
+    /// The attribute exists but doesn't apply to this kind of item, like
+    /// `rename_all` on a field or `minimum` on an enum variant. The message
+    /// lists the attributes allowed here.
+    DisallowedAttribute = "E002", "Attribute not allowed here";
+
+    /// The attribute's value has the wrong kind, isn't one of the accepted
+    /// values, is missing, or the attribute is given twice. `description` is
+    /// the exception: repeating it adds lines.
+    InvalidAttributeValue = "E003", "Invalid attribute value";
+
+    /// Unions have no schema. `AsSchemaWithSerde` also rejects unit structs;
+    /// derive `AsSchema` for them instead.
+    UnsupportedType = "E004", "Unsupported type";
+
+    /// `max_depth` counts how many levels of a recursive type are expanded.
+    /// Zero levels would leave nothing of the type, so it must be at least 1.
+    InvalidMaxDepth = "E005", "max_depth must be at least 1";
+
+    /// Some container attributes only fit some types: `min_items` and
+    /// `max_items` tuple structs with several fields, `repr` and
+    /// `rename_all_fields` enums, `one_of` enums with data, and `transparent`
+    /// structs.
+    MisplacedContainerAttribute = "E006", "Container attribute doesn't apply to this type";
+
+    /// A `transparent` struct is represented as its only field. Skip every
+    /// other field with `#[schema(skip)]`.
+    TransparentFieldCount = "E007", "Transparent struct needs exactly one field";
+
+    /// `rename_all` and `rename_all_with` both rename every field, so only one
+    /// of them can be set.
+    ConflictingRenameAll = "E008", "rename_all conflicts with rename_all_with";
+
+    /// `AsSchema` represents a tuple struct whose fields have different types
+    /// as an array of unspecified items, which leaves field attributes nothing
+    /// to apply to. Derive `AsSchemaWithSerde` instead, which represents it as
+    /// an object of its fields, or set `#[schema(tuple = "array")]` to keep
+    /// the array and list the fields' descriptions in its own.
+    TupleStructAttributes = "E009", "Attributes on fields of a tuple struct";
+
+    /// The enum's representation attributes contradict each other: `untagged`
+    /// with `tag`, `content` without `tag`, a `tag` and `content` of the same
+    /// name, or `repr = "integer"` or `one_of` with any of them.
+    ConflictingEnumRepresentation = "E010", "Conflicting enum representation";
+
+    /// The data of a tagged enum's variants is merged into one object, so
+    /// variants can't set their own `r#type` or `as_schema`, and their fields
+    /// can't be named like the tag. Internally tagged enums, without
+    /// `content`, only support unit and struct variants, like serde.
+    InvalidTaggedVariant = "E011", "Variant doesn't fit a tagged enum";
+
+    /// An untagged enum is any of its variants' data. Unit variants only make
+    /// it nullable, so at least one variant needs data.
+    UntaggedWithoutData = "E012", "Untagged enum without data";
+
+    /// `repr = "integer"` represents an enum by its discriminants. It needs at
+    /// least one variant, none of them with data, renamed or aliased, and
+    /// every explicit discriminant must be an integer literal.
+    InvalidIntegerRepr = "E013", "Enum can't be represented by its discriminants";
+
+    /// The `format` doesn't belong to the `r#type`: `float`, `double`, `int32`
+    /// and `int64` are for numbers and integers, `enum` for strings.
+    IncompatibleFormat = "E014", "Type and format don't match";
+
+    /// `min_items` and `max_items` can't be negative, and `min_items` can't
+    /// exceed `max_items`. On fixed-size arrays, `[T; N]`, they can only be N:
+    /// serde reads exactly N items, so any other length would fail to parse.
+    /// On tuple structs they can only relax the length:
+    /// `min_items <= N <= max_items`.
+    InvalidItemBounds = "E015", "Invalid min_items or max_items";
+
+    /// `minimum` and `maximum` only apply to numbers, and `minimum` can't
+    /// exceed `maximum`. For other types holding numbers, set
+    /// `r#type = "Number"` or `r#type = "Integer"`.
+    InvalidNumericBounds = "E016", "Invalid minimum or maximum";
+
+    /// `pattern` only applies to strings. For other types holding strings,
+    /// set `r#type = "String"`.
+    PatternOnNonString = "E017", "pattern on a type that isn't a string";
+
+    /// `big_int_as_string` only applies to integers, and sets the type and
+    /// pattern itself: it can't be combined with `r#type`, `format`,
+    /// `as_schema`, `as_schema_generic`, `pattern`, `minimum` or `maximum`.
+    InvalidBigIntAsString = "E018", "Invalid big_int_as_string";
+
+    /// `AsSchemaWithSerde` derives the deserializer from serde's attributes,
+    /// so attributes changing the layout, like `rename` or `tag`, must be
+    /// given to serde rather than `schema`, and serde can't be ignored.
+    SerdeOwnsLayout = "E019", "AsSchemaWithSerde takes the layout from serde";
+
+    /// A container's `as_schema` gives the whole schema, so attributes
+    /// shaping the derived one, like `rename_all`, `tag` or `transparent`,
+    /// have nothing to apply to. `title`, `description`, `example` and
+    /// `nullable` still apply on top.
+    ContainerAsSchema = "E020", "Attribute conflicts with the container's as_schema";
+
+    /// `with = "module"` takes the field's schema from `module::as_schema`,
+    /// so the field can't also set `as_schema` or `as_schema_generic`.
+    ConflictingWith = "E021", "with conflicts with as_schema";
+
+    /// `min_length` and `max_length` only apply to strings, can't be
+    /// negative, and `min_length` can't exceed `max_length`. A `char` is
+    /// always one character long. For other types holding strings, set
+    /// `r#type = "String"`.
+    InvalidLengthBounds = "E022", "Invalid min_length or max_length";
+
+    /// `items(...)` customizes the elements of a list, like `Vec<T>` or
+    /// `[T; N]`, on top of their own schema. The field must be such a list,
+    /// and can't replace its schema with `r#type`, `as_schema`,
+    /// `as_schema_generic` or `with`.
+    InvalidListItems = "E023", "items on a type that isn't a list";
+
+    /// `enum_values` makes a string enum. On a field, it only applies to
+    /// strings, or types set to `r#type = "String"`, and replaces attributes
+    /// shaping other schemas, like `pattern`, `as_schema` or `items`. A
+    /// string `default` must be one of the values. On a container, it gives
+    /// the whole schema, like `as_schema`.
+    InvalidEnumValues = "E024", "Invalid enum_values";
+
+    /// `as_string` gives a field a string schema, whatever its type, so it
+    /// can't be combined with attributes giving another schema (`r#type`,
+    /// `format`, `as_schema`, `as_schema_generic`, `with`,
+    /// `big_int_as_string`) or shaping lists and numbers (`items`,
+    /// `min_items`, `max_items`, `minimum`, `maximum`).
+    InvalidAsString = "E025", "Invalid as_string";
+
+    /// `description_from` takes the description from a `&str` constant,
+    /// which the derive can't read, so it can't be combined with
+    /// `description` or extended with it. Put the whole text in the
+    /// constant. Variants of tagged enums, whose descriptions are listed in
+    /// the tag's, can't use it either.
+    ConflictingDescription = "E026", "description conflicts with description_from";
+
+    /// `via_schemars` takes a field's schema from its `schemars::JsonSchema`
+    /// implementation, so it can't be combined with attributes giving
+    /// another schema (`r#type`, `format`, `as_schema`,
+    /// `as_schema_generic`, `with`, `as_string`, `big_int_as_string`,
+    /// `enum_values`) or with `items`, which shapes the list's own.
+    InvalidViaSchemars = "E027", "Invalid via_schemars";
+}
+
+impl Diagnostic {
+    /// `message`, prefixed with the code
+    pub(crate) fn message(self, message: impl Display) -> String {
+        format!("schema[{}]: {message}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The chapter users read, from the same table as the messages
+    fn render() -> String {
+        let mut chapter = String::from(
+            "## Diagnostics\n\n\
+             Errors of the `AsSchema` and `AsSchemaWithSerde` derives start with a \
+             code,\nlike `schema[E003]`, explained here.\n",
+        );
+        for diagnostic in Diagnostic::ALL {
+            chapter.push_str(&format!(
+                "\n### {}: {}\n\n",
+                diagnostic.code(),
+                diagnostic.summary()
+            ));
+            for line in diagnostic.explanation().lines() {
+                chapter.push_str(line.trim());
+                chapter.push('\n');
+            }
+        }
+        chapter
+    }
+
+    #[test]
+    fn codes_are_unique_and_ordered() {
+        let codes: Vec<_> = Diagnostic::ALL.iter().map(|d| d.code()).collect();
+        for (i, code) in codes.iter().enumerate() {
+            assert_eq!(*code, format!("E{:03}", i + 1));
+        }
+    }
+
+    // Run with UPDATE_DIAGNOSTICS=1 to rewrite the chapter
+    #[test]
+    fn chapter_is_current() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/DIAGNOSTICS.md");
+        let rendered = render();
+        if std::env::var_os("UPDATE_DIAGNOSTICS").is_some() {
+            std::fs::write(path, &rendered).unwrap();
+        }
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap_or_default(),
+            rendered,
+            "DIAGNOSTICS.md is out of date, rerun with UPDATE_DIAGNOSTICS=1"
+        );
+    }
+}
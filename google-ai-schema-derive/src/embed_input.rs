@@ -0,0 +1,205 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, parse_quote_spanned, spanned::Spanned as _, Data, DataStruct,
+    DeriveInput, Error, Member, Type, WherePredicate,
+};
+
+use crate::attr;
+
+pub(super) fn derive_embed_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    embed_input(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn embed_input(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let crate_path: syn::Path =
+        attr::parse_embed_top(&input.attrs)?.unwrap_or_else(|| parse_quote!(::google_ai_rs));
+
+    let Data::Struct(DataStruct { fields, .. }) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "EmbedInput can only be derived for structs",
+        ));
+    };
+
+    let mut title: Option<(Member, Type)> = None;
+    let mut body: Vec<(Member, Type)> = Vec::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let embed_attrs = attr::parse_embed_field(&field.attrs)?;
+        if embed_attrs.skip.unwrap_or_default() {
+            continue;
+        }
+
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(i.into()),
+        };
+
+        if embed_attrs.title.unwrap_or_default() {
+            if title.is_some() {
+                return Err(Error::new_spanned(
+                    field,
+                    "Only one field can be marked with #[embed(title)]",
+                ));
+            }
+            title = Some((member, field.ty.clone()));
+        } else {
+            body.push((member, field.ty.clone()));
+        }
+    }
+
+    if body.is_empty() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "EmbedInput needs at least one field that isn't the title or skipped",
+        ));
+    }
+
+    // Bound each field so generic fields get a readable error at their type
+    let mut predicates: Vec<WherePredicate> = body
+        .iter()
+        .map(|(_, ty)| parse_quote_spanned!(ty.span()=> #ty: #crate_path::IntoParts))
+        .collect();
+
+    if let Some((_, ty)) = &title {
+        predicates.push(parse_quote_spanned!(ty.span()=>
+            #ty: ::std::convert::Into<::std::option::Option<::std::string::String>>
+        ));
+    }
+
+    let where_clause = input.generics.make_where_clause();
+    for predicate in predicates {
+        if !where_clause.predicates.iter().any(|p| *p == predicate) {
+            where_clause.predicates.push(predicate);
+        }
+    }
+
+    let title = match &title {
+        Some((member, _)) => quote! { ::std::convert::Into::into(self.#member) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let body = body.iter().map(|(member, _)| member);
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #crate_path::embedding::EmbedInput for #ident #ty_generics #where_clause {
+            fn into_embed_input(
+                self,
+            ) -> ::std::result::Result<
+                (
+                    ::std::option::Option<::std::string::String>,
+                    #crate_path::Content,
+                ),
+                #crate_path::Error,
+            > {
+                let mut parts = ::std::vec::Vec::new();
+                #(
+                    parts.extend(#crate_path::IntoParts::into_parts(self.#body));
+                )*
+                ::std::result::Result::Ok((#title, #crate_path::Content::from(parts)))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn embed_input_errors() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            error_like: &'static str,
+        }
+
+        let tests = [
+            Test {
+                title: "enum",
+                input: parse_quote! {
+                    enum E { A(String) }
+                },
+                error_like: "only be derived for structs",
+            },
+            Test {
+                title: "two titles",
+                input: parse_quote! {
+                    struct S {
+                        #[embed(title)]
+                        a: String,
+                        #[embed(title)]
+                        b: String,
+                        body: String,
+                    }
+                },
+                error_like: "Only one field",
+            },
+            Test {
+                title: "no body",
+                input: parse_quote! {
+                    struct S {
+                        #[embed(title)]
+                        a: String,
+                        #[embed(skip)]
+                        b: String,
+                    }
+                },
+                error_like: "at least one field",
+            },
+            Test {
+                title: "unknown attribute",
+                input: parse_quote! {
+                    struct S {
+                        #[embed(body)]
+                        a: String,
+                    }
+                },
+                error_like: "Unsupported embed attribute body",
+            },
+        ];
+
+        for test in tests {
+            println!("title: {}", test.title);
+            match embed_input(test.input) {
+                Ok(_) => panic!("test did not fail"),
+                Err(err) => assert!(err.to_string().contains(test.error_like), "{err}"),
+            }
+        }
+    }
+
+    #[test]
+    fn embed_input_bounds() {
+        let input: DeriveInput = parse_quote! {
+            #[embed(crate_path = "crate")]
+            struct Doc<T> {
+                #[embed(title)]
+                title: Option<String>,
+                body: T,
+                #[embed(skip)]
+                id: u64,
+            }
+        };
+
+        // Token spacing differs between quote! invocations
+        let expanded = embed_input(input).unwrap().to_string().replace(' ', "");
+        let want = quote! {
+            where
+                T: crate::IntoParts,
+                Option<String>: ::std::convert::Into<::std::option::Option<::std::string::String>>
+        };
+        assert!(
+            expanded.contains(&want.to_string().replace(' ', "")),
+            "{expanded}"
+        );
+        assert!(!expanded.contains("self.id"), "{expanded}");
+    }
+}
@@ -0,0 +1,427 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, parse_quote_spanned, spanned::Spanned as _, Data, DataStruct,
+    DeriveInput, Error, Member, WherePredicate,
+};
+
+use crate::attr::{self, Role, Spanned};
+
+pub(super) fn derive_into_contents(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_contents(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+struct PromptField {
+    member: Member,
+    name: String,
+    role: Role,
+    prefix: Option<String>,
+    order: i64,
+    before: Option<Spanned<String>>,
+    system: bool,
+    skip_if_empty: bool,
+}
+
+fn into_contents(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let top_attr = attr::parse_content_top(&input.attrs)?;
+    let crate_path = top_attr
+        .crate_path
+        .unwrap_or_else(|| parse_quote!(::google_ai_rs));
+    let default_role = top_attr.role.unwrap_or_default();
+
+    let Data::Struct(DataStruct { fields, .. }) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "IntoContents can only be derived for structs",
+        ));
+    };
+
+    let mut prompt_fields = Vec::with_capacity(fields.len());
+    let mut skipped = Vec::new();
+    let mut predicates: Vec<WherePredicate> = Vec::with_capacity(fields.len());
+
+    for (i, field) in fields.iter().enumerate() {
+        let content_attrs = attr::parse_content_field(&field.attrs)?;
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(i.into()),
+        };
+        let name = match &member {
+            Member::Named(ident) => ident.to_string(),
+            Member::Unnamed(index) => index.index.to_string(),
+        };
+
+        if content_attrs.skip.unwrap_or_default() {
+            skipped.push(name);
+            continue;
+        }
+
+        let system = content_attrs.system.unwrap_or_default();
+        if system && content_attrs.role.is_some() {
+            return Err(Error::new_spanned(
+                field,
+                "system fields go into the system instruction and can't have a role",
+            ));
+        }
+
+        if let Some(before) = &content_attrs.before {
+            if content_attrs.order.is_some() {
+                return Err(before.error("`before` and `order` can't be combined"));
+            }
+        }
+
+        let ty = &field.ty;
+        predicates.push(parse_quote_spanned!(ty.span()=> #ty: #crate_path::IntoParts));
+
+        prompt_fields.push(PromptField {
+            member,
+            name,
+            role: content_attrs.role.unwrap_or(default_role),
+            prefix: content_attrs.prefix,
+            order: content_attrs.order.unwrap_or_default(),
+            before: content_attrs.before,
+            system,
+            skip_if_empty: content_attrs.skip_if_empty.unwrap_or_default(),
+        });
+    }
+
+    let prompt_fields = layout(prompt_fields, &skipped)?;
+
+    let where_clause = input.generics.make_where_clause();
+    for predicate in predicates {
+        if !where_clause.predicates.iter().any(|p| *p == predicate) {
+            where_clause.predicates.push(predicate);
+        }
+    }
+
+    let has_system = prompt_fields.iter().any(|f| f.system);
+    let pushes = prompt_fields.iter().map(|field| {
+        let member = &field.member;
+        let prefix = field.prefix.as_ref().map(|prefix| {
+            quote! {
+                parts.insert(0, #crate_path::Part::from(#prefix));
+            }
+        });
+        let skip_if_empty = field.skip_if_empty.then(|| {
+            quote! {
+                if parts.iter().all(#crate_path::Part::is_empty) {
+                    parts.clear();
+                }
+            }
+        });
+
+        let push = if field.system {
+            quote! { system.extend(parts) }
+        } else {
+            let role = field.role.as_str();
+            quote! {
+                // Consecutive fields of the same role make up a single turn
+                match contents.last_mut() {
+                    ::std::option::Option::Some(last) if last.role == #role => {
+                        last.parts.extend(parts)
+                    }
+                    _ => contents.push(#crate_path::Content {
+                        role: ::std::string::String::from(#role),
+                        parts,
+                    }),
+                }
+            }
+        };
+
+        quote! {
+            let mut parts = #crate_path::IntoParts::into_parts(self.#member);
+            #skip_if_empty
+            if !parts.is_empty() {
+                #prefix
+                #push
+            }
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = if has_system {
+        quote! {
+            fn into_contents(self) -> ::std::vec::Vec<#crate_path::Content> {
+                // Without a separate system instruction, it leads the contents
+                let (system, mut contents) =
+                    #crate_path::IntoContents::into_system_and_contents(self);
+                if let ::std::option::Option::Some(system) = system {
+                    contents.insert(0, system);
+                }
+                contents
+            }
+
+            fn into_system_and_contents(
+                self,
+            ) -> (
+                ::std::option::Option<#crate_path::Content>,
+                ::std::vec::Vec<#crate_path::Content>,
+            ) {
+                let mut system: ::std::vec::Vec<#crate_path::Part> = ::std::vec::Vec::new();
+                let mut contents: ::std::vec::Vec<#crate_path::Content> = ::std::vec::Vec::new();
+                #({ #pushes })*
+                let system = if system.is_empty() {
+                    ::std::option::Option::None
+                } else {
+                    ::std::option::Option::Some(#crate_path::Content::from(system))
+                };
+                (system, contents)
+            }
+        }
+    } else {
+        quote! {
+            fn into_contents(self) -> ::std::vec::Vec<#crate_path::Content> {
+                let mut contents: ::std::vec::Vec<#crate_path::Content> = ::std::vec::Vec::new();
+                #({ #pushes })*
+                contents
+            }
+        }
+    };
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #crate_path::IntoContents for #ident #ty_generics #where_clause {
+            #body
+        }
+    })
+}
+
+/// Orders fields by `order`, then moves every field with `before` right in
+/// front of the field it names
+fn layout(mut fields: Vec<PromptField>, skipped: &[String]) -> Result<Vec<PromptField>, Error> {
+    for field in &fields {
+        let Some(before) = &field.before else {
+            continue;
+        };
+
+        let target = before.to_string();
+        let Some(target) = fields.iter().find(|f| f.name == target) else {
+            return Err(if skipped.contains(&target) {
+                before.error(format!("field `{target}` is skipped"))
+            } else {
+                before.error(format!("no field named `{target}`"))
+            });
+        };
+
+        if target.system != field.system {
+            return Err(before.error("`before` can't cross between system and other fields"));
+        }
+    }
+
+    // Stable, so fields without an order keep their declaration order
+    fields.sort_by_key(|f| f.order);
+
+    let len = fields.len();
+    let (placed, mut pending): (Vec<_>, Vec<_>) =
+        fields.into_iter().partition(|f| f.before.is_none());
+
+    fn place(field: PromptField, pending: &mut Vec<PromptField>, out: &mut Vec<PromptField>) {
+        // Fields naming the same target keep their relative order
+        while let Some(i) = pending.iter().position(|f| {
+            f.before
+                .as_ref()
+                .is_some_and(|b| b.to_string() == field.name)
+        }) {
+            let before = pending.remove(i);
+            place(before, pending, out);
+        }
+        out.push(field);
+    }
+
+    let mut out = Vec::with_capacity(len);
+    for field in placed {
+        place(field, &mut pending, &mut out);
+    }
+
+    // Whatever is left only points at other pending fields
+    if let Some(field) = pending.first() {
+        let before = field.before.as_ref().expect("pending fields have `before`");
+        return Err(before.error("`before` forms a cycle"));
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn into_contents_errors() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            error_like: &'static str,
+        }
+
+        let tests = [
+            Test {
+                title: "enum",
+                input: parse_quote! {
+                    enum E { A(String) }
+                },
+                error_like: "only be derived for structs",
+            },
+            Test {
+                title: "unknown role",
+                input: parse_quote! {
+                    struct S {
+                        #[content(role = "system")]
+                        a: String,
+                    }
+                },
+                error_like: "Unknown value system for Role",
+            },
+            Test {
+                title: "unknown attribute",
+                input: parse_quote! {
+                    struct S {
+                        #[content(title)]
+                        a: String,
+                    }
+                },
+                error_like: "Unsupported content attribute title",
+            },
+            Test {
+                title: "system with role",
+                input: parse_quote! {
+                    struct S {
+                        #[content(system, role = "model")]
+                        a: String,
+                    }
+                },
+                error_like: "can't have a role",
+            },
+            Test {
+                title: "before with order",
+                input: parse_quote! {
+                    struct S {
+                        a: String,
+                        #[content(before = "a", order = 1)]
+                        b: String,
+                    }
+                },
+                error_like: "can't be combined",
+            },
+            Test {
+                title: "unknown before",
+                input: parse_quote! {
+                    struct S {
+                        #[content(before = "c")]
+                        a: String,
+                    }
+                },
+                error_like: "no field named `c`",
+            },
+            Test {
+                title: "before skipped",
+                input: parse_quote! {
+                    struct S {
+                        #[content(before = "b")]
+                        a: String,
+                        #[content(skip)]
+                        b: String,
+                    }
+                },
+                error_like: "field `b` is skipped",
+            },
+            Test {
+                title: "before system",
+                input: parse_quote! {
+                    struct S {
+                        #[content(before = "b")]
+                        a: String,
+                        #[content(system)]
+                        b: String,
+                    }
+                },
+                error_like: "can't cross",
+            },
+            Test {
+                title: "before cycle",
+                input: parse_quote! {
+                    struct S {
+                        #[content(before = "b")]
+                        a: String,
+                        #[content(before = "a")]
+                        b: String,
+                        c: String,
+                    }
+                },
+                error_like: "forms a cycle",
+            },
+        ];
+
+        for test in tests {
+            println!("title: {}", test.title);
+            match into_contents(test.input) {
+                Ok(_) => panic!("test did not fail"),
+                Err(err) => assert!(err.to_string().contains(test.error_like), "{err}"),
+            }
+        }
+    }
+
+    #[test]
+    fn into_contents_order() {
+        let input: DeriveInput = parse_quote! {
+            #[content(crate_path = "crate", role = "model")]
+            struct Prompt {
+                question: String,
+                #[content(order = -1, role = "user", prefix = "Context:")]
+                context: Option<String>,
+                #[content(skip)]
+                id: u64,
+            }
+        };
+
+        // Token spacing differs between quote! invocations
+        let expanded = into_contents(input).unwrap().to_string().replace(' ', "");
+        let context = expanded.find("self.context").unwrap();
+        let question = expanded.find("self.question").unwrap();
+        assert!(context < question, "{expanded}");
+        assert!(!expanded.contains("self.id"), "{expanded}");
+        assert!(expanded.contains(r#"Part::from("Context:")"#), "{expanded}");
+        assert!(expanded.contains(r#"last.role=="model""#), "{expanded}");
+    }
+
+    #[test]
+    fn into_contents_layout() {
+        let input: DeriveInput = parse_quote! {
+            #[content(crate_path = "crate")]
+            struct Prompt {
+                question: String,
+                #[content(before = "question")]
+                examples: Vec<String>,
+                #[content(before = "examples", skip_if_empty)]
+                context: String,
+                #[content(order = -1)]
+                history: Vec<String>,
+                #[content(system)]
+                persona: String,
+            }
+        };
+
+        let expanded = into_contents(input).unwrap().to_string().replace(' ', "");
+        let position = |field: &str| {
+            expanded
+                .find(&format!("self.{field}"))
+                .unwrap_or_else(|| panic!("{field} missing: {expanded}"))
+        };
+
+        assert!(position("history") < position("context"), "{expanded}");
+        assert!(position("context") < position("examples"), "{expanded}");
+        assert!(position("examples") < position("question"), "{expanded}");
+        assert!(
+            expanded.contains("fninto_system_and_contents"),
+            "{expanded}"
+        );
+        assert!(expanded.contains("system.extend(parts)"), "{expanded}");
+        assert_eq!(expanded.matches("Part::is_empty").count(), 1, "{expanded}");
+    }
+}
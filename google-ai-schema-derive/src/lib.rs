@@ -14,34 +14,168 @@
 //! ## Core Macros
 //! - `#[derive(AsSchema)]`: Main derivation macro for schema generation
 //! - `#[derive(AsSchemaWithSerde)]`: Enhanced version with deeper Serde integration
+//! - `#[derive(EmbedInput)]`: Flattens a struct into a titled document for embedding
+//! - `#[derive(IntoContents)]`: Turns a struct of prompt fields into conversation contents
 //!
 //! ## Attribute Reference
 //! ### Container Attributes (struct/enum level)
+//! - `title`: Schema title
+//! - `auto_title`: Title the type and its fields after their identifiers,
+//!   e.g. `first_name` as "First name", unless they set `title`
 //! - `description`: Overall schema description
-//! - `ignore_serde`: Disable serde integration
+//! - `description_from`: Path of a `&str` constant giving the description in
+//!   place of `description`, e.g. `description_from = texts::USER`, to keep
+//!   long or localized prompt text in one place
+//! - `example`: Example value, appended to the description as
+//!   `Example: ...`. Strings, numbers and booleans are accepted
+//! - `ignore_serde`: Disable serde integration, for types whose serde
+//!   attributes describe a different wire format than Gemini responses
 //! - `rename_all`: Naming convention (e.g., "camelCase", "snake_case")
+//! - `rename_all_fields`: Naming convention of the fields of every struct
+//!   variant, unless the variant sets `rename_all`. Read from
+//!   `#[serde(rename_all_fields)]` too
+//! - `transparent`: Represent a struct as its only field that isn't
+//!   skipped. Read from `#[serde(transparent)]` too
+//! - `ordered`: Ask the model for fields in declaration order, through
+//!   `property_ordering`
 //! - `rename_all_with`: Custom renaming function
 //! - `crate_path`: Custom crate path specification
 //! - `nullable`: Mark entire structure as nullable
+//! - `all_optional`: Leave every field out of `required`, as if each had
+//!   `required = false`. Fields can still opt back in with `required`.
+//!   `AsSchemaWithSerde` reads missing ones as their `Default`; otherwise
+//!   serde still needs `Option` or `#[serde(default)]` to read them missing
+//! - `ignore_limits`: Silence the warnings about schemas likely too large
+//!   for the API (see Important Notes)
+//! - `min/max_items`: Relax the length of tuple structs, which are
+//!   represented as fixed-size arrays
+//! - `tuple`: `tuple = "array"` keeps a tuple struct whose fields have
+//!   different types an array of unspecified items even when its fields
+//!   have attributes, which otherwise fails. Their descriptions are listed
+//!   in the array's, as `Item 1: ...`; anything else they set is ignored,
+//!   with a warning
+//! - `max_depth`: Allow the type to contain itself, expanding it this many
+//!   levels deep; deeper levels are left unspecified and nullable
+//! - `tag`: Represent an enum as one object whose `tag` field names the
+//!   variant, next to the fields of every struct variant. Read from
+//!   `#[serde(tag)]` too, matching internally tagged enums
+//! - `untagged`: Represent an enum as any of its variants' data, with
+//!   `anyOf`. Read from `#[serde(untagged)]` too
+//! - `one_of`: Represent an enum with data as any of one object per
+//!   variant, with `anyOf`, so the model fills exactly one variant. Each
+//!   object requires its variant's name as only field, and unit variants
+//!   are together one string enum, matching serde's default representation
+//! - `content`: With `tag`, put the variant's data in the `content` field
+//!   instead. Read from `#[serde(content)]` too, matching adjacently tagged
+//!   enums
+//! - `bound`: Where predicates of the `AsSchema` impl, replacing the ones
+//!   inferred from field types, e.g. `bound = "T: AsSchema + Default"` or
+//!   `bound = ""` for none. Like serde's `bound`, which it doesn't read
+//! - `as_schema`: Function giving the type's whole schema, e.g. for types
+//!   whose fields can't derive one. The derive still writes the impl,
+//!   bounding type parameters by `AsSchema`; `title`, `description`,
+//!   `example` and `nullable` apply on top
+//! - `enum_values`: Represent the type as a string enum of these values,
+//!   e.g. `enum_values("low", "medium", "high")`, for types whose valid
+//!   values are known but that aren't Rust enums. Like `as_schema`, it
+//!   gives the whole schema; `title`, `description`, `example` and
+//!   `nullable` apply on top. `AsSchema` only
+//! - `repr`: `repr = "integer"` represents an enum without data by its
+//!   discriminants, e.g. `Low = 1`, as an integer schema listing them. This
+//!   matches `serde_repr`. Discriminants must be integer literals
 //!
 //! ### Field/Variant Attributes
+//! - `title`: Field title
 //! - `description`: Field-specific documentation
+//! - `description_from`: Path of a `&str` constant giving the description,
+//!   like the container's. Not on variants of tagged enums
+//! - `example`: Example value, appended to the description as `Example: ...`
 //! - `format`: Schema format specification (e.g., "date-time", "email")
 //! - `type`: Specific schema type
 //! - `as_schema`: Custom schema generation function
 //! - `as_schema_generic`: Generic custom schema function
+//! - `with`: Module giving the field's schema, through `module::as_schema()`,
+//!   and deserializer, like serde's `with`. `AsSchemaWithSerde` deserializes
+//!   the field with `module::deserialize`; with `AsSchema`, add
+//!   `#[serde(with = "module")]` yourself. `google_ai_rs::schema::time`'s
+//!   modules fit
 //! - `required`: Force requirement status
-//! - `min/max_items`: Array size constraints
+//! - `default`: Value used when the field is missing, e.g. `default = 10`.
+//!   `AsSchemaWithSerde` fills it in when deserializing too.
+//!   Fields with a default, including `#[serde(default)]` ones, aren't
+//!   required unless `required` says otherwise, and neither are `Option`s
+//!   with `#[serde(skip_serializing_if)]`. Fields serde never reads,
+//!   `#[serde(skip_deserializing)]`, are left out like skipped ones
+//! - `min/max_items`: Array size constraints. On `[T; N]` they can only
+//!   repeat the length, N, which is the only one serde reads
+//! - `minimum`/`maximum`: Bounds of numbers, e.g. `minimum = 0, maximum = 100`.
+//!   Fields of other types need `type = "Number"` or `type = "Integer"`
+//! - `min_length`/`max_length`: Bounds of a string's length, e.g.
+//!   `min_length = 1, max_length = 280`. Fields of other types need
+//!   `type = "String"`
+//! - `pattern`: Regular expression a string must match, e.g.
+//!   `pattern = "^[A-Z]{2}\\d{4}$"`. Fields of other types need `type = "String"`
+//! - `enum_values`: Values a string is one of, e.g.
+//!   `enum_values("low", "medium", "high")`, making it a string enum without
+//!   defining a Rust enum. Fields of other types need `type = "String"`
+//! - `items(...)`: Attributes of a list's elements, e.g.
+//!   `items(description = "A tag", pattern = "^#")` on a `Vec<String>`.
+//!   Takes the attributes of a field, except those naming, defaulting or
+//!   skipping it
+//! - `big_int_as_string`: Represent an integer as a string of digits, so that
+//!   large `u64`/`i64` values aren't rounded as JSON numbers. Deserialize it
+//!   with `#[serde(with = "google_ai_rs::schema::big_int")]`, or use the
+//!   `BigIntString` wrapper instead
+//! - `as_string`: Represent any `FromStr` + `Display` type as a string,
+//!   e.g. IDs or IP addresses, without it implementing `AsSchema`.
+//!   `Option`s of one are nullable. `AsSchemaWithSerde` parses it with
+//!   `FromStr` itself; otherwise deserialize it with
+//!   `#[serde(with = "google_ai_rs::schema::as_string")]`, or
+//!   `google_ai_rs::schema::as_string::option` for an `Option`
+//! - `via_schemars`: Take the schema from the type's `schemars::JsonSchema`
+//!   implementation, converted at runtime, for types of crates that
+//!   describe them with schemars rather than `AsSchema`. Needs the
+//!   `schemars` feature of `google_ai_rs`. Put it on a list itself, not in
+//!   `items(...)`
 //! - `nullable`: Mark item as nullable
+//! - `alias` (variants only): A former name of the variant, repeatable.
+//!   The description tells the model what it was renamed to, so older
+//!   prompts still make sense. Read from `#[serde(alias)]` too; with
+//!   `AsSchemaWithSerde`, the generated deserializer accepts it as well
 //! - `skip`: Exclude field from schema
+//! - `rename_all`/`rename_all_with` (struct variants only): Rename the
+//!   variant's fields, overriding the container's
 //!
 //! ## Important Notes
-//! - **Recursive Types**: Not supported due to JSON Schema limitations
+//! - **Recursive Types**: Schemas can't refer to themselves, so recursive
+//!   types need `max_depth` to be expanded a fixed number of levels
 //! - **Serde Integration**: Use `AsSchemaWithSerde` for complex serde representations (e.g with Tuple structs)
 //! - **Type-Format Compatibility**: Mismatches like `r#type="String" format="float"` throw compile errors
 //! - `rename_all` and `rename_all_with` are mutually exclusive
+//! - **Paths**: `as_schema`, `as_schema_generic`, `with`, `rename_all_with`,
+//!   `description_from` and `crate_path` take a path either bare,
+//!   `as_schema = my_schema`, which rust-analyzer can navigate and rename,
+//!   or quoted, `as_schema = "my_schema"`
+//! - **Serde Name Parity**: When a type also has `#[serde(...)]` attributes, a
+//!   deprecation warning points at every item whose schema name differs from
+//!   the one serde expects, including names produced by `rename_all_with`
+//! - **Cached Schemas**: Types without type or const parameters build their
+//!   schema once and clone it afterwards, so `as_schema` functions they name
+//!   run only on first use
+//! - **API Limits**: Gemini rejects schemas nested too deep, or with too many
+//!   properties or enum values, at request time. The derive warns when what
+//!   it can see of the schema passes 10 levels, 100 properties or 500 enum
+//!   values. Other types' schemas are only known at run time, so only this
+//!   type's own fields and variants, and the collections wrapping them,
+//!   count toward these
+//!
+#![doc = include_str!("../DIAGNOSTICS.md")]
 
 mod attr;
+mod diagnostics;
+mod embed_input;
+mod into_contents;
+mod limits;
 mod schema;
 mod serde_support;
 
@@ -52,7 +186,8 @@ extern crate syn;
 
 use std::{cell::LazyCell, collections::HashMap};
 
-use attr::{Attr, TopAttr};
+use attr::{Attr, Spanned, TopAttr};
+use diagnostics::Diagnostic;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::ToTokens;
@@ -62,8 +197,9 @@ use syn::{
     punctuated::Punctuated,
     spanned::Spanned as _,
     token::{Colon, Comma, Paren},
-    Data, DataEnum, DataStruct, DeriveInput, Error, Field, Fields, FieldsNamed, FieldsUnnamed,
-    Path, PredicateType, TraitBound, Type, TypeParamBound, TypeTuple, Variant, WherePredicate,
+    Data, DataEnum, DataStruct, DeriveInput, Error, Expr, ExprLit, Field, Fields, FieldsNamed,
+    FieldsUnnamed, Lit, Path, PredicateType, TraitBound, Type, TypeParamBound, TypeTuple, Variant,
+    WhereClause, WherePredicate,
 };
 
 /// Derive macro for AsSchema trait.
@@ -73,6 +209,7 @@ use syn::{
 /// ```rust
 /// # mod google_ai_rs {
 /// #   pub trait AsSchema { fn as_schema() -> Schema; }
+/// #   pub mod schema { pub fn cached(_: &std::sync::OnceLock<super::Schema>, f: impl FnOnce() -> super::Schema) -> super::Schema { f() } }
 /// #   pub enum SchemaType { Unspecified = 0, String = 1, Number = 2, Integer = 3, Boolean = 4, Array = 5,Object = 6, }
 /// #   #[derive(Default, PartialEq, Eq, Debug)]
 /// #   pub struct Schema { pub r#type: i32, pub format: String, pub description: String, pub nullable: bool, pub r#enum: Vec<String>,
@@ -116,6 +253,7 @@ use syn::{
 /// ```rust
 /// # mod google_ai_rs {
 /// #   pub trait AsSchema { fn as_schema() -> Schema; }
+/// #   pub mod schema { pub fn cached(_: &std::sync::OnceLock<super::Schema>, f: impl FnOnce() -> super::Schema) -> super::Schema { f() } }
 /// #   pub enum SchemaType { Unspecified = 0, String = 1, Number = 2, Integer = 3, Boolean = 4, Array = 5,Object = 6, }
 /// #   #[derive(Default)]
 /// #   pub struct Schema { pub r#type: i32, pub format: String, pub description: String, pub nullable: bool, pub r#enum: Vec<String>,
@@ -129,7 +267,7 @@ use syn::{
 /// #[derive(AsSchema)]
 /// # #[schema(crate_path = "google_ai_rs")]
 /// struct Timestamp {
-///     #[schema(as_schema = "datetime_schema")]
+///     #[schema(as_schema = datetime_schema)]
 ///     millis: i64,
 /// }
 ///
@@ -146,6 +284,7 @@ use syn::{
 /// ```rust
 /// # mod google_ai_rs {
 /// #   pub trait AsSchema { fn as_schema() -> Schema; }
+/// #   pub mod schema { pub fn cached(_: &std::sync::OnceLock<super::Schema>, f: impl FnOnce() -> super::Schema) -> super::Schema { f() } }
 /// #   pub enum SchemaType { Unspecified = 0, String = 1, Number = 2, Integer = 3, Boolean = 4, Array = 5,Object = 6, }
 /// #   #[derive(Default)]
 /// #   pub struct Schema { pub r#type: i32, pub format: String, pub description: String, pub nullable: bool, pub r#enum: Vec<String>,
@@ -200,6 +339,7 @@ use syn::{
 /// ```rust
 /// # mod google_ai_rs {
 /// #   pub trait AsSchema { fn as_schema() -> Schema; }
+/// #   pub mod schema { pub fn cached(_: &std::sync::OnceLock<super::Schema>, f: impl FnOnce() -> super::Schema) -> super::Schema { f() } }
 /// #   pub enum SchemaType { Unspecified = 0, String = 1, Number = 2, Integer = 3, Boolean = 4, Array = 5,Object = 6, }
 /// #   #[derive(Default)]
 /// #   pub struct Schema { pub r#type: i32, pub format: String, pub description: String, pub nullable: bool, pub r#enum: Vec<String>,
@@ -242,6 +382,7 @@ use syn::{
 /// ```rust
 /// # mod google_ai_rs {
 /// #   pub trait AsSchema { fn as_schema() -> Schema; }
+/// #   pub mod schema { pub fn cached(_: &std::sync::OnceLock<super::Schema>, f: impl FnOnce() -> super::Schema) -> super::Schema { f() } }
 /// #   pub enum SchemaType { Unspecified = 0, String = 1, Number = 2, Integer = 3, Boolean = 4, Array = 5,Object = 6, }
 /// #   #[derive(Default, PartialEq, Eq, Debug)]
 /// #   pub struct Schema { pub r#type: i32, pub format: String, pub description: String, pub nullable: bool, pub r#enum: Vec<String>,
@@ -276,6 +417,7 @@ use syn::{
 /// ```rust
 /// # mod google_ai_rs {
 /// #   pub trait AsSchema { fn as_schema() -> Schema; }
+/// #   pub mod schema { pub fn cached(_: &std::sync::OnceLock<super::Schema>, f: impl FnOnce() -> super::Schema) -> super::Schema { f() } }
 /// #   pub enum SchemaType { Unspecified = 0, String = 1, Number = 2, Integer = 3, Boolean = 4, Array = 5,Object = 6, }
 /// #   #[derive(Default, PartialEq, Eq, Debug)]
 /// #   pub struct Schema { pub r#type: i32, pub format: String, pub description: String, pub nullable: bool, pub r#enum: Vec<String>,
@@ -326,6 +468,7 @@ pub fn derive_schema(input: TokenStream) -> TokenStream {
 fn derive_schema_base(input: DeriveInput) -> Result<SchemaImplOwned, Error> {
     let mut ctx = Context::new(input)?;
     let schema = generate_schema(&mut ctx)?;
+    limits::check(&mut ctx, &schema);
     Ok(SchemaImplOwned { ctx, schema })
 }
 
@@ -333,11 +476,17 @@ fn derive_schema_base(input: DeriveInput) -> Result<SchemaImplOwned, Error> {
 ///
 /// **This is a specialized, opinionated implementation with several constraints**
 ///
-/// Only supports tuple structs for now
-///
 /// It represents tuples as objects with positional field names ("0", "1", etc)
 /// and derives the corresponding serde::Deserialize for it.
 ///
+/// Structs with named fields and enums, tagged or not, are derived as with
+/// `AsSchema` and serde's `Deserialize`, with one source for their layout:
+/// serde attributes. Schema attributes that would change it (`rename`,
+/// `rename_all`, `skip`, `tag`, `untagged`, ...) are rejected in favor of
+/// their serde counterparts, which the schema reads, so the schema and the
+/// deserializer can't drift apart. `ignore_serde` is rejected for the same
+/// reason.
+///
 /// Adding serde and schema attributes is just as natural as if one added
 /// both `#[derive(AsSchame)]` and `#[derive(serde::Deserialize)]`.
 ///
@@ -349,35 +498,192 @@ pub fn derive_schema_with_serde(input: TokenStream) -> TokenStream {
     crate::serde_support::derive_schema_with_serde(input)
 }
 
+/// Derive macro for `EmbedInput`, turning a struct into a document for
+/// embedding models
+///
+/// The field marked `#[embed(title)]` becomes the document title and must
+/// convert into `Option<String>`. Every other field, in declaration order,
+/// is appended to the document content and must implement `IntoParts`.
+///
+/// # Attributes
+/// - `#[embed(title)]`: Use the field as the document title
+/// - `#[embed(skip)]`: Leave the field out of the document
+/// - `#[embed(crate_path = "...")]` (container): Custom crate path specification
+///
+/// # Example
+/// ```rust,ignore
+/// use google_ai_rs::EmbedInput;
+///
+/// #[derive(EmbedInput)]
+/// struct Article {
+///     #[embed(title)]
+///     headline: String,
+///     summary: String,
+///     body: String,
+///     #[embed(skip)]
+///     id: u64,
+/// }
+/// ```
+#[proc_macro_derive(EmbedInput, attributes(embed))]
+pub fn derive_embed_input(input: TokenStream) -> TokenStream {
+    crate::embed_input::derive_embed_input(input)
+}
+
+/// Derive macro for `IntoContents`, turning a struct of prompt fields into
+/// conversation contents
+///
+/// Every field must implement `IntoParts` (strings, `Part`, `Vec<Part>`,
+/// `Option<...>` of those, ...). Fields are emitted in declaration order
+/// unless reordered with `order` or `before`; consecutive fields with the
+/// same role are merged into one `Content`, and fields that produce no parts
+/// (e.g. `None`) are left out.
+///
+/// Fields marked `system` make up the request's system instruction, after
+/// the model's own. Where there is no system instruction to fill (e.g. chat
+/// history), they lead the contents as a user turn instead.
+///
+/// # Attributes
+/// - `#[content(role = "user" | "model")]`: Role of the field's content.
+///   Defaults to the container's role, or `"user"`
+/// - `#[content(system)]`: Put the field in the system instruction
+/// - `#[content(prefix = "...")]`: Text part inserted before the field's parts
+/// - `#[content(order = N)]`: Sort key; fields without one have order `0`
+/// - `#[content(before = "field")]`: Place the field right before another
+///   one. Can't be combined with `order`
+/// - `#[content(skip_if_empty)]`: Also leave the field (and its prefix) out
+///   when all its parts are empty text
+/// - `#[content(skip)]`: Leave the field out of the contents
+/// - `#[content(role = "...")]` (container): Default role for all fields
+/// - `#[content(crate_path = "...")]` (container): Custom crate path specification
+///
+/// # Example
+/// ```rust,ignore
+/// use google_ai_rs::{IntoContents, Part};
+///
+/// #[derive(IntoContents)]
+/// struct UserQuery {
+///     #[content(system)]
+///     persona: &'static str,
+///     question: String,
+///     #[content(prefix = "Context:", before = "question", skip_if_empty)]
+///     context: String,
+///     attachments: Vec<Part>,
+///     #[content(skip)]
+///     request_id: u64,
+/// }
+/// ```
+#[proc_macro_derive(IntoContents, attributes(content))]
+pub fn derive_into_contents(input: TokenStream) -> TokenStream {
+    crate::into_contents::derive_into_contents(input)
+}
+
 struct Context {
     input: DeriveInput,
     trait_bound: TraitBound,
     crate_path: Path,
     top_attr: TopAttr,
-    // as big brother, let's help serde_support.
-    // It may report false negative because not all type is visited
+    // The where clause as written, before `constrain` adds to it
+    where_clause: Option<WhereClause>,
+    // Whether `constrain` bounds what std wrappers wrap rather than the
+    // wrappers themselves
+    peel_wrappers: bool,
+    // as big brother, let's help serde_support: whether a deserialized
+    // field borrows for 'static
     has_static: bool,
+    // Emitted alongside the impl as deprecation warnings of the named
+    // constant; there's no stable way for derives to warn.
+    warnings: Vec<(Span, &'static str, String)>,
 }
 
 impl Context {
     // Instantiates a new Context fetching the crate_path
     // from the top attr alongside.
     fn new(input: DeriveInput) -> Result<Self, Error> {
-        let top_attr = attr::parse_top(&input.attrs)?;
+        let top_attr = attr::parse_top(&input)?;
         let crate_path = top_attr
             .crate_path
             .clone()
             .unwrap_or_else(|| parse_quote!(::google_ai_rs));
 
         Ok(Self {
+            where_clause: input.generics.where_clause.clone(),
+            has_static: borrows_static(&input, top_attr.ignore_serde),
             input,
             trait_bound: parse_quote!(#crate_path::AsSchema),
             crate_path,
             top_attr,
-            has_static: false,
+            peel_wrappers: true,
+            warnings: Vec::new(),
         })
     }
 
+    fn warn(&mut self, name: &'static str, span: Span, message: impl Into<String>) {
+        self.warnings.push((span, name, message.into()))
+    }
+
+    // Warns when the schema names an item differently from what serde
+    // will expect when deserializing the response.
+    fn check_serde_name(
+        &mut self,
+        span: Span,
+        original_name: &str,
+        schema_name: &Value<String>,
+        schema_attrs: &Attr,
+        is_enum: bool,
+    ) {
+        if !self.top_attr.has_serde || self.top_attr.ignore_serde {
+            return;
+        }
+
+        let serde_name = schema_attrs.serde_rename.clone().unwrap_or_else(|| {
+            match self.top_attr.serde_rename_all {
+                Some(style) if is_enum => attr::rename_all_variants(style)(original_name),
+                Some(style) => attr::rename_all(style)(original_name),
+                None => original_name.to_owned(),
+            }
+        });
+
+        match schema_name {
+            Value::Raw(name) if *name != serde_name => self.warn(
+                "schema_name_differs_from_serde",
+                span,
+                format!(
+                    "schema names `{original_name}` \"{name}\" but serde expects \"{serde_name}\""
+                ),
+            ),
+            Value::ReCompute(..) => self.warn(
+                "schema_name_differs_from_serde",
+                span,
+                format!(
+                    "`rename_all_with` isn't applied by serde, which expects `{original_name}` \
+                     as \"{serde_name}\". Add a matching `#[serde(rename)]` or set `ignore_serde`"
+                ),
+            ),
+            _ => {}
+        }
+    }
+
+    // Warns when a field's schema comes from a `with` module that serde
+    // won't deserialize it through.
+    fn check_serde_with(&mut self, span: Span, schema_attrs: &Attr) {
+        if !self.top_attr.has_serde || self.top_attr.ignore_serde || schema_attrs.serde_with {
+            return;
+        }
+        let Some(with) = &schema_attrs.with else {
+            return;
+        };
+
+        let with = with.to_token_stream().to_string().replace(' ', "");
+        self.warn(
+            "schema_with_not_in_serde",
+            span,
+            format!(
+                "the schema comes from `{with}::as_schema` but serde doesn't read the field \
+                 with `{with}`. Add `#[serde(with = \"{with}\")]` or derive `AsSchemaWithSerde`"
+            ),
+        );
+    }
+
     // constrain bounds items type to the #crate::AsSchema
     // trait. It checks for static borrows along the way
     // for use in the serde_support module.
@@ -386,18 +692,19 @@ impl Context {
         // just add all...
         // we get infinite constrain with recursive types.
         // "it's not a bug, it's a feature".
+        // Unless they asked for recursion: the bound would never resolve.
+        if self.top_attr.max_depth.is_some() && mentions(ty, &self.input.ident) {
+            return false;
+        }
         let predicate = LazyCell::new(|| -> WherePredicate {
-            if !self.has_static {
-                // Let's handle static detection raw...
-
-                // FIXME: no, I won't
-                self.has_static = ty.to_token_stream().to_string().contains("'static");
-            }
-
-            // FIXME: The error is only on "Box" if Q is not AsSchema
-            // struct T {
-            //     field: Box<Q>
-            // }
+            // Wrappers are only AsSchema if what they wrap is, so bound that
+            // instead: a type's span is its first token, and for
+            // `Box<Q>` an error on `Box` says nothing about `Q`.
+            let ty = if self.peel_wrappers {
+                innermost(ty)
+            } else {
+                ty
+            };
 
             // Fix span... parse_quote_spanned won't
             let mut bound = self.trait_bound.clone();
@@ -426,21 +733,313 @@ impl Context {
     }
 }
 
+// The type inside std wrappers whose AsSchema impls forward to it, e.g.
+// `Q` for `Option<Vec<&Q>>`
+fn innermost(ty: &Type) -> &Type {
+    const WRAPPERS: &[&str] = &[
+        "Option",
+        "Box",
+        "Rc",
+        "Arc",
+        "Weak",
+        "Cell",
+        "RefCell",
+        "Mutex",
+        "RwLock",
+        "PhantomData",
+        "Vec",
+        "VecDeque",
+        "LinkedList",
+        "HashSet",
+        "BTreeSet",
+        "BinaryHeap",
+    ];
+    match ty {
+        Type::Array(array) => innermost(&array.elem),
+        Type::Group(group) => innermost(&group.elem),
+        Type::Paren(paren) => innermost(&paren.elem),
+        Type::Ptr(ptr) => innermost(&ptr.elem),
+        Type::Reference(reference) => innermost(&reference.elem),
+        Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return ty;
+            };
+            let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+                return ty;
+            };
+            let mut args = args
+                .args
+                .iter()
+                .filter(|arg| !matches!(arg, syn::GenericArgument::Lifetime(_)));
+            match (args.next(), args.next()) {
+                (Some(syn::GenericArgument::Type(inner)), None)
+                    if WRAPPERS.iter().any(|wrapper| last.ident == wrapper) =>
+                {
+                    innermost(inner)
+                }
+                _ => ty,
+            }
+        }
+        _ => ty,
+    }
+}
+
+// Whether a field that isn't skipped borrows for 'static. Every field
+// counts, not just those `constrain` bounds: one with a generated schema is
+// deserialized all the same.
+fn borrows_static(input: &DeriveInput, ignore_serde: bool) -> bool {
+    fn mentions_static(tokens: proc_macro2::TokenStream) -> bool {
+        // `static` can't be anything else in a type
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(i) => i == "static",
+            proc_macro2::TokenTree::Group(g) => mentions_static(g.stream()),
+            _ => false,
+        })
+    }
+    // Invalid attributes are reported by the schema pass
+    let fields = |fields: &Fields| {
+        fields.iter().any(|field| {
+            let skipped = attr::parse_field(&field.attrs, ignore_serde)
+                .is_ok_and(|attr| attr.skip == Some(true));
+            !skipped && mentions_static(field.ty.to_token_stream())
+        })
+    };
+    match &input.data {
+        Data::Struct(data) => fields(&data.fields),
+        Data::Enum(data) => data.variants.iter().any(|variant| {
+            let skipped = attr::parse_variant(&variant.attrs, ignore_serde)
+                .is_ok_and(|attr| attr.skip == Some(true));
+            !skipped && fields(&variant.fields)
+        }),
+        Data::Union(_) => false,
+    }
+}
+
+// Whether `ty` refers to the type being derived
+fn mentions(ty: &Type, ident: &syn::Ident) -> bool {
+    fn walk(tokens: proc_macro2::TokenStream, ident: &syn::Ident) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(i) => i == *ident || i == "Self",
+            proc_macro2::TokenTree::Group(g) => walk(g.stream(), ident),
+            _ => false,
+        })
+    }
+    walk(ty.to_token_stream(), ident)
+}
+
 fn generate_schema(ctx: &mut Context) -> Result<Schema, Error> {
-    match ctx.input.data.clone() {
+    let top_attr = &ctx.top_attr;
+    if let Some(max_depth) = &top_attr.max_depth {
+        if max_depth.value() == 0 {
+            return Err(max_depth
+                .error(Diagnostic::InvalidMaxDepth.message("max_depth must be at least 1")));
+        }
+    }
+    let is_array = matches!(
+        &ctx.input.data,
+        Data::Struct(DataStruct { fields: Fields::Unnamed(fields), .. }) if fields.unnamed.len() > 1
+    );
+    if let Some(bound) = top_attr.min_items.as_ref().or(top_attr.max_items.as_ref()) {
+        if !is_array {
+            return Err(bound.error(Diagnostic::MisplacedContainerAttribute.message(
+                "Schema attributes min_items and max_items are only supported on \
+                 tuple structs with several fields. Set them on the field instead.",
+            )));
+        }
+    }
+    if let Some(tuple) = &top_attr.tuple {
+        if !is_array {
+            return Err(tuple.error(Diagnostic::MisplacedContainerAttribute.message(
+                "Schema attribute tuple is only supported on tuple structs with several fields",
+            )));
+        }
+    }
+
+    if !matches!(ctx.input.data, Data::Enum(_)) {
+        if let Some(one_of) = &top_attr.one_of {
+            return Err(one_of.error(
+                Diagnostic::MisplacedContainerAttribute
+                    .message("Schema attribute one_of is only supported on enums"),
+            ));
+        }
+        if let Some(repr) = &top_attr.repr {
+            return Err(repr.error(
+                Diagnostic::MisplacedContainerAttribute
+                    .message("Schema attribute repr is only supported on enums"),
+            ));
+        }
+        if top_attr.rename_all_fields.is_some() {
+            return Err(Error::new_spanned(
+                &ctx.input.ident,
+                Diagnostic::MisplacedContainerAttribute
+                    .message("Schema attribute rename_all_fields is only supported on enums"),
+            ));
+        }
+    }
+
+    if top_attr.transparent && !matches!(ctx.input.data, Data::Struct(_)) {
+        return Err(Error::new_spanned(
+            &ctx.input.ident,
+            Diagnostic::MisplacedContainerAttribute
+                .message("Schema attribute transparent is only supported on structs"),
+        ));
+    }
+
+    let mut schema = match ctx.input.data.clone() {
+        _ if ctx.top_attr.enum_values.is_some() => container_enum_values(ctx),
+        _ if ctx.top_attr.as_schema.is_some() => container_as_schema(ctx),
         Data::Struct(data) => impl_struct(ctx, &data),
         Data::Enum(data) => impl_enum(ctx, &data),
         Data::Union(_) => Err(Error::new_spanned(
             &ctx.input,
-            "Unions are not supported by AsSchema derive",
+            Diagnostic::UnsupportedType.message("Unions are not supported by AsSchema derive"),
         )),
+    }?;
+    schema.example = ctx.top_attr.example.clone();
+    schema.title = ctx.top_attr.title.clone().or_else(|| {
+        ctx.top_attr
+            .auto_title
+            .then(|| title_from_ident(&ctx.input.ident.to_string(), true))
+    });
+    Ok(schema)
+}
+
+// Delegates the whole schema to the container's as_schema function. Its
+// fields aren't visited, so only the type parameters are bound, the way
+// std's derives bound them.
+fn container_as_schema(ctx: &mut Context) -> Result<Schema, Error> {
+    if let Some(name) = shaping_attribute(&ctx.top_attr) {
+        return Err(Error::new_spanned(
+            &ctx.input.ident,
+            Diagnostic::ContainerAsSchema.message(format!(
+                "Schema attribute {name} can't be combined with the container's as_schema, \
+                 which gives the whole schema"
+            )),
+        ));
+    }
+
+    let params: Vec<Type> = ctx
+        .input
+        .generics
+        .type_params()
+        .map(|param| {
+            let ident = &param.ident;
+            parse_quote!(#ident)
+        })
+        .collect();
+    for param in &params {
+        ctx.constrain(param);
+    }
+
+    Ok(Schema {
+        description: ctx.top_attr.description.clone(),
+        description_from: ctx.top_attr.description_from.clone(),
+        nullable: ctx.top_attr.nullable,
+        base: BaseSchema::AsSschema(ctx.top_attr.as_schema.clone().unwrap()),
+        ..Default::default()
+    })
+}
+
+// Represents the whole type as a string enum of the container's
+// enum_values. Its fields aren't visited, and nothing is bound: the schema
+// doesn't depend on any type.
+fn container_enum_values(ctx: &mut Context) -> Result<Schema, Error> {
+    let top_attr = &ctx.top_attr;
+    let values = top_attr.enum_values.as_ref().unwrap();
+    let conflict = shaping_attribute(top_attr).or(top_attr.as_schema.as_ref().map(|_| "as_schema"));
+    if let Some(name) = conflict {
+        return Err(values.error(Diagnostic::InvalidEnumValues.message(format!(
+            "Schema attribute {name} can't be combined with the container's enum_values, \
+             which give the whole schema"
+        ))));
     }
+
+    Ok(Schema {
+        r#type: Some(schema::Type::String),
+        format: Some(Format::Enum),
+        description: top_attr.description.clone(),
+        description_from: top_attr.description_from.clone(),
+        nullable: top_attr.nullable,
+        r#enum: values
+            .clone()
+            .into_inner()
+            .into_iter()
+            .map(Value::Raw)
+            .collect(),
+        ..Default::default()
+    })
+}
+
+// The first container attribute set that shapes the derived schema, which
+// attributes giving the whole schema leave nothing to apply to
+fn shaping_attribute(top_attr: &TopAttr) -> Option<&'static str> {
+    let conflicts = [
+        ("rename_all", top_attr.rename_all.is_some()),
+        ("rename_all_with", top_attr.rename_all_with.is_some()),
+        ("rename_all_fields", top_attr.rename_all_fields.is_some()),
+        ("tag", top_attr.tag.is_some()),
+        ("content", top_attr.content.is_some()),
+        ("untagged", top_attr.untagged.is_some()),
+        ("one_of", top_attr.one_of.is_some()),
+        ("repr", top_attr.repr.is_some()),
+        ("transparent", top_attr.transparent),
+        ("max_depth", top_attr.max_depth.is_some()),
+        ("min_items", top_attr.min_items.is_some()),
+        ("max_items", top_attr.max_items.is_some()),
+        ("tuple", top_attr.tuple.is_some()),
+        ("ordered", top_attr.ordered),
+        ("all_optional", top_attr.all_optional),
+    ];
+    conflicts
+        .into_iter()
+        .find_map(|(name, set)| set.then_some(name))
 }
 
 fn impl_struct(ctx: &mut Context, data: &DataStruct) -> Result<Schema, Error> {
+    if ctx.top_attr.transparent {
+        return transparent_struct(ctx, &data.fields);
+    }
     dispatch_struct_fields(ctx, &data.fields)
 }
 
+// Represents a struct as its only field, like serde's transparent. Other
+// fields must be skipped. Transferable top_attrs are applied if the field
+// doesn't set them, as with 1-element tuple structs.
+fn transparent_struct(ctx: &mut Context, fields: &Fields) -> Result<Schema, Error> {
+    let ignore_serde = ctx.top_attr.ignore_serde;
+    let mut kept = Vec::with_capacity(1);
+    for field in fields {
+        let schema_attrs = match fields {
+            Fields::Named(_) => attr::parse_field(&field.attrs, ignore_serde)?,
+            _ => attr::parse_tuple(&field.attrs, ignore_serde)?,
+        };
+        if !schema_attrs.skip.unwrap_or_default() {
+            kept.push((field, schema_attrs));
+        }
+    }
+
+    let (field, mut schema_attrs) = match <[_; 1]>::try_from(kept) {
+        Ok([kept]) => kept,
+        Err(_) => {
+            return Err(Error::new_spanned(
+                &ctx.input.ident,
+                Diagnostic::TransparentFieldCount
+                    .message("A transparent struct must have exactly one field that isn't skipped"),
+            ))
+        }
+    };
+
+    if schema_attrs.description.is_none() && schema_attrs.description_from.is_none() {
+        schema_attrs.description = ctx.top_attr.description.clone();
+        schema_attrs.description_from = ctx.top_attr.description_from.clone();
+    }
+    if schema_attrs.nullable.is_none() {
+        schema_attrs.nullable = ctx.top_attr.nullable
+    }
+
+    generate_item_schema(ctx, &schema_attrs, &field.ty)
+}
+
 fn dispatch_struct_fields(ctx: &mut Context, fields: &Fields) -> Result<Schema, Error> {
     match fields {
         Fields::Named(fields) => named_struct(ctx, fields),
@@ -455,6 +1054,7 @@ fn unit_struct(ctx: &mut Context) -> Result<Schema, Error> {
     Ok(Schema {
         r#type: Some(schema::Type::Object),
         description: top_attr.description.clone(),
+        description_from: top_attr.description_from.clone(),
         nullable: top_attr.nullable,
         ..Default::default()
     })
@@ -470,7 +1070,8 @@ fn unit_struct(ctx: &mut Context) -> Result<Schema, Error> {
 //     represented as an array of this type.
 //   else
 //      it is represented as array of unspecified items or it errors if
-//      it finds any attribute probably meant for us on any field
+//      it finds any attribute probably meant for us on any field, unless
+//      `tuple = "array"` asks to list their descriptions instead
 //
 //  with the max_min_items as the length of the tuple
 fn tuple_struct(ctx: &mut Context, fields: &FieldsUnnamed) -> Result<Schema, Error> {
@@ -481,13 +1082,11 @@ fn tuple_struct(ctx: &mut Context, fields: &FieldsUnnamed) -> Result<Schema, Err
         let top_attr = &ctx.top_attr;
 
         let inner_ty = &fields.unnamed[0].ty;
-        let mut schema_attrs = attr::parse_tuple(
-            &fields.unnamed[0].attrs,
-            top_attr.ignore_serde.unwrap_or(false),
-        )?;
+        let mut schema_attrs = attr::parse_tuple(&fields.unnamed[0].attrs, top_attr.ignore_serde)?;
 
-        if schema_attrs.description.is_none() {
-            schema_attrs.description = top_attr.description.clone()
+        if schema_attrs.description.is_none() && schema_attrs.description_from.is_none() {
+            schema_attrs.description = top_attr.description.clone();
+            schema_attrs.description_from = top_attr.description_from.clone();
         }
 
         if schema_attrs.nullable.is_none() {
@@ -510,24 +1109,39 @@ fn tuple_struct(ctx: &mut Context, fields: &FieldsUnnamed) -> Result<Schema, Err
                 }
             });
 
+        let mut description = ctx.top_attr.description.clone();
         let item_schema = if let Some(Some(item_ty)) = equal_item_ty {
             // We have too many attr options
             generate_item_schema(ctx, &Attr::default(), item_ty)?
+        } else if ctx.top_attr.tuple.is_some() {
+            let items = tuple_item_descriptions(ctx, fields)?;
+            if !items.is_empty() {
+                description = Some(match description {
+                    Some(description) => format!("{description}\n{items}"),
+                    None => items,
+                });
+            }
+            Schema {
+                r#type: Some(schema::Type::Unspecified),
+                ..Default::default()
+            }
         } else {
             // we used to indescriminately treat as an array of unspecified type
             // we keep that as the default but recommend using AsSchemaWithSerde
             // if we find attributes for us which might show that AsSchemaWithSerde
             // is the man for the job.
-
-            // TODO: Maybe provide a way to not make this cause an unrecoverable error
             fields.unnamed.iter().try_for_each(|f| {
                 f.attrs.iter().try_for_each(|attr| {
                     if attr.path().is_ident("schema") {
                         Err(Error::new_spanned(
                             attr,
-                            "Consider deriving with AsSchemaWithSerde for more control. \
-                               AsSchema derivation represents tuple structs as \
-                               an array of unspecified items which doens't support attributes.",
+                            Diagnostic::TupleStructAttributes.message(
+                                "Consider deriving with AsSchemaWithSerde for more control. \
+                                 AsSchema derivation represents tuple structs as \
+                                 an array of unspecified items which doens't support attributes. \
+                                 Set `#[schema(tuple = \"array\")]` to keep that array and list \
+                                 the fields' descriptions in its own.",
+                            ),
                         ))
                     } else {
                         Ok(())
@@ -541,21 +1155,55 @@ fn tuple_struct(ctx: &mut Context, fields: &FieldsUnnamed) -> Result<Schema, Err
             }
         };
 
-        let len = Some(fields.unnamed.len() as i64);
+        let top_attr = &ctx.top_attr;
+        let len = fields.unnamed.len() as i64;
+        check_items_bounds(
+            top_attr.min_items.as_ref(),
+            top_attr.max_items.as_ref(),
+            Some(len),
+        )?;
 
         Ok(Schema {
             r#type: Some(schema::Type::Array),
-            description: ctx.top_attr.description.clone(),
-            max_items: len,
-            min_items: len,
+            description,
+            description_from: top_attr.description_from.clone(),
+            max_items: Some(top_attr.max_items.as_ref().map_or(len, Spanned::value)),
+            min_items: Some(top_attr.min_items.as_ref().map_or(len, Spanned::value)),
             items: Some(item_schema.into()),
             ..Default::default()
         })
     }
 }
 
+// Lists the descriptions of a `tuple = "array"` struct's fields, one line
+// per described field. Items are unspecified, so other attributes have
+// nothing to apply to; they're ignored with a warning.
+fn tuple_item_descriptions(ctx: &mut Context, fields: &FieldsUnnamed) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for (i, field) in fields.unnamed.iter().enumerate() {
+        let mut schema_attrs = attr::parse_tuple(&field.attrs, true)?;
+        if let Some(description) = schema_attrs.description.take() {
+            lines.push(format!("Item {}: {description}", i + 1));
+        }
+        if schema_attrs != Attr::default() {
+            ctx.warn(
+                "tuple_attribute_ignored",
+                field.ty.span(),
+                format!(
+                    "only `description` applies to item {} of a `tuple = \"array\"` struct, \
+                     whose items are unspecified; its other schema attributes are ignored",
+                    i + 1
+                ),
+            );
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
 trait StructItem {
     fn name(&self) -> String;
+    fn span(&self) -> Span;
+    fn is_option(&self) -> bool;
     fn schema_attrs(&self, top_attr: &TopAttr) -> Result<Attr, Error>;
     fn schema(&self, ctx: &mut Context, schema_attrs: &Attr) -> Result<Schema, Error>;
 }
@@ -565,6 +1213,14 @@ impl<I: StructItem> StructItem for &I {
         (*self).name()
     }
 
+    fn span(&self) -> Span {
+        (*self).span()
+    }
+
+    fn is_option(&self) -> bool {
+        (*self).is_option()
+    }
+
     fn schema_attrs(&self, top_attr: &TopAttr) -> Result<Attr, Error> {
         (*self).schema_attrs(top_attr)
     }
@@ -585,6 +1241,7 @@ where
     let mut properties = HashMap::with_capacity(items.size_hint().0);
 
     let mut required = Vec::new();
+    let mut property_ordering = Vec::new();
 
     for item in items {
         let schema_attrs = item.schema_attrs(&ctx.top_attr)?;
@@ -595,9 +1252,25 @@ where
         let original_item_name = item.name();
 
         let field_name = rename_item(rename_all.as_ref(), &original_item_name, &schema_attrs);
+        ctx.check_serde_name(
+            item.span(),
+            &original_item_name,
+            &field_name,
+            &schema_attrs,
+            is_enum,
+        );
+        ctx.check_serde_with(item.span(), &schema_attrs);
 
         let nullable = schema_attrs.nullable;
-        let required_flag = if nullable.is_some() {
+        // serde fills in missing fields that have a default. Fields it skips
+        // serializing are missing from values it writes itself, but it only
+        // reads them back if they're Options or have a default too
+        let has_default = schema_attrs.default.is_some()
+            || schema_attrs.serde_default
+            || ctx.top_attr.serde_default;
+        let may_be_missing =
+            has_default || (schema_attrs.serde_skip_serializing_if && item.is_option());
+        let required_flag = if nullable.is_some() || may_be_missing || ctx.top_attr.all_optional {
             schema_attrs.required.unwrap_or(false)
         } else {
             schema_attrs.required.unwrap_or(true)
@@ -607,7 +1280,19 @@ where
             required.push(field_name.clone());
         }
 
-        let field_schema = item.schema(ctx, &schema_attrs)?;
+        if ctx.top_attr.ordered {
+            property_ordering.push(field_name.clone());
+        }
+
+        let mut field_schema = item.schema(ctx, &schema_attrs)?;
+        if field_schema.title.is_none() && ctx.top_attr.auto_title {
+            field_schema.title = Some(title_from_ident(&original_item_name, is_enum));
+        }
+        // Only variants have aliases
+        if !schema_attrs.aliases.is_empty() {
+            let former = former_names(&schema_attrs.aliases);
+            field_schema.notes.push(format!("Formerly {former}"));
+        }
 
         properties.insert(field_name, field_schema);
     }
@@ -615,9 +1300,11 @@ where
     Ok(Schema {
         r#type: Some(schema::Type::Object),
         description: ctx.top_attr.description.clone(),
+        description_from: ctx.top_attr.description_from.clone(),
         nullable: ctx.top_attr.nullable,
         properties,
         required,
+        property_ordering,
         ..Default::default()
     })
 }
@@ -630,8 +1317,16 @@ impl StructItem for Field {
             .to_string()
     }
 
+    fn span(&self) -> Span {
+        self.ident.span()
+    }
+
+    fn is_option(&self) -> bool {
+        is_option(&self.ty)
+    }
+
     fn schema_attrs(&self, top_attr: &TopAttr) -> Result<Attr, Error> {
-        attr::parse_field(&self.attrs, top_attr.ignore_serde.unwrap_or(false))
+        attr::parse_field(&self.attrs, top_attr.ignore_serde)
     }
 
     fn schema(&self, ctx: &mut Context, schema_attrs: &Attr) -> Result<Schema, Error> {
@@ -648,10 +1343,18 @@ impl StructItem for Variant {
         self.ident.to_string()
     }
 
+    fn span(&self) -> Span {
+        self.ident.span()
+    }
+
+    fn is_option(&self) -> bool {
+        false
+    }
+
     fn schema_attrs(&self, top_attr: &TopAttr) -> Result<Attr, Error> {
         // We treat as an object field
         // Make all fields not required by default
-        let mut attr = attr::parse_field(&self.attrs, top_attr.ignore_serde.unwrap_or(false))?;
+        let mut attr = attr::parse_variant(&self.attrs, top_attr.ignore_serde)?;
         if attr.required.is_none() {
             attr.required = Some(false)
         }
@@ -698,11 +1401,50 @@ impl StructItem for Variant {
             // FIXME: Reconsider context purity.
 
             let original_description = ctx.top_attr.description.take();
+            let original_description_from = ctx.top_attr.description_from.take();
             let original_nullable = ctx.top_attr.nullable.take();
-            let schema = dispatch_struct_fields(ctx, &self.fields)?;
+
+            let original_rename_all = ctx.top_attr.rename_all;
+            let original_rename_all_with = ctx.top_attr.rename_all_with.clone();
+            let original_serde_rename_all = ctx.top_attr.serde_rename_all;
+
+            // The fields of struct variants are renamed by the variant's
+            // rename_all(_with), then the container's rename_all_fields,
+            // falling back to its rename_all. Serde only looks at the first
+            // two.
+            if schema_attrs.rename_all.is_some() || schema_attrs.rename_all_with.is_some() {
+                ctx.top_attr.rename_all = schema_attrs.rename_all;
+                ctx.top_attr.rename_all_with = schema_attrs.rename_all_with.clone();
+            } else if let Some(style) = schema_attrs
+                .serde_rename_all
+                .or(ctx.top_attr.rename_all_fields)
+            {
+                ctx.top_attr.rename_all = Some(style);
+                ctx.top_attr.rename_all_with = None;
+            }
+            ctx.top_attr.serde_rename_all = schema_attrs
+                .serde_rename_all
+                .or(ctx.top_attr.serde_rename_all_fields);
+
+            // Tuple variants are arrays whose bounds tuple_struct validates
+            let original_min_items = ctx.top_attr.min_items.take();
+            let original_max_items = ctx.top_attr.max_items.take();
+            if matches!(&self.fields, Fields::Unnamed(f) if f.unnamed.len() > 1) {
+                ctx.top_attr.min_items = schema_attrs.min_items;
+                ctx.top_attr.max_items = schema_attrs.max_items;
+            }
+
+            let schema = dispatch_struct_fields(ctx, &self.fields);
+
             ctx.top_attr.description = original_description;
+            ctx.top_attr.description_from = original_description_from;
             ctx.top_attr.nullable = original_nullable;
-            schema
+            ctx.top_attr.rename_all = original_rename_all;
+            ctx.top_attr.rename_all_with = original_rename_all_with;
+            ctx.top_attr.serde_rename_all = original_serde_rename_all;
+            ctx.top_attr.min_items = original_min_items;
+            ctx.top_attr.max_items = original_max_items;
+            schema?
         };
 
         // macro is tired of me by now.. lol
@@ -712,11 +1454,19 @@ impl StructItem for Variant {
                     schema.$property = schema_attrs.$property.clone()
                 })*
             }};
+            ($($property:ident)* => $map:path) => {{
+                $(if schema.$property.is_none() {
+                    schema.$property = schema_attrs.$property.as_ref().map($map)
+                })*
+            }};
         }
         // We add the top attributes values to the schema
         // if they're not filled
         transfer_properties! {
-            description nullable max_items min_items
+            description description_from nullable
+        }
+        transfer_properties! {
+            max_items min_items => Spanned::value
         }
 
         Ok(schema)
@@ -732,585 +1482,3603 @@ impl StructItem for Variant {
 //   of the variant. This matches the default tag of serde. All field
 //   is not required by default so that not all is provided and so maybe
 //   at least one will be.
+//
+// `repr = "integer"` represents data-less variants by their discriminants
+// instead, see integer_enum.
+//
+// Tagged and untagged enums are represented differently, see tagged_enum
+// and untagged_enum, and so are enums opting into one_of, see
+// one_of_enum.
 fn impl_enum(ctx: &mut Context, data: &DataEnum) -> Result<Schema, Error> {
+    if let Some(repr) = ctx.top_attr.repr {
+        if repr.value() == attr::Repr::Integer {
+            return integer_enum(ctx, data, repr);
+        }
+    }
+    if let Some(one_of) = ctx.top_attr.one_of {
+        if one_of.value() {
+            return one_of_enum(ctx, data, one_of);
+        }
+    }
+    if let Some(untagged) = ctx.top_attr.untagged {
+        if untagged.value() {
+            if let Some(tag) = &ctx.top_attr.tag {
+                return Err(tag.error(
+                    Diagnostic::ConflictingEnumRepresentation
+                        .message("An untagged enum can't have a tag"),
+                ));
+            }
+            return untagged_enum(ctx, data);
+        }
+    }
+    if let Some(tag) = ctx.top_attr.tag.clone() {
+        let content = ctx.top_attr.content.clone();
+        return tagged_enum(ctx, data, tag, content);
+    }
+    if let Some(content) = &ctx.top_attr.content {
+        return Err(content.error(
+            Diagnostic::ConflictingEnumRepresentation
+                .message("Schema attribute content requires tag"),
+        ));
+    }
+
     // check if it has data
     let has_data = data.variants.iter().any(|v| !v.fields.is_empty());
     if has_data {
         named_struct_like(ctx, &data.variants, IS_ENUM)
     } else {
-        let top_attr = &ctx.top_attr;
-        let rename_all = prepare_rename_all(top_attr, IS_ENUM)?;
+        let rename_all = prepare_rename_all(&ctx.top_attr, IS_ENUM)?;
 
         let mut variants = Vec::with_capacity(data.variants.len());
+        let mut notes = Vec::new();
 
         for variant in &data.variants {
-            let schema_attrs =
-                attr::parse_plain_enum(&variant.attrs, top_attr.ignore_serde.unwrap_or(false))?;
+            let schema_attrs = attr::parse_plain_enum(&variant.attrs, ctx.top_attr.ignore_serde)?;
 
             if schema_attrs.skip.unwrap_or_default() {
                 continue;
             }
 
-            let field_name = rename_item(
-                rename_all.as_ref(),
-                &variant.ident.to_string(),
+            let original_name = variant.ident.to_string();
+            let field_name = rename_item(rename_all.as_ref(), &original_name, &schema_attrs);
+            ctx.check_serde_name(
+                variant.ident.span(),
+                &original_name,
+                &field_name,
                 &schema_attrs,
+                IS_ENUM,
             );
 
+            notes.extend(alias_note(&field_name, &original_name, &schema_attrs));
             variants.push(field_name);
         }
 
         Ok(Schema {
             r#type: Some(schema::Type::String),
             format: Some(Format::Enum),
-            description: top_attr.description.clone(),
+            description: ctx.top_attr.description.clone(),
+            description_from: ctx.top_attr.description_from.clone(),
             r#enum: variants,
+            notes,
             ..Default::default()
         })
     }
 }
 
-// does constrain
-fn generate_item_schema(
+// Tells the model what a variant used to be called, so that names in
+// older prompts still make sense
+fn alias_note(name: &Value<String>, original_name: &str, schema_attrs: &Attr) -> Option<String> {
+    if schema_attrs.aliases.is_empty() {
+        return None;
+    }
+    let name = match name {
+        Value::Raw(name) => name,
+        Value::ReCompute(..) => original_name,
+    };
+    Some(format!(
+        "{name} was formerly {}",
+        former_names(&schema_attrs.aliases)
+    ))
+}
+
+// `"A"`, `"A" or "B"`, `"A", "B" or "C"`
+fn former_names(aliases: &[String]) -> String {
+    let quoted: Vec<_> = aliases.iter().map(|alias| format!("{alias:?}")).collect();
+    match quoted.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+// Represents a data-less enum by its discriminants, the way serde_repr
+// serializes it: an integer schema listing the values in `enum`, bounded
+// by the smallest and largest so the range holds even where the list
+// isn't enforced. Discriminants follow Rust's rules, counting up from the
+// previous one or 0.
+fn integer_enum(
     ctx: &mut Context,
-    schema_attrs: &Attr,
-    item_ty: &Type,
+    data: &DataEnum,
+    repr: Spanned<attr::Repr>,
 ) -> Result<Schema, Error> {
-    let description = schema_attrs.description.clone();
-    let nullable = schema_attrs.nullable;
-    let min_items = schema_attrs.min_items;
-    let max_items = schema_attrs.max_items;
+    if let Some(tag) = &ctx.top_attr.tag {
+        return Err(tag.error(
+            Diagnostic::ConflictingEnumRepresentation
+                .message("An enum with repr = \"integer\" can't have a tag"),
+        ));
+    }
+    if let Some(untagged) = &ctx.top_attr.untagged {
+        return Err(untagged.error(
+            Diagnostic::ConflictingEnumRepresentation
+                .message("An enum with repr = \"integer\" can't be untagged"),
+        ));
+    }
+    if let Some(variant) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+        return Err(Error::new_spanned(
+            &variant.fields,
+            Diagnostic::InvalidIntegerRepr
+                .message("repr = \"integer\" is only supported on enums without data"),
+        ));
+    }
+    if data.variants.is_empty() {
+        return Err(repr.error(
+            Diagnostic::InvalidIntegerRepr.message("repr = \"integer\" needs at least one variant"),
+        ));
+    }
 
-    if let Some(ty) = schema_attrs.r#type {
-        let format = schema_attrs.format;
+    let mut values = Vec::with_capacity(data.variants.len());
+    let mut next: i128 = 0;
 
-        // Validate type and format combination
-        if let Some(format) = format {
-            if !ty.value().is_compatible_with(format.value()) {
-                let mut err = ty.error(format!("`{format}` is not compatible with {ty}"));
-                let err_format = format.error(format!("`{format}` is not compatible with {ty}"));
+    for variant in &data.variants {
+        let schema_attrs = attr::parse_plain_enum(&variant.attrs, ctx.top_attr.ignore_serde)?;
 
-                err.combine(err_format);
-                return Err(err);
-            }
+        let value = match &variant.discriminant {
+            Some((_, expr)) => discriminant(expr)?,
+            None => next,
+        };
+        next = value + 1;
+
+        if schema_attrs.rename.is_some() {
+            return Err(Error::new_spanned(
+                &variant.ident,
+                Diagnostic::InvalidIntegerRepr.message(
+                    "Variants of an enum with repr = \"integer\" are named by their \
+                     discriminant and can't be renamed",
+                ),
+            ));
+        }
+        if !schema_attrs.aliases.is_empty() {
+            return Err(Error::new_spanned(
+                &variant.ident,
+                Diagnostic::InvalidIntegerRepr.message(
+                    "Variants of an enum with repr = \"integer\" are named by their \
+                     discriminant and can't have aliases",
+                ),
+            ));
         }
+        if !schema_attrs.skip.unwrap_or_default() {
+            values.push(value);
+        }
+    }
 
-        Ok(Schema {
-            r#type: Some(ty.into_inner()),
-            format: format.map(|c| c.into_inner()),
-            description,
-            nullable,
-            max_items,
-            min_items,
-            ..Default::default()
-        })
-    } else {
-        let base = if let Some(as_schema) = &schema_attrs.as_schema {
-            BaseSchema::AsSschema(as_schema.clone())
-        } else if let Some(as_schema_generic) = &schema_attrs.as_schema_generic {
-            BaseSchema::AsSschemaGeneric(as_schema_generic.clone(), item_ty.clone())
-        } else {
-            ctx.constrain(item_ty);
-            BaseSchema::Type(item_ty.clone())
-        };
+    let (minimum, maximum) = (values.iter().min(), values.iter().max());
+    Ok(Schema {
+        r#type: Some(schema::Type::Integer),
+        description: ctx.top_attr.description.clone(),
+        description_from: ctx.top_attr.description_from.clone(),
+        minimum: minimum.map(|&v| v as f64),
+        maximum: maximum.map(|&v| v as f64),
+        r#enum: values.iter().map(|v| Value::Raw(v.to_string())).collect(),
+        ..Default::default()
+    })
+}
 
-        Ok(Schema {
-            description,
-            nullable,
-            max_items,
-            min_items,
-            base,
-            ..Default::default()
-        })
+// Evaluates an explicit discriminant, `3` or `-1`. Anything else, like a
+// constant, can't be known to the macro.
+fn discriminant(expr: &Expr) -> Result<i128, Error> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse(),
+        Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => discriminant(expr).map(|v| -v),
+        Expr::Group(group) => discriminant(&group.expr),
+        Expr::Paren(paren) => discriminant(&paren.expr),
+        _ => Err(Error::new_spanned(
+            expr,
+            Diagnostic::InvalidIntegerRepr.message(
+                "Discriminants of an enum with repr = \"integer\" must be integer literals",
+            ),
+        )),
     }
 }
 
-const IS_ENUM: bool = true;
+// Represents a tagged enum as one object whose tag is a required string
+// enum of the variant names. The tag's description lists the variants' own
+// descriptions.
+//
+// Internally tagged, `#[serde(tag = "...")]`: the fields of every struct
+// variant are merged in next to the tag, none of them required since each
+// belongs to a single variant. Their descriptions say which. Tuple variants
+// are rejected; their data can't be merged without knowing its schema,
+// which only exists at runtime.
+//
+// Adjacently tagged, `#[serde(tag = "...", content = "...")]`: the data sits
+// in the content property instead, merged the same way if every variant
+// with data is a struct variant. Otherwise the content is left unspecified
+// and its description gives each variant's shape. It's only required when
+// every variant has data.
+fn tagged_enum(
+    ctx: &mut Context,
+    data: &DataEnum,
+    tag: Spanned<String>,
+    content: Option<Spanned<String>>,
+) -> Result<Schema, Error> {
+    let rename_all = prepare_rename_all(&ctx.top_attr, IS_ENUM)?;
+    let tag_name = Value::Raw(tag.to_string());
+    let content_name = content.as_ref().map(|c| Value::Raw(c.to_string()));
+    if content_name.as_ref() == Some(&tag_name) {
+        return Err(content.unwrap().error(
+            Diagnostic::ConflictingEnumRepresentation
+                .message("The tag and content can't share a name"),
+        ));
+    }
 
-fn prepare_rename_all(top_attr: &TopAttr, is_enum: bool) -> Result<Option<RenameAll>, Error> {
-    if let Some(style) = top_attr.rename_all {
-        if let Some(ref rename_all_with) = top_attr.rename_all_with {
-            return Err(Error::new(
-                rename_all_with.span(), // The whole Attribute should be spanned
-                "Schema attributes rename_all and rename_all_with can't be both set.",
-            ));
+    let mut variants = Vec::with_capacity(data.variants.len());
+    let mut variant_descriptions = Vec::new();
+    let mut notes = Vec::new();
+    let mut shapes = Vec::new();
+    let mut has_unit = false;
+    let mut has_tuple = false;
+    let mut fields = HashMap::new();
+
+    for variant in &data.variants {
+        let schema_attrs = variant.schema_attrs(&ctx.top_attr)?;
+        if schema_attrs.skip.unwrap_or_default() {
+            continue;
         }
 
-        let rename_all = if is_enum {
-            attr::rename_all_variants(style)
-        } else {
-            attr::rename_all(style)
+        let original_name = variant.name();
+        let variant_name = rename_item(rename_all.as_ref(), &original_name, &schema_attrs);
+        ctx.check_serde_name(
+            variant.ident.span(),
+            &original_name,
+            &variant_name,
+            &schema_attrs,
+            IS_ENUM,
+        );
+
+        let name = match &variant_name {
+            Value::Raw(name) => name.clone(),
+            Value::ReCompute(..) => original_name.clone(),
         };
-        Ok(Some(RenameAll::RenameAll(rename_all)))
-    } else if let Some(ref rename_all_with) = top_attr.rename_all_with {
-        Ok(Some(RenameAll::RenameWith(rename_all_with.clone())))
+        if let Some(description) = &schema_attrs.description {
+            variant_descriptions.push(format!("{name}: {description}"));
+        }
+        if let Some(description_from) = &schema_attrs.description_from {
+            return Err(Error::new_spanned(
+                description_from,
+                Diagnostic::ConflictingDescription.message(
+                    "Variants of tagged enums can't set description_from; their descriptions \
+                     are listed in the tag's",
+                ),
+            ));
+        }
+
+        if !matches!(variant.fields, Fields::Unit)
+            && (schema_attrs.r#type.is_some()
+                || schema_attrs.as_schema.is_some()
+                || schema_attrs.as_schema_generic.is_some())
+        {
+            return Err(Error::new(
+                variant.ident.span(),
+                Diagnostic::InvalidTaggedVariant.message(
+                    "Variants of tagged enums can't set type, as_schema or \
+                     as_schema_generic; their data is merged into the enum's schema",
+                ),
+            ));
+        }
+
+        match &variant.fields {
+            Fields::Unit => {
+                has_unit = true;
+                shapes.push(format!("{name}: none"));
+            }
+            Fields::Named(_) => {
+                let variant_schema = variant.schema(ctx, &schema_attrs)?;
+
+                let mut field_names: Vec<_> = variant_schema
+                    .properties
+                    .keys()
+                    .map(|name| match name {
+                        Value::Raw(name) | Value::ReCompute(_, name) => name.as_str(),
+                    })
+                    .collect();
+                field_names.sort_unstable();
+                shapes.push(format!("{name}: an object with {}", field_names.join(", ")));
+
+                for (field_name, mut field) in variant_schema.properties {
+                    if content_name.is_none() && field_name == tag_name {
+                        return Err(Error::new(
+                            variant.ident.span(),
+                            Diagnostic::InvalidTaggedVariant.message(format!(
+                                "A field of variant `{original_name}` is named like the tag \"{tag}\""
+                            )),
+                        ));
+                    }
+
+                    let note = match &variant_name {
+                        Value::Raw(name) => format!("Only when {tag} is \"{name}\""),
+                        Value::ReCompute(..) => format!("Only for variant {original_name}"),
+                    };
+                    field.description = Some(match field.description {
+                        Some(description) => format!("{description}\n{note}"),
+                        None => note,
+                    });
+
+                    // Variants sharing a field share its schema; serde picks
+                    // whichever the tag names
+                    fields.entry(field_name).or_insert(field);
+                }
+            }
+            Fields::Unnamed(unnamed) => {
+                if content.is_none() {
+                    return Err(Error::new(
+                        variant.ident.span(),
+                        Diagnostic::InvalidTaggedVariant.message(
+                            "Internally tagged enums only support unit and struct variants",
+                        ),
+                    ));
+                }
+                has_tuple = true;
+
+                let types: Vec<_> = unnamed
+                    .unnamed
+                    .iter()
+                    .map(|f| f.ty.to_token_stream().to_string())
+                    .collect();
+                shapes.push(match types.as_slice() {
+                    [ty] => format!("{name}: {ty}"),
+                    types => format!("{name}: an array of {}", types.join(", ")),
+                });
+            }
+        }
+
+        notes.extend(alias_note(&variant_name, &original_name, &schema_attrs));
+        variants.push(variant_name);
+    }
+
+    let mut properties = match content_name {
+        None => fields,
+        Some(ref content_name) => {
+            let shapes = format!("Depends on {tag}:\n{}", shapes.join("\n"));
+            let content = if has_tuple {
+                Schema {
+                    r#type: Some(schema::Type::Unspecified),
+                    description: Some(shapes),
+                    nullable: has_unit.then_some(true),
+                    ..Default::default()
+                }
+            } else {
+                Schema {
+                    r#type: Some(schema::Type::Object),
+                    description: Some(shapes),
+                    nullable: has_unit.then_some(true),
+                    properties: fields,
+                    ..Default::default()
+                }
+            };
+            HashMap::from([(content_name.clone(), content)])
+        }
+    };
+
+    properties.insert(
+        tag_name.clone(),
+        Schema {
+            r#type: Some(schema::Type::String),
+            format: Some(Format::Enum),
+            description: (!variant_descriptions.is_empty())
+                .then(|| variant_descriptions.join("\n")),
+            r#enum: variants,
+            notes,
+            ..Default::default()
+        },
+    );
+
+    let mut required = vec![tag_name];
+    if let Some(content_name) = content_name {
+        if !has_unit {
+            required.push(content_name);
+        }
+    }
+
+    Ok(Schema {
+        r#type: Some(schema::Type::Object),
+        description: ctx.top_attr.description.clone(),
+        description_from: ctx.top_attr.description_from.clone(),
+        nullable: ctx.top_attr.nullable,
+        properties,
+        required,
+        ..Default::default()
+    })
+}
+
+// Represents an enum with data as any of one object per variant, each
+// requiring the variant's name as only field. Unlike the default object
+// with every variant as an optional field, this admits exactly one
+// variant, which is what serde's default representation reads. Unit
+// variants, which serde writes as their bare names, are together one
+// string enum.
+fn one_of_enum(ctx: &mut Context, data: &DataEnum, one_of: Spanned<bool>) -> Result<Schema, Error> {
+    let conflict = [
+        ("tag", ctx.top_attr.tag.is_some()),
+        ("content", ctx.top_attr.content.is_some()),
+        ("untagged", ctx.top_attr.untagged.is_some()),
+        ("repr", ctx.top_attr.repr.is_some()),
+    ]
+    .into_iter()
+    .find_map(|(name, set)| set.then_some(name));
+    if let Some(name) = conflict {
+        return Err(one_of.error(
+            Diagnostic::ConflictingEnumRepresentation
+                .message(format!("A one_of enum can't have {name}")),
+        ));
+    }
+    if data.variants.iter().all(|v| v.fields.is_empty()) {
+        return Err(
+            one_of.error(Diagnostic::MisplacedContainerAttribute.message(
+                "Schema attribute one_of is only supported on enums with data. \
+             Enums without data are already one of their variants' names.",
+            )),
+        );
+    }
+
+    let mut any_of = Vec::with_capacity(data.variants.len());
+    let mut unit_names = Vec::new();
+    let mut unit_notes = Vec::new();
+    for variant in &data.variants {
+        // Each variant is the only field of a struct-like enum of its own,
+        // so that it's named, titled and checked against serde as usual
+        let object = named_struct_like(ctx, std::iter::once(variant), IS_ENUM)?;
+        let Some((name, schema)) = object.properties.into_iter().next() else {
+            // Skipped
+            continue;
+        };
+
+        if matches!(variant.fields, Fields::Unit) {
+            let schema_attrs = variant.schema_attrs(&ctx.top_attr)?;
+            unit_notes.extend(alias_note(&name, &variant.ident.to_string(), &schema_attrs));
+            unit_names.push(name);
+            continue;
+        }
+        any_of.push(Schema {
+            r#type: Some(schema::Type::Object),
+            properties: HashMap::from([(name.clone(), schema)]),
+            required: vec![name],
+            ..Default::default()
+        });
+    }
+
+    if !unit_names.is_empty() {
+        any_of.push(Schema {
+            r#type: Some(schema::Type::String),
+            format: Some(Format::Enum),
+            r#enum: unit_names,
+            notes: unit_notes,
+            ..Default::default()
+        });
+    }
+
+    Ok(Schema {
+        description: ctx.top_attr.description.clone(),
+        description_from: ctx.top_attr.description_from.clone(),
+        nullable: ctx.top_attr.nullable,
+        any_of,
+        ..Default::default()
+    })
+}
+
+// Represents an untagged enum, `#[serde(untagged)]`, as any of its
+// variants' data: struct variants as objects, newtype variants as their
+// inner type and other tuple variants as arrays. Unit variants, which serde
+// reads from null, make the whole schema nullable instead.
+fn untagged_enum(ctx: &mut Context, data: &DataEnum) -> Result<Schema, Error> {
+    let mut any_of = Vec::with_capacity(data.variants.len());
+    let mut nullable = ctx.top_attr.nullable;
+
+    for variant in &data.variants {
+        let schema_attrs = variant.schema_attrs(&ctx.top_attr)?;
+        if schema_attrs.skip.unwrap_or_default() {
+            continue;
+        }
+        if matches!(variant.fields, Fields::Unit) {
+            nullable = Some(true);
+            continue;
+        }
+        any_of.push(variant.schema(ctx, &schema_attrs)?);
+    }
+
+    if any_of.is_empty() {
+        return Err(Error::new_spanned(
+            &ctx.input.ident,
+            Diagnostic::UntaggedWithoutData.message("An untagged enum needs a variant with data"),
+        ));
+    }
+
+    Ok(Schema {
+        description: ctx.top_attr.description.clone(),
+        description_from: ctx.top_attr.description_from.clone(),
+        nullable,
+        any_of,
+        ..Default::default()
+    })
+}
+
+// does constrain
+fn generate_item_schema(
+    ctx: &mut Context,
+    schema_attrs: &Attr,
+    item_ty: &Type,
+) -> Result<Schema, Error> {
+    let title = schema_attrs.title.clone();
+    let description = schema_attrs.description.clone();
+    let description_from = schema_attrs.description_from.clone();
+    let example = schema_attrs.example.clone();
+    let default = schema_attrs.default.clone();
+    let mut nullable = schema_attrs.nullable;
+    let via_schemars = check_via_schemars(schema_attrs, item_ty)?;
+    let as_string = check_as_string(schema_attrs, item_ty)?;
+    if as_string {
+        nullable = nullable.or(is_option(item_ty).then_some(true));
+    }
+    if let Some(values) = &schema_attrs.enum_values {
+        return enum_values_schema(schema_attrs, values, item_ty);
+    }
+    check_items_bounds(
+        schema_attrs.min_items.as_ref(),
+        schema_attrs.max_items.as_ref(),
+        None,
+    )?;
+    if let Some(len) = fixed_array_len(item_ty) {
+        check_array_bounds(
+            schema_attrs.min_items.as_ref(),
+            schema_attrs.max_items.as_ref(),
+            len,
+        )?;
+    }
+    let min_items = schema_attrs.min_items.as_ref().map(Spanned::value);
+    let max_items = schema_attrs.max_items.as_ref().map(Spanned::value);
+    check_numeric_bounds(schema_attrs, item_ty)?;
+    let minimum = schema_attrs.minimum.as_ref().map(Spanned::value);
+    let maximum = schema_attrs.maximum.as_ref().map(Spanned::value);
+    check_pattern(schema_attrs, item_ty)?;
+    let big_int_pattern = big_int_pattern(schema_attrs, item_ty)?;
+    check_length_bounds(schema_attrs, item_ty, big_int_pattern.is_some())?;
+    let min_length = schema_attrs.min_length.as_ref().map(Spanned::value);
+    let max_length = schema_attrs.max_length.as_ref().map(Spanned::value);
+    let pattern = big_int_pattern
+        .map(str::to_owned)
+        .or_else(|| schema_attrs.pattern.clone().map(Spanned::into_inner));
+    let items = match &schema_attrs.items {
+        Some(items_attrs) => Some(list_items_schema(ctx, schema_attrs, items_attrs, item_ty)?),
+        None => None,
+    };
+
+    let format = schema_attrs.format;
+    let ty = match schema_attrs.r#type {
+        Some(ty) => {
+            // Validate type and format combination
+            if let Some(format) = format {
+                if !ty.value().is_compatible_with(format.value()) {
+                    let message = Diagnostic::IncompatibleFormat
+                        .message(format!("`{format}` is not compatible with {ty}"));
+                    let mut err = ty.error(&message);
+                    let err_format = format.error(message);
+
+                    err.combine(err_format);
+                    return Err(err);
+                }
+            }
+            Some(ty.into_inner())
+        }
+        None => (big_int_pattern.is_some() || as_string).then_some(schema::Type::String),
+    };
+
+    if let Some(ty) = ty {
+        Ok(Schema {
+            r#type: Some(ty),
+            format: format.map(|c| c.into_inner()),
+            title,
+            description,
+            description_from,
+            example,
+            default,
+            nullable,
+            max_items,
+            min_items,
+            minimum,
+            maximum,
+            min_length,
+            max_length,
+            pattern,
+            ..Default::default()
+        })
     } else {
-        Ok(None)
+        let base = if let Some(as_schema) = &schema_attrs.as_schema {
+            BaseSchema::AsSschema(as_schema.clone())
+        } else if via_schemars {
+            let crate_path = &ctx.crate_path;
+            BaseSchema::AsSschema(parse_quote!(#crate_path::schema::from_json_schema::<#item_ty>))
+        } else if let Some(as_schema_generic) = &schema_attrs.as_schema_generic {
+            BaseSchema::AsSschemaGeneric(as_schema_generic.clone(), item_ty.clone())
+        } else {
+            ctx.constrain(item_ty);
+            BaseSchema::Type(item_ty.clone())
+        };
+
+        Ok(Schema {
+            format: format.map(|c| c.into_inner()),
+            title,
+            description,
+            description_from,
+            example,
+            default,
+            nullable,
+            max_items,
+            min_items,
+            minimum,
+            maximum,
+            min_length,
+            max_length,
+            pattern,
+            items,
+            base,
+            ..Default::default()
+        })
     }
 }
 
-#[derive(Debug)]
-enum RenameAll {
-    RenameAll(fn(&str) -> String),
-    RenameWith(syn::ExprPath),
+// Rejects item bounds the API won't accept. Against a tuple struct's length,
+// bounds may only be relaxed: min_items lowered and max_items raised.
+fn check_items_bounds(
+    min_items: Option<&Spanned<i64>>,
+    max_items: Option<&Spanned<i64>>,
+    fixed_len: Option<i64>,
+) -> Result<(), Error> {
+    for bound in [min_items, max_items].into_iter().flatten() {
+        if bound.value() < 0 {
+            return Err(
+                bound.error(Diagnostic::InvalidItemBounds.message("Item bounds can't be negative"))
+            );
+        }
+    }
+
+    if let (Some(min), Some(max)) = (min_items, max_items) {
+        if min.value() > max.value() {
+            return Err(min.error(Diagnostic::InvalidItemBounds.message(format!(
+                "min_items ({min}) is greater than max_items ({max})"
+            ))));
+        }
+    }
+
+    if let Some(len) = fixed_len {
+        if let Some(min) = min_items.filter(|min| min.value() > len) {
+            return Err(min.error(Diagnostic::InvalidItemBounds.message(format!(
+                "min_items ({min}) exceeds the fixed length ({len}). It can only be lowered."
+            ))));
+        }
+
+        if let Some(max) = max_items.filter(|max| max.value() < len) {
+            return Err(max.error(Diagnostic::InvalidItemBounds.message(format!(
+                "max_items ({max}) is below the fixed length ({len}). It can only be raised."
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
+// Rejects item bounds on `[T; N]` other than N. Serde reads exactly N items,
+// so telling the model any other length is allowed breaks parsing.
+fn check_array_bounds(
+    min_items: Option<&Spanned<i64>>,
+    max_items: Option<&Spanned<i64>>,
+    len: i64,
+) -> Result<(), Error> {
+    for (name, bound) in [("min_items", min_items), ("max_items", max_items)] {
+        if let Some(bound) = bound.filter(|bound| bound.value() != len) {
+            return Err(bound.error(Diagnostic::InvalidItemBounds.message(format!(
+                "{name} ({bound}) differs from the array's length ({len}). \
+                 Arrays only deserialize from exactly that many items."
+            ))));
+        }
+    }
+    Ok(())
+}
+
+// Rejects numeric bounds on anything but numbers
+fn check_numeric_bounds(schema_attrs: &Attr, item_ty: &Type) -> Result<(), Error> {
+    const NUMBERS: &[&str] = &[
+        "u8",
+        "u16",
+        "u32",
+        "u64",
+        "u128",
+        "usize",
+        "i8",
+        "i16",
+        "i32",
+        "i64",
+        "i128",
+        "isize",
+        "f32",
+        "f64",
+        "NonZeroU8",
+        "NonZeroU16",
+        "NonZeroU32",
+        "NonZeroU64",
+        "NonZeroU128",
+        "NonZeroUsize",
+        "NonZeroI8",
+        "NonZeroI16",
+        "NonZeroI32",
+        "NonZeroI64",
+        "NonZeroI128",
+        "NonZeroIsize",
+    ];
+
+    let Some(bound) = schema_attrs.minimum.or(schema_attrs.maximum) else {
+        return Ok(());
+    };
+
+    if let (Some(min), Some(max)) = (schema_attrs.minimum, schema_attrs.maximum) {
+        if min.value() > max.value() {
+            return Err(min.error(
+                Diagnostic::InvalidNumericBounds
+                    .message(format!("minimum ({min}) is greater than maximum ({max})")),
+            ));
+        }
+    }
+
+    let types = [schema::Type::Number, schema::Type::Integer];
+    if !item_is(schema_attrs, item_ty, &types, NUMBERS) {
+        return Err(bound.error(Diagnostic::InvalidNumericBounds.message(
+            "Schema attributes minimum and maximum are only supported on numbers. \
+             For other types holding numbers, set `r#type = \"Number\"` or `r#type = \"Integer\"`",
+        )));
+    }
+    Ok(())
+}
+
+// Rejects a pattern on anything but strings
+fn check_pattern(schema_attrs: &Attr, item_ty: &Type) -> Result<(), Error> {
+    const STRINGS: &[&str] = &["String", "str", "char", "Path", "PathBuf"];
+
+    let Some(pattern) = &schema_attrs.pattern else {
+        return Ok(());
+    };
+    if !item_is(schema_attrs, item_ty, &[schema::Type::String], STRINGS) {
+        return Err(pattern.error(Diagnostic::PatternOnNonString.message(
+            "Schema attribute pattern is only supported on strings. \
+             For other types holding strings, set `r#type = \"String\"`",
+        )));
+    }
+    Ok(())
+}
+
+// Represents a string item as a string enum of its enum_values, nullable
+// when it's an Option
+fn enum_values_schema(
+    schema_attrs: &Attr,
+    values: &Spanned<Vec<String>>,
+    item_ty: &Type,
+) -> Result<Schema, Error> {
+    const STRINGS: &[&str] = &["String", "str", "Path", "PathBuf"];
+
+    let conflicts = [
+        ("with", schema_attrs.with.is_some()),
+        ("as_schema", schema_attrs.as_schema.is_some()),
+        (
+            "as_schema_generic",
+            schema_attrs.as_schema_generic.is_some(),
+        ),
+        (
+            "format",
+            schema_attrs
+                .format
+                .is_some_and(|format| format.value() != Format::Enum),
+        ),
+        ("min_items", schema_attrs.min_items.is_some()),
+        ("max_items", schema_attrs.max_items.is_some()),
+        ("minimum", schema_attrs.minimum.is_some()),
+        ("maximum", schema_attrs.maximum.is_some()),
+        ("min_length", schema_attrs.min_length.is_some()),
+        ("max_length", schema_attrs.max_length.is_some()),
+        ("pattern", schema_attrs.pattern.is_some()),
+        (
+            "big_int_as_string",
+            schema_attrs.big_int_as_string == Some(true),
+        ),
+        ("items", schema_attrs.items.is_some()),
+    ];
+    if let Some((name, _)) = conflicts.iter().find(|(_, set)| *set) {
+        return Err(values.error(Diagnostic::InvalidEnumValues.message(format!(
+            "Schema attribute {name} can't be combined with enum_values, which make a string enum"
+        ))));
+    }
+
+    let is_string = match schema_attrs.r#type {
+        Some(ty) => ty.value() == schema::Type::String,
+        None => schema_attrs.as_string == Some(true) || names_type(item_ty, STRINGS),
+    };
+    if !is_string {
+        return Err(values.error(Diagnostic::InvalidEnumValues.message(
+            "Schema attribute enum_values is only supported on strings. \
+             For other types holding strings, set `r#type = \"String\"`",
+        )));
+    }
+
+    let values = values.clone().into_inner();
+    if let Some(attr::DefaultValue::String(default)) = &schema_attrs.default {
+        if !values.contains(default) {
+            return Err(Error::new_spanned(
+                item_ty,
+                Diagnostic::InvalidEnumValues.message(format!(
+                    "The default \"{default}\" isn't one of the enum_values"
+                )),
+            ));
+        }
+    }
+
+    Ok(Schema {
+        r#type: Some(schema::Type::String),
+        format: Some(Format::Enum),
+        title: schema_attrs.title.clone(),
+        description: schema_attrs.description.clone(),
+        description_from: schema_attrs.description_from.clone(),
+        example: schema_attrs.example.clone(),
+        default: schema_attrs.default.clone(),
+        nullable: schema_attrs.nullable.or(is_option(item_ty).then_some(true)),
+        r#enum: values.into_iter().map(Value::Raw).collect(),
+        ..Default::default()
+    })
+}
+
+// Whether `ty` is an `Option`
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Group(ty) => is_option(&ty.elem),
+        Type::Paren(ty) => is_option(&ty.elem),
+        Type::Path(ty) if ty.qself.is_none() => ty
+            .path
+            .segments
+            .last()
+            .is_some_and(|last| last.ident == "Option"),
+        _ => false,
+    }
 }
 
-fn rename_item(rename_all: Option<&RenameAll>, item_name: &str, item_attr: &Attr) -> Value<String> {
-    // Apply the rename attribute on the item or fallback to the cont_attr rename_all or the original name
-    macro_rules! or_rename {
-        ($f:expr) => {
-            if let Some(ref rename) = item_attr.rename {
-                Value::Raw(rename.to_string())
-            } else {
-                $f
+// The schema of a list's elements customized by `items(...)`, replacing the
+// one of the list's own schema
+fn list_items_schema(
+    ctx: &mut Context,
+    schema_attrs: &Attr,
+    items_attrs: &Attr,
+    item_ty: &Type,
+) -> Result<Box<Schema>, Error> {
+    if schema_attrs.r#type.is_some()
+        || schema_attrs.as_schema.is_some()
+        || schema_attrs.as_schema_generic.is_some()
+    {
+        return Err(Error::new_spanned(
+            item_ty,
+            Diagnostic::InvalidListItems.message(
+                "items(...) customizes the elements of the list's own schema. It can't be \
+                 combined with r#type, as_schema, as_schema_generic or with",
+            ),
+        ));
+    }
+    let Some(element_ty) = list_element(item_ty) else {
+        return Err(Error::new_spanned(
+            item_ty,
+            Diagnostic::InvalidListItems.message(
+                "Schema attribute items(...) is only supported on lists, like `Vec<T>` or `[T; N]`",
+            ),
+        ));
+    };
+    generate_item_schema(ctx, items_attrs, element_ty).map(Box::new)
+}
+
+// Rejects length bounds the API won't accept, or on anything but strings.
+// Integers marked `big_int_as_string` are strings of digits.
+fn check_length_bounds(schema_attrs: &Attr, item_ty: &Type, big_int: bool) -> Result<(), Error> {
+    const STRINGS: &[&str] = &["String", "str", "Path", "PathBuf"];
+
+    let (min_length, max_length) = (&schema_attrs.min_length, &schema_attrs.max_length);
+    let Some(bound) = min_length.as_ref().or(max_length.as_ref()) else {
+        return Ok(());
+    };
+
+    for bound in [min_length, max_length].into_iter().flatten() {
+        if bound.value() < 0 {
+            return Err(bound.error(
+                Diagnostic::InvalidLengthBounds.message("Length bounds can't be negative"),
+            ));
+        }
+    }
+
+    if let (Some(min), Some(max)) = (min_length, max_length) {
+        if min.value() > max.value() {
+            return Err(min.error(Diagnostic::InvalidLengthBounds.message(format!(
+                "min_length ({min}) is greater than max_length ({max})"
+            ))));
+        }
+    }
+
+    if !big_int && !item_is(schema_attrs, item_ty, &[schema::Type::String], STRINGS) {
+        return Err(bound.error(Diagnostic::InvalidLengthBounds.message(
+            "Schema attributes min_length and max_length are only supported on strings. \
+             For other types holding strings, set `r#type = \"String\"`",
+        )));
+    }
+    Ok(())
+}
+
+// Returns the digits pattern standing in for an integer marked
+// `big_int_as_string`, which otherwise sets no type of its own
+fn big_int_pattern(schema_attrs: &Attr, item_ty: &Type) -> Result<Option<&'static str>, Error> {
+    const UNSIGNED: &[&str] = &["u8", "u16", "u32", "u64", "u128", "usize"];
+    const SIGNED: &[&str] = &["i8", "i16", "i32", "i64", "i128", "isize"];
+
+    if schema_attrs.big_int_as_string != Some(true) {
+        return Ok(None);
+    }
+    if schema_attrs.r#type.is_some()
+        || schema_attrs.format.is_some()
+        || schema_attrs.as_schema.is_some()
+        || schema_attrs.as_schema_generic.is_some()
+        || schema_attrs.pattern.is_some()
+        || schema_attrs.minimum.is_some()
+        || schema_attrs.maximum.is_some()
+    {
+        return Err(Error::new_spanned(
+            item_ty,
+            Diagnostic::InvalidBigIntAsString.message(
+                "big_int_as_string sets the type and pattern itself. It can't be combined with \
+                 r#type, format, as_schema, as_schema_generic, pattern, minimum or maximum",
+            ),
+        ));
+    }
+
+    if names_type(item_ty, UNSIGNED) {
+        Ok(Some("^[0-9]+$"))
+    } else if names_type(item_ty, SIGNED) {
+        Ok(Some("^-?[0-9]+$"))
+    } else {
+        Err(Error::new_spanned(
+            item_ty,
+            Diagnostic::InvalidBigIntAsString
+                .message("big_int_as_string is only supported on integers"),
+        ))
+    }
+}
+
+// Whether an item's schema has one of `types`: its explicit type if set,
+// else whether its Rust type is one of `names`. Custom schemas are taken
+// on trust.
+fn item_is(schema_attrs: &Attr, item_ty: &Type, types: &[schema::Type], names: &[&str]) -> bool {
+    if schema_attrs.as_string == Some(true) {
+        return types.contains(&schema::Type::String);
+    }
+    match schema_attrs.r#type {
+        Some(ty) => types.contains(&ty.value()),
+        None => {
+            schema_attrs.as_schema.is_some()
+                || schema_attrs.as_schema_generic.is_some()
+                || schema_attrs.via_schemars == Some(true)
+                || names_type(item_ty, names)
+        }
+    }
+}
+
+// Whether the item is represented as a string by `as_string`, which gives
+// the schema itself. The type only needs `FromStr` and `Display`, which
+// serde takes care of, so it isn't bound by `AsSchema`.
+fn check_as_string(schema_attrs: &Attr, item_ty: &Type) -> Result<bool, Error> {
+    if schema_attrs.as_string != Some(true) {
+        return Ok(false);
+    }
+    let conflicts = [
+        ("r#type", schema_attrs.r#type.is_some()),
+        ("format", schema_attrs.format.is_some()),
+        ("with", schema_attrs.with.is_some()),
+        ("as_schema", schema_attrs.as_schema.is_some()),
+        (
+            "as_schema_generic",
+            schema_attrs.as_schema_generic.is_some(),
+        ),
+        (
+            "big_int_as_string",
+            schema_attrs.big_int_as_string == Some(true),
+        ),
+        ("items", schema_attrs.items.is_some()),
+        ("min_items", schema_attrs.min_items.is_some()),
+        ("max_items", schema_attrs.max_items.is_some()),
+        ("minimum", schema_attrs.minimum.is_some()),
+        ("maximum", schema_attrs.maximum.is_some()),
+    ];
+    if let Some((name, _)) = conflicts.iter().find(|(_, set)| *set) {
+        return Err(Error::new_spanned(
+            item_ty,
+            Diagnostic::InvalidAsString.message(format!(
+                "as_string gives the item a string schema, so it can't be combined with {name}"
+            )),
+        ));
+    }
+    Ok(true)
+}
+
+// Whether the item takes its schema from `schemars::JsonSchema`, which
+// replaces its own. The type isn't bound by `AsSchema`.
+fn check_via_schemars(schema_attrs: &Attr, item_ty: &Type) -> Result<bool, Error> {
+    if schema_attrs.via_schemars != Some(true) {
+        return Ok(false);
+    }
+    let conflicts = [
+        ("r#type", schema_attrs.r#type.is_some()),
+        ("format", schema_attrs.format.is_some()),
+        ("with", schema_attrs.with.is_some()),
+        ("as_schema", schema_attrs.as_schema.is_some()),
+        (
+            "as_schema_generic",
+            schema_attrs.as_schema_generic.is_some(),
+        ),
+        ("as_string", schema_attrs.as_string == Some(true)),
+        (
+            "big_int_as_string",
+            schema_attrs.big_int_as_string == Some(true),
+        ),
+        ("enum_values", schema_attrs.enum_values.is_some()),
+        ("items", schema_attrs.items.is_some()),
+    ];
+    if let Some((name, _)) = conflicts.iter().find(|(_, set)| *set) {
+        return Err(Error::new_spanned(
+            item_ty,
+            Diagnostic::InvalidViaSchemars.message(format!(
+                "via_schemars takes the item's schema from schemars, so it can't be combined \
+                 with {name}"
+            )),
+        ));
+    }
+    Ok(true)
+}
+
+// Whether a Rust type is one of `names`, possibly behind Option, Box, Rc,
+// Arc, Cow or a reference
+fn names_type(ty: &Type, names: &[&str]) -> bool {
+    match ty {
+        Type::Group(ty) => names_type(&ty.elem, names),
+        Type::Paren(ty) => names_type(&ty.elem, names),
+        Type::Reference(ty) => names_type(&ty.elem, names),
+        Type::Path(ty) if ty.qself.is_none() => {
+            let Some(last) = ty.path.segments.last() else {
+                return false;
+            };
+            let ident = last.ident.to_string();
+            match &last.arguments {
+                syn::PathArguments::None => names.contains(&ident.as_str()),
+                syn::PathArguments::AngleBracketed(args)
+                    if matches!(ident.as_str(), "Option" | "Box" | "Rc" | "Arc" | "Cow") =>
+                {
+                    let mut inner = args.args.iter().filter_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    });
+                    match (inner.next(), inner.next()) {
+                        (Some(ty), None) => names_type(ty, names),
+                        _ => false,
+                    }
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+// Returns N of `[T; N]` (possibly behind a reference) when it's a literal.
+// The element type of a list, possibly behind Option, Box, Rc, Arc, Cow or a
+// reference
+fn list_element(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Array(ty) => Some(&ty.elem),
+        Type::Slice(ty) => Some(&ty.elem),
+        Type::Group(ty) => list_element(&ty.elem),
+        Type::Paren(ty) => list_element(&ty.elem),
+        Type::Reference(ty) => list_element(&ty.elem),
+        Type::Path(ty) if ty.qself.is_none() => {
+            let last = ty.path.segments.last()?;
+            let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+                return None;
+            };
+            let mut inner = args.args.iter().filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            });
+            let (Some(inner), None) = (inner.next(), inner.next()) else {
+                return None;
+            };
+            match last.ident.to_string().as_str() {
+                "Option" | "Box" | "Rc" | "Arc" | "Cow" => list_element(inner),
+                "Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" | "BinaryHeap" => {
+                    Some(inner)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn fixed_array_len(ty: &Type) -> Option<i64> {
+    match ty {
+        Type::Array(array) => match &array.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) => len.base10_parse().ok(),
+            _ => None,
+        },
+        Type::Group(ty) => fixed_array_len(&ty.elem),
+        Type::Paren(ty) => fixed_array_len(&ty.elem),
+        Type::Reference(ty) => fixed_array_len(&ty.elem),
+        _ => None,
+    }
+}
+
+const IS_ENUM: bool = true;
+
+fn prepare_rename_all(top_attr: &TopAttr, is_enum: bool) -> Result<Option<RenameAll>, Error> {
+    if let Some(style) = top_attr.rename_all {
+        if let Some(ref rename_all_with) = top_attr.rename_all_with {
+            return Err(Error::new(
+                rename_all_with.span(), // The whole Attribute should be spanned
+                Diagnostic::ConflictingRenameAll
+                    .message("Schema attributes rename_all and rename_all_with can't be both set."),
+            ));
+        }
+
+        let rename_all = if is_enum {
+            attr::rename_all_variants(style)
+        } else {
+            attr::rename_all(style)
+        };
+        Ok(Some(RenameAll::RenameAll(rename_all)))
+    } else if let Some(ref rename_all_with) = top_attr.rename_all_with {
+        Ok(Some(RenameAll::RenameWith(rename_all_with.clone())))
+    } else {
+        Ok(None)
+    }
+}
+
+#[derive(Debug)]
+enum RenameAll {
+    RenameAll(fn(&str) -> String),
+    RenameWith(syn::ExprPath),
+}
+
+/// Sentence-cases an identifier for a title, e.g. `first_name` or
+/// `FirstName` to "First name"
+fn title_from_ident(ident: &str, is_pascal: bool) -> String {
+    let snake = if is_pascal {
+        attr::rename_all_variants(attr::Case::Snake)(ident)
+    } else {
+        ident.trim_start_matches("r#").to_owned()
+    };
+
+    let mut words = snake.split('_').filter(|word| !word.is_empty());
+    let mut title = String::with_capacity(snake.len());
+    if let Some(first) = words.next() {
+        let mut chars = first.chars();
+        title.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        title.push_str(chars.as_str());
+    }
+    for word in words {
+        title.push(' ');
+        title.push_str(word);
+    }
+    title
+}
+
+fn rename_item(rename_all: Option<&RenameAll>, item_name: &str, item_attr: &Attr) -> Value<String> {
+    // Apply the rename attribute on the item or fallback to the cont_attr rename_all or the original name
+    macro_rules! or_rename {
+        ($f:expr) => {
+            if let Some(ref rename) = item_attr.rename {
+                Value::Raw(rename.to_string())
+            } else {
+                $f
+            }
+        };
+    }
+
+    match rename_all {
+        Some(RenameAll::RenameAll(rename_all)) => or_rename!(Value::Raw(rename_all(item_name))),
+        Some(RenameAll::RenameWith(rename_all_with)) => or_rename!(Value::ReCompute(
+            rename_all_with.clone(),
+            item_name.to_string()
+        )),
+        None => or_rename!(Value::Raw(item_name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use syn::WhereClause;
+
+    use super::*;
+    use attr::DefaultValue;
+
+    #[test]
+    fn context_init() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            crate_path: Path,
+            trait_bound: TraitBound,
+        }
+
+        let tests = [
+            Test {
+                title: "crate specified",
+                input: parse_quote! {
+                    #[schema(crate_path = "crate_path")]
+                    struct S {
+
+                    }
+                },
+                crate_path: parse_quote!(crate_path),
+                trait_bound: parse_quote!(crate_path::AsSchema),
+            },
+            Test {
+                title: "crate unspecified",
+                input: parse_quote! {
+                    struct S {
+                    }
+                },
+                crate_path: parse_quote!(::google_ai_rs),
+                trait_bound: parse_quote!(::google_ai_rs::AsSchema),
+            },
+        ];
+
+        for test in tests {
+            println!("title: {}", test.title);
+            let ctx = Context::new(test.input).unwrap();
+            assert_eq!(ctx.crate_path, test.crate_path);
+            assert_eq!(ctx.trait_bound, test.trait_bound);
+            assert!(!ctx.has_static);
+        }
+    }
+
+    #[test]
+    fn context_constrain() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            where_clause: Option<WhereClause>,
+            // we test that we're properly supporting serde by recognizing 'static
+            has_static: bool,
+        }
+
+        let tests = [
+            Test {
+                title: "plain static",
+                input: parse_quote! {
+                    struct S {
+                        field: &'static Type
+                    }
+                },
+                where_clause: Some(parse_quote! {where Type: ::google_ai_rs::AsSchema}),
+                has_static: true,
+            },
+            Test {
+                title: "no static",
+                input: parse_quote! {
+                    struct S {
+                        field: staticType
+                    }
+                },
+                where_clause: Some(parse_quote! {where staticType: ::google_ai_rs::AsSchema}),
+                has_static: false,
+            },
+            Test {
+                title: "generic lifetime",
+                input: parse_quote! {
+                    struct S<'a> {
+                        field: &'a Type,
+                    }
+                },
+                where_clause: Some(parse_quote! {where Type: ::google_ai_rs::AsSchema}),
+                has_static: false,
+            },
+            Test {
+                title: "inside static",
+                input: parse_quote! {
+                    struct S {
+                        field: Cow<'static, Type>
+                    }
+                },
+                where_clause: Some(
+                    parse_quote! {where Cow<'static, Type>: ::google_ai_rs::AsSchema},
+                ),
+                has_static: true,
+            },
+            Test {
+                title: "skipped",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(skip)]
+                        field: Cow<'static, Type>
+                    }
+                },
+                where_clause: None,
+                has_static: false,
+            },
+            Test {
+                title: "skipped (1)",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(r#type = "String")]
+                        external: Type
+                    }
+                },
+                where_clause: None,
+                has_static: false,
+            },
+            Test {
+                title: "skipped (2)",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(as_schema = "type_as_schema")]
+                        external: Type
+                    }
+                },
+                where_clause: None,
+                has_static: false,
+            },
+            Test {
+                title: "skipped (3)",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(as_schema_generic = "wrapper_as_schema_generic")]
+                        external: Wrapper<Type>
+                    }
+                },
+                where_clause: None,
+                has_static: false,
+            },
+            Test {
+                title: "generated and static",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(r#type = "String")]
+                        external: &'static Type
+                    }
+                },
+                where_clause: None,
+                has_static: true,
+            },
+            Test {
+                title: "static in skipped variant",
+                input: parse_quote! {
+                    enum E {
+                        #[schema(skip)]
+                        A(&'static Type),
+                        B(u8),
+                    }
+                },
+                where_clause: Some(parse_quote! {where u8: ::google_ai_rs::AsSchema}),
+                has_static: false,
+            },
+            Test {
+                title: "double bound",
+                input: parse_quote! {
+                    struct S {
+                        field: Type,
+                        field1: Type
+                    }
+                },
+                where_clause: Some(parse_quote! {where Type: ::google_ai_rs::AsSchema}),
+                has_static: false,
+            },
+            Test {
+                title: "wrapped",
+                input: parse_quote! {
+                    struct S {
+                        field: Option<Vec<Box<[Type; 2]>>>,
+                        field1: Arc<Type>,
+                        field2: Wrapper<Type>,
+                        field3: HashMap<String, Type>,
+                    }
+                },
+                where_clause: Some(parse_quote! {
+                    where
+                        Type: ::google_ai_rs::AsSchema,
+                        Wrapper<Type>: ::google_ai_rs::AsSchema,
+                        HashMap<String, Type>: ::google_ai_rs::AsSchema
+                }),
+                has_static: false,
+            },
+            Test {
+                title: "double bound exists",
+                input: parse_quote! {
+                    struct S<T: ::google_ai_rs::AsSchema> {
+                        field: T,
+                    }
+                },
+                where_clause: Some(parse_quote! {where T: ::google_ai_rs::AsSchema}),
+                has_static: false,
+            },
+        ];
+
+        for test in tests {
+            println!("title: {}", test.title);
+            let mut ctx = Context::new(test.input).unwrap();
+            _ = generate_schema(&mut ctx);
+
+            assert_eq!(ctx.input.generics.where_clause, test.where_clause);
+            assert_eq!(ctx.has_static, test.has_static);
+        }
+    }
+
+    #[test]
+    fn test_derive_schema() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            want: Option<Schema>,
+            should_fail: bool,
+            error_like: Option<Vec<&'static str>>,
+        }
+
+        // Test as_schema&_generic
+        let tests = [
+            Test {
+                title: "unit struct",
+                input: parse_quote! {
+                    #[schema(description = "unit struct")]
+                    #[schema(nullable = "false")]
+                    struct U;
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::Object),
+                    description: Some("unit struct".to_owned()),
+                    nullable: Some(false),
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "tuple intended for AsSchemaWithSerde",
+                input: parse_quote! {
+                    #[schema(description = "Represents a radioactive element")]
+                    struct T(
+                       #[schema(description = "Element name")] String,
+                       #[schema(description = "Half life")] f64,
+                    );
+                },
+                want: None,
+                should_fail: true,
+                error_like: Some(vec!["AsSchemaWithSerde"]),
+            },
+            Test {
+                title: "tuple struct",
+                input: parse_quote! {
+                    struct T(String, f64);
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::Array),
+                    items: Some(
+                        Schema {
+                            r#type: Some(schema::Type::Unspecified),
+                            ..Default::default()
+                        }
+                        .into(),
+                    ),
+                    max_items: Some(2),
+                    min_items: Some(2),
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "enum",
+                input: parse_quote! {
+                    enum E {
+                        Variant1,
+                        Variant2,
+                    }
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::String),
+                    format: Some(Format::Enum),
+                    r#enum: vec![Value::Raw("Variant1".into()), Value::Raw("Variant2".into())],
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "named struct",
+                input: parse_quote! {
+                    struct S<'a, T, U> {
+                        field: Vec<T>,
+                        field1: Option<&'a U>,
+                    }
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::Object),
+                    properties: [
+                        (
+                            Value::Raw("field".into()),
+                            Schema {
+                                base: BaseSchema::Type(parse_quote!(Vec<T>)),
+                                ..Default::default()
+                            },
+                        ),
+                        (
+                            Value::Raw("field1".into()),
+                            Schema {
+                                base: BaseSchema::Type(parse_quote!(Option<&'a U>)),
+                                ..Default::default()
+                            },
+                        ),
+                    ]
+                    .into(),
+                    required: vec![Value::Raw("field".into()), Value::Raw("field1".into())],
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "rename_all_with",
+                input: parse_quote! {
+                    #[schema(rename_all_with = "suitcase")]
+                    struct S {
+                        field: (),
+                        field1: (),
+                    }
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::Object),
+                    properties: [
+                        (
+                            Value::ReCompute(parse_quote!(suitcase), "field".into()),
+                            Schema {
+                                base: BaseSchema::Type(parse_quote!(())),
+                                ..Default::default()
+                            },
+                        ),
+                        (
+                            Value::ReCompute(parse_quote!(suitcase), "field1".into()),
+                            Schema {
+                                base: BaseSchema::Type(parse_quote!(())),
+                                ..Default::default()
+                            },
+                        ),
+                    ]
+                    .into(),
+                    required: vec![
+                        Value::ReCompute(parse_quote!(suitcase), "field".into()),
+                        Value::ReCompute(parse_quote!(suitcase), "field1".into()),
+                    ],
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "rename_all_with (1)",
+                input: parse_quote! {
+                    #[schema(rename_all_with = "misc::prettycase")]
+                    enum Enum {
+                        Variant1,
+                        Variant2,
+                    }
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::String),
+                    format: Some(Format::Enum),
+                    r#enum: vec![
+                        Value::ReCompute(parse_quote!(misc::prettycase), "Variant1".into()),
+                        Value::ReCompute(parse_quote!(misc::prettycase), "Variant2".into()),
+                    ],
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "as_schema",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(as_schema = "concrete::as_schema")]
+                        field: Type
+                    }
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::Object),
+                    properties: [(
+                        Value::Raw("field".into()),
+                        Schema {
+                            base: BaseSchema::AsSschema(parse_quote!(concrete::as_schema)),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                    required: vec![Value::Raw("field".into())],
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "as_schema_generic",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(as_schema_generic = "generic::as_schema_generic")]
+                        field: Wrapper<Type>
+                    }
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::Object),
+                    properties: [(
+                        Value::Raw("field".into()),
+                        Schema {
+                            base: BaseSchema::AsSschemaGeneric(
+                                parse_quote!(generic::as_schema_generic),
+                                parse_quote!(Wrapper<Type>),
+                            ),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                    required: vec![Value::Raw("field".into())],
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "with",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(with = "time::rfc3339", description = "When")]
+                        field: SystemTime
+                    }
+                },
+                want: Some(Schema {
+                    r#type: Some(schema::Type::Object),
+                    properties: [(
+                        Value::Raw("field".into()),
+                        Schema {
+                            base: BaseSchema::AsSschema(parse_quote!(time::rfc3339::as_schema)),
+                            description: Some("When".into()),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                    required: vec![Value::Raw("field".into())],
+                    ..Default::default()
+                }),
+                should_fail: false,
+                error_like: None,
+            },
+            Test {
+                title: "with and as_schema",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(with = "time::rfc3339", as_schema = "other")]
+                        field: SystemTime
+                    }
+                },
+                want: None,
+                should_fail: true,
+                error_like: Some(vec!["E021"]),
+            },
+        ];
+
+        for test in tests {
+            println!("title: {}", test.title);
+            let derived = derive_schema_base(test.input);
+
+            if test.should_fail {
+                match derived {
+                    Ok(_) => panic!("test did not fail"),
+                    Err(err) => {
+                        if let Some(error_like) = test.error_like {
+                            let mut matches = false;
+                            let err = err.to_string();
+
+                            for like in error_like {
+                                matches = matches || err.contains(like)
+                            }
+                            println!("{err}");
+                            assert!(matches);
+                        }
+                    }
+                }
+            } else {
+                let derived = derived.unwrap_or_else(|err| panic!("test failed: {err:#?}"));
+                assert_eq!(derived.schema, test.want.unwrap())
+            }
+        }
+    }
+
+    #[test]
+    fn struct_variant_rename_all_with() {
+        let derived = derive_schema_base(parse_quote! {
+            #[schema(rename_all_with = "suitcase")]
+            enum E {
+                #[schema(rename_all_with = "misc::prettycase")]
+                Pretty { field: () },
+                Plain { field: () },
+            }
+        })
+        .unwrap();
+
+        let fields_of = |variant: &str| {
+            let variant = Value::ReCompute(parse_quote!(suitcase), variant.into());
+            let mut keys: Vec<_> = derived.schema.properties[&variant]
+                .properties
+                .keys()
+                .cloned()
+                .collect();
+            keys.sort_by_key(|k| format!("{k:?}"));
+            keys
+        };
+
+        assert_eq!(
+            fields_of("Pretty"),
+            vec![Value::ReCompute(
+                parse_quote!(misc::prettycase),
+                "field".into()
+            )]
+        );
+        assert_eq!(
+            fields_of("Plain"),
+            vec![Value::ReCompute(parse_quote!(suitcase), "field".into())]
+        );
+    }
+
+    #[test]
+    fn rename_all_fields() {
+        let derived = derive_schema_base(parse_quote! {
+            #[schema(rename_all = "snake_case", rename_all_fields = "camelCase")]
+            enum E {
+                FirstVariant { field_one: () },
+                #[schema(rename_all = "SCREAMING_SNAKE_CASE")]
+                Second { field_one: () },
+            }
+        })
+        .unwrap();
+
+        let fields_of = |variant: &str| {
+            let variant = &derived.schema.properties[&Value::Raw(variant.to_owned())];
+            variant.properties.keys().cloned().collect::<Vec<_>>()
+        };
+        assert_eq!(fields_of("first_variant"), [Value::Raw("fieldOne".into())]);
+        assert_eq!(fields_of("second"), [Value::Raw("FIELD_ONE".into())]);
+
+        let err = derive_schema_base(parse_quote! {
+            #[schema(rename_all_fields = "camelCase")]
+            struct S { field_one: () }
+        })
+        .map(|_| ())
+        .unwrap_err();
+        assert!(err.to_string().contains("only supported on enums"), "{err}");
+    }
+
+    #[test]
+    fn serde_name_warnings() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            // Substrings of the expected warnings, in order
+            warnings: Vec<&'static str>,
+        }
+
+        let tests = [
+            Test {
+                title: "no serde",
+                input: parse_quote! {
+                    #[schema(rename_all = "camelCase")]
+                    struct S { field_one: () }
+                },
+                warnings: vec![],
+            },
+            Test {
+                title: "serde only",
+                input: parse_quote! {
+                    #[serde(rename_all = "camelCase")]
+                    struct S { field_one: () }
+                },
+                warnings: vec![],
+            },
+            Test {
+                title: "diverging rename_all",
+                input: parse_quote! {
+                    #[schema(rename_all = "camelCase")]
+                    #[serde(rename_all = "snake_case")]
+                    struct S { field_one: (), field: () }
+                },
+                warnings: vec!["\"fieldOne\" but serde expects \"field_one\""],
+            },
+            Test {
+                title: "diverging rename",
+                input: parse_quote! {
+                    enum E {
+                        #[serde(rename = "a")]
+                        #[schema(rename = "b")]
+                        A,
+                    }
+                },
+                warnings: vec!["\"b\" but serde expects \"a\""],
+            },
+            Test {
+                title: "rename_all_with",
+                input: parse_quote! {
+                    #[schema(rename_all_with = "suitcase")]
+                    #[serde(deny_unknown_fields)]
+                    struct S { field: () }
+                },
+                warnings: vec!["`rename_all_with`"],
+            },
+            Test {
+                title: "struct variant",
+                input: parse_quote! {
+                    enum E {
+                        #[serde(rename_all = "camelCase")]
+                        #[schema(rename_all = "kebab-case")]
+                        V { field_one: () },
+                    }
+                },
+                warnings: vec!["\"field-one\" but serde expects \"fieldOne\""],
+            },
+            Test {
+                title: "serde rename_all_fields",
+                input: parse_quote! {
+                    #[serde(rename_all_fields = "camelCase")]
+                    enum E {
+                        V { field_one: () },
+                    }
+                },
+                warnings: vec![],
+            },
+            Test {
+                title: "diverging rename_all_fields",
+                input: parse_quote! {
+                    #[schema(rename_all_fields = "kebab-case")]
+                    #[serde(rename_all_fields = "camelCase")]
+                    enum E {
+                        V { field_one: () },
+                    }
+                },
+                warnings: vec!["\"field-one\" but serde expects \"fieldOne\""],
+            },
+            Test {
+                title: "ignore_serde",
+                input: parse_quote! {
+                    #[schema(ignore_serde)]
+                    #[schema(rename_all = "camelCase")]
+                    #[serde(rename_all = "snake_case")]
+                    struct S { field_one: () }
+                },
+                warnings: vec![],
+            },
+            Test {
+                title: "with in serde too",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(with = "time::seconds")]
+                        #[serde(with = "time::seconds")]
+                        a: Duration,
+                        #[schema(with = "time::seconds")]
+                        #[serde(deserialize_with = "time::seconds::deserialize")]
+                        b: Duration,
+                    }
+                },
+                warnings: vec![],
+            },
+            Test {
+                title: "with missing from serde",
+                input: parse_quote! {
+                    #[serde(deny_unknown_fields)]
+                    struct S {
+                        #[schema(with = "time::seconds")]
+                        a: Duration,
+                    }
+                },
+                warnings: vec!["`#[serde(with = \"time::seconds\")]`"],
+            },
+        ];
+
+        for test in tests {
+            println!("title: {}", test.title);
+            let derived = derive_schema_base(test.input).unwrap();
+            let warnings = &derived.ctx.warnings;
+            assert_eq!(warnings.len(), test.warnings.len(), "{warnings:?}");
+            for ((_, _, warning), like) in warnings.iter().zip(test.warnings) {
+                assert!(warning.contains(like), "{warning}");
             }
-        };
+        }
     }
 
-    match rename_all {
-        Some(RenameAll::RenameAll(rename_all)) => or_rename!(Value::Raw(rename_all(item_name))),
-        Some(RenameAll::RenameWith(rename_all_with)) => or_rename!(Value::ReCompute(
-            rename_all_with.clone(),
-            item_name.to_string()
-        )),
-        None => or_rename!(Value::Raw(item_name.to_string())),
-    }
-}
+    #[test]
+    fn items_bounds() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            // (min_items, max_items) of the struct or its only field, or an error like
+            want: Result<(Option<i64>, Option<i64>), &'static str>,
+        }
 
-#[cfg(test)]
-mod test {
-    use syn::WhereClause;
+        let tests = [
+            Test {
+                title: "array length repeated",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(min_items = 4, max_items = 4)]
+                        field: [u8; 4],
+                    }
+                },
+                want: Ok((Some(4), Some(4))),
+            },
+            Test {
+                title: "relaxed array",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(min_items = 2, max_items = 6)]
+                        field: [u8; 4],
+                    }
+                },
+                want: Err("min_items (2) differs from the array's length (4)"),
+            },
+            Test {
+                title: "raised max",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(max_items = 5)]
+                        field: [u8; 4],
+                    }
+                },
+                want: Err("max_items (5) differs"),
+            },
+            Test {
+                title: "tightened min",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(min_items = 5)]
+                        field: [u8; 4],
+                    }
+                },
+                want: Err("min_items (5) differs"),
+            },
+            Test {
+                title: "tightened max behind reference",
+                input: parse_quote! {
+                    struct S<'a> {
+                        #[schema(max_items = 3)]
+                        field: &'a [u8; 4],
+                    }
+                },
+                want: Err("max_items (3) differs"),
+            },
+            Test {
+                title: "const generic length",
+                input: parse_quote! {
+                    struct S<const N: usize> {
+                        #[schema(max_items = 3)]
+                        field: [u8; N],
+                    }
+                },
+                want: Ok((None, Some(3))),
+            },
+            Test {
+                title: "inverted",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(min_items = 3, max_items = 2)]
+                        field: Vec<u8>,
+                    }
+                },
+                want: Err("greater than max_items"),
+            },
+            Test {
+                title: "negative",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(min_items = -1)]
+                        field: Vec<u8>,
+                    }
+                },
+                want: Err("negative"),
+            },
+            Test {
+                title: "relaxed tuple struct",
+                input: parse_quote! {
+                    #[schema(min_items = 1)]
+                    struct T(u8, u8);
+                },
+                want: Ok((Some(1), Some(2))),
+            },
+            Test {
+                title: "tightened tuple struct",
+                input: parse_quote! {
+                    #[schema(max_items = 1)]
+                    struct T(u8, u8);
+                },
+                want: Err("can only be raised"),
+            },
+            Test {
+                title: "tightened tuple variant",
+                input: parse_quote! {
+                    enum E {
+                        #[schema(min_items = 3)]
+                        V(u8, u8),
+                    }
+                },
+                want: Err("can only be lowered"),
+            },
+            Test {
+                title: "container bounds on named struct",
+                input: parse_quote! {
+                    #[schema(min_items = 1)]
+                    struct S {
+                        field: Vec<u8>,
+                    }
+                },
+                want: Err("tuple structs"),
+            },
+        ];
 
-    use super::*;
+        for test in tests {
+            println!("title: {}", test.title);
+            let derived = derive_schema_base(test.input);
+
+            match test.want {
+                Ok(want) => {
+                    let schema = derived
+                        .unwrap_or_else(|err| panic!("test failed: {err:#?}"))
+                        .schema;
+                    let schema = schema.properties.values().next().unwrap_or(&schema);
+                    assert_eq!((schema.min_items, schema.max_items), want);
+                }
+                Err(like) => match derived {
+                    Ok(_) => panic!("test did not fail"),
+                    Err(err) => assert!(err.to_string().contains(like), "{err}"),
+                },
+            }
+        }
+    }
 
     #[test]
-    fn context_init() {
+    fn numeric_bounds() {
         struct Test {
             title: &'static str,
             input: DeriveInput,
-            crate_path: Path,
-            trait_bound: TraitBound,
+            // (minimum, maximum) of the only field, or an error like
+            want: Result<(Option<f64>, Option<f64>), &'static str>,
         }
 
         let tests = [
             Test {
-                title: "crate specified",
+                title: "integer",
                 input: parse_quote! {
-                    #[schema(crate_path = "crate_path")]
                     struct S {
-
+                        #[schema(minimum = 0, maximum = 100)]
+                        field: u8,
                     }
                 },
-                crate_path: parse_quote!(crate_path),
-                trait_bound: parse_quote!(crate_path::AsSchema),
+                want: Ok((Some(0.0), Some(100.0))),
             },
             Test {
-                title: "crate unspecified",
+                title: "optional float",
                 input: parse_quote! {
                     struct S {
+                        #[schema(minimum = -1.5)]
+                        field: Option<f64>,
                     }
                 },
-                crate_path: parse_quote!(::google_ai_rs),
-                trait_bound: parse_quote!(::google_ai_rs::AsSchema),
+                want: Ok((Some(-1.5), None)),
+            },
+            Test {
+                title: "explicit type",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(r#type = "Integer", maximum = 10)]
+                        field: Score,
+                    }
+                },
+                want: Ok((None, Some(10.0))),
+            },
+            Test {
+                title: "newtype variant field",
+                input: parse_quote! {
+                    enum E {
+                        V(#[schema(maximum = 1)] f32),
+                    }
+                },
+                want: Ok((None, Some(1.0))),
+            },
+            Test {
+                title: "string",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(minimum = 0)]
+                        field: String,
+                    }
+                },
+                want: Err("only supported on numbers"),
+            },
+            Test {
+                title: "explicit non-numeric type",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(r#type = "String", minimum = 0)]
+                        field: u8,
+                    }
+                },
+                want: Err("only supported on numbers"),
+            },
+            Test {
+                title: "inverted",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(minimum = 2, maximum = 1)]
+                        field: i32,
+                    }
+                },
+                want: Err("greater than maximum"),
+            },
+            Test {
+                title: "not a number",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(minimum = "0")]
+                        field: i32,
+                    }
+                },
+                want: Err("Expected a number"),
+            },
+            Test {
+                title: "on a variant",
+                input: parse_quote! {
+                    enum E {
+                        #[schema(minimum = 0)]
+                        V(u8),
+                    }
+                },
+                want: Err("minimum"),
             },
         ];
 
         for test in tests {
             println!("title: {}", test.title);
-            let ctx = Context::new(test.input).unwrap();
-            assert_eq!(ctx.crate_path, test.crate_path);
-            assert_eq!(ctx.trait_bound, test.trait_bound);
-            assert!(!ctx.has_static);
+            let derived = derive_schema_base(test.input);
+
+            match test.want {
+                Ok(want) => {
+                    let schema = derived
+                        .unwrap_or_else(|err| panic!("test failed: {err:#?}"))
+                        .schema;
+                    let schema = schema.properties.values().next().unwrap_or(&schema);
+                    assert_eq!((schema.minimum, schema.maximum), want);
+                }
+                Err(like) => match derived {
+                    Ok(_) => panic!("test did not fail"),
+                    Err(err) => assert!(err.to_string().contains(like), "{err}"),
+                },
+            }
         }
     }
 
     #[test]
-    fn context_constrain() {
+    fn pattern() {
         struct Test {
             title: &'static str,
             input: DeriveInput,
-            where_clause: Option<WhereClause>,
-            // we test that we're properly supporting serde by recognizing 'static
-            has_static: bool,
+            // pattern of the only field, or an error like
+            want: Result<&'static str, &'static str>,
         }
 
         let tests = [
             Test {
-                title: "plain static",
+                title: "string",
                 input: parse_quote! {
                     struct S {
-                        field: &'static Type
+                        #[schema(pattern = "^[A-Z]{2}\\d{4}$")]
+                        field: String,
                     }
                 },
-                where_clause: Some(parse_quote! {where &'static Type: ::google_ai_rs::AsSchema}),
-                has_static: true,
+                want: Ok("^[A-Z]{2}\\d{4}$"),
             },
             Test {
-                title: "no static",
+                title: "borrowed",
+                input: parse_quote! {
+                    struct S<'a> {
+                        #[schema(pattern = "^a")]
+                        field: Option<Cow<'a, str>>,
+                    }
+                },
+                want: Ok("^a"),
+            },
+            Test {
+                title: "explicit type",
                 input: parse_quote! {
                     struct S {
-                        field: staticType
+                        #[schema(r#type = "String", pattern = "^a")]
+                        field: Code,
                     }
                 },
-                where_clause: Some(parse_quote! {where staticType: ::google_ai_rs::AsSchema}),
-                has_static: false,
+                want: Ok("^a"),
             },
             Test {
-                title: "generic lifetime",
+                title: "number",
                 input: parse_quote! {
-                    struct S<'a> {
-                        field: &'a Type,
+                    struct S {
+                        #[schema(pattern = "^1")]
+                        field: u32,
                     }
                 },
-                where_clause: Some(parse_quote! {where &'a Type: ::google_ai_rs::AsSchema}),
-                has_static: false,
+                want: Err("only supported on strings"),
             },
             Test {
-                title: "inside static",
+                title: "list of strings",
                 input: parse_quote! {
                     struct S {
-                        field: Cow<'static, Type>
+                        #[schema(pattern = "^a")]
+                        field: Vec<String>,
+                    }
+                },
+                want: Err("only supported on strings"),
+            },
+        ];
+
+        for test in tests {
+            println!("title: {}", test.title);
+            let derived = derive_schema_base(test.input);
+
+            match test.want {
+                Ok(want) => {
+                    let schema = derived
+                        .unwrap_or_else(|err| panic!("test failed: {err:#?}"))
+                        .schema;
+                    let schema = schema.properties.values().next().unwrap();
+                    assert_eq!(schema.pattern.as_deref(), Some(want));
+                }
+                Err(like) => match derived {
+                    Ok(_) => panic!("test did not fail"),
+                    Err(err) => assert!(err.to_string().contains(like), "{err}"),
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn length_bounds() {
+        struct Test {
+            title: &'static str,
+            input: DeriveInput,
+            // (min_length, max_length) of the only field, or an error like
+            want: Result<(Option<i64>, Option<i64>), &'static str>,
+        }
+
+        let tests = [
+            Test {
+                title: "string",
+                input: parse_quote! {
+                    struct S {
+                        #[schema(min_length = 1, max_length = 280)]
+                        field: String,
                     }
                 },
-                where_clause: Some(
-                    parse_quote! {where Cow<'static, Type>: ::google_ai_rs::AsSchema},
-                ),
-                has_static: true,
+                want: Ok((Some(1), Some(280))),
             },
             Test {
-                title: "skipped",
+                title: "borrowed",
                 input: parse_quote! {
-                    struct S {
-                        #[schema(skip)]
-                        field: Cow<'static, Type>
+                    struct S<'a> {
+                        #[schema(max_length = 8)]
+                        field: Option<&'a str>,
                     }
                 },
-                where_clause: None,
-                has_static: false,
+                want: Ok((None, Some(8))),
             },
             Test {
-                title: "skipped (1)",
+                title: "explicit type",
                 input: parse_quote! {
                     struct S {
-                        #[schema(r#type = "String")]
-                        external: Type
+                        #[schema(r#type = "String", min_length = 3)]
+                        field: Code,
                     }
                 },
-                where_clause: None,
-                has_static: false,
+                want: Ok((Some(3), None)),
             },
             Test {
-                title: "skipped (2)",
+                title: "big int as string",
                 input: parse_quote! {
                     struct S {
-                        #[schema(as_schema = "type_as_schema")]
-                        external: Type
+                        #[schema(big_int_as_string, max_length = 20)]
+                        field: u64,
                     }
                 },
-                where_clause: None,
-                has_static: false,
+                want: Ok((None, Some(20))),
             },
             Test {
-                title: "skipped (3)",
+                title: "char",
                 input: parse_quote! {
                     struct S {
-                        #[schema(as_schema_generic = "wrapper_as_schema_generic")]
-                        external: Wrapper<Type>
+                        #[schema(max_length = 2)]
+                        field: char,
                     }
                 },
-                where_clause: None,
-                has_static: false,
+                want: Err("only supported on strings"),
             },
             Test {
-                title: "generated and skipped - static false negative", // this is admissibly a bug that I won't fix yet
+                title: "list of strings",
                 input: parse_quote! {
                     struct S {
-                        #[schema(r#type = "String")]
-                        external: &'static Type
+                        #[schema(min_length = 1)]
+                        field: Vec<String>,
                     }
                 },
-                where_clause: None,
-                has_static: false,
+                want: Err("only supported on strings"),
             },
             Test {
-                title: "double bound",
+                title: "negative",
                 input: parse_quote! {
                     struct S {
-                        field: Type,
-                        field1: Type
+                        #[schema(min_length = -1)]
+                        field: String,
                     }
                 },
-                where_clause: Some(parse_quote! {where Type: ::google_ai_rs::AsSchema}),
-                has_static: false,
+                want: Err("can't be negative"),
             },
             Test {
-                title: "double bound exists",
+                title: "min over max",
                 input: parse_quote! {
-                    struct S<T: ::google_ai_rs::AsSchema> {
-                        field: T,
+                    struct S {
+                        #[schema(min_length = 5, max_length = 4)]
+                        field: String,
                     }
                 },
-                where_clause: Some(parse_quote! {where T: ::google_ai_rs::AsSchema}),
-                has_static: false,
+                want: Err("min_length (5) is greater than max_length (4)"),
             },
         ];
 
         for test in tests {
             println!("title: {}", test.title);
-            let mut ctx = Context::new(test.input).unwrap();
-            _ = generate_schema(&mut ctx);
+            let derived = derive_schema_base(test.input);
 
-            assert_eq!(ctx.input.generics.where_clause, test.where_clause);
-            assert_eq!(ctx.has_static, test.has_static);
+            match test.want {
+                Ok(want) => {
+                    let schema = derived
+                        .unwrap_or_else(|err| panic!("test failed: {err:#?}"))
+                        .schema;
+                    let schema = schema.properties.values().next().unwrap();
+                    assert_eq!((schema.min_length, schema.max_length), want);
+                }
+                Err(like) => match derived {
+                    Ok(_) => panic!("test did not fail"),
+                    Err(err) => assert!(err.to_string().contains(like), "{err}"),
+                },
+            }
         }
     }
 
     #[test]
-    fn test_derive_schema() {
+    fn list_items() {
+        let schema = derive_schema_base(parse_quote! {
+            struct S {
+                #[schema(max_items = 5, items(description = "A tag", pattern = "^#"))]
+                tags: Vec<String>,
+                #[schema(items(format = "int64", nullable))]
+                ids: Option<[Option<i64>; 3]>,
+                #[schema(items(items(minimum = 0)))]
+                grid: Vec<Vec<u8>>,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        let tags = &schema.properties[&Value::Raw("tags".to_owned())];
+        assert_eq!(tags.max_items, Some(5));
+        assert_eq!(tags.base, BaseSchema::Type(parse_quote!(Vec<String>)));
+        let tag = tags.items.as_deref().unwrap();
+        assert_eq!(tag.description.as_deref(), Some("A tag"));
+        assert_eq!(tag.pattern.as_deref(), Some("^#"));
+        assert_eq!(tag.base, BaseSchema::Type(parse_quote!(String)));
+
+        let ids = &schema.properties[&Value::Raw("ids".to_owned())];
+        let id = ids.items.as_deref().unwrap();
+        assert_eq!(id.format, Some(Format::Int64));
+        assert_eq!(id.nullable, Some(true));
+        assert_eq!(id.base, BaseSchema::Type(parse_quote!(Option<i64>)));
+
+        let grid = &schema.properties[&Value::Raw("grid".to_owned())];
+        let cell = grid.items.as_ref().unwrap().items.as_deref().unwrap();
+        assert_eq!(cell.minimum, Some(0.0));
+
+        for (input, like) in [
+            (
+                quote::quote! { #[schema(items(description = "a"))] field: String },
+                "only supported on lists",
+            ),
+            (
+                quote::quote! { #[schema(r#type = "Array", items(nullable))] field: Vec<u8> },
+                "can't be combined with r#type",
+            ),
+            (
+                quote::quote! { #[schema(items(rename = "a"))] field: Vec<u8> },
+                "Disallowed schema attribute rename",
+            ),
+            (
+                quote::quote! { #[schema(items(pattern = "^a"))] field: Vec<u8> },
+                "only supported on strings",
+            ),
+        ] {
+            let err = derive_schema_base(parse_quote! { struct S { #input } })
+                .map(|_| ())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(like), "{err}");
+            assert_eq!(err.matches("schema[").count(), 1, "{err}");
+        }
+    }
+
+    #[test]
+    fn enum_values() {
+        let schema = derive_schema_base(parse_quote! {
+            struct S {
+                #[schema(enum_values("low", "medium", "high"), default = "low")]
+                level: String,
+                #[schema(enum_values("a"), description = "Grade")]
+                grade: Option<&'static str>,
+                #[schema(r#type = "String", enum_values("EUR", "USD"))]
+                currency: Currency,
+                #[schema(items(enum_values("x", "y")))]
+                axes: Vec<String>,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        let values = |schema: &Schema| -> Vec<String> {
+            schema
+                .r#enum
+                .iter()
+                .map(|v| match v {
+                    Value::Raw(v) => v.clone(),
+                    Value::ReCompute(..) => unreachable!(),
+                })
+                .collect()
+        };
+        let level = &schema.properties[&Value::Raw("level".to_owned())];
+        assert_eq!(level.r#type, Some(schema::Type::String));
+        assert_eq!(level.format, Some(Format::Enum));
+        assert_eq!(values(level), ["low", "medium", "high"]);
+        assert_eq!(level.base, BaseSchema::Empty);
+
+        let grade = &schema.properties[&Value::Raw("grade".to_owned())];
+        assert_eq!(grade.nullable, Some(true));
+        assert_eq!(grade.description.as_deref(), Some("Grade"));
+
+        let currency = &schema.properties[&Value::Raw("currency".to_owned())];
+        assert_eq!(values(currency), ["EUR", "USD"]);
+        let axes = &schema.properties[&Value::Raw("axes".to_owned())];
+        assert_eq!(values(axes.items.as_deref().unwrap()), ["x", "y"]);
+
+        let container = derive_schema_base(parse_quote! {
+            #[schema(enum_values("red", "green"), description = "A color")]
+            struct Color(u32);
+        })
+        .unwrap()
+        .schema;
+        assert_eq!(values(&container), ["red", "green"]);
+        assert_eq!(container.description.as_deref(), Some("A color"));
+        assert!(container.properties.is_empty());
+
+        for (input, like) in [
+            (
+                quote::quote! { struct S { #[schema(enum_values("a"))] n: u8 } },
+                "only supported on strings",
+            ),
+            (
+                quote::quote! { struct S { #[schema(enum_values("a"), pattern = "^a")] s: String } },
+                "pattern can't be combined with enum_values",
+            ),
+            (
+                quote::quote! { struct S { #[schema(enum_values("a", "a"))] s: String } },
+                "listed twice",
+            ),
+            (
+                quote::quote! { struct S { #[schema(enum_values())] s: String } },
+                "at least one value",
+            ),
+            (
+                quote::quote! { struct S { #[schema(enum_values("a"), default = "b")] s: String } },
+                "isn't one of the enum_values",
+            ),
+            (
+                quote::quote! { #[schema(enum_values("a"), transparent)] struct S { s: String } },
+                "transparent can't be combined with the container's enum_values",
+            ),
+            (
+                quote::quote! { enum E { #[schema(enum_values("a"))] A } },
+                "Disallowed schema attribute enum_values",
+            ),
+        ] {
+            let err = derive_schema_base(parse_quote! { #input })
+                .map(|_| ())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(like), "{err}");
+        }
+    }
+
+    #[test]
+    fn description_from() {
+        let path = |path: &Option<syn::ExprPath>| path.to_token_stream().to_string();
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(description_from = "TEXT::USER")]
+            struct User {
+                #[schema(description_from = TEXT::NAME, example = "Ada")]
+                name: String,
+                #[schema(description_from = TEXT::TAGS, items(description_from = TEXT::TAG))]
+                tags: Vec<String>,
+            }
+        })
+        .unwrap()
+        .schema;
+        assert_eq!(path(&schema.description_from), "TEXT :: USER");
+        let name = &schema.properties[&Value::Raw("name".to_owned())];
+        assert_eq!(path(&name.description_from), "TEXT :: NAME");
+        assert_eq!(name.description, None);
+        let tokens = name.to_token_stream().to_string();
+        assert!(
+            tokens.contains(
+                "schema . description = :: std :: string :: String :: from (TEXT :: NAME)"
+            ),
+            "{tokens}"
+        );
+        assert!(tokens.contains("\"\\nExample: Ada\""), "{tokens}");
+        let tags = &schema.properties[&Value::Raw("tags".to_owned())];
+        assert_eq!(
+            path(&tags.items.as_ref().unwrap().description_from),
+            "TEXT :: TAG"
+        );
+
+        // Notes of tagged enums' fields follow the constant
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(tag = "kind")]
+            enum Shape {
+                Circle {
+                    #[schema(description_from = TEXT::RADIUS)]
+                    radius: u8,
+                },
+            }
+        })
+        .unwrap()
+        .schema;
+        let radius = &schema.properties[&Value::Raw("radius".to_owned())];
+        assert_eq!(path(&radius.description_from), "TEXT :: RADIUS");
+        assert_eq!(
+            radius.description.as_deref(),
+            Some("Only when kind is \"Circle\"")
+        );
+
+        for (input, like) in [
+            (
+                quote::quote! { struct S { #[schema(description = "a", description_from = A)] s: String } },
+                "description and description_from can't be both set",
+            ),
+            (
+                quote::quote! { #[schema(description_from = A, description = "a")] struct S(String); },
+                "description and description_from can't be both set",
+            ),
+            (
+                quote::quote! { #[schema(tag = "kind")] enum E { #[schema(description_from = A)] A {} } },
+                "Variants of tagged enums can't set description_from",
+            ),
+        ] {
+            let err = derive_schema_base(parse_quote! { #input })
+                .map(|_| ())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(like), "{err}");
+        }
+    }
+
+    #[test]
+    fn big_int_as_string() {
+        let schema = derive_schema_base(parse_quote! {
+            struct S {
+                #[schema(big_int_as_string)]
+                id: u64,
+                #[schema(big_int_as_string, description = "Balance in cents")]
+                balance: i128,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        let id = &schema.properties[&Value::Raw("id".to_owned())];
+        assert_eq!(id.r#type, Some(schema::Type::String));
+        assert_eq!(id.pattern.as_deref(), Some("^[0-9]+$"));
+        let balance = &schema.properties[&Value::Raw("balance".to_owned())];
+        assert_eq!(balance.pattern.as_deref(), Some("^-?[0-9]+$"));
+        assert_eq!(balance.description.as_deref(), Some("Balance in cents"));
+
+        let tests: [(DeriveInput, &str); 3] = [
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(big_int_as_string)]
+                        field: f64,
+                    }
+                },
+                "only supported on integers",
+            ),
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(big_int_as_string, minimum = 0)]
+                        field: u64,
+                    }
+                },
+                "can't be combined",
+            ),
+            (
+                parse_quote! {
+                    enum E {
+                        #[schema(big_int_as_string)]
+                        A(u64),
+                    }
+                },
+                "big_int_as_string",
+            ),
+        ];
+        for (input, like) in tests {
+            let err = derive_schema_base(input).err().unwrap();
+            assert!(err.to_string().contains(like), "{err}");
+        }
+    }
+
+    #[test]
+    fn as_string() {
+        let derived = derive_schema_base(parse_quote! {
+            struct S<T> {
+                #[schema(as_string, description = "The server's address")]
+                address: IpAddr,
+                #[schema(as_string, pattern = "^[A-Z]{3}$")]
+                currency: Option<Currency>,
+                #[schema(as_string, enum_values("a", "b"))]
+                grade: T,
+            }
+        })
+        .unwrap();
+        let schema = derived.schema;
+
+        let address = &schema.properties[&Value::Raw("address".to_owned())];
+        assert_eq!(address.r#type, Some(schema::Type::String));
+        assert_eq!(address.nullable, None);
+        assert_eq!(address.description.as_deref(), Some("The server's address"));
+        let currency = &schema.properties[&Value::Raw("currency".to_owned())];
+        assert_eq!(currency.r#type, Some(schema::Type::String));
+        assert_eq!(currency.nullable, Some(true));
+        assert_eq!(currency.pattern.as_deref(), Some("^[A-Z]{3}$"));
+        let grade = &schema.properties[&Value::Raw("grade".to_owned())];
+        assert_eq!(grade.r#enum.len(), 2);
+        // Only FromStr is needed, which the schema doesn't bound
+        let where_clause = &derived.ctx.input.generics.where_clause;
+        assert!(where_clause
+            .as_ref()
+            .is_none_or(|c| c.predicates.is_empty()));
+
+        let tests: [(DeriveInput, &str); 3] = [
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(as_string, r#type = "Integer")]
+                        field: Id,
+                    }
+                },
+                "can't be combined with r#type",
+            ),
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(as_string, minimum = 0)]
+                        field: Id,
+                    }
+                },
+                "can't be combined with minimum",
+            ),
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(items(as_string))]
+                        field: Vec<Id>,
+                    }
+                },
+                "as_string",
+            ),
+        ];
+        for (input, like) in tests {
+            let err = derive_schema_base(input).err().unwrap();
+            assert!(err.to_string().contains(like), "{err}");
+        }
+    }
+
+    #[test]
+    fn via_schemars() {
+        let derived = derive_schema_base(parse_quote! {
+            struct S<T> {
+                #[schema(via_schemars, description = "Amount due")]
+                total: Money<T>,
+                #[schema(via_schemars, min_items = 1)]
+                codes: Vec<Code>,
+            }
+        })
+        .unwrap();
+        let schema = derived.schema;
+
+        let total = &schema.properties[&Value::Raw("total".to_owned())];
+        assert_eq!(
+            total.base,
+            BaseSchema::AsSschema(parse_quote!(
+                ::google_ai_rs::schema::from_json_schema::<Money<T>>
+            ))
+        );
+        assert_eq!(total.description.as_deref(), Some("Amount due"));
+        let codes = &schema.properties[&Value::Raw("codes".to_owned())];
+        assert_eq!(
+            codes.base,
+            BaseSchema::AsSschema(parse_quote!(
+                ::google_ai_rs::schema::from_json_schema::<Vec<Code>>
+            ))
+        );
+        assert_eq!(codes.min_items, Some(1));
+        // schemars gives the schema, so AsSchema isn't bound
+        let where_clause = &derived.ctx.input.generics.where_clause;
+        assert!(where_clause
+            .as_ref()
+            .is_none_or(|c| c.predicates.is_empty()));
+
+        let tests: [(DeriveInput, &str); 4] = [
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(items(via_schemars))]
+                        field: Vec<Code>,
+                    }
+                },
+                "via_schemars",
+            ),
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(via_schemars, as_string)]
+                        field: Money,
+                    }
+                },
+                "can't be combined with as_string",
+            ),
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(via_schemars, items(description = "A code"))]
+                        field: Vec<Code>,
+                    }
+                },
+                "can't be combined with items",
+            ),
+            (
+                parse_quote! {
+                    enum E {
+                        #[schema(via_schemars)]
+                        A(Money),
+                    }
+                },
+                "via_schemars",
+            ),
+        ];
+        for (input, like) in tests {
+            let err = derive_schema_base(input).err().unwrap();
+            assert!(err.to_string().contains(like), "{err}");
+        }
+    }
+
+    #[test]
+    fn example() {
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(example = r#"{"date": "2024-03-01"}"#)]
+            struct S {
+                #[schema(example = "2024-03-01")]
+                date: String,
+                #[schema(example = 4.5)]
+                rating: f32,
+                #[schema(example = true)]
+                done: bool,
+                plain: String,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        assert_eq!(schema.example.as_deref(), Some(r#"{"date": "2024-03-01"}"#));
+        let example = |field: &str| {
+            schema.properties[&Value::Raw(field.to_owned())]
+                .example
+                .as_deref()
+        };
+        assert_eq!(example("date"), Some("2024-03-01"));
+        assert_eq!(example("rating"), Some("4.5"));
+        assert_eq!(example("done"), Some("true"));
+        assert_eq!(example("plain"), None);
+
+        let err = derive_schema_base(parse_quote! {
+            enum E {
+                #[schema(example = "a")]
+                A,
+            }
+        })
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("example"), "{err}");
+    }
+
+    #[test]
+    fn default() {
         struct Test {
             title: &'static str,
             input: DeriveInput,
-            want: Option<Schema>,
-            should_fail: bool,
-            error_like: Option<Vec<&'static str>>,
+            // default of the only field and whether it's required
+            want: (Option<DefaultValue>, bool),
         }
 
-        // Test as_schema&_generic
         let tests = [
             Test {
-                title: "unit struct",
+                title: "none",
                 input: parse_quote! {
-                    #[schema(description = "unit struct")]
-                    #[schema(nullable = "false")]
-                    struct U;
+                    struct S {
+                        field: u32,
+                    }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::Object),
-                    description: Some("unit struct".to_owned()),
-                    nullable: Some(false),
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (None, true),
             },
             Test {
-                title: "tuple intended for AsSchemaWithSerde",
+                title: "number",
                 input: parse_quote! {
-                    #[schema(description = "Represents a radioactive element")]
-                    struct T(
-                       #[schema(description = "Element name")] String,
-                       #[schema(description = "Half life")] f64,
-                    );
+                    struct S {
+                        #[schema(default = 10)]
+                        field: u32,
+                    }
                 },
-                want: None,
-                should_fail: true,
-                error_like: Some(vec!["AsSchemaWithSerde"]),
+                want: (Some(DefaultValue::Number(10.0)), false),
             },
             Test {
-                title: "tuple struct",
+                title: "forced required",
                 input: parse_quote! {
-                    struct T(String, f64);
+                    struct S {
+                        #[schema(default = "en", required)]
+                        field: String,
+                    }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::Array),
-                    items: Some(
-                        Schema {
-                            r#type: Some(schema::Type::Unspecified),
-                            ..Default::default()
-                        }
-                        .into(),
-                    ),
-                    max_items: Some(2),
-                    min_items: Some(2),
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (Some(DefaultValue::String("en".into())), true),
             },
             Test {
-                title: "enum",
+                title: "boolean",
                 input: parse_quote! {
-                    enum E {
-                        Variant1,
-                        Variant2,
+                    struct S {
+                        #[schema(default = true)]
+                        field: bool,
                     }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::String),
-                    format: Some(Format::Enum),
-                    r#enum: vec![Value::Raw("Variant1".into()), Value::Raw("Variant2".into())],
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (Some(DefaultValue::Bool(true)), false),
             },
             Test {
-                title: "named struct",
+                title: "serde field",
                 input: parse_quote! {
-                    struct S<'a, T, U> {
-                        field: Vec<T>,
-                        field1: Option<&'a U>,
+                    struct S {
+                        #[serde(default = "default_limit")]
+                        field: u32,
                     }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::Object),
-                    properties: [
-                        (
-                            Value::Raw("field".into()),
-                            Schema {
-                                base: BaseSchema::Type(parse_quote!(Vec<T>)),
-                                ..Default::default()
-                            },
-                        ),
-                        (
-                            Value::Raw("field1".into()),
-                            Schema {
-                                base: BaseSchema::Type(parse_quote!(Option<&'a U>)),
-                                ..Default::default()
-                            },
-                        ),
-                    ]
-                    .into(),
-                    required: vec![Value::Raw("field".into()), Value::Raw("field1".into())],
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (None, false),
+            },
+            Test {
+                title: "serde container",
+                input: parse_quote! {
+                    #[serde(default)]
+                    struct S {
+                        field: bool,
+                    }
+                },
+                want: (None, false),
+            },
+            Test {
+                title: "serde default forced required",
+                input: parse_quote! {
+                    struct S {
+                        #[serde(default)]
+                        #[schema(required)]
+                        field: u32,
+                    }
+                },
+                want: (None, true),
             },
             Test {
-                title: "rename_all_with",
+                title: "serde skip_serializing_if",
                 input: parse_quote! {
-                    #[schema(rename_all_with = "suitcase")]
                     struct S {
-                        field: (),
-                        field1: (),
+                        #[serde(skip_serializing_if = "Option::is_none")]
+                        field: Option<u32>,
                     }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::Object),
-                    properties: [
-                        (
-                            Value::ReCompute(parse_quote!(suitcase), "field".into()),
-                            Schema {
-                                base: BaseSchema::Type(parse_quote!(())),
-                                ..Default::default()
-                            },
-                        ),
-                        (
-                            Value::ReCompute(parse_quote!(suitcase), "field1".into()),
-                            Schema {
-                                base: BaseSchema::Type(parse_quote!(())),
-                                ..Default::default()
-                            },
-                        ),
-                    ]
-                    .into(),
-                    required: vec![
-                        Value::ReCompute(parse_quote!(suitcase), "field".into()),
-                        Value::ReCompute(parse_quote!(suitcase), "field1".into()),
-                    ],
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (None, false),
             },
             Test {
-                title: "rename_all_with (1)",
+                title: "serde skip_serializing_if with default",
                 input: parse_quote! {
-                    #[schema(rename_all_with = "misc::prettycase")]
-                    enum Enum {
-                        Variant1,
-                        Variant2,
+                    struct S {
+                        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+                        field: Vec<u32>,
                     }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::String),
-                    format: Some(Format::Enum),
-                    r#enum: vec![
-                        Value::ReCompute(parse_quote!(misc::prettycase), "Variant1".into()),
-                        Value::ReCompute(parse_quote!(misc::prettycase), "Variant2".into()),
-                    ],
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (None, false),
             },
             Test {
-                title: "as_schema",
+                title: "serde skip_serializing_if without default",
                 input: parse_quote! {
                     struct S {
-                        #[schema(as_schema = "concrete::as_schema")]
-                        field: Type
+                        #[serde(skip_serializing_if = "Vec::is_empty")]
+                        field: Vec<u32>,
                     }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::Object),
-                    properties: [(
-                        Value::Raw("field".into()),
-                        Schema {
-                            base: BaseSchema::AsSschema(parse_quote!(concrete::as_schema)),
-                            ..Default::default()
-                        },
-                    )]
-                    .into(),
-                    required: vec![Value::Raw("field".into())],
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (None, true),
             },
             Test {
-                title: "as_schema_generic",
+                title: "serde ignored",
                 input: parse_quote! {
+                    #[schema(ignore_serde)]
                     struct S {
-                        #[schema(as_schema_generic = "generic::as_schema_generic")]
-                        field: Wrapper<Type>
+                        #[serde(default)]
+                        field: u32,
                     }
                 },
-                want: Some(Schema {
-                    r#type: Some(schema::Type::Object),
-                    properties: [(
-                        Value::Raw("field".into()),
-                        Schema {
-                            base: BaseSchema::AsSschemaGeneric(
-                                parse_quote!(generic::as_schema_generic),
-                                parse_quote!(Wrapper<Type>),
-                            ),
-                            ..Default::default()
-                        },
-                    )]
-                    .into(),
-                    required: vec![Value::Raw("field".into())],
-                    ..Default::default()
-                }),
-                should_fail: false,
-                error_like: None,
+                want: (None, true),
             },
         ];
 
         for test in tests {
             println!("title: {}", test.title);
-            let derived = derive_schema_base(test.input);
+            let schema = derive_schema_base(test.input)
+                .unwrap_or_else(|err| panic!("test failed: {err:#?}"))
+                .schema;
+            let field = schema.properties.values().next().unwrap();
+            assert_eq!(
+                (field.default.clone(), !schema.required.is_empty()),
+                test.want
+            );
+        }
 
-            if test.should_fail {
-                match derived {
-                    Ok(_) => panic!("test did not fail"),
-                    Err(err) => {
-                        if let Some(error_like) = test.error_like {
-                            let mut matches = false;
-                            let err = err.to_string();
+        // Never read by serde, so never asked of the model
+        let schema = derive_schema_base(parse_quote! {
+            struct S {
+                field: u32,
+                #[serde(skip_deserializing)]
+                cache: u32,
+            }
+        })
+        .unwrap()
+        .schema;
+        assert_eq!(schema.properties.len(), 1);
+    }
 
-                            for like in error_like {
-                                matches = matches || err.contains(like)
-                            }
-                            println!("{err}");
-                            assert!(matches);
-                        }
+    #[test]
+    fn title() {
+        assert_eq!(title_from_ident("first_name", false), "First name");
+        assert_eq!(title_from_ident("r#type", false), "Type");
+        assert_eq!(title_from_ident("UserProfile", true), "User profile");
+
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(auto_title)]
+            struct UserProfile {
+                first_name: String,
+                #[schema(title = "Age in years")]
+                age: u8,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        assert_eq!(schema.title.as_deref(), Some("User profile"));
+        let title = |field: &str| {
+            schema.properties[&Value::Raw(field.to_owned())]
+                .title
+                .as_deref()
+        };
+        assert_eq!(title("first_name"), Some("First name"));
+        assert_eq!(title("age"), Some("Age in years"));
+
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(title = "Profile")]
+            struct UserProfile {
+                first_name: String,
+            }
+        })
+        .unwrap()
+        .schema;
+        assert_eq!(schema.title.as_deref(), Some("Profile"));
+        assert_eq!(schema.properties.values().next().unwrap().title, None);
+    }
+
+    #[test]
+    fn ordered() {
+        let ordering = |input| {
+            derive_schema_base(input)
+                .unwrap()
+                .schema
+                .property_ordering
+                .into_iter()
+                .map(|name| match name {
+                    Value::Raw(name) => name,
+                    Value::ReCompute(_, name) => name,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ordering(parse_quote! {
+                #[schema(ordered)]
+                struct S {
+                    zeta: String,
+                    alpha: String,
+                    #[schema(skip)]
+                    skipped: String,
+                    mid: String,
+                }
+            }),
+            ["zeta", "alpha", "mid"]
+        );
+        assert!(ordering(parse_quote! {
+            struct S {
+                zeta: String,
+                alpha: String,
+            }
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn transparent() {
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(transparent, description = "A user id")]
+            struct UserId {
+                #[schema(minimum = 1)]
+                id: u64,
+                #[schema(skip)]
+                marker: PhantomData<()>,
+            }
+        })
+        .unwrap()
+        .schema;
+        assert!(schema.properties.is_empty());
+        assert_eq!(schema.minimum, Some(1.0));
+        assert_eq!(schema.description.as_deref(), Some("A user id"));
+
+        let serde = derive_schema_base(parse_quote! {
+            #[serde(transparent)]
+            struct Name {
+                name: String,
+            }
+        })
+        .unwrap()
+        .schema;
+        assert!(serde.properties.is_empty());
+
+        let errors = [
+            parse_quote! {
+                #[schema(transparent)]
+                struct S { a: u8, b: u8 }
+            },
+            parse_quote! {
+                #[schema(transparent)]
+                struct S;
+            },
+            parse_quote! {
+                #[schema(transparent)]
+                enum E { A(u8) }
+            },
+        ];
+        for input in errors {
+            let err = derive_schema_base(input).map(|_| ()).unwrap_err();
+            assert!(err.to_string().contains("transparent"), "{err}");
+        }
+    }
+
+    #[test]
+    fn all_optional() {
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(all_optional)]
+            struct S {
+                a: u8,
+                b: String,
+                #[schema(required)]
+                id: u64,
+            }
+        })
+        .unwrap()
+        .schema;
+        assert_eq!(schema.required, [Value::Raw("id".to_owned())]);
+
+        // Fields of struct variants too
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(all_optional)]
+            enum E {
+                A { a: u8 },
+            }
+        })
+        .unwrap()
+        .schema;
+        let a = &schema.properties[&Value::Raw("A".to_owned())];
+        assert!(a.required.is_empty());
+    }
+
+    #[test]
+    fn api_limits() {
+        let limit_warnings = |input: DeriveInput| -> Vec<String> {
+            let derived = derive_schema_base(input).unwrap();
+            derived
+                .ctx
+                .warnings
+                .into_iter()
+                .filter(|(_, name, _)| *name == "schema_exceeds_api_limits")
+                .map(|(_, _, message)| message)
+                .collect()
+        };
+
+        let fields = (0..=limits::MAX_PROPERTIES).map(|i| quote::format_ident!("f{i}"));
+        let wide: DeriveInput = parse_quote! { struct S { #(#fields: u8,)* } };
+        let warnings = limit_warnings(wide.clone());
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(
+            warnings[0].contains("at least 101 properties"),
+            "{}",
+            warnings[0]
+        );
+
+        let variants = (0..=limits::MAX_ENUM_VALUES).map(|i| quote::format_ident!("V{i}"));
+        let warnings = limit_warnings(parse_quote! { enum E { #(#variants,)* } });
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].contains("501 enum values"), "{}", warnings[0]);
+
+        // The object, then a level per collection and the innermost type
+        let deep: DeriveInput = parse_quote! {
+            struct S { grid: Option<Vec<Vec<Vec<Vec<Vec<Vec<Vec<Vec<[Box<u8>; 2]>>>>>>>>> }
+        };
+        let warnings = limit_warnings(deep);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(
+            warnings[0].contains("at least 11 levels"),
+            "{}",
+            warnings[0]
+        );
+
+        let shallow = parse_quote! { struct S { grid: Vec<Vec<Matrix>>, name: Option<String> } };
+        assert!(limit_warnings(shallow).is_empty());
+
+        let mut ignored = wide;
+        ignored.attrs.push(parse_quote!(#[schema(ignore_limits)]));
+        assert!(limit_warnings(ignored).is_empty());
+    }
+
+    #[test]
+    fn tuple_array() {
+        let derived = derive_schema_base(parse_quote! {
+            #[schema(tuple = "array", description = "A radioactive element")]
+            struct T(
+                #[schema(description = "Element name")] String,
+                f64,
+                #[schema(description = "Half life", minimum = 0)] f64,
+            );
+        })
+        .unwrap();
+
+        let schema = derived.schema;
+        assert_eq!(schema.r#type, Some(schema::Type::Array));
+        assert_eq!(
+            schema.description.as_deref(),
+            Some("A radioactive element\nItem 1: Element name\nItem 3: Half life")
+        );
+        assert_eq!(
+            schema.items.as_deref().unwrap().r#type,
+            Some(schema::Type::Unspecified)
+        );
+        assert_eq!(schema.min_items, Some(3));
+        let warnings = &derived.ctx.warnings;
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert_eq!(warnings[0].1, "tuple_attribute_ignored");
+        assert!(warnings[0].2.contains("item 3"), "{}", warnings[0].2);
+
+        let tests: [(DeriveInput, &str); 2] = [
+            (
+                parse_quote! {
+                    #[schema(tuple = "array")]
+                    struct T(String);
+                },
+                "only supported on tuple structs with several fields",
+            ),
+            (
+                parse_quote! {
+                    #[schema(tuple = "object")]
+                    struct T(String, f64);
+                },
+                "Unknown value object",
+            ),
+        ];
+        for (input, like) in tests {
+            let err = derive_schema_base(input).err().unwrap();
+            assert!(err.to_string().contains(like), "{err}");
+        }
+    }
+
+    #[test]
+    fn container_as_schema() {
+        let derived = derive_schema_base(parse_quote! {
+            #[schema(as_schema = "external_schema::<T>", description = "Opaque")]
+            struct Wrapper<T> {
+                inner: External<T>,
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            derived.schema.base,
+            BaseSchema::AsSschema(parse_quote!(external_schema::<T>))
+        );
+        assert!(derived.schema.properties.is_empty());
+        assert_eq!(derived.schema.description.as_deref(), Some("Opaque"));
+        // Type parameters are bound, fields aren't visited
+        assert_eq!(
+            derived.ctx.input.generics.where_clause,
+            Some(parse_quote! {where T: ::google_ai_rs::AsSchema})
+        );
+
+        let err = derive_schema_base(parse_quote! {
+            #[schema(as_schema = "external_schema", rename_all = "camelCase")]
+            struct S {
+                field_one: u8,
+            }
+        })
+        .err()
+        .unwrap();
+        assert!(err.to_string().starts_with("schema[E020]"), "{err}");
+    }
+
+    #[test]
+    fn bound() {
+        let impl_of = |input: DeriveInput| derive_schema_base(input).unwrap().to_token_stream();
+        let where_of = |input: DeriveInput| {
+            let tokens = impl_of(input).to_string();
+            let start = tokens.find("where").expect("a where clause");
+            let end = start + tokens[start..].find('{').unwrap();
+            tokens[start..end].trim().to_owned()
+        };
+
+        let inferred = where_of(parse_quote! {
+            struct S<T> where T: Default { #[schema(as_schema = "wrapped::<T>")] a: Wrapper<T>, b: T }
+        });
+        assert!(
+            inferred.contains("T : :: google_ai_rs :: AsSchema"),
+            "{inferred}"
+        );
+
+        let custom = where_of(parse_quote! {
+            #[schema(bound = "T: Clone, Wrapper<T>: Send")]
+            struct S<T> where T: Default { #[schema(as_schema = "wrapped::<T>")] a: Wrapper<T>, b: T }
+        });
+        assert_eq!(
+            custom,
+            quote::quote!(where T: Default, T: Clone, Wrapper<T>: Send).to_string()
+        );
+
+        let none = impl_of(parse_quote! {
+            #[schema(bound = "")]
+            struct S<T> { b: T }
+        })
+        .to_string();
+        assert!(!none.contains("AsSchema for S < T > where T"), "{none}");
+
+        let err = derive_schema_base(parse_quote! {
+            #[schema(bound = "T:: Clone")]
+            struct S<T> { b: T }
+        })
+        .err()
+        .unwrap();
+        assert!(err.to_string().starts_with("schema[E003]"), "{err}");
+    }
+
+    #[test]
+    fn error_codes() {
+        let tests: [(DeriveInput, &str); 3] = [
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(descripton = "typo")]
+                        field: u8,
+                    }
+                },
+                "schema[E001]: Unsupported schema attribute descripton",
+            ),
+            (
+                parse_quote! {
+                    struct S {
+                        #[schema(pattern = "^a")]
+                        field: u8,
+                    }
+                },
+                "schema[E017]: ",
+            ),
+            (
+                parse_quote! {
+                    union U {
+                        a: u8,
                     }
+                },
+                "schema[E004]: ",
+            ),
+        ];
+        for (input, want) in tests {
+            let err = derive_schema_base(input).err().unwrap();
+            assert!(err.to_string().starts_with(want), "{err}");
+        }
+    }
+
+    #[test]
+    fn aliases() {
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(description = "Status")]
+            enum Status {
+                #[schema(alias = "DONE", alias = "FINISHED")]
+                Complete,
+                #[serde(alias = "WIP")]
+                Pending,
+                Failed,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        assert_eq!(
+            schema.notes,
+            [
+                r#"Complete was formerly "DONE" or "FINISHED""#,
+                r#"Pending was formerly "WIP""#,
+            ]
+        );
+        assert_eq!(schema.description.as_deref(), Some("Status"));
+        assert_eq!(schema.r#enum.len(), 3);
+
+        // Variants with data carry their own note
+        let schema = derive_schema_base(parse_quote! {
+            enum Shape {
+                #[schema(alias = "Round")]
+                Circle { radius: f32 },
+            }
+        })
+        .unwrap()
+        .schema;
+        let circle = &schema.properties[&Value::Raw("Circle".to_owned())];
+        assert_eq!(circle.notes, [r#"Formerly "Round""#]);
+
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(tag = "type")]
+            enum Shape {
+                #[schema(alias = "round")]
+                circle { radius: f32 },
+            }
+        })
+        .unwrap()
+        .schema;
+        let tag = &schema.properties[&Value::Raw("type".to_owned())];
+        assert_eq!(tag.notes, [r#"circle was formerly "round""#]);
+
+        let rejected: [DeriveInput; 2] = [
+            parse_quote! {
+                struct S {
+                    #[schema(alias = "old")]
+                    field: u8,
                 }
-            } else {
-                let derived = derived.unwrap_or_else(|err| panic!("test failed: {err:#?}"));
-                assert_eq!(derived.schema, test.want.unwrap())
+            },
+            parse_quote! {
+                #[schema(repr = "integer")]
+                enum E {
+                    #[schema(alias = "Zero")]
+                    A,
+                }
+            },
+        ];
+        for input in rejected {
+            let err = derive_schema_base(input).err().unwrap();
+            assert!(err.to_string().contains("alias"), "{err}");
+        }
+    }
+
+    #[test]
+    fn integer_repr() {
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(repr = "integer", description = "Priority")]
+            enum Priority {
+                Low = 1,
+                Medium,
+                #[schema(skip)]
+                Unused,
+                High = 10,
+                Negative = -2,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        assert_eq!(schema.r#type, Some(schema::Type::Integer));
+        assert_eq!(schema.format, None);
+        assert_eq!(schema.description.as_deref(), Some("Priority"));
+        assert_eq!(
+            schema.r#enum,
+            ["1", "2", "10", "-2"].map(|v| Value::Raw(v.to_owned()))
+        );
+        assert_eq!((schema.minimum, schema.maximum), (Some(-2.0), Some(10.0)));
+
+        let implicit = derive_schema_base(parse_quote! {
+            #[schema(repr = "integer")]
+            enum E { A, B, C }
+        })
+        .unwrap()
+        .schema;
+        assert_eq!(
+            implicit.r#enum,
+            ["0", "1", "2"].map(|v| Value::Raw(v.to_owned()))
+        );
+
+        let errors = [
+            (
+                parse_quote! {
+                    #[schema(repr = "integer")]
+                    enum E { A(u8), B }
+                },
+                "only supported on enums without data",
+            ),
+            (
+                parse_quote! {
+                    #[schema(repr = "integer")]
+                    enum E { A = OFFSET, B }
+                },
+                "must be integer literals",
+            ),
+            (
+                parse_quote! {
+                    #[schema(repr = "integer")]
+                    enum E { #[schema(rename = "a")] A }
+                },
+                "can't be renamed",
+            ),
+            (
+                parse_quote! {
+                    #[schema(repr = "integer")]
+                    struct S { a: u8 }
+                },
+                "only supported on enums",
+            ),
+            (
+                parse_quote! {
+                    #[schema(repr = "float")]
+                    enum E { A }
+                },
+                "Unknown value float for Repr",
+            ),
+        ];
+        for (input, like) in errors {
+            match derive_schema_base(input) {
+                Ok(_) => panic!("{like}: test did not fail"),
+                Err(err) => assert!(err.to_string().contains(like), "{err}"),
+            }
+        }
+    }
+
+    #[test]
+    fn one_of() {
+        let schema = derive_schema_base(parse_quote! {
+            #[schema(one_of, rename_all = "snake_case", description = "A shape")]
+            enum Shape {
+                Circle { radius: f64 },
+                Square(f64),
+                #[schema(skip)]
+                Unused(u8),
+                #[schema(alias = "Dot")]
+                Point,
+                Empty,
+            }
+        })
+        .unwrap()
+        .schema;
+
+        assert_eq!(schema.r#type, None);
+        assert_eq!(schema.description.as_deref(), Some("A shape"));
+        assert_eq!(schema.any_of.len(), 3);
+
+        for (variant, name) in schema.any_of[..2].iter().zip(["circle", "square"]) {
+            let name = Value::Raw(name.to_owned());
+            assert_eq!(variant.r#type, Some(schema::Type::Object));
+            assert_eq!(variant.description, None);
+            assert_eq!(variant.required, std::slice::from_ref(&name));
+            assert_eq!(variant.properties.len(), 1);
+            assert!(variant.properties.contains_key(&name));
+        }
+        let circle = &schema.any_of[0].properties[&Value::Raw("circle".to_owned())];
+        assert_eq!(circle.r#type, Some(schema::Type::Object));
+        assert_eq!(circle.required, [Value::Raw("radius".to_owned())]);
+
+        let units = &schema.any_of[2];
+        assert_eq!(units.format, Some(Format::Enum));
+        assert_eq!(
+            units.r#enum,
+            ["point", "empty"].map(|v| Value::Raw(v.to_owned()))
+        );
+        assert_eq!(units.notes.len(), 1);
+        assert!(units.notes[0].contains("Dot"), "{:?}", units.notes);
+
+        let errors = [
+            (
+                parse_quote! {
+                    #[schema(one_of)]
+                    enum E { A, B }
+                },
+                "only supported on enums with data",
+            ),
+            (
+                parse_quote! {
+                    #[schema(one_of)]
+                    struct S { a: u8 }
+                },
+                "only supported on enums",
+            ),
+            (
+                parse_quote! {
+                    #[schema(one_of, tag = "kind")]
+                    enum E { A { a: u8 } }
+                },
+                "can't have tag",
+            ),
+            (
+                parse_quote! {
+                    #[schema(one_of, untagged)]
+                    enum E { A(u8) }
+                },
+                "can't have untagged",
+            ),
+        ];
+        for (input, like) in errors {
+            match derive_schema_base(input) {
+                Ok(_) => panic!("{like}: test did not fail"),
+                Err(err) => assert!(err.to_string().contains(like), "{err}"),
             }
         }
     }
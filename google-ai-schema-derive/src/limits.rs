@@ -0,0 +1,125 @@
+// Warns about schemas the API is likely to reject as too large
+//
+// Gemini refuses schemas that nest too deep or have too many properties or
+// enum values, but only once a request is made. The derive only sees the
+// type's own fields and variants: other types' schemas are built at run
+// time. What it computes is a lower bound, so a warning is never spurious
+// about the size, only about the limit. The API doesn't publish exact
+// limits, hence warnings rather than errors, and `ignore_limits` to
+// silence them.
+
+use syn::Type;
+
+use crate::{
+    schema::{BaseSchema, Schema},
+    Context,
+};
+
+pub(crate) const MAX_DEPTH: usize = 10;
+pub(crate) const MAX_PROPERTIES: usize = 100;
+pub(crate) const MAX_ENUM_VALUES: usize = 500;
+
+const LINT: &str = "schema_exceeds_api_limits";
+
+pub(crate) fn check(ctx: &mut Context, schema: &Schema) {
+    if ctx.top_attr.ignore_limits {
+        return;
+    }
+    let span = ctx.input.ident.span();
+
+    let depth = depth(schema);
+    if depth > MAX_DEPTH {
+        ctx.warn(
+            LINT,
+            span,
+            format!(
+                "schema nests at least {depth} levels deep; the API tends to reject more \
+                 than {MAX_DEPTH}. Flatten it or set `#[schema(ignore_limits)]`"
+            ),
+        );
+    }
+
+    let properties = properties(schema);
+    if properties > MAX_PROPERTIES {
+        ctx.warn(
+            LINT,
+            span,
+            format!(
+                "schema has at least {properties} properties; the API tends to reject more \
+                 than {MAX_PROPERTIES}. Split it or set `#[schema(ignore_limits)]`"
+            ),
+        );
+    }
+
+    let enum_values = enum_values(schema);
+    if enum_values > MAX_ENUM_VALUES {
+        ctx.warn(
+            LINT,
+            span,
+            format!(
+                "schema has {enum_values} enum values; the API tends to reject more than \
+                 {MAX_ENUM_VALUES}. Use a string with a description or set \
+                 `#[schema(ignore_limits)]`"
+            ),
+        );
+    }
+}
+
+fn children(schema: &Schema) -> impl Iterator<Item = &Schema> {
+    schema
+        .properties
+        .values()
+        .chain(schema.items.as_deref())
+        .chain(&schema.any_of)
+}
+
+fn depth(schema: &Schema) -> usize {
+    let own = 1 + children(schema).map(depth).max().unwrap_or(0);
+    match &schema.base {
+        BaseSchema::Type(ty) => own.max(type_depth(ty)),
+        _ => own,
+    }
+}
+
+fn properties(schema: &Schema) -> usize {
+    schema.properties.len() + children(schema).map(properties).sum::<usize>()
+}
+
+fn enum_values(schema: &Schema) -> usize {
+    schema.r#enum.len() + children(schema).map(enum_values).sum::<usize>()
+}
+
+// Levels of a field's schema that can be told from its type: one per
+// collection, none for pointers and `Option`, and one for anything else.
+fn type_depth(ty: &Type) -> usize {
+    match ty {
+        Type::Array(ty) => 1 + type_depth(&ty.elem),
+        Type::Slice(ty) => 1 + type_depth(&ty.elem),
+        Type::Group(ty) => type_depth(&ty.elem),
+        Type::Paren(ty) => type_depth(&ty.elem),
+        Type::Reference(ty) => type_depth(&ty.elem),
+        Type::Path(ty) if ty.qself.is_none() => {
+            let Some(last) = ty.path.segments.last() else {
+                return 1;
+            };
+            let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+                return 1;
+            };
+            let mut inner = args.args.iter().filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            });
+            let (Some(inner), None) = (inner.next(), inner.next()) else {
+                return 1;
+            };
+            match last.ident.to_string().as_str() {
+                "Option" | "Box" | "Rc" | "Arc" | "Cow" => type_depth(inner),
+                "Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" | "BinaryHeap" => {
+                    1 + type_depth(inner)
+                }
+                _ => 1,
+            }
+        }
+        _ => 1,
+    }
+}
@@ -6,9 +6,9 @@ use std::{
     collections::HashMap,
     fmt::{self, Debug},
 };
-use syn::{spanned::Spanned as _, token::Brace, ExprPath, Ident};
+use syn::{spanned::Spanned as _, token::Brace, ExprPath, Ident, WhereClause};
 
-use crate::Context;
+use crate::{attr::DefaultValue, Context};
 
 macro_rules! quote_each_token_spanned {
     ($span_elem:ident=> $tokens:ident $($tts:tt)*) => {
@@ -214,20 +214,36 @@ impl ToTokens for BaseSchema {
 }
 
 // Most of these can be borrowed but some are created within a function
-#[derive(PartialEq, Eq, Debug, Default)]
+#[derive(PartialEq, Debug, Default)]
 pub(super) struct Schema {
     // specifables
     pub(super) r#type: Option<Type>,
     pub(super) format: Option<Format>,
+    pub(super) title: Option<String>,
     pub(super) description: Option<String>,
+    /// Path of a `&str` constant the description starts with, followed by
+    /// `description`
+    pub(super) description_from: Option<ExprPath>,
+    /// Appended to the description, after the item's own
+    pub(super) example: Option<String>,
+    /// Lines appended to the description, after the example
+    pub(super) notes: Vec<String>,
     pub(super) nullable: Option<bool>,
     pub(super) max_items: Option<i64>,
     pub(super) min_items: Option<i64>,
+    pub(super) minimum: Option<f64>,
+    pub(super) maximum: Option<f64>,
+    pub(super) min_length: Option<i64>,
+    pub(super) max_length: Option<i64>,
+    pub(super) pattern: Option<String>,
+    pub(super) default: Option<DefaultValue>,
 
     pub(super) r#enum: Vec<Value<String>>,
     pub(super) items: Option<Box<Schema>>,
     pub(super) properties: HashMap<Value<String>, Schema>,
     pub(super) required: Vec<Value<String>>, // TODO: Avoid double computation here. 'required's are from properties
+    pub(super) property_ordering: Vec<Value<String>>,
+    pub(super) any_of: Vec<Schema>,
     pub(super) base: BaseSchema,
 }
 
@@ -255,6 +271,17 @@ impl ToTokens for Schema {
                         }
                     )*
                 }};
+                // A negative number is a `-` before the literal, which a
+                // trailing `.into()` would bind tighter than
+                (some, $($property:ident)*) => {{
+                    $(
+                        if let Some(property) = &self.$property {
+                            quote_each_token! {tokens
+                                schema.$property = ::std::option::Option::Some(#property);
+                            }
+                        }
+                    )*
+                }};
                 ($($property:ident)*) => {{
                     $(
                         if let Some(property) = &self.$property {
@@ -267,11 +294,51 @@ impl ToTokens for Schema {
             }
 
             transfer_properties! {
-                r#type format description nullable min_items max_items
+                r#type format title nullable min_items max_items
+                min_length max_length pattern
             }
 
             transfer_properties! {
-                vec, required r#enum
+                some, minimum maximum
+            }
+
+            // Text known to the macro can only follow a constant's
+            let mut appended = Vec::new();
+            match (&self.description_from, &self.description) {
+                (Some(description_from), description) => {
+                    quote_each_token_spanned! {description_from=> tokens
+                        schema.description = ::std::string::String::from(#description_from);
+                    }
+                    appended.extend(description.clone());
+                }
+                (None, Some(description)) => {
+                    quote_each_token! {tokens
+                        schema.description = #description.into();
+                    }
+                }
+                (None, None) => {}
+            }
+
+            transfer_properties! {
+                vec, required property_ordering r#enum any_of
+            }
+
+            if let Some(default) = &self.default {
+                quote_each_token! {tokens
+                    schema.default = ::std::option::Option::Some(#default.into());
+                }
+            }
+
+            appended.extend(self.example.as_ref().map(|example| format!("Example: {example}")));
+            for line in appended.iter().chain(&self.notes) {
+                let appended = format!("\n{line}");
+                quote_each_token! {tokens
+                    if schema.description.is_empty() {
+                        schema.description = #line.into();
+                    } else {
+                        schema.description.push_str(#appended);
+                    }
+                }
             }
 
             if !self.properties.is_empty() {
@@ -302,6 +369,16 @@ impl ToTokens for Schema {
     }
 }
 
+impl ToTokens for DefaultValue {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        match self {
+            DefaultValue::String(s) => s.to_tokens(tokens),
+            DefaultValue::Number(n) => n.to_tokens(tokens),
+            DefaultValue::Bool(b) => b.to_tokens(tokens),
+        }
+    }
+}
+
 impl Schema {
     fn vet(&self) {
         if self.base.is_some() && (!self.properties.is_empty() || !self.required.is_empty()) {
@@ -359,9 +436,48 @@ impl ToTokens for SchemaImpl<'_> {
     fn to_tokens(&self, mut tokens: &mut TokenStream2) {
         let input = &self.ctx.input;
         let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        // `bound` replaces the predicates inferred from field types
+        let bounded = self.ctx.top_attr.bound.as_ref().map(|bound| {
+            let mut clause = self
+                .ctx
+                .where_clause
+                .clone()
+                .unwrap_or_else(|| WhereClause {
+                    where_token: Default::default(),
+                    predicates: Default::default(),
+                });
+            clause.predicates.extend(bound.0.iter().cloned());
+            clause
+        });
+        let where_clause = bounded.as_ref().or(where_clause);
         let ident = &input.ident;
         let crate_path = &self.ctx.crate_path;
         let schema = &self.schema;
+        let body = match &self.ctx.top_attr.max_depth {
+            Some(max_depth) => {
+                let max_depth = max_depth.value();
+                quote::quote! {
+                    #crate_path::schema::recursion_limited::<Self>(#max_depth, || { #schema })
+                }
+            }
+            None => schema.to_token_stream(),
+        };
+        // A static is shared by every instantiation of a generic impl, so
+        // only types without type or const parameters cache their schema.
+        let is_generic = input
+            .generics
+            .params
+            .iter()
+            .any(|param| !matches!(param, syn::GenericParam::Lifetime(_)));
+        let body = if is_generic {
+            body
+        } else {
+            quote::quote! {
+                static SCHEMA: ::std::sync::OnceLock<#crate_path::Schema> =
+                    ::std::sync::OnceLock::new();
+                #crate_path::schema::cached(&SCHEMA, || { #body })
+            }
+        };
 
         quote_each_token! {tokens
             #[automatically_derived]
@@ -369,10 +485,22 @@ impl ToTokens for SchemaImpl<'_> {
                 fn as_schema() -> #crate_path::Schema {
                     #[allow(unused_imports)]
                     use #crate_path::{Schema, SchemaType};
-                    #schema
+                    #body
                 }
             }
         };
+
+        for (span, name, message) in &self.ctx.warnings {
+            let name = Ident::new(name, *span);
+            tokens.extend(quote::quote_spanned! {*span=>
+                const _: () = {
+                    #[deprecated(note = #message)]
+                    #[allow(non_upper_case_globals)]
+                    const #name: () = ();
+                    #name
+                };
+            });
+        }
     }
 }
 
@@ -4,12 +4,16 @@ use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{format_ident, quote, ToTokens as _};
 use syn::{
-    parse_macro_input, parse_quote, punctuated::Punctuated, spanned::Spanned as _, Data,
-    DataStruct, DeriveInput, Error, Fields, FieldsNamed, FieldsUnnamed, Lifetime,
-    PredicateLifetime, TraitBound, TypeParamBound, WherePredicate,
+    parse_macro_input, parse_quote, punctuated::Punctuated, spanned::Spanned as _, Attribute, Data,
+    DataEnum, DataStruct, DeriveInput, Error, Expr, Field, Fields, FieldsNamed, FieldsUnnamed,
+    Lifetime, PredicateLifetime, Token, TraitBound, TypeParamBound, WherePredicate,
 };
 
-use crate::{attr::SetAttr, Schema, SchemaImpl};
+use crate::{
+    attr::{self, SetAttr},
+    diagnostics::Diagnostic,
+    Schema, SchemaImpl,
+};
 
 macro_rules! r#try {
     ($expr:expr) => {
@@ -25,16 +29,23 @@ pub(super) fn derive_schema_with_serde(input: TokenStream) -> TokenStream {
     let mut ctx = r#try!(Context::new(input));
 
     let (schema, serde_impl) = r#try!(match ctx.input.data.clone() {
-        // Data::Enum(data) => impl_enum(ctx, &data),
         Data::Struct(DataStruct {
             fields: Fields::Unnamed(fields),
             ..
         }) => tuple_struct(&mut ctx, fields),
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => named_struct(&mut ctx, fields),
+        Data::Enum(data) => impl_enum(&mut ctx, data),
         _ => Err(Error::new_spanned(
             &ctx.input,
-            "AsSchemaWithSerde currently only supports tuples",
+            Diagnostic::UnsupportedType.message(
+                "AsSchemaWithSerde supports tuple structs, structs with named fields and enums",
+            ),
         )),
     });
+    crate::limits::check(&mut ctx, &schema);
 
     let schema_impl = SchemaImpl {
         ctx: &ctx,
@@ -87,7 +98,12 @@ struct Context {
 
 impl Context {
     fn new(input: DeriveInput) -> Result<Self, Error> {
-        let inner = crate::Context::new(input)?;
+        let mut inner = crate::Context::new(input)?;
+        // We derive the serde side ourselves, so the names agree by construction
+        inner.top_attr.has_serde = false;
+        // Serde's impls for wrappers don't all forward, e.g. `&'a T` is only
+        // `Deserialize` for strings and bytes
+        inner.peel_wrappers = false;
 
         let serde_path = SetAttr::find_serde_crate(&inner.input.attrs)?;
         let serde_path = serde_path.unwrap_or_else(|| syn::parse_quote!(::serde));
@@ -178,8 +194,11 @@ fn tuple_struct(
         4. Create helper struct for deserialization
     */
 
+    let crate_path = ctx.crate_path.clone();
     let (mut field_defs, mut field_names) = (Vec::new(), Vec::new());
     for (i, field) in fields.unnamed.iter_mut().enumerate() {
+        with_to_serde(&crate_path, field)?;
+
         // TODO: Maybe giving the freedom to override our lifeless
         // index-name isn't bad?
 
@@ -246,6 +265,310 @@ fn tuple_struct(
     Ok((schema, serde_impl))
 }
 
+// Schema attributes that change the wire layout. Serde must own them, or
+// the schema and the deserializer drift apart; the schema reads serde's.
+const CONTAINER_LAYOUT: &[&str] = &[
+    "rename_all",
+    "rename_all_with",
+    "rename_all_fields",
+    "tag",
+    "content",
+    "untagged",
+    "transparent",
+    "repr",
+];
+const ITEM_LAYOUT: &[&str] = &["rename", "rename_all", "rename_all_with", "skip"];
+
+// Rejects `ignore_serde` and the container's layout attributes
+fn check_container(ctx: &Context) -> Result<(), Error> {
+    if ctx.top_attr.ignore_serde {
+        return Err(Error::new_spanned(
+            &ctx.input.ident,
+            Diagnostic::SerdeOwnsLayout
+                .message("AsSchemaWithSerde reads serde attributes and can't ignore them"),
+        ));
+    }
+    if let Some(values) = &ctx.top_attr.enum_values {
+        return Err(values.error(Diagnostic::SerdeOwnsLayout.message(
+            "enum_values replaces the type's layout, which AsSchemaWithSerde derives the serde \
+             deserializer from. Derive AsSchema instead",
+        )));
+    }
+    check_layout(&ctx.input.attrs, CONTAINER_LAYOUT)
+}
+
+// Rejects `#[schema(...)]` attributes in `layout`, pointing at the serde
+// attribute to use instead
+fn check_layout(attrs: &[Attribute], layout: &[&str]) -> Result<(), Error> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("schema")) {
+        attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                let name = ident.to_string();
+                if layout.contains(&name.as_str()) {
+                    return Err(meta.error(Diagnostic::SerdeOwnsLayout.message(format!(
+                        "AsSchemaWithSerde takes the layout from serde. \
+                         Use #[serde({name})] instead of #[schema({name})]"
+                    ))));
+                }
+            }
+            // Skip the value, whatever it is
+            if meta.input.peek(Token![=]) {
+                meta.value()?.parse::<Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                let nested;
+                syn::parenthesized!(nested in meta.input);
+                nested.parse::<TokenStream2>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+// Strips our attributes so they don't reach serde's derive
+fn without_schema(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|attr| !attr.path().is_ident("schema"));
+}
+
+// Has serde deserialize a field with the module its `#[schema(with)]`
+// takes the schema from, or from a string with `FromStr` if it's
+// `#[schema(as_string)]`
+fn with_to_serde(crate_path: &syn::Path, field: &mut Field) -> Result<(), Error> {
+    let schema_attrs = attr::parse_field(&field.attrs, true)?;
+    if let Some(with) = schema_attrs.with {
+        let with = with.to_token_stream().to_string().replace(' ', "");
+        field.attrs.push(parse_quote!(#[serde(with = #with)]));
+    }
+    if schema_attrs.as_string == Some(true) {
+        let crate_path = crate_path.to_token_stream().to_string().replace(' ', "");
+        if crate::is_option(&field.ty) {
+            let with = format!("{crate_path}::schema::as_string::option::deserialize");
+            field
+                .attrs
+                .push(parse_quote!(#[serde(deserialize_with = #with)]));
+            // Missing like any other Option, which deserialize_with turns off
+            if !attr::parse_field(&field.attrs, false)?.serde_default {
+                field.attrs.push(parse_quote!(#[serde(default)]));
+            }
+        } else {
+            let with = format!("{crate_path}::schema::as_string::deserialize");
+            field
+                .attrs
+                .push(parse_quote!(#[serde(deserialize_with = #with)]));
+        }
+    }
+    Ok(())
+}
+
+// Default values of the fields the schema lets the model leave out, as
+// associated functions of the helper for `#[serde(default = "...")]`
+struct Defaults {
+    helper: syn::Ident,
+    all_optional: bool,
+    container_default: bool,
+    fns: Vec<TokenStream2>,
+}
+
+impl Defaults {
+    fn new(ctx: &Context) -> Self {
+        Self {
+            helper: format_ident!("__{}Helper", ctx.input.ident),
+            all_optional: ctx.top_attr.all_optional,
+            container_default: ctx.top_attr.serde_default,
+            fns: Vec::new(),
+        }
+    }
+
+    // Has serde fill in a field the schema doesn't require because of its
+    // default or `all_optional`, unless serde already defaults it
+    fn add(&mut self, field: &mut Field) -> Result<(), Error> {
+        let schema_attrs = attr::parse_field(&field.attrs, true)?;
+        if !(schema_attrs.default.is_some() || self.all_optional)
+            || schema_attrs.required == Some(true)
+            || self.container_default
+            || attr::parse_field(&field.attrs, false)?.serde_default
+        {
+            return Ok(());
+        }
+
+        let Some(default) = schema_attrs.default else {
+            field.attrs.push(parse_quote!(#[serde(default)]));
+            return Ok(());
+        };
+        let value = match default {
+            attr::DefaultValue::String(s) => quote! { ::std::convert::From::from(#s) },
+            attr::DefaultValue::Number(n) => quote! { #n as _ },
+            attr::DefaultValue::Bool(b) => quote! { #b },
+        };
+        let ty = &field.ty;
+        let value = if crate::is_option(ty) {
+            quote! { ::std::option::Option::Some(#value) }
+        } else {
+            value
+        };
+        let name = format_ident!("__schema_default_{}", self.fns.len());
+        self.fns.push(quote! {
+            fn #name() -> #ty {
+                #value
+            }
+        });
+
+        let path = format!("{}::{name}", self.helper);
+        field.attrs.push(parse_quote!(#[serde(default = #path)]));
+        Ok(())
+    }
+
+    fn declare(&self, ctx: &Context) -> TokenStream2 {
+        if self.fns.is_empty() {
+            return TokenStream2::new();
+        }
+        let helper = &self.helper;
+        let fns = &self.fns;
+        let (impl_generics, ty_generics, where_clause) = ctx.input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics #helper #ty_generics #where_clause {
+                #(#fns)*
+            }
+        }
+    }
+}
+
+// Declares a copy of the input deriving serde's Deserialize, with the
+// same serde attributes, and deserializes through it
+fn helper(ctx: &Context, data: Data) -> TokenStream2 {
+    let input = &ctx.input;
+    let serde_path = &ctx.serde_path;
+    let ident = format_ident!("__{}Helper", input.ident);
+    let generics = &input.generics;
+    let where_clause = &generics.where_clause;
+    let attrs = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"));
+
+    let body = match data {
+        Data::Struct(DataStruct { fields, .. }) => quote! { #fields },
+        Data::Enum(DataEnum { variants, .. }) => quote! { { #variants } },
+        Data::Union(_) => unreachable!("unions are rejected"),
+    };
+    let keyword = match &input.data {
+        Data::Enum(_) => quote! { enum },
+        _ => quote! { struct },
+    };
+    let (_, ty_generics, _) = generics.split_for_impl();
+
+    quote! {
+        #[derive(#serde_path::Deserialize)]
+        #(#attrs)*
+        #keyword #ident #generics #where_clause #body
+
+        let helper = <#ident #ty_generics as #serde_path::Deserialize>::deserialize(__deserializer)?;
+    }
+}
+
+fn named_struct(
+    ctx: &mut Context,
+    mut fields: FieldsNamed,
+) -> Result<(Schema, TokenStream2), Error> {
+    check_container(ctx)?;
+    let crate_path = ctx.crate_path.clone();
+    let mut defaults = Defaults::new(ctx);
+    for field in fields.named.iter_mut() {
+        check_layout(&field.attrs, ITEM_LAYOUT)?;
+        with_to_serde(&crate_path, field)?;
+        defaults.add(field)?;
+        without_schema(&mut field.attrs);
+    }
+
+    let names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+    let declare = helper(
+        ctx,
+        Data::Struct(DataStruct {
+            struct_token: Default::default(),
+            fields: Fields::Named(fields.clone()),
+            semi_token: None,
+        }),
+    );
+    let declare_defaults = defaults.declare(ctx);
+    let serde_impl = quote! {
+        #declare
+        #declare_defaults
+        Ok(Self {
+            #(#names: helper.#names,)*
+        })
+    };
+
+    // keep this below so it doesn't change the bounds
+    let schema = crate::generate_schema(ctx)?;
+    Ok((schema, serde_impl))
+}
+
+fn impl_enum(ctx: &mut Context, mut data: DataEnum) -> Result<(Schema, TokenStream2), Error> {
+    check_container(ctx)?;
+    let crate_path = ctx.crate_path.clone();
+    let helper_ident = format_ident!("__{}Helper", ctx.input.ident);
+    let mut defaults = Defaults::new(ctx);
+
+    let mut arms = Vec::with_capacity(data.variants.len());
+    for variant in data.variants.iter_mut() {
+        check_layout(&variant.attrs, ITEM_LAYOUT)?;
+        // Our aliases are accepted when deserializing, like serde's. Repeating
+        // one serde already has is harmless.
+        let aliases = attr::parse_variant(&variant.attrs, true)?.aliases;
+        without_schema(&mut variant.attrs);
+        variant.attrs.extend(
+            aliases
+                .iter()
+                .map(|alias| parse_quote!(#[serde(alias = #alias)])),
+        );
+
+        let ident = &variant.ident;
+        let arm = match &mut variant.fields {
+            Fields::Named(fields) => {
+                for field in fields.named.iter_mut() {
+                    check_layout(&field.attrs, ITEM_LAYOUT)?;
+                    with_to_serde(&crate_path, field)?;
+                    defaults.add(field)?;
+                    without_schema(&mut field.attrs);
+                }
+                let names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+                quote! {
+                    #helper_ident::#ident { #(#names),* } => Self::#ident { #(#names),* }
+                }
+            }
+            Fields::Unnamed(fields) => {
+                for field in fields.unnamed.iter_mut() {
+                    check_layout(&field.attrs, ITEM_LAYOUT)?;
+                    with_to_serde(&crate_path, field)?;
+                    without_schema(&mut field.attrs);
+                }
+                let names: Vec<_> = (0..fields.unnamed.len())
+                    .map(|i| format_ident!("__field{i}"))
+                    .collect();
+                quote! {
+                    #helper_ident::#ident(#(#names),*) => Self::#ident(#(#names),*)
+                }
+            }
+            Fields::Unit => quote! { #helper_ident::#ident => Self::#ident },
+        };
+        arms.push(arm);
+    }
+
+    let declare = helper(ctx, Data::Enum(data));
+    let declare_defaults = defaults.declare(ctx);
+    let serde_impl = quote! {
+        #declare
+        #declare_defaults
+        Ok(match helper {
+            #(#arms,)*
+        })
+    };
+
+    // keep this below so it doesn't change the bounds
+    let schema = crate::generate_schema(ctx)?;
+    Ok((schema, serde_impl))
+}
+
 #[cfg(test)]
 mod test {
     use syn::WhereClause;
@@ -268,6 +591,197 @@ mod test {
         assert_eq!(ctx.serde_path, parse_quote!(mod_serde))
     }
 
+    #[test]
+    fn layout_from_serde() {
+        let derive = |input: DeriveInput| {
+            let mut ctx = Context::new(input)?;
+            match ctx.input.data.clone() {
+                Data::Struct(DataStruct {
+                    fields: Fields::Named(fields),
+                    ..
+                }) => named_struct(&mut ctx, fields),
+                Data::Enum(data) => impl_enum(&mut ctx, data),
+                _ => unreachable!(),
+            }
+        };
+
+        let (schema, serde_impl) = derive(parse_quote! {
+            #[serde(tag = "kind")]
+            enum E {
+                A { #[schema(description = "x")] x: u8 },
+                B,
+            }
+        })
+        .unwrap();
+        assert!(schema
+            .properties
+            .contains_key(&crate::Value::Raw("kind".into())));
+        let serde_impl = serde_impl.to_string();
+        assert!(serde_impl.contains("tag = \"kind\""), "{serde_impl}");
+        assert!(!serde_impl.contains("schema"), "{serde_impl}");
+
+        let (_, serde_impl) = derive(parse_quote! {
+            enum E {
+                #[schema(alias = "OLD")]
+                New,
+            }
+        })
+        .unwrap();
+        let serde_impl = serde_impl.to_string();
+        assert!(serde_impl.contains("alias = \"OLD\""), "{serde_impl}");
+
+        let (schema, serde_impl) = derive(parse_quote! {
+            struct S {
+                #[schema(with = "time::rfc3339")]
+                at: SystemTime,
+            }
+        })
+        .unwrap();
+        let serde_impl = serde_impl.to_string();
+        assert!(
+            serde_impl.contains("with = \"time::rfc3339\""),
+            "{serde_impl}"
+        );
+        assert!(matches!(
+            &schema.properties[&crate::Value::Raw("at".into())].base,
+            crate::schema::BaseSchema::AsSschema(path) if *path == parse_quote!(time::rfc3339::as_schema)
+        ));
+
+        let (_, serde_impl) = derive(parse_quote! {
+            #[schema(crate_path = "ai")]
+            struct S {
+                #[schema(as_string)]
+                address: IpAddr,
+                #[schema(as_string)]
+                backup: Option<IpAddr>,
+            }
+        })
+        .unwrap();
+        let serde_impl = serde_impl.to_string();
+        assert!(
+            serde_impl.contains(
+                "# [serde (deserialize_with = \"ai::schema::as_string::deserialize\")] address"
+            ),
+            "{serde_impl}"
+        );
+        assert!(
+            serde_impl.contains(
+                "# [serde (deserialize_with = \"ai::schema::as_string::option::deserialize\")] \
+                 # [serde (default)] backup"
+            ),
+            "{serde_impl}"
+        );
+
+        // Nested attributes are skipped over, not mistaken for layout
+        let err = derive(parse_quote! {
+            struct S {
+                #[schema(items(description = "x"), rename_all = "camelCase")]
+                a: Vec<u8>,
+            }
+        })
+        .map(|_| ())
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("Use #[serde(rename_all)]"), "{err}");
+        let (schema, _) = derive(parse_quote! {
+            struct S {
+                #[schema(enum_values("a", "b"))]
+                b: String,
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            schema.properties[&crate::Value::Raw("b".into())]
+                .r#enum
+                .len(),
+            2
+        );
+
+        let rejected: [DeriveInput; 5] = [
+            parse_quote! {
+                #[schema(rename_all = "camelCase")]
+                struct S { field_one: u8 }
+            },
+            parse_quote! {
+                struct S { #[schema(description = "a", rename = "b")] a: u8 }
+            },
+            parse_quote! {
+                enum E { #[schema(skip)] A, B }
+            },
+            parse_quote! {
+                #[schema(ignore_serde)]
+                struct S { a: u8 }
+            },
+            parse_quote! {
+                #[schema(enum_values("a", "b"))]
+                struct S { a: u8 }
+            },
+        ];
+        for input in rejected {
+            let err = derive(input).map(|_| ()).unwrap_err();
+            assert!(err.to_string().contains("serde"), "{err}");
+        }
+    }
+
+    #[test]
+    fn defaults_to_serde() {
+        let derive = |input: DeriveInput| {
+            let mut ctx = Context::new(input).unwrap();
+            let Data::Struct(DataStruct {
+                fields: Fields::Named(fields),
+                ..
+            }) = ctx.input.data.clone()
+            else {
+                unreachable!()
+            };
+            named_struct(&mut ctx, fields).unwrap().1.to_string()
+        };
+
+        let serde_impl = derive(parse_quote! {
+            struct S {
+                #[schema(default = 3)]
+                n: u32,
+                #[schema(default = "en")]
+                language: Option<String>,
+                #[schema(default = 3, required)]
+                forced: u32,
+                #[schema(default = 3)]
+                #[serde(default)]
+                own: u32,
+            }
+        });
+        assert!(
+            serde_impl.contains("default = \"__SHelper::__schema_default_0\"")
+                && serde_impl.contains("fn __schema_default_0 () -> u32 { 3f64 as _ }"),
+            "{serde_impl}"
+        );
+        assert!(
+            serde_impl.contains("default = \"__SHelper::__schema_default_1\"")
+                && serde_impl.contains("Some (:: std :: convert :: From :: from (\"en\"))"),
+            "{serde_impl}"
+        );
+        assert!(!serde_impl.contains("__schema_default_2"), "{serde_impl}");
+        assert_eq!(
+            serde_impl.matches("serde (default)").count(),
+            1,
+            "{serde_impl}"
+        );
+
+        let serde_impl = derive(parse_quote! {
+            #[schema(all_optional)]
+            struct S {
+                n: u32,
+                #[schema(required)]
+                forced: u32,
+            }
+        });
+        assert_eq!(
+            serde_impl.matches("serde (default)").count(),
+            1,
+            "{serde_impl}"
+        );
+    }
+
     #[test]
     fn change_to_serde() {
         struct Test {